@@ -0,0 +1,53 @@
+/* C ABI of libchartium, built with the `ffi` feature. See src/ffi.rs for the details. */
+#ifndef CHARTIUM_H
+#define CHARTIUM_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define CHARTIUM_ABI_VERSION 1
+#define CHARTIUM_INVALID_HANDLE SIZE_MAX
+
+typedef struct ChartiumModule ChartiumModule;
+typedef struct ChartiumJob ChartiumJob;
+typedef struct ChartiumSvgRenderer ChartiumSvgRenderer;
+
+uint32_t chartium_abi_version(void);
+
+ChartiumModule *chartium_module_new(void);
+void chartium_module_free(ChartiumModule *module);
+size_t chartium_create_trace(ChartiumModule *module, const char *id, const char *x_type);
+bool chartium_load_trace(ChartiumModule *module, size_t handle, const double *x,
+                         const double *y, size_t len);
+
+/* method: 0 keeps every point, 1 is M4, 2 is LTTB */
+size_t chartium_downsample(const float *x, const float *y, size_t len, uint8_t method,
+                           size_t buckets, float *out_x, float *out_y, size_t capacity);
+size_t chartium_ticks(double from, double to, double *out_values, double *out_positions,
+                      size_t capacity);
+
+ChartiumJob *chartium_job_new(const char *x_type);
+void chartium_job_free(ChartiumJob *job);
+void chartium_job_set_view(ChartiumJob *job, double x_from, double x_to, double y_from,
+                           double y_to);
+void chartium_job_set_margins(ChartiumJob *job, uint32_t margin, uint32_t x_label_space,
+                              uint32_t y_label_space);
+void chartium_job_add_trace(ChartiumJob *job, size_t handle, const uint8_t color[3],
+                            uint32_t width);
+
+ChartiumSvgRenderer *chartium_svg_renderer_new(uint32_t width, uint32_t height);
+void chartium_svg_renderer_free(ChartiumSvgRenderer *renderer);
+char *chartium_render_svg(ChartiumSvgRenderer *renderer, const ChartiumModule *module,
+                          const ChartiumJob *job);
+void chartium_string_free(char *text);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,318 @@
+//! Arrow IPC stream import, the decoded columns serve as trace storage without being copied
+
+use std::{collections::HashMap, ops::Range, rc::Rc};
+
+use arrow_array::{cast::AsArray, types::*, Array, ArrayRef, PrimitiveArray, RecordBatch};
+use arrow_buffer::Buffer;
+use arrow_ipc::reader::StreamDecoder;
+use arrow_schema::{ArrowError, DataType};
+use wasm_bindgen::prelude::*;
+
+use crate::{data::DataIdx, prelude::*, trace::Segment};
+
+use super::DataModule;
+
+macro_rules! columns {
+    ( $( $variant:ident($arrow:ty) ),+ ) => {
+        /// Numeric Arrow column, temporal columns being viewed as their integer values
+        #[derive(Clone)]
+        pub enum Column {
+            $( $variant(PrimitiveArray<$arrow>), )+
+        }
+
+        impl Column {
+            fn value(&self, i: usize) -> RangePrec {
+                match self {
+                    $( Column::$variant(a) => a.value(i) as RangePrec, )+
+                }
+            }
+
+            fn is_valid(&self, i: usize) -> bool {
+                match self {
+                    $( Column::$variant(a) => a.is_valid(i), )+
+                }
+            }
+
+            fn len(&self) -> usize {
+                match self {
+                    $( Column::$variant(a) => a.len(), )+
+                }
+            }
+
+            fn slice(&self, offset: usize, len: usize) -> Self {
+                match self {
+                    $( Column::$variant(a) => Column::$variant(a.slice(offset, len)), )+
+                }
+            }
+        }
+    };
+}
+
+columns!(
+    I8(Int8Type),
+    I16(Int16Type),
+    I32(Int32Type),
+    I64(Int64Type),
+    U8(UInt8Type),
+    U16(UInt16Type),
+    U32(UInt32Type),
+    U64(UInt64Type),
+    F32(Float32Type),
+    F64(Float64Type)
+);
+
+impl Column {
+    /// View of `array` when it's numeric, sharing its buffers
+    pub fn new(array: &ArrayRef) -> Option<Self> {
+        Some(match array.data_type() {
+            DataType::Int8 => Column::I8(array.as_primitive().clone()),
+            DataType::Int16 => Column::I16(array.as_primitive().clone()),
+            DataType::Int32 => Column::I32(array.as_primitive().clone()),
+            DataType::Int64 => Column::I64(array.as_primitive().clone()),
+            DataType::UInt8 => Column::U8(array.as_primitive().clone()),
+            DataType::UInt16 => Column::U16(array.as_primitive().clone()),
+            DataType::UInt32 => Column::U32(array.as_primitive().clone()),
+            DataType::UInt64 => Column::U64(array.as_primitive().clone()),
+            DataType::Float32 => Column::F32(array.as_primitive().clone()),
+            DataType::Float64 => Column::F64(array.as_primitive().clone()),
+            DataType::Date32 | DataType::Time32(_) => {
+                Column::I32(Self::reinterpret(array, DataType::Int32)?)
+            }
+            DataType::Date64
+            | DataType::Time64(_)
+            | DataType::Timestamp(..)
+            | DataType::Duration(_) => Column::I64(Self::reinterpret(array, DataType::Int64)?),
+            _ => return None,
+        })
+    }
+
+    fn reinterpret<T: ArrowPrimitiveType>(
+        array: &ArrayRef,
+        data_type: DataType,
+    ) -> Option<PrimitiveArray<T>> {
+        let data = array
+            .to_data()
+            .into_builder()
+            .data_type(data_type)
+            .build()
+            .ok()?;
+
+        Some(PrimitiveArray::from(data))
+    }
+}
+
+/// Name of the x type traces plotted against a column of `data_type` are created with
+fn x_type_name(data_type: &DataType) -> &'static str {
+    match data_type {
+        DataType::Date32 | DataType::Date64 | DataType::Timestamp(..) => "DateTime",
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => "I64",
+        DataType::UInt8 | DataType::UInt16 | DataType::UInt32 | DataType::UInt64 => "U64",
+        _ => "F64",
+    }
+}
+
+/// Segment reading straight from the columns of a record batch, null rows are skipped
+#[derive(Clone)]
+pub struct ArrowSegment {
+    x: Column,
+    y: Column,
+    shift: (RangePrec, RangePrec),
+}
+
+impl ArrowSegment {
+    pub fn new(x: Column, y: Column) -> Self {
+        Self {
+            x,
+            y,
+            shift: (0.0, 0.0),
+        }
+    }
+
+    fn x_at(&self, i: usize) -> RangePrec {
+        self.x.value(i) + self.shift.0
+    }
+
+    fn y_at(&self, i: usize) -> RangePrec {
+        self.y.value(i) + self.shift.1
+    }
+
+    fn is_valid(&self, i: usize) -> bool {
+        self.x.is_valid(i) && self.y.is_valid(i)
+    }
+
+    /// First row whose x doesn't satisfy `pred`, the column being sorted by x
+    fn partition_point(&self, pred: impl Fn(RangePrec) -> bool) -> usize {
+        let (mut lo, mut hi) = (0, self.x.len());
+
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+
+            if pred(self.x_at(mid)) {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+
+        lo
+    }
+
+    fn rows_in(&self, from: RangePrec, to: RangePrec) -> Range<usize> {
+        let start = self.partition_point(|x| x < from);
+        let end = self.partition_point(|x| x <= to).max(start);
+
+        start..end
+    }
+}
+
+impl Segment for ArrowSegment {
+    fn from(&self) -> RangePrec {
+        if self.x.len() == 0 {
+            return 1.0;
+        }
+
+        self.x_at(0)
+    }
+
+    fn to(&self) -> RangePrec {
+        match self.x.len() {
+            0 => 0.0,
+            len => self.x_at(len - 1),
+        }
+    }
+
+    fn iter_in<'a>(
+        &'a self,
+        from: RangePrec,
+        to: RangePrec,
+    ) -> Box<dyn Iterator<Item = (DataPrec, DataPrec)> + 'a> {
+        Box::new(
+            self.iter_high_prec(from, to)
+                .map(|(x, y)| (x as DataPrec, y as DataPrec)),
+        )
+    }
+
+    fn iter_with_origin<'a>(
+        &'a self,
+        from: RangePrec,
+        to: RangePrec,
+        x_orig: RangePrec,
+        y_orig: RangePrec,
+    ) -> Box<dyn Iterator<Item = (DataPrec, DataPrec)> + 'a> {
+        Box::new(
+            self.iter_high_prec(from, to)
+                .map(move |(x, y)| ((x - x_orig) as DataPrec, (y - y_orig) as DataPrec)),
+        )
+    }
+
+    fn iter_high_prec<'a>(
+        &'a self,
+        from: RangePrec,
+        to: RangePrec,
+    ) -> Box<dyn Iterator<Item = (RangePrec, RangePrec)> + 'a> {
+        Box::new(
+            self.rows_in(from, to)
+                .filter(|&i| self.is_valid(i))
+                .map(|i| (self.x_at(i), self.y_at(i))),
+        )
+    }
+
+    fn value_at(&self, x: RangePrec) -> Option<RangePrec> {
+        let right = self.partition_point(|cx| cx < x);
+
+        if right >= self.x.len() || !self.is_valid(right) {
+            return None;
+        }
+        if self.x_at(right) == x {
+            return Some(self.y_at(right));
+        }
+
+        let left = right.checked_sub(1).filter(|&i| self.is_valid(i))?;
+        let (left_x, right_x) = (self.x_at(left), self.x_at(right));
+
+        Some(
+            ((right_x - x) * self.y_at(left) + (x - left_x) * self.y_at(right))
+                / (right_x - left_x),
+        )
+    }
+
+    fn shrink(&mut self, from: RangePrec, to: RangePrec) {
+        let rows = self.rows_in(from, to);
+
+        self.x = self.x.slice(rows.start, rows.len());
+        self.y = self.y.slice(rows.start, rows.len());
+    }
+
+    fn shift(&mut self, shift_x: RangePrec, shift_y: RangePrec) {
+        self.shift.0 += shift_x;
+        self.shift.1 += shift_y;
+    }
+}
+
+fn js_error(err: ArrowError) -> JsValue {
+    JsValue::from_str(&err.to_string())
+}
+
+#[wasm_bindgen]
+impl DataModule {
+    /// Imports the numeric columns of an Arrow IPC stream as traces plotted against
+    /// `x_column`, which has to be sorted. Columns are loaded into the writable trace with
+    /// the same id, or into a newly created one. Returns an object mapping column names to
+    /// trace handles.
+    pub fn import_arrow(&mut self, bytes: Vec<u8>, x_column: &str) -> Result<JsValue, JsValue> {
+        // the stream is decoded in place, record batches borrow its buffer
+        let mut buffer = Buffer::from_vec(bytes);
+        let mut decoder = StreamDecoder::new();
+        let mut handles = HashMap::new();
+
+        while !buffer.is_empty() {
+            if let Some(batch) = decoder.decode(&mut buffer).map_err(js_error)? {
+                self.import_batch(&batch, x_column, &mut handles)?;
+            }
+        }
+        decoder.finish().map_err(js_error)?;
+
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        Ok(serde::Serialize::serialize(&handles, &serializer)?)
+    }
+}
+
+impl DataModule {
+    fn import_batch(
+        &mut self,
+        batch: &RecordBatch,
+        x_column: &str,
+        handles: &mut HashMap<String, DataIdx>,
+    ) -> Result<(), JsValue> {
+        let x_array = batch
+            .column_by_name(x_column)
+            .ok_or_else(|| JsValue::from_str(&format!("Missing the x column {}", x_column)))?;
+        let x = Column::new(x_array).ok_or_else(|| {
+            JsValue::from_str(&format!("The x column {} isn't numeric", x_column))
+        })?;
+        let x_type = x_type_name(x_array.data_type());
+
+        for (field, array) in batch.schema().fields().iter().zip(batch.columns()) {
+            let name = field.name();
+            let Some(y) = Column::new(array).filter(|_| name != x_column) else {
+                continue;
+            };
+
+            let handle = match handles.get(name) {
+                Some(&handle) => handle,
+                None => {
+                    let handle = self.trace_for_column(name, x_type);
+
+                    handles.insert(name.clone(), handle);
+                    handle
+                }
+            };
+
+            let segment: Rc<dyn Segment> = Rc::new(ArrowSegment::new(x.clone(), y));
+            self.traces.get_mut(&handle).unwrap().push_segment(segment);
+            self.invalidate(handle);
+        }
+
+        Ok(())
+    }
+}
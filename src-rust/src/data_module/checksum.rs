@@ -0,0 +1,43 @@
+//! Checksums of trace data, so hosts syncing data between sessions or machines can verify the
+//! chart draws what the source holds
+
+use wasm_bindgen::prelude::*;
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::{data::DataIdx, prelude::*};
+
+use super::DataModule;
+
+/// XXH3 64 of the `points` as consecutive little endian f64 `x` and `y`, whatever precision
+/// they're stored in
+pub fn checksum_points(points: impl IntoIterator<Item = (RangePrec, RangePrec)>) -> u64 {
+    let mut hasher = Xxh3::new();
+
+    for (x, y) in points {
+        hasher.update(&x.to_le_bytes());
+        hasher.update(&y.to_le_bytes());
+    }
+
+    hasher.digest()
+}
+
+#[wasm_bindgen]
+impl DataModule {
+    /// Checksum of all points of a trace as 16 hex digits, equal for equal data in any session,
+    /// see `checksum_points`
+    pub fn checksum(&self, handle: DataIdx) -> Result<String, JsValue> {
+        self.trace_checksum(handle)
+            .map(|checksum| format!("{:016x}", checksum))
+            .ok_or_else(|| JsValue::from_str(&format!("Invalid trace handle {}", handle)))
+    }
+}
+
+impl DataModule {
+    pub fn trace_checksum(&self, handle: DataIdx) -> Option<u64> {
+        let trace = self.get_trace(handle)?;
+
+        Some(checksum_points(
+            trace.get_data_high_prec(RangePrec::MIN, RangePrec::MAX),
+        ))
+    }
+}
@@ -0,0 +1,262 @@
+//! Delimited text import, parsed in place instead of going through JS arrays first
+
+use std::{collections::HashMap, rc::Rc};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    parallel,
+    prelude::*,
+    trace::{Segment, SharedSegment},
+};
+
+use super::DataModule;
+
+/// Values treated as missing rather than as text
+const MISSING: [&str; 6] = ["", "-", "NA", "N/A", "null", "NULL"];
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct CsvOptions {
+    /// Field separator, `0` picking the most frequent of `,`, `;` and tab in the first line
+    pub delimiter: u8,
+    /// Column holding x, either numbers or ISO-8601 timestamps
+    pub x_column: usize,
+    /// Keeps missing values as NaN gaps instead of skipping them
+    pub keep_nan: bool,
+    header: Option<bool>,
+    columns: Option<Vec<usize>>,
+}
+
+#[wasm_bindgen]
+impl CsvOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            delimiter: 0,
+            x_column: 0,
+            keep_nan: false,
+            header: None,
+            columns: None,
+        }
+    }
+
+    /// Whether the first row holds column names, detected unless set
+    pub fn set_header(&mut self, header: bool) {
+        self.header = Some(header);
+    }
+
+    /// Imports only the given columns, otherwise all numeric ones besides x are
+    pub fn select_columns(&mut self, columns: Vec<usize>) {
+        self.columns = Some(columns);
+    }
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parsed x value, timestamps in epoch milliseconds
+enum XValue {
+    Number(RangePrec),
+    Timestamp(RangePrec),
+}
+
+impl XValue {
+    fn parse(field: &str) -> Option<Self> {
+        if let Ok(x) = field.parse::<RangePrec>() {
+            return x.is_finite().then_some(XValue::Number(x));
+        }
+
+        let millis = DateTime::parse_from_rfc3339(field)
+            .map(|d| d.timestamp_millis())
+            .or_else(|_| {
+                NaiveDateTime::parse_from_str(field, "%Y-%m-%dT%H:%M:%S%.f")
+                    .or_else(|_| NaiveDateTime::parse_from_str(field, "%Y-%m-%d %H:%M:%S%.f"))
+                    .map(|d| d.and_utc().timestamp_millis())
+            })
+            .or_else(|_| {
+                NaiveDate::parse_from_str(field, "%Y-%m-%d")
+                    .map(|d| d.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp_millis())
+            })
+            .ok()?;
+
+        Some(XValue::Timestamp(millis as RangePrec))
+    }
+
+    fn value(&self) -> RangePrec {
+        match *self {
+            XValue::Number(x) | XValue::Timestamp(x) => x,
+        }
+    }
+}
+
+/// Parsed y value, `Ok(None)` when it's missing and `Err` when it isn't a number
+fn parse_y(field: &str) -> Result<Option<RangePrec>, ()> {
+    if MISSING.contains(&field) {
+        return Ok(None);
+    }
+
+    match field.parse::<RangePrec>() {
+        Ok(y) if y.is_nan() => Ok(None),
+        Ok(y) => Ok(Some(y)),
+        Err(_) => Err(()),
+    }
+}
+
+/// Splits a line into trimmed fields, honouring double quotes and their `""` escapes
+pub(super) fn split_fields(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => {
+                fields.push(field.trim().to_string());
+                field.clear();
+            }
+            c => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+
+    fields
+}
+
+fn detect_delimiter(line: &str) -> char {
+    [',', ';', '\t']
+        .into_iter()
+        .max_by_key(|&d| line.matches(d).count())
+        .filter(|&d| line.contains(d))
+        .unwrap_or(',')
+}
+
+#[wasm_bindgen]
+impl DataModule {
+    /// Imports delimited text, creating a trace for every numeric column or loading into
+    /// the writable trace named like the column. Returns an object mapping column names to
+    /// trace handles.
+    pub fn import_csv(&mut self, bytes: &[u8], options: &CsvOptions) -> Result<JsValue, JsValue> {
+        let text = String::from_utf8_lossy(bytes);
+        let lines: Vec<&str> = text
+            .lines()
+            .map(|l| l.trim_end_matches('\r'))
+            .filter(|l| !l.trim().is_empty())
+            .collect();
+
+        let Some(first) = lines.first() else {
+            return Ok(js_sys::Object::new().into());
+        };
+        let delimiter = match options.delimiter {
+            0 => detect_delimiter(first),
+            d => d as char,
+        };
+
+        let parts = parallel::split(lines.len(), parallel::MIN_PART / 8, |_| true);
+        let mut rows: Vec<Vec<String>> = parallel::map(&parts, |part| {
+            lines[part]
+                .iter()
+                .map(|l| split_fields(l, delimiter))
+                .collect::<Vec<_>>()
+        })
+        .concat();
+        let x_col = options.x_column;
+
+        let header = options.header.unwrap_or_else(|| {
+            rows[0].iter().enumerate().any(|(i, field)| {
+                if i == x_col {
+                    XValue::parse(field).is_none()
+                } else {
+                    parse_y(field).is_err()
+                }
+            })
+        });
+        let names: Vec<String> = match header {
+            true => rows.remove(0),
+            false => Vec::new(),
+        };
+
+        // rows without a usable x are dropped, the rest ordered by it
+        let mut parsed: Vec<(XValue, Vec<String>)> = rows
+            .into_iter()
+            .filter_map(|row| Some((XValue::parse(row.get(x_col)?)?, row)))
+            .collect();
+        parsed.sort_by(|a, b| a.0.value().total_cmp(&b.0.value()));
+
+        if parsed.is_empty() {
+            return Err(JsValue::from_str(&format!(
+                "Column {} holds no numbers or timestamps to use as x",
+                x_col
+            )));
+        }
+
+        let x_type = match parsed[0].0 {
+            XValue::Timestamp(_) => "DateTime",
+            XValue::Number(_) => "F64",
+        };
+        let width = parsed.iter().map(|(_, row)| row.len()).max().unwrap_or(0);
+        let columns: Vec<usize> = match &options.columns {
+            Some(columns) => columns.clone(),
+            None => (0..width).filter(|&c| c != x_col).collect(),
+        };
+
+        let mut handles = HashMap::new();
+
+        for column in columns {
+            let values: Result<Vec<_>, ()> = parsed
+                .iter()
+                .map(|(x, row)| Ok((x.value(), parse_y(row.get(column).map_or("", |f| f))?)))
+                .collect();
+
+            // text columns are skipped unless asked for, their text then being missing values
+            let values = match (values, &options.columns) {
+                (Ok(values), _) => values,
+                (Err(_), Some(_)) => parsed
+                    .iter()
+                    .map(|(x, row)| {
+                        let y = row.get(column).and_then(|f| parse_y(f).ok().flatten());
+                        (x.value(), y)
+                    })
+                    .collect(),
+                (Err(_), None) => continue,
+            };
+
+            let (x, y): (Vec<RangePrec>, Vec<RangePrec>) = values
+                .into_iter()
+                .filter_map(|(x, y)| match y {
+                    Some(y) => Some((x, y)),
+                    None if options.keep_nan => Some((x, RangePrec::NAN)),
+                    None => None,
+                })
+                .unzip();
+            if x.is_empty() {
+                continue;
+            }
+
+            let name = names
+                .get(column)
+                .filter(|n| !n.is_empty())
+                .cloned()
+                .unwrap_or_else(|| format!("column {}", column));
+            let handle = self.trace_for_column(&name, x_type);
+
+            let segment: Rc<dyn Segment> = Rc::new(SharedSegment::new(Rc::new(x), Rc::new(y)));
+            self.traces.get_mut(&handle).unwrap().push_segment(segment);
+            self.invalidate(handle);
+            handles.insert(name, handle);
+        }
+
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        Ok(serde::Serialize::serialize(&handles, &serializer)?)
+    }
+}
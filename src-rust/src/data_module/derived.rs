@@ -0,0 +1,132 @@
+//! Traces computed from others by an expression, kept up to date as their inputs change
+
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    data::DataIdx,
+    trace::{Expression, ExpressionSegment, Segment},
+};
+
+use super::DataModule;
+
+/// Expression of a derived trace and the handles bound to its variables, in their order
+#[derive(Clone, Debug)]
+pub struct Derived {
+    pub expression: Expression,
+    pub inputs: Vec<DataIdx>,
+}
+
+#[wasm_bindgen]
+impl DataModule {
+    /// Creates a read-only trace of `expression`, e.g. `(a - b) / c`, its variables bound to
+    /// `inputs` in the order they first appear in it. Unlike an expression trace it follows its
+    /// inputs, showing data loaded into them later, and can be an input of another one.
+    pub fn create_derived(
+        &mut self,
+        expression: &str,
+        inputs: &[DataIdx],
+    ) -> Result<DataIdx, JsValue> {
+        let parsed = Expression::parse(expression)
+            .map_err(|err| JsValue::from_str(&format!("Invalid expression: {}", err)))?;
+
+        if parsed.variables().len() != inputs.len() {
+            return Err(JsValue::from_str(&format!(
+                "{} reads {} traces, {} given",
+                expression,
+                parsed.variables().len(),
+                inputs.len()
+            )));
+        }
+
+        if let Some(handle) = inputs.iter().find(|h| !self.traces.contains_key(h)) {
+            return Err(JsValue::from_str(&format!(
+                "Invalid input handle {}",
+                handle
+            )));
+        }
+        let x_type = inputs
+            .first()
+            .map(|handle| self.traces[handle].x_type.clone())
+            .ok_or_else(|| JsValue::from_str("The expression reads no traces"))?;
+
+        let handle = self.create_trace(expression, &x_type);
+        self.traces.get_mut(&handle).unwrap().read_only = true;
+        self.derived.insert(
+            handle,
+            Derived {
+                expression: parsed,
+                inputs: inputs.to_vec(),
+            },
+        );
+        self.rebuild_derived(handle);
+
+        Ok(handle)
+    }
+
+    /// Recomputes the derived, resampled and rolling traces reading `handle`, directly or
+    /// through others. The module does so itself, call it after changing a trace by
+    /// `get_trace_mut`.
+    pub fn invalidate(&mut self, handle: DataIdx) {
+        self.revision += 1;
+
+        // dependents are created after their inputs, so handle order is dependency order
+        let mut dependents: Vec<DataIdx> = self
+            .derived
+            .keys()
+            .chain(self.rollings.keys())
+            .chain(self.resampled.keys())
+            .copied()
+            .collect();
+        dependents.sort_unstable();
+
+        let mut changed = vec![handle];
+        for dependent in dependents {
+            if let Some(derived) = self.derived.get(&dependent) {
+                if derived.inputs.iter().any(|input| changed.contains(input)) {
+                    self.rebuild_derived(dependent);
+                    changed.push(dependent);
+                }
+            } else if let Some(resampled) = self.resampled.get(&dependent) {
+                if changed.contains(&resampled.source) {
+                    self.rebuild_resampled(dependent);
+                    changed.push(dependent);
+                }
+            } else if changed.contains(&self.rollings[&dependent].source()) {
+                self.update_rolling(dependent);
+                changed.extend_from_slice(self.rollings[&dependent].outputs());
+            }
+        }
+    }
+}
+
+impl DataModule {
+    pub fn get_derived(&self, handle: DataIdx) -> Option<&Derived> {
+        self.derived.get(&handle)
+    }
+
+    /// Replaces the segment of a derived trace by one over the current segments of its inputs,
+    /// disposed inputs reading as empty
+    fn rebuild_derived(&mut self, handle: DataIdx) {
+        let Some(derived) = self.derived.get(&handle) else {
+            return;
+        };
+
+        let inputs = derived
+            .inputs
+            .iter()
+            .map(|input| {
+                self.traces
+                    .get(input)
+                    .map_or_else(Vec::new, |t| t.segments.clone())
+            })
+            .collect();
+        let segment: Rc<dyn Segment> =
+            Rc::new(ExpressionSegment::new(derived.expression.clone(), inputs));
+
+        if let Some(trace) = self.traces.get_mut(&handle) {
+            trace.segments = vec![segment];
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Sampling statistics of a trace, explaining charts that look odd
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{data::DataIdx, prelude::*};
+
+use super::DataModule;
+
+/// Gaps reported at most, the widest ones
+const MAX_GAPS: usize = 5;
+/// How many typical intervals an interval has to span to count as a gap
+const GAP_FACTOR: RangePrec = 1.5;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+pub struct Gap {
+    pub from: RangePrec,
+    pub to: RangePrec,
+    pub length: RangePrec,
+}
+
+#[derive(Serialize, Clone, PartialEq, Debug, Default)]
+pub struct Diagnosis {
+    pub points: usize,
+    /// Median distance between consecutive increasing x values
+    pub interval: Option<RangePrec>,
+    /// Standard deviation of the distances between consecutive increasing x values
+    pub jitter: Option<RangePrec>,
+    /// Widest intervals longer than the typical one, widest first
+    pub gaps: Vec<Gap>,
+    /// Points at the same x as their predecessor
+    pub duplicates: usize,
+    /// Points at a smaller x than their predecessor, and the x of the first such point
+    pub out_of_order: usize,
+    pub first_out_of_order: Option<RangePrec>,
+    /// Points whose x or y is NaN or infinite
+    pub non_finite: usize,
+}
+
+/// Diagnoses the points in the order they are stored
+pub fn diagnose(points: impl Iterator<Item = (RangePrec, RangePrec)>) -> Diagnosis {
+    let mut diagnosis = Diagnosis::default();
+    let mut intervals: Vec<(RangePrec, RangePrec)> = Vec::new();
+    let mut last: Option<RangePrec> = None;
+
+    for (x, y) in points {
+        diagnosis.points += 1;
+
+        if !x.is_finite() || !y.is_finite() {
+            diagnosis.non_finite += 1;
+        }
+        if !x.is_finite() {
+            continue;
+        }
+
+        if let Some(prev) = last {
+            if x > prev {
+                intervals.push((prev, x));
+            } else if x == prev {
+                diagnosis.duplicates += 1;
+            } else {
+                diagnosis.out_of_order += 1;
+                diagnosis.first_out_of_order.get_or_insert(x);
+            }
+        }
+
+        last = Some(x);
+    }
+
+    if intervals.is_empty() {
+        return diagnosis;
+    }
+
+    let mut lengths: Vec<RangePrec> = intervals.iter().map(|(a, b)| b - a).collect();
+    let mean = lengths.iter().sum::<RangePrec>() / lengths.len() as RangePrec;
+    let variance = lengths
+        .iter()
+        .map(|l| (l - mean).powi(2))
+        .sum::<RangePrec>()
+        / lengths.len() as RangePrec;
+
+    lengths.sort_by(RangePrec::total_cmp);
+    let interval = lengths[(lengths.len() - 1) / 2];
+
+    diagnosis.interval = Some(interval);
+    diagnosis.jitter = Some(variance.sqrt());
+
+    let mut gaps: Vec<Gap> = intervals
+        .into_iter()
+        .filter(|(a, b)| b - a > interval * GAP_FACTOR)
+        .map(|(from, to)| Gap {
+            from,
+            to,
+            length: to - from,
+        })
+        .collect();
+    gaps.sort_by(|a, b| b.length.total_cmp(&a.length));
+    gaps.truncate(MAX_GAPS);
+    diagnosis.gaps = gaps;
+
+    diagnosis
+}
+
+#[wasm_bindgen]
+impl DataModule {
+    /// Reports the typical sample interval of a trace, its jitter, the largest gaps and the
+    /// duplicate, out of order and non finite points, `null` for unknown handles
+    pub fn diagnose(&self, handle: DataIdx) -> JsValue {
+        let Some(trace) = self.get_trace(handle) else {
+            return JsValue::NULL;
+        };
+
+        // every stored point in storage order, out of order ones included
+        let points = trace
+            .segments
+            .iter()
+            .flat_map(|s| s.iter_high_prec(RangePrec::MIN, RangePrec::MAX));
+
+        serde_wasm_bindgen::to_value(&diagnose(points)).unwrap()
+    }
+}
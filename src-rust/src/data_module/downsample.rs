@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{parallel, prelude::*};
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Downsampling {
+    None = 0,
+    /// Keeps the first, last, minimal and maximal point of every bucket, preserving extrema
+    M4 = 1,
+    /// Largest-Triangle-Three-Buckets, one visually significant point per bucket
+    Lttb = 2,
+}
+
+/// How faithfully drawn data follows the data it was decimated from, from best to worst
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Fidelity {
+    /// Every point is drawn
+    Exact,
+    /// Points are dropped, but the extrema of every bucket are kept
+    MinMax,
+    /// Any point may be dropped, extrema included
+    Lossy,
+}
+
+impl Fidelity {
+    /// Fidelity of data reduced by `method`, `reduced` telling whether any points were dropped
+    pub fn of(method: Downsampling, reduced: bool) -> Self {
+        match (method, reduced) {
+            (_, false) | (Downsampling::None, _) => Fidelity::Exact,
+            (Downsampling::M4, _) => Fidelity::MinMax,
+            (Downsampling::Lttb, _) => Fidelity::Lossy,
+        }
+    }
+}
+
+/// Fidelity of a drawn trace, with the error measured when it was asked for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Decimation {
+    pub fidelity: Fidelity,
+    pub max_error: Option<DataPrec>,
+}
+
+impl Decimation {
+    pub const EXACT: Decimation = Decimation {
+        fidelity: Fidelity::Exact,
+        max_error: Some(0.0),
+    };
+}
+
+/// Largest vertical distance of an `original` point from the line through `decimated`, both
+/// sorted by x. Points outside of the decimated line are compared to its nearest end.
+pub fn max_error(
+    original: &[(DataPrec, DataPrec)],
+    decimated: &[(DataPrec, DataPrec)],
+) -> DataPrec {
+    if decimated.is_empty() {
+        return 0.0;
+    }
+
+    let mut i = 0;
+    let mut max: DataPrec = 0.0;
+
+    for &(x, y) in original {
+        while i + 1 < decimated.len() && decimated[i + 1].0 <= x {
+            i += 1;
+        }
+
+        let (x0, y0) = decimated[i];
+        let line = match decimated.get(i + 1) {
+            Some(&(x1, y1)) if x > x0 && x1 > x0 => y0 + (y1 - y0) * (x - x0) / (x1 - x0),
+            _ => y0,
+        };
+
+        max = max.max((y - line).abs());
+    }
+
+    max
+}
+
+/// Reduces `data` spanning `from..to` to roughly `buckets` horizontal buckets
+pub fn downsample(
+    data: Vec<(DataPrec, DataPrec)>,
+    method: Downsampling,
+    from: DataPrec,
+    to: DataPrec,
+    buckets: usize,
+) -> Vec<(DataPrec, DataPrec)> {
+    if buckets == 0 || data.len() <= 4 * buckets {
+        return data;
+    }
+
+    match method {
+        Downsampling::None => data,
+        Downsampling::M4 => m4(&data, from, to, buckets),
+        Downsampling::Lttb => lttb(&data, buckets + 2),
+    }
+}
+
+pub fn m4(
+    data: &[(DataPrec, DataPrec)],
+    from: DataPrec,
+    to: DataPrec,
+    buckets: usize,
+) -> Vec<(DataPrec, DataPrec)> {
+    let width = (to - from) / buckets as DataPrec;
+    let bucket_of = |x: DataPrec| (((x - from) / width) as usize).min(buckets - 1);
+
+    // the parts start at the first point of a bucket, so they reduce to what the whole would
+    let parts = parallel::split(data.len(), parallel::MIN_PART, |i| {
+        bucket_of(data[i].0) != bucket_of(data[i - 1].0)
+    });
+
+    parallel::map(&parts, |part| m4_buckets(&data[part], bucket_of)).concat()
+}
+
+/// First, last, minimal and maximal point of each run of `data` in the same bucket
+fn m4_buckets(
+    data: &[(DataPrec, DataPrec)],
+    bucket_of: impl Fn(DataPrec) -> usize,
+) -> Vec<(DataPrec, DataPrec)> {
+    let mut out = Vec::new();
+    let mut start = 0;
+
+    while start < data.len() {
+        let bucket = bucket_of(data[start].0);
+
+        let (mut min, mut max) = (start, start);
+        let mut end = start + 1;
+
+        while end < data.len() && bucket_of(data[end].0) == bucket {
+            if data[end].1 < data[min].1 {
+                min = end;
+            }
+            if data[end].1 > data[max].1 {
+                max = end;
+            }
+
+            end += 1;
+        }
+
+        let mut picked = [start, min, max, end - 1];
+        picked.sort_unstable();
+
+        let mut last = None;
+        for i in picked {
+            if last != Some(i) {
+                out.push(data[i]);
+                last = Some(i);
+            }
+        }
+
+        start = end;
+    }
+
+    out
+}
+
+pub fn lttb(data: &[(DataPrec, DataPrec)], threshold: usize) -> Vec<(DataPrec, DataPrec)> {
+    if threshold < 3 || data.len() <= threshold {
+        return data.to_vec();
+    }
+
+    let mut out = Vec::with_capacity(threshold);
+    let every = (data.len() - 2) as f64 / (threshold - 2) as f64;
+
+    let mut a = 0;
+    out.push(data[0]);
+
+    for i in 0..(threshold - 2) {
+        let next_start = ((i + 1) as f64 * every) as usize + 1;
+        let next_end = (((i + 2) as f64 * every) as usize + 1).min(data.len());
+
+        let (avg_x, avg_y) = {
+            let next = &data[next_start..next_end.max(next_start + 1).min(data.len())];
+            let len = next.len() as f64;
+            let (sx, sy) = next.iter().fold((0.0, 0.0), |(sx, sy), &(x, y)| {
+                (sx + x as f64, sy + y as f64)
+            });
+
+            (sx / len, sy / len)
+        };
+
+        let start = (i as f64 * every) as usize + 1;
+        let end = next_start;
+
+        let (ax, ay) = (data[a].0 as f64, data[a].1 as f64);
+        let mut best = start;
+        let mut best_area = -1.0;
+
+        for (j, &(x, y)) in data.iter().enumerate().take(end).skip(start) {
+            let area = ((ax - avg_x) * (y as f64 - ay) - (ax - x as f64) * (avg_y - ay)).abs();
+
+            if area > best_area {
+                best_area = area;
+                best = j;
+            }
+        }
+
+        out.push(data[best]);
+        a = best;
+    }
+
+    out.push(data[data.len() - 1]);
+
+    out
+}
@@ -0,0 +1,78 @@
+//! Series aggregated per bucket by the backend, kept as their min, max and mean traces
+
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    data::DataIdx,
+    prelude::*,
+    trace::{Segment, SharedSegment},
+};
+
+use super::DataModule;
+
+/// Traces of an envelope, all sharing their x values
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Envelope {
+    pub mean: DataIdx,
+    pub min: DataIdx,
+    pub max: DataIdx,
+}
+
+#[wasm_bindgen]
+impl DataModule {
+    /// Creates an envelope of per bucket aggregates, e.g. of a continuous aggregate or a range
+    /// query with a step. The returned handle is its mean, with the min and max traces named
+    /// `id.min` and `id.max` behind it.
+    pub fn create_envelope_trace(&mut self, id: &str, x_type: &str) -> DataIdx {
+        let mean = self.create_trace(id, x_type);
+        let min = self.create_trace(&format!("{}.min", id), x_type);
+        let max = self.create_trace(&format!("{}.max", id), x_type);
+
+        self.envelopes.insert(mean, Envelope { mean, min, max });
+
+        mean
+    }
+
+    /// Loads buckets starting at `x` into an envelope, the columns being as long as `x`
+    pub fn load_envelope(
+        &mut self,
+        handle: DataIdx,
+        x: Vec<RangePrec>,
+        min: Vec<RangePrec>,
+        max: Vec<RangePrec>,
+        mean: Vec<RangePrec>,
+    ) -> Result<(), JsValue> {
+        let envelope = self
+            .get_envelope(handle)
+            .ok_or_else(|| JsValue::from_str(&format!("Handle {} is not an envelope", handle)))?;
+
+        if [&min, &max, &mean].iter().any(|c| c.len() != x.len()) {
+            return Err(JsValue::from_str(
+                "The envelope columns differ in length from x",
+            ));
+        }
+
+        // the columns are stored as they are, sharing their x
+        let x = Rc::new(x);
+        for (trace, y) in [
+            (envelope.min, min),
+            (envelope.max, max),
+            (envelope.mean, mean),
+        ] {
+            let segment: Rc<dyn Segment> = Rc::new(SharedSegment::new(x.clone(), Rc::new(y)));
+            self.traces.get_mut(&trace).unwrap().push_segment(segment);
+            self.invalidate(trace);
+        }
+
+        Ok(())
+    }
+}
+
+impl DataModule {
+    /// Envelope whose mean is `handle`
+    pub fn get_envelope(&self, handle: DataIdx) -> Option<Envelope> {
+        self.envelopes.get(&handle).copied()
+    }
+}
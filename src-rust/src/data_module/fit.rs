@@ -0,0 +1,143 @@
+//! Ranges showing traces, e.g. for resetting the zoom or autoscaling
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    data::DataIdx,
+    prelude::*,
+    renderers::ticks,
+    structs::{View, ViewLock},
+};
+
+use super::DataModule;
+
+/// View spanning all of the `(x, y)` extents given as `(from, to)` pairs. The y range grows by
+/// `padding` times its height on both sides, with `include_zero` first reaching down or up
+/// to zero and with `nice_bounds` then widened to the nearest ticks. A range without height
+/// is inflated as `ticks::inflate` does. The x range spans the data exactly. Axes held by `lock`
+/// keep their ranges.
+pub fn fit_view(
+    extents: impl IntoIterator<Item = ((RangePrec, RangePrec), (RangePrec, RangePrec))>,
+    padding: RangePrec,
+    include_zero: bool,
+    nice_bounds: bool,
+    lock: &ViewLock,
+) -> Option<View> {
+    let (x, y) = extents
+        .into_iter()
+        .filter(|((x0, x1), (y0, y1))| x0 <= x1 && y0 <= y1)
+        .reduce(|(x, y), (ex, ey)| (union(x, ex), union(y, ey)))?;
+
+    Some(lock.apply(View::new(x, fit_y(y, padding, include_zero, nice_bounds))))
+}
+
+/// Padded y range of the traces of `extents` in the visible window, given as `(min, max)`,
+/// snapped outwards to the nearest ticks. `padding_pct` is the percentage of the range's
+/// height added on both sides.
+pub fn suggest_range(
+    extents: impl IntoIterator<Item = (RangePrec, RangePrec)>,
+    padding_pct: RangePrec,
+) -> Option<(RangePrec, RangePrec)> {
+    let y = extents
+        .into_iter()
+        .filter(|(min, max)| min <= max)
+        .reduce(union)?;
+
+    Some(fit_y(y, padding_pct / 100.0, false, true))
+}
+
+/// `(y_from, y_to)` grown as described by `fit_view`
+fn fit_y(
+    (mut y_from, mut y_to): (RangePrec, RangePrec),
+    padding: RangePrec,
+    include_zero: bool,
+    nice_bounds: bool,
+) -> (RangePrec, RangePrec) {
+    if include_zero {
+        y_from = y_from.min(0.0);
+        y_to = y_to.max(0.0);
+    }
+
+    let height = y_to - y_from;
+    if height > 0.0 {
+        y_from -= height * padding.max(0.0);
+        y_to += height * padding.max(0.0);
+    } else {
+        (y_from, y_to) = ticks::inflate(y_from, y_to);
+    }
+
+    match nice_bounds {
+        true => ticks::nice_bounds(y_from, y_to),
+        false => (y_from, y_to),
+    }
+}
+
+/// Range containing nothing, the identity of `union`
+const EMPTY: (RangePrec, RangePrec) = (RangePrec::MAX, RangePrec::MIN);
+
+fn union(a: (RangePrec, RangePrec), b: (RangePrec, RangePrec)) -> (RangePrec, RangePrec) {
+    (a.0.min(b.0), a.1.max(b.1))
+}
+
+#[wasm_bindgen]
+impl DataModule {
+    /// `{ x_from, x_to, y_from, y_to }` showing the whole of the traces of `handles`, to be
+    /// assigned to a `RenderJob`. See `fit_view` for the meaning of the options. `null` when
+    /// none of the traces hold finite values.
+    pub fn fit_view(
+        &self,
+        handles: &[DataIdx],
+        padding: RangePrec,
+        include_zero: bool,
+        nice_bounds: bool,
+        lock: &ViewLock,
+    ) -> JsValue {
+        let extents = handles
+            .iter()
+            .filter_map(|&handle| self.get_trace(handle))
+            .map(|trace| {
+                trace
+                    .segments
+                    .iter()
+                    .flat_map(|s| s.iter_high_prec(RangePrec::MIN, RangePrec::MAX))
+                    .filter(|(x, y)| x.is_finite() && y.is_finite())
+                    .fold((EMPTY, EMPTY), |(x, y), (px, py)| {
+                        (union(x, (px, px)), union(y, (py, py)))
+                    })
+            });
+
+        match fit_view(extents, padding, include_zero, nice_bounds, lock) {
+            Some(view) => serde_wasm_bindgen::to_value(&view).unwrap(),
+            None => JsValue::NULL,
+        }
+    }
+
+    /// `[y_from, y_to]` showing the traces of `handles` between `x_from` and `x_to` with
+    /// `padding_pct` percent of room above and below, snapped to the ticks. Traces listed in
+    /// `blacklist`, e.g. the ones hidden in the legend, are left out. `undefined` when no
+    /// trace has finite values in the window.
+    pub fn suggest_range(
+        &self,
+        handles: &[DataIdx],
+        x_from: RangePrec,
+        x_to: RangePrec,
+        padding_pct: RangePrec,
+        blacklist: &[DataIdx],
+    ) -> Option<Box<[RangePrec]>> {
+        let extents = handles
+            .iter()
+            .filter(|handle| !blacklist.contains(handle))
+            .filter_map(|&handle| self.get_trace(handle))
+            .map(|trace| {
+                trace
+                    .get_data_high_prec(x_from, x_to)
+                    .map(|p| p.1)
+                    .filter(|y| y.is_finite())
+                    .fold(EMPTY, |y, py| union(y, (py, py)))
+            });
+
+        let (from, to) = suggest_range(extents, padding_pct)?;
+
+        Some(Box::new([from, to]))
+    }
+}
@@ -0,0 +1,75 @@
+//! Rules breaking the lines of traces at dropouts instead of bridging them
+
+use wasm_bindgen::prelude::*;
+
+use crate::{data::DataIdx, prelude::*};
+
+use super::DataModule;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GapRules {
+    /// Ends the line at missing values, which are drawn as they are otherwise
+    pub break_on_nan: bool,
+    /// Ends the line between points further apart in x, `0` never does
+    pub max_x_gap: RangePrec,
+}
+
+impl GapRules {
+    pub fn new(break_on_nan: bool, max_x_gap: RangePrec) -> Self {
+        Self {
+            break_on_nan,
+            max_x_gap,
+        }
+    }
+
+    /// Drops the missing values of `points` when breaking at them, returning the indices of
+    /// the points starting a new line. `previous` is the point the line comes from, if any.
+    pub fn split(
+        &self,
+        points: &mut Vec<(DataPrec, DataPrec)>,
+        previous: Option<(DataPrec, DataPrec)>,
+    ) -> Vec<usize> {
+        let mut breaks = Vec::new();
+        let mut last = previous.map(|p| p.0);
+        let mut missing = false;
+        let mut kept = 0;
+
+        for i in 0..points.len() {
+            let (x, y) = points[i];
+            if self.break_on_nan && y.is_nan() {
+                missing = last.is_some();
+                continue;
+            }
+
+            let jump = last.is_some_and(|last| {
+                self.max_x_gap > 0.0 && (x - last) as RangePrec > self.max_x_gap
+            });
+            if missing || jump {
+                breaks.push(kept);
+            }
+
+            missing = false;
+            last = Some(x);
+            points[kept] = (x, y);
+            kept += 1;
+        }
+        points.truncate(kept);
+
+        breaks
+    }
+}
+
+#[wasm_bindgen]
+impl DataModule {
+    /// Breaks the line of a trace at missing values with `break_on_nan`, and between points
+    /// more than `max_x_gap` x units apart unless it's `0`. Applies to bundles made after.
+    pub fn set_gap_rules(&mut self, handle: DataIdx, break_on_nan: bool, max_x_gap: RangePrec) {
+        self.gap_rules
+            .insert(handle, GapRules::new(break_on_nan, max_x_gap));
+    }
+
+    pub fn get_gap_rules(&self, handle: DataIdx) -> GapRules {
+        self.gap_rules.get(&handle).copied().unwrap_or_default()
+    }
+}
@@ -0,0 +1,356 @@
+//! InfluxDB line protocol and annotated CSV import, every measurement, field and tag set
+//! becoming a trace
+
+use std::{collections::BTreeMap, rc::Rc};
+
+use chrono::DateTime;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    data::DataIdx,
+    prelude::*,
+    trace::{Segment, SharedSegment},
+};
+
+use super::{csv::split_fields, DataModule};
+
+/// Columns of annotated CSV that aren't tags even when grouped by
+const RESERVED_COLUMNS: [&str; 3] = ["", "result", "table"];
+
+/// Series of a measurement field with one set of tags, `x` in epoch milliseconds
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct InfluxSeries {
+    pub name: String,
+    pub measurement: String,
+    pub field: String,
+    pub tags: BTreeMap<String, String>,
+    #[serde(skip)]
+    pub x: Vec<RangePrec>,
+    #[serde(skip)]
+    pub y: Vec<RangePrec>,
+}
+
+/// Series being collected under their names, e.g. `cpu.usage_user{host="a"}`
+#[derive(Default)]
+struct Collector(BTreeMap<String, InfluxSeries>);
+
+impl Collector {
+    fn push(
+        &mut self,
+        measurement: &str,
+        field: &str,
+        tags: &BTreeMap<String, String>,
+        point: (RangePrec, RangePrec),
+    ) {
+        let tag_list: Vec<_> = tags.iter().map(|(k, v)| format!("{}={:?}", k, v)).collect();
+        let name = match tag_list.is_empty() {
+            true => format!("{}.{}", measurement, field),
+            false => format!("{}.{}{{{}}}", measurement, field, tag_list.join(", ")),
+        };
+
+        let series = self.0.entry(name.clone()).or_insert_with(|| InfluxSeries {
+            name,
+            measurement: measurement.to_string(),
+            field: field.to_string(),
+            tags: tags.clone(),
+            x: Vec::new(),
+            y: Vec::new(),
+        });
+        series.x.push(point.0);
+        series.y.push(point.1);
+    }
+
+    /// Series with their points ordered by x, writes needn't come in order
+    fn finish(self) -> Vec<InfluxSeries> {
+        self.0
+            .into_values()
+            .map(|mut series| {
+                let mut points: Vec<_> = series.x.iter().copied().zip(series.y).collect();
+                points.sort_by(|a, b| a.0.total_cmp(&b.0));
+                (series.x, series.y) = points.into_iter().unzip();
+
+                series
+            })
+            .collect()
+    }
+}
+
+/// Splits at `separator`s that are neither escaped by a backslash nor in a quoted string
+fn split_unescaped(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut start, mut escaped, mut quoted) = (0, false, false);
+
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+
+    parts
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.extend(chars.next()),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// `key=value` with both unescaped
+fn key_value(pair: &str) -> Option<(String, &str)> {
+    match split_unescaped(pair, '=')[..] {
+        [key, value] if !key.is_empty() => Some((unescape(key), value)),
+        _ => None,
+    }
+}
+
+/// Value of a line protocol field, `None` for strings, booleans counting as 0 and 1
+fn field_value(value: &str) -> Result<Option<RangePrec>, ()> {
+    if value.starts_with('"') {
+        return Ok(None);
+    }
+
+    match value {
+        "t" | "T" | "true" | "True" | "TRUE" => return Ok(Some(1.0)),
+        "f" | "F" | "false" | "False" | "FALSE" => return Ok(Some(0.0)),
+        _ => {}
+    }
+
+    match value.strip_suffix(['i', 'u']) {
+        Some(integer) => integer.parse::<i128>().map(|v| v as RangePrec).ok(),
+        None => value.parse::<RangePrec>().ok(),
+    }
+    .map(Some)
+    .ok_or(())
+}
+
+/// Series of line protocol, timestamps being in `precision`, one of `ns`, `us`, `ms` and `s`.
+/// Every line needs its timestamp, string fields are skipped.
+pub fn parse_line_protocol(text: &str, precision: &str) -> Result<Vec<InfluxSeries>, String> {
+    let millis_per_unit = match precision {
+        "ns" => 1e-6,
+        "us" => 1e-3,
+        "ms" => 1.0,
+        "s" => 1e3,
+        _ => return Err(format!("Unknown timestamp precision {}", precision)),
+    };
+    let mut collector = Collector::default();
+
+    for (n, line) in text.lines().enumerate().map(|(n, l)| (n + 1, l.trim())) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let malformed = || format!("Line {} is malformed", n);
+        let parts: Vec<_> = split_unescaped(line, ' ')
+            .into_iter()
+            .filter(|p| !p.is_empty())
+            .collect();
+        let (key, fields, timestamp) = match parts[..] {
+            [key, fields, timestamp] => (key, fields, timestamp),
+            [_, _] => return Err(format!("Line {} has no timestamp", n)),
+            _ => return Err(malformed()),
+        };
+
+        let x = timestamp.parse::<i64>().map_err(|_| malformed())? as RangePrec * millis_per_unit;
+
+        let mut key = split_unescaped(key, ',').into_iter();
+        let measurement = unescape(key.next().unwrap_or_default());
+        if measurement.is_empty() {
+            return Err(malformed());
+        }
+        let tags = key
+            .map(|tag| key_value(tag).map(|(k, v)| (k, unescape(v))))
+            .collect::<Option<BTreeMap<_, _>>>()
+            .ok_or_else(malformed)?;
+
+        for field in split_unescaped(fields, ',') {
+            let (field, value) = key_value(field).ok_or_else(malformed)?;
+
+            if let Some(y) = field_value(value).map_err(|_| malformed())? {
+                collector.push(&measurement, &field, &tags, (x, y));
+            }
+        }
+    }
+
+    Ok(collector.finish())
+}
+
+/// Annotations and header of the table of annotated CSV being read
+#[derive(Default)]
+struct Table {
+    datatypes: Vec<String>,
+    groups: Vec<String>,
+    defaults: Vec<String>,
+    header: Option<Vec<String>>,
+}
+
+impl Table {
+    fn column(&self, name: &str) -> Option<usize> {
+        self.header.as_ref()?.iter().position(|c| c == name)
+    }
+
+    fn field<'a>(&'a self, row: &'a [String], i: usize) -> &'a str {
+        match row.get(i).map(String::as_str) {
+            Some("") | None => self.defaults.get(i).map_or("", String::as_str),
+            Some(field) => field,
+        }
+    }
+
+    /// Grouped columns that aren't one of the `_` prefixed ones, or all of those if the
+    /// table isn't annotated with its grouping
+    fn tag_columns(&self) -> Vec<usize> {
+        let header = self.header.as_deref().unwrap_or_default();
+
+        (0..header.len())
+            .filter(|&i| !header[i].starts_with('_') && !RESERVED_COLUMNS.contains(&&*header[i]))
+            .filter(|&i| self.groups.get(i).is_none_or(|g| g == "true"))
+            .collect()
+    }
+}
+
+/// Series of annotated CSV as written by Flux queries, tables being separated by empty lines.
+/// Rows need their `_time` and `_value`, string values are skipped.
+pub fn parse_annotated_csv(text: &str) -> Result<Vec<InfluxSeries>, String> {
+    let mut collector = Collector::default();
+    let mut table = Table::default();
+    let mut found_values = false;
+
+    for line in text.lines().map(|l| l.trim_end_matches('\r')) {
+        if line.trim().is_empty() {
+            table = Table::default();
+            continue;
+        }
+
+        let row = split_fields(line, ',');
+        match row[0].as_str() {
+            "#datatype" => table.datatypes = row,
+            "#group" => table.groups = row,
+            "#default" => table.defaults = row,
+            annotation if annotation.starts_with('#') => {}
+            _ if table.header.is_none() => table.header = Some(row),
+            _ => {
+                if let Some(error) = table.column("error") {
+                    return Err(format!("Flux query failed: {}", table.field(&row, error)));
+                }
+
+                let (Some(time), Some(value)) = (table.column("_time"), table.column("_value"))
+                else {
+                    continue;
+                };
+                found_values = true;
+
+                let y = match table.datatypes.get(value).map(String::as_str) {
+                    Some("string") => continue,
+                    Some("boolean") => match table.field(&row, value) {
+                        "true" => 1.0,
+                        "false" => 0.0,
+                        _ => continue,
+                    },
+                    _ => match table.field(&row, value).parse::<RangePrec>() {
+                        Ok(y) => y,
+                        Err(_) => continue,
+                    },
+                };
+                let Ok(x) = DateTime::parse_from_rfc3339(table.field(&row, time)) else {
+                    continue;
+                };
+
+                let tags = table
+                    .tag_columns()
+                    .into_iter()
+                    .map(|i| {
+                        let name = table.header.as_ref().unwrap()[i].clone();
+                        (name, table.field(&row, i).to_string())
+                    })
+                    .collect();
+                let measurement = table
+                    .column("_measurement")
+                    .map_or("", |i| table.field(&row, i));
+                let field = table
+                    .column("_field")
+                    .map_or("_value", |i| table.field(&row, i));
+
+                collector.push(
+                    measurement,
+                    field,
+                    &tags,
+                    (x.timestamp_micros() as RangePrec / 1e3, y),
+                );
+            }
+        }
+    }
+
+    if !found_values {
+        return Err("No table has both a _time and a _value column".to_string());
+    }
+
+    Ok(collector.finish())
+}
+
+#[wasm_bindgen]
+impl DataModule {
+    /// Imports InfluxDB line protocol with timestamps in `precision`, one of `ns`, `us`, `ms`
+    /// and `s`. See `import_annotated_csv` for where the series go and what's returned.
+    pub fn import_line_protocol(
+        &mut self,
+        text: &str,
+        precision: &str,
+    ) -> Result<JsValue, JsValue> {
+        let series = parse_line_protocol(text, precision).map_err(|e| JsValue::from_str(&e))?;
+
+        self.load_influx(series)
+    }
+
+    /// Imports the annotated CSV of Flux query results, loading every measurement field and
+    /// tag set into the writable `DateTime` trace named like `cpu.usage_user{host="a"}` or a
+    /// newly created one. Returns an array of `{ handle, name, measurement, field, tags }`.
+    pub fn import_annotated_csv(&mut self, text: &str) -> Result<JsValue, JsValue> {
+        let series = parse_annotated_csv(text).map_err(|e| JsValue::from_str(&e))?;
+
+        self.load_influx(series)
+    }
+}
+
+/// Trace a series was loaded into
+#[derive(Serialize)]
+struct ImportedSeries {
+    handle: DataIdx,
+    #[serde(flatten)]
+    series: InfluxSeries,
+}
+
+impl DataModule {
+    fn load_influx(&mut self, series: Vec<InfluxSeries>) -> Result<JsValue, JsValue> {
+        let mut imported = Vec::with_capacity(series.len());
+
+        for mut series in series {
+            let handle = self.trace_for_column(&series.name, "DateTime");
+            let (x, y) = (std::mem::take(&mut series.x), std::mem::take(&mut series.y));
+
+            let segment: Rc<dyn Segment> = Rc::new(SharedSegment::new(Rc::new(x), Rc::new(y)));
+            self.traces.get_mut(&handle).unwrap().push_segment(segment);
+            self.invalidate(handle);
+            imported.push(ImportedSeries { handle, series });
+        }
+
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        Ok(imported.serialize(&serializer)?)
+    }
+}
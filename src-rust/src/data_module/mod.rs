@@ -1,21 +1,85 @@
 use std::{collections::HashMap, rc::Rc};
 
-use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
 
 use crate::{
     data::{DataIdx, TypeDescriptor},
     prelude::*,
     structs::MetaCounter,
-    trace::{SharedSegment, TraceDescriptor, TraceMetas},
+    trace::{ImplicitSegment, RollingTrace, Segment, SharedSegment, TraceDescriptor, TraceMetas},
 };
 
+#[cfg(feature = "arrow")]
+mod arrow;
+mod checksum;
+mod csv;
+mod derived;
+mod diagnose;
+mod downsample;
+mod envelope;
+mod fit;
+mod gaps;
+mod influx;
+mod ohlc;
+mod playback;
+#[cfg(feature = "prometheus")]
+mod prometheus;
+mod resample;
+mod rolling;
+mod runs;
+mod sonify;
+mod stats;
+mod tiles;
 mod traceops;
+mod validation;
+
+#[cfg(feature = "arrow")]
+pub use arrow::*;
+pub use checksum::*;
+pub use csv::*;
+pub use derived::*;
+pub use diagnose::*;
+pub use downsample::*;
+pub use envelope::*;
+pub use fit::*;
+pub use gaps::*;
+pub use influx::*;
+pub use ohlc::*;
+pub use playback::*;
+#[cfg(feature = "prometheus")]
+pub use prometheus::*;
+pub use resample::*;
+pub use rolling::*;
+pub use runs::*;
+pub use sonify::*;
+pub use stats::*;
+pub use tiles::*;
+pub use validation::*;
 
 #[wasm_bindgen]
 #[derive(Default)]
 pub struct DataModule {
     next_handle: DataIdx,
     traces: HashMap<DataIdx, TraceDescriptor>,
+    ohlc_traces: HashMap<DataIdx, OhlcTrace>,
+    /// Levels of detail restored by `import_tiles`
+    pyramids: HashMap<DataIdx, Pyramid>,
+    /// Envelopes under the handles of their means
+    envelopes: HashMap<DataIdx, Envelope>,
+    /// Expressions of the traces made by `create_derived`
+    derived: HashMap<DataIdx, Derived>,
+    /// Moving aggregates under the handles of their first output
+    rollings: HashMap<DataIdx, Rolling>,
+    /// Sources and grids of the traces made by `resample`
+    resampled: HashMap<DataIdx, Resampled>,
+    /// Where the lines of traces break, not bridging every gap if set
+    gap_rules: HashMap<DataIdx, GapRules>,
+    /// Playbacks under the handles of their first replay
+    playbacks: HashMap<DataIdx, Playback>,
+    /// Plausibility rules the appends to a trace are checked against
+    validators: HashMap<DataIdx, Validator>,
+    /// Bumped on every change to the traces
+    revision: u64,
 }
 
 #[wasm_bindgen]
@@ -34,6 +98,7 @@ impl DataModule {
             TraceDescriptor {
                 id: id.to_string(),
                 x_type: x_type.to_string(),
+                read_only: false,
 
                 segments: vec![],
             },
@@ -43,11 +108,140 @@ impl DataModule {
     }
 
     pub fn dispose_trace(&mut self, handle: usize) {
+        // the bounds of an envelope go with its mean
+        if let Some(envelope) = self.envelopes.remove(&handle) {
+            self.dispose_trace(envelope.min);
+            self.dispose_trace(envelope.max);
+        }
+
         self.traces.remove(&handle);
+        self.ohlc_traces.remove(&handle);
+        self.pyramids.remove(&handle);
+        self.derived.remove(&handle);
+        self.rollings.remove(&handle);
+        self.resampled.remove(&handle);
+        self.gap_rules.remove(&handle);
+        self.playbacks.remove(&handle);
+        self.validators.remove(&handle);
+        self.invalidate(handle);
+    }
+
+    /// Creates a live trace keeping at most `capacity` points no older than `window` x units
+    /// behind the newest one, a zero `window` only limiting the count
+    pub fn create_rolling_trace(
+        &mut self,
+        id: &str,
+        x_type: &str,
+        capacity: usize,
+        window: RangePrec,
+    ) -> DataIdx {
+        let handle = self.create_trace(id, x_type);
+        let rolling: Rc<dyn Segment> = Rc::new(RollingTrace::new(capacity, window));
+
+        self.traces.get_mut(&handle).unwrap().segments.push(rolling);
+
+        handle
+    }
+
+    /// Appends to a rolling trace, evicting its oldest points as needed. Points breaking the
+    /// trace's validation rules are reported and, if the rules say so, gapped.
+    pub fn push_rolling(&mut self, handle: DataIdx, x: &[RangePrec], y: &[RangePrec]) {
+        let mut points: Vec<_> = x.iter().copied().zip(y.iter().copied()).collect();
+        self.validate_append(handle, &mut points);
+
+        let trace = self
+            .traces
+            .get_mut(&handle)
+            .unwrap_or_else(|| panic!("Handle {} is invalid", handle));
+        let segment = trace
+            .segments
+            .first_mut()
+            .unwrap_or_else(|| panic!("Handle {} is not a rolling trace", handle));
+
+        // pinned references keep the points they've been pinned with
+        if !dyn_clone::rc_make_mut(segment).append(&points) {
+            panic!("Handle {} is not a rolling trace", handle);
+        }
+
+        self.invalidate(handle);
+    }
+
+    /// Loads regularly sampled values, e.g. audio or ADC captures, without an x column. The
+    /// x of the i-th value is `start + i * dx`, generated when the trace is read.
+    pub fn load_implicit(
+        &mut self,
+        handle: DataIdx,
+        start: RangePrec,
+        dx: RangePrec,
+        y: Vec<f64>,
+    ) -> JsValue {
+        let mut counter = MetaCounter::new(1);
+        y.iter().for_each(|&val| counter.add(0, val));
+
+        let mut metas = counter.iter_metas().next().unwrap();
+        metas.handle = handle;
+
+        match self.traces.get_mut(&handle) {
+            Some(trace) if trace.read_only => {
+                panic!("Handle {} is a read-only reference", handle);
+            }
+            Some(trace) => trace.push_segment(
+                Rc::new(ImplicitSegment::new(start, dx, Rc::new(y))) as Rc<dyn Segment>
+            ),
+            None => {
+                panic!("Handle {} is invalid", handle);
+            }
+        };
+        self.invalidate(handle);
+
+        serde_wasm_bindgen::to_value(&metas).unwrap()
+    }
+
+    /// Creates a trace of OHLC candles, which bundles draw as candlesticks
+    pub fn create_ohlc_trace(&mut self, id: &str) -> DataIdx {
+        let handle = self.next_handle;
+        self.next_handle += 1;
+
+        self.ohlc_traces.insert(handle, OhlcTrace::new(id));
+
+        handle
+    }
+
+    /// Loads candles into an OHLC trace, `values` holding open, high, low and close for each x
+    pub fn load_ohlc(&mut self, handle: DataIdx, x: &[RangePrec], values: &[RangePrec]) {
+        self.ohlc_traces
+            .get_mut(&handle)
+            .unwrap_or_else(|| panic!("Handle {} is not an OHLC trace", handle))
+            .load(x, values);
+        self.revision += 1;
+    }
+
+    /// Pins a trace of another module, e.g. a benchmark index, as a read-only reference
+    /// trace of this one. The segments are shared with `source`, nothing is copied.
+    pub fn pin_reference(&mut self, source: &DataModule, handle: DataIdx) -> DataIdx {
+        let trace = source
+            .get_trace(handle)
+            .expect("Invalid source handle in pin_reference");
+
+        let pinned = self.next_handle;
+        self.next_handle += 1;
+
+        self.traces.insert(
+            pinned,
+            TraceDescriptor {
+                id: trace.id.clone(),
+                x_type: trace.x_type.clone(),
+                read_only: true,
+
+                segments: trace.segments.clone(),
+            },
+        );
+
+        pinned
     }
 
     pub fn print_data_as_csv(&self, ptrs: &[DataIdx], from: RangePrec, to: RangePrec) -> String {
-        use chrono::{DateTime, NaiveDateTime, Utc};
+        use chrono::DateTime;
         let mut output = String::new();
 
         let len = (to - from) as usize;
@@ -56,12 +250,10 @@ impl DataModule {
             let x = from + i as RangePrec;
 
             output.push_str(
-                &DateTime::<Utc>::from_utc(
-                    NaiveDateTime::from_timestamp_opt(x as i64 * 60, 0).unwrap(),
-                    Utc,
-                )
-                .format("%m/%d/%Y %H:%M:%S")
-                .to_string(),
+                &DateTime::from_timestamp(x as i64 * 60, 0)
+                    .unwrap()
+                    .format("%m/%d/%Y %H:%M:%S")
+                    .to_string(),
             );
 
             for (_, val) in self.get_data_at(ptrs, x) {
@@ -76,10 +268,43 @@ impl DataModule {
 }
 
 impl DataModule {
+    /// Changes whenever the points of any trace do, so renders can tell nothing changed
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
     pub fn get_trace(&self, handle: DataIdx) -> Option<&TraceDescriptor> {
         self.traces.get(&handle)
     }
 
+    pub fn get_ohlc_trace(&self, handle: DataIdx) -> Option<&OhlcTrace> {
+        self.ohlc_traces.get(&handle)
+    }
+
+    /// Writable trace with the id `name` for an imported column, created if there's none
+    fn trace_for_column(&mut self, name: &str, x_type: &str) -> DataIdx {
+        let existing = self
+            .traces
+            .iter()
+            .find(|(_, t)| t.id == name && !t.read_only)
+            .map(|(&handle, _)| handle);
+
+        existing.unwrap_or_else(|| self.create_trace(name, x_type))
+    }
+
+    /// Loads the points of the `x` and `y` columns into a writable trace, returning whether
+    /// there is one under `handle`
+    pub fn load_points(&mut self, handle: DataIdx, x: Vec<RangePrec>, y: Vec<RangePrec>) -> bool {
+        let Some(trace) = self.traces.get_mut(&handle).filter(|t| !t.read_only) else {
+            return false;
+        };
+
+        trace.push_segment(Rc::new(SharedSegment::new(Rc::new(x), Rc::new(y))) as Rc<dyn Segment>);
+        self.invalidate(handle);
+
+        true
+    }
+
     pub fn get_trace_mut(&mut self, handle: DataIdx) -> Option<&mut TraceDescriptor> {
         self.traces.get_mut(&handle)
     }
@@ -97,6 +322,45 @@ impl DataModule {
         })
     }
 
+    /// Data of a trace relative to `from`, reduced to `buckets` when downsampling is requested
+    pub fn get_downsampled_data(
+        &self,
+        handle: DataIdx,
+        from: RangePrec,
+        to: RangePrec,
+        method: Downsampling,
+        buckets: usize,
+    ) -> Option<Vec<(DataPrec, DataPrec)>> {
+        let data = self
+            .get_trace(handle)?
+            .get_data_with_origin(from, to, from, 0.0)
+            .collect();
+
+        Some(downsample(
+            data,
+            method,
+            0.0,
+            (to - from) as DataPrec,
+            buckets,
+        ))
+    }
+
+    /// Scale and offset turning the values of a trace into percent change relative to its
+    /// value at `x`, `None` when there is no usable reference value there
+    pub fn relative_transform(
+        &self,
+        handle: DataIdx,
+        x: RangePrec,
+    ) -> Option<(RangePrec, RangePrec)> {
+        let reference = self.get_trace(handle)?.get_data_at(x)?;
+
+        if reference == 0.0 || !reference.is_finite() {
+            return None;
+        }
+
+        Some((100.0 / reference, -100.0))
+    }
+
     pub fn bulkload_segments(
         &mut self,
         ptrs: &[DataIdx],
@@ -144,13 +408,17 @@ impl DataModule {
             metas.push(m);
 
             match self.traces.get_mut(handle) {
-                Some(trace) => {
-                    trace.push_segment(Box::new(SharedSegment::new(x.clone(), Rc::new(d))))
+                Some(trace) if trace.read_only => {
+                    panic!("Handle {} is a read-only reference", handle);
                 }
+                Some(trace) => trace.push_segment(
+                    Rc::new(SharedSegment::new(x.clone(), Rc::new(d))) as Rc<dyn Segment>,
+                ),
                 None => {
                     panic!("Handle {} is invalid", handle);
                 }
             };
+            self.invalidate(*handle);
         }
 
         metas
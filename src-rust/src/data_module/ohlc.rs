@@ -0,0 +1,105 @@
+use crate::prelude::*;
+
+/// Open, high, low and close value of a single period
+pub type Ohlc = [RangePrec; 4];
+
+/// Trace of OHLC candles sorted by x, one candle per x
+#[derive(Clone, Default)]
+pub struct OhlcTrace {
+    pub id: String,
+    x: Vec<RangePrec>,
+    values: Vec<Ohlc>,
+}
+
+impl OhlcTrace {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Merges in candles at `x`, `values` holding 4 values per x, replacing loaded candles
+    /// at the same x
+    pub fn load(&mut self, x: &[RangePrec], values: &[RangePrec]) {
+        let mut candles: Vec<(RangePrec, Ohlc)> = x
+            .iter()
+            .zip(values.chunks_exact(4))
+            .map(|(&x, v)| (x, [v[0], v[1], v[2], v[3]]))
+            .chain(self.x.iter().copied().zip(self.values.iter().copied()))
+            .collect();
+
+        // the sort is stable and the new candles come first, so they survive the dedup
+        candles.sort_by(|a, b| a.0.total_cmp(&b.0));
+        candles.dedup_by(|later, earlier| later.0 == earlier.0);
+
+        (self.x, self.values) = candles.into_iter().unzip();
+    }
+
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+
+    /// Candles between `from` and `to`, x being relative to `x_orig`
+    pub fn iter_with_origin(
+        &self,
+        from: RangePrec,
+        to: RangePrec,
+        x_orig: RangePrec,
+    ) -> impl Iterator<Item = (DataPrec, [DataPrec; 4])> + '_ {
+        let start = self.x.partition_point(|&x| x < from);
+        let end = self.x.partition_point(|&x| x <= to);
+
+        self.x[start..end]
+            .iter()
+            .zip(&self.values[start..end])
+            .map(move |(&x, v)| ((x - x_orig) as DataPrec, v.map(|v| v as DataPrec)))
+    }
+
+    /// Candle loaded exactly at `x`
+    pub fn candle_at(&self, x: RangePrec) -> Option<Ohlc> {
+        let i = self.x.partition_point(|&cx| cx < x);
+
+        (self.x.get(i) == Some(&x)).then(|| self.values[i])
+    }
+}
+
+/// Merges `candles` spanning `from..to` into at most `buckets` equally wide periods,
+/// each keeping the first open, the extremes and the last close
+pub fn aggregate_candles(
+    candles: &[(DataPrec, [DataPrec; 4])],
+    from: DataPrec,
+    to: DataPrec,
+    buckets: usize,
+) -> Vec<(DataPrec, [DataPrec; 4])> {
+    if buckets == 0 || candles.len() <= buckets {
+        return candles.to_vec();
+    }
+
+    let width = (to - from) / buckets as DataPrec;
+    let bucket_of = |x: DataPrec| (((x - from) / width) as usize).min(buckets - 1);
+
+    let mut out: Vec<(DataPrec, [DataPrec; 4])> = Vec::with_capacity(buckets);
+    let mut last_bucket = None;
+
+    for &(x, [open, high, low, close]) in candles {
+        let bucket = bucket_of(x);
+
+        match out.last_mut() {
+            Some((_, merged)) if last_bucket == Some(bucket) => {
+                merged[1] = merged[1].max(high);
+                merged[2] = merged[2].min(low);
+                merged[3] = close;
+            }
+            _ => out.push((x, [open, high, low, close])),
+        }
+
+        last_bucket = Some(bucket);
+    }
+
+    out
+}
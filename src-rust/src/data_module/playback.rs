@@ -0,0 +1,315 @@
+//! Replays of recorded traces, revealing their points over time as if they were being appended
+
+use std::rc::Rc;
+
+use js_sys::Function;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    data::DataIdx,
+    prelude::*,
+    trace::{RollingTrace, Segment},
+};
+
+use super::DataModule;
+
+/// Position of a playback between `from` and `to`, moving `speed` x units per millisecond
+/// while playing
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlaybackClock {
+    pub from: RangePrec,
+    pub to: RangePrec,
+    pub position: RangePrec,
+    pub speed: RangePrec,
+    pub looping: bool,
+    pub playing: bool,
+}
+
+/// What advancing a clock did, points past `after` up to the position being new unless it
+/// `restarted` from the beginning
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlaybackStep {
+    pub after: RangePrec,
+    pub restarted: bool,
+    pub ended: bool,
+}
+
+impl PlaybackClock {
+    pub fn new(from: RangePrec, to: RangePrec) -> Self {
+        Self {
+            from,
+            to,
+            position: from,
+            speed: 1.0,
+            looping: false,
+            playing: false,
+        }
+    }
+
+    /// Moves the position `elapsed` milliseconds on, wrapping around to `from` when looping
+    /// or else pausing at `to`
+    pub fn advance(&mut self, elapsed: RangePrec) -> PlaybackStep {
+        let after = self.position;
+        let mut step = PlaybackStep {
+            after,
+            restarted: false,
+            ended: false,
+        };
+        if !self.playing || elapsed.is_nan() || elapsed <= 0.0 {
+            return step;
+        }
+
+        self.position += elapsed * self.speed;
+        if self.position > self.to {
+            let span = self.to - self.from;
+
+            if self.looping && span > 0.0 {
+                self.position = self.from + (self.position - self.from) % span;
+                step.restarted = true;
+            } else {
+                self.position = self.to;
+                self.playing = false;
+                step.ended = true;
+            }
+        }
+
+        step
+    }
+}
+
+/// State of a playback reported to the host after every advance, `x_from` and `x_to` being the
+/// viewport following the playhead at `x`
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct PlaybackFrame {
+    pub x: RangePrec,
+    pub x_from: RangePrec,
+    pub x_to: RangePrec,
+    pub playing: bool,
+    pub restarted: bool,
+    pub ended: bool,
+}
+
+/// Recorded `sources` replayed into their `outputs`
+pub struct Playback {
+    sources: Vec<DataIdx>,
+    outputs: Vec<DataIdx>,
+    clock: PlaybackClock,
+    /// Width of the followed viewport, `0` showing everything played so far
+    window: RangePrec,
+    listener: Option<Function>,
+}
+
+impl Playback {
+    pub fn outputs(&self) -> &[DataIdx] {
+        &self.outputs
+    }
+
+    pub fn clock(&self) -> &PlaybackClock {
+        &self.clock
+    }
+
+    fn frame(&self, step: PlaybackStep) -> PlaybackFrame {
+        let x = self.clock.position;
+        let x_from = match self.window > 0.0 {
+            true => x - self.window,
+            false => self.clock.from,
+        };
+
+        PlaybackFrame {
+            x,
+            x_from,
+            x_to: x,
+            playing: self.clock.playing,
+            restarted: step.restarted,
+            ended: step.ended,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl DataModule {
+    /// Creates read-only traces replaying `handles`, starting paused at their first point.
+    /// Returns the handles of the replays, the first one standing for the whole playback.
+    /// `window` is the width of the viewport following the playhead, `0` showing everything
+    /// played so far.
+    pub fn create_playback(
+        &mut self,
+        handles: Vec<DataIdx>,
+        window: RangePrec,
+    ) -> Result<Vec<DataIdx>, JsValue> {
+        if handles.is_empty() {
+            return Err(JsValue::from_str("A playback needs at least one trace"));
+        }
+
+        let mut range = (RangePrec::INFINITY, RangePrec::NEG_INFINITY);
+        let mut sources = Vec::with_capacity(handles.len());
+        for &handle in &handles {
+            let trace = self
+                .get_trace(handle)
+                .ok_or_else(|| JsValue::from_str(&format!("Invalid trace handle {}", handle)))?;
+
+            for segment in &trace.segments {
+                range = (range.0.min(segment.from()), range.1.max(segment.to()));
+            }
+            sources.push((handle, format!("{} replay", trace.id), trace.x_type.clone()));
+        }
+        if range.0 > range.1 {
+            return Err(JsValue::from_str("The traces have no points to play"));
+        }
+
+        let outputs: Vec<_> = sources
+            .iter()
+            .map(|(_, id, x_type)| {
+                let output = self.create_trace(id, x_type);
+                self.traces.get_mut(&output).unwrap().read_only = true;
+
+                output
+            })
+            .collect();
+
+        self.playbacks.insert(
+            outputs[0],
+            Playback {
+                sources: sources.into_iter().map(|s| s.0).collect(),
+                outputs: outputs.clone(),
+                clock: PlaybackClock::new(range.0, range.1),
+                window: window.max(0.0),
+                listener: None,
+            },
+        );
+        self.replay(outputs[0], None);
+
+        Ok(outputs)
+    }
+
+    pub fn play(&mut self, handle: DataIdx) {
+        if let Some(playback) = self.playbacks.get_mut(&handle) {
+            // playing an ended playback starts it over
+            if playback.clock.position >= playback.clock.to {
+                playback.clock.position = playback.clock.from;
+                self.replay(handle, None);
+            }
+
+            self.playbacks.get_mut(&handle).unwrap().clock.playing = true;
+        }
+    }
+
+    pub fn pause(&mut self, handle: DataIdx) {
+        if let Some(playback) = self.playbacks.get_mut(&handle) {
+            playback.clock.playing = false;
+        }
+    }
+
+    /// Sets how many x units play per millisecond, `1` replaying `DateTime` traces in real time
+    pub fn set_playback_speed(&mut self, handle: DataIdx, speed: RangePrec) {
+        if let Some(playback) = self.playbacks.get_mut(&handle) {
+            playback.clock.speed = speed.max(0.0);
+        }
+    }
+
+    /// Makes the playback start over once it reaches the end instead of pausing there
+    pub fn set_playback_loop(&mut self, handle: DataIdx, looping: bool) {
+        if let Some(playback) = self.playbacks.get_mut(&handle) {
+            playback.clock.looping = looping;
+        }
+    }
+
+    /// Sets a callback called with every frame `advance_playback` returns, or removes it
+    pub fn set_playback_listener(&mut self, handle: DataIdx, listener: Option<Function>) {
+        if let Some(playback) = self.playbacks.get_mut(&handle) {
+            playback.listener = listener;
+        }
+    }
+
+    /// Jumps to `x`, clamped to the recorded range, replaying everything up to it
+    pub fn seek_playback(&mut self, handle: DataIdx, x: RangePrec) {
+        if let Some(playback) = self.playbacks.get_mut(&handle) {
+            let clock = &mut playback.clock;
+            clock.position = x.clamp(clock.from, clock.to);
+            self.replay(handle, None);
+        }
+    }
+
+    /// Moves the playback `elapsed` milliseconds on, appending the points it passed to the
+    /// replays. Returns the frame as `{ x, x_from, x_to, playing, restarted, ended }`, which
+    /// is also passed to the listener.
+    pub fn advance_playback(
+        &mut self,
+        handle: DataIdx,
+        elapsed: RangePrec,
+    ) -> Result<JsValue, JsValue> {
+        let frame = self
+            .step_playback(handle, elapsed)
+            .ok_or_else(|| JsValue::from_str(&format!("Handle {} is not a playback", handle)))?;
+        let frame = serde_wasm_bindgen::to_value(&frame)?;
+
+        if let Some(listener) = &self.playbacks[&handle].listener {
+            listener.call1(&JsValue::NULL, &frame)?;
+        }
+
+        Ok(frame)
+    }
+}
+
+impl DataModule {
+    pub fn get_playback(&self, handle: DataIdx) -> Option<&Playback> {
+        self.playbacks.get(&handle)
+    }
+
+    /// Advances the playback `handle` and updates its replays, see `advance_playback`
+    pub fn step_playback(&mut self, handle: DataIdx, elapsed: RangePrec) -> Option<PlaybackFrame> {
+        let playback = self.playbacks.get_mut(&handle)?;
+        let step = playback.clock.advance(elapsed);
+        let frame = playback.frame(step);
+
+        match step.restarted {
+            true => self.replay(handle, None),
+            false if frame.x > step.after => self.replay(handle, Some(step.after)),
+            false => {}
+        }
+
+        Some(frame)
+    }
+
+    /// Appends the recorded points past `after` up to the position of the playback `handle`
+    /// to its replays, or replays them from scratch without `after`
+    fn replay(&mut self, handle: DataIdx, after: Option<RangePrec>) {
+        let Some(playback) = self.playbacks.get(&handle) else {
+            return;
+        };
+        let position = playback.clock.position;
+        let pairs: Vec<_> = playback
+            .sources
+            .iter()
+            .copied()
+            .zip(playback.outputs.iter().copied())
+            .collect();
+
+        for (source, output) in pairs {
+            let from = after.unwrap_or(RangePrec::MIN);
+            let points: Vec<_> = self
+                .traces
+                .get(&source)
+                .into_iter()
+                .flat_map(|t| t.get_data_high_prec(from, position))
+                .filter(|&(x, _)| x > from && x <= position)
+                .collect();
+            let Some(trace) = self.traces.get_mut(&output) else {
+                continue;
+            };
+
+            match (after, trace.segments.first_mut()) {
+                (Some(_), Some(segment)) => {
+                    dyn_clone::rc_make_mut(segment).append(&points);
+                }
+                _ => {
+                    let mut segment = RollingTrace::unbounded();
+                    segment.push(&points);
+                    trace.segments = vec![Rc::new(segment) as Rc<dyn Segment>];
+                }
+            }
+            self.invalidate(output);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Import of Prometheus HTTP API range query responses, the `matrix` results of
+//! `/api/v1/query_range`
+
+use std::{collections::BTreeMap, rc::Rc};
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    data::DataIdx,
+    prelude::*,
+    trace::{Segment, SharedSegment},
+};
+
+use super::DataModule;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Response {
+    status: String,
+    data: Option<ResponseData>,
+    error_type: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResponseData {
+    result_type: String,
+    result: Vec<ResponseSeries>,
+}
+
+#[derive(Deserialize)]
+struct ResponseSeries {
+    #[serde(default)]
+    metric: BTreeMap<String, String>,
+    #[serde(default)]
+    values: Vec<(RangePrec, String)>,
+}
+
+/// Series of a range query, `x` in epoch milliseconds
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrometheusSeries {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    pub x: Vec<RangePrec>,
+    pub y: Vec<RangePrec>,
+}
+
+/// Name of a series as Prometheus prints it, e.g. `up{instance="a:9100", job="node"}`
+pub fn series_name(labels: &BTreeMap<String, String>) -> String {
+    let metric = labels.get("__name__").map_or("", String::as_str);
+    let rest: Vec<_> = labels
+        .iter()
+        .filter(|(k, _)| *k != "__name__")
+        .map(|(k, v)| format!("{}={:?}", k, v))
+        .collect();
+
+    match rest.is_empty() {
+        true if !metric.is_empty() => metric.to_string(),
+        _ => format!("{}{{{}}}", metric, rest.join(", ")),
+    }
+}
+
+/// Series of a range query response. Samples are seconds since the epoch paired with their
+/// value as text, `NaN` and `±Inf` included, series without samples are skipped.
+pub fn parse_prometheus(json: &str) -> Result<Vec<PrometheusSeries>, String> {
+    let response: Response =
+        serde_json::from_str(json).map_err(|e| format!("Malformed Prometheus response: {}", e))?;
+
+    if response.status != "success" {
+        return Err(format!(
+            "Prometheus query failed: {}: {}",
+            response.error_type.as_deref().unwrap_or("unknown"),
+            response.error.as_deref().unwrap_or("no error given"),
+        ));
+    }
+
+    let data = response.data.ok_or("Prometheus response holds no data")?;
+    if data.result_type != "matrix" {
+        return Err(format!(
+            "Expected a matrix from a range query, got a {}",
+            data.result_type
+        ));
+    }
+
+    data.result
+        .into_iter()
+        .filter(|series| !series.values.is_empty())
+        .map(|series| {
+            let name = series_name(&series.metric);
+            let (x, y) = series
+                .values
+                .iter()
+                .map(|(t, value)| {
+                    let y = value
+                        .parse::<RangePrec>()
+                        .map_err(|_| format!("Sample {:?} of {} isn't a number", value, name))?;
+
+                    Ok((t * 1000.0, y))
+                })
+                .collect::<Result<Vec<_>, String>>()?
+                .into_iter()
+                .unzip();
+
+            Ok(PrometheusSeries {
+                name,
+                labels: series.metric,
+                x,
+                y,
+            })
+        })
+        .collect()
+}
+
+/// Trace a series was loaded into
+#[derive(Serialize)]
+struct ImportedSeries {
+    handle: DataIdx,
+    name: String,
+    labels: BTreeMap<String, String>,
+}
+
+#[wasm_bindgen]
+impl DataModule {
+    /// Imports the JSON of a Prometheus range query response, loading every series into the
+    /// writable `DateTime` trace named like it or a newly created one. Returns an array of
+    /// `{ handle, name, labels }`, `labels` holding the labels of the series.
+    pub fn import_prometheus(&mut self, json: &str) -> Result<JsValue, JsValue> {
+        let series = parse_prometheus(json).map_err(|e| JsValue::from_str(&e))?;
+        let mut imported = Vec::with_capacity(series.len());
+
+        for PrometheusSeries { name, labels, x, y } in series {
+            let handle = self.trace_for_column(&name, "DateTime");
+
+            let segment: Rc<dyn Segment> = Rc::new(SharedSegment::new(Rc::new(x), Rc::new(y)));
+            self.traces.get_mut(&handle).unwrap().push_segment(segment);
+            self.invalidate(handle);
+            imported.push(ImportedSeries {
+                handle,
+                name,
+                labels,
+            });
+        }
+
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        Ok(imported.serialize(&serializer)?)
+    }
+}
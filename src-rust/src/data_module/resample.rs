@@ -0,0 +1,134 @@
+//! Traces interpolated onto a grid of x values, so traces sampled at different rates line up
+
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    data::DataIdx,
+    prelude::*,
+    trace::{Segment, SharedSegment},
+};
+
+use super::DataModule;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Straight line between the points around each x
+    Linear = 0,
+    /// Value of the last point at or before each x, as a sample and hold would
+    Previous = 1,
+    /// Value of the closer of the points around each x
+    Nearest = 2,
+}
+
+/// Values of `points` at the ascending `grid`, `NaN` where the grid is outside of them. Both
+/// are walked once together.
+pub fn resample(
+    points: &[(RangePrec, RangePrec)],
+    grid: &[RangePrec],
+    interpolation: Interpolation,
+) -> Vec<RangePrec> {
+    let mut next = 0;
+
+    grid.iter()
+        .map(|&x| {
+            while next < points.len() && points[next].0 < x {
+                next += 1;
+            }
+
+            let right = points.get(next);
+            let left = next.checked_sub(1).and_then(|i| points.get(i));
+
+            match (left, right) {
+                (_, Some(&(rx, ry))) if rx == x => ry,
+                (Some(&(lx, ly)), Some(&(rx, ry))) => match interpolation {
+                    Interpolation::Linear => ly + (ry - ly) * (x - lx) / (rx - lx),
+                    Interpolation::Previous => ly,
+                    Interpolation::Nearest if x - lx <= rx - x => ly,
+                    Interpolation::Nearest => ry,
+                },
+                _ => RangePrec::NAN,
+            }
+        })
+        .collect()
+}
+
+/// Source and grid of a resampled trace
+#[derive(Clone, Debug)]
+pub struct Resampled {
+    pub source: DataIdx,
+    pub grid: Rc<Vec<RangePrec>>,
+    pub interpolation: Interpolation,
+}
+
+#[wasm_bindgen]
+impl DataModule {
+    /// Creates a read-only trace of `handle` interpolated onto the ascending `x_grid`, e.g.
+    /// to subtract or stack traces sampled at different rates. It's recomputed whenever the
+    /// source changes, grid values outside of the source's points being gaps.
+    pub fn resample(
+        &mut self,
+        handle: DataIdx,
+        x_grid: Vec<RangePrec>,
+        interpolation: Interpolation,
+    ) -> Result<DataIdx, JsValue> {
+        if x_grid.windows(2).any(|w| w[0].is_nan() || w[0] >= w[1]) {
+            return Err(JsValue::from_str("The grid has to be strictly ascending"));
+        }
+
+        let (id, x_type) = self
+            .get_trace(handle)
+            .map(|t| (format!("{} resampled", t.id), t.x_type.clone()))
+            .ok_or_else(|| JsValue::from_str(&format!("Invalid trace handle {}", handle)))?;
+
+        let resampled = self.create_trace(&id, &x_type);
+        self.traces.get_mut(&resampled).unwrap().read_only = true;
+        self.resampled.insert(
+            resampled,
+            Resampled {
+                source: handle,
+                grid: Rc::new(x_grid),
+                interpolation,
+            },
+        );
+        self.rebuild_resampled(resampled);
+
+        Ok(resampled)
+    }
+}
+
+impl DataModule {
+    pub fn get_resampled(&self, handle: DataIdx) -> Option<&Resampled> {
+        self.resampled.get(&handle)
+    }
+
+    /// Interpolates the current points of the source of `handle` onto its grid
+    pub(super) fn rebuild_resampled(&mut self, handle: DataIdx) {
+        let Some(resampled) = self.resampled.get(&handle) else {
+            return;
+        };
+
+        if resampled.grid.is_empty() {
+            return;
+        }
+
+        // the ends of the grid are interpolated from the points past them
+        let points: Vec<_> = self
+            .traces
+            .get(&resampled.source)
+            .map(|t| {
+                t.get_data_high_prec(RangePrec::MIN, RangePrec::MAX)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let y = resample(&points, &resampled.grid, resampled.interpolation);
+
+        let segment: Rc<dyn Segment> =
+            Rc::new(SharedSegment::new(resampled.grid.clone(), Rc::new(y)));
+        if let Some(trace) = self.traces.get_mut(&handle) {
+            trace.segments = vec![segment];
+        }
+    }
+}
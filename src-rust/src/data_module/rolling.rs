@@ -0,0 +1,270 @@
+//! Moving aggregates of traces, folding in the points appended to their sources as they come
+
+use std::{collections::VecDeque, rc::Rc};
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    data::DataIdx,
+    prelude::*,
+    trace::{RollingTrace, Segment},
+};
+
+use super::{DataModule, Envelope};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RollingKind {
+    Mean,
+    /// Exponential moving average with the window as its time constant
+    Ema,
+    Min,
+    Max,
+    /// Moving average between the moving min and max, as an envelope
+    Envelope,
+}
+
+impl RollingKind {
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "mean" | "sma" => Some(Self::Mean),
+            "ema" => Some(Self::Ema),
+            "min" => Some(Self::Min),
+            "max" => Some(Self::Max),
+            "envelope" => Some(Self::Envelope),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Mean => "mean",
+            Self::Ema => "ema",
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::Envelope => "envelope",
+        }
+    }
+}
+
+/// Aggregates at a point of the points at most `window` x units behind it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RollingValues {
+    pub mean: RangePrec,
+    pub ema: RangePrec,
+    pub min: RangePrec,
+    pub max: RangePrec,
+}
+
+/// Points within the window behind the newest one, with the candidates for its min and max
+/// kept in monotonic queues so every point is added and evicted once
+#[derive(Clone, Debug)]
+pub struct RollingWindow {
+    window: RangePrec,
+    points: VecDeque<(RangePrec, RangePrec)>,
+    sum: RangePrec,
+    mins: VecDeque<(RangePrec, RangePrec)>,
+    maxs: VecDeque<(RangePrec, RangePrec)>,
+    ema: Option<(RangePrec, RangePrec)>,
+}
+
+impl RollingWindow {
+    pub fn new(window: RangePrec) -> Self {
+        Self {
+            window,
+            points: VecDeque::new(),
+            sum: 0.0,
+            mins: VecDeque::new(),
+            maxs: VecDeque::new(),
+            ema: None,
+        }
+    }
+
+    /// Aggregates once the finite `(x, y)` past all the points before it is added
+    pub fn push(&mut self, x: RangePrec, y: RangePrec) -> RollingValues {
+        let start = x - self.window;
+
+        while let Some(&(_, old)) = self.points.front().filter(|p| p.0 <= start) {
+            self.sum -= old;
+            self.points.pop_front();
+        }
+        self.points.push_back((x, y));
+        self.sum += y;
+
+        while self.mins.back().is_some_and(|p| p.1 >= y) {
+            self.mins.pop_back();
+        }
+        while self.maxs.back().is_some_and(|p| p.1 <= y) {
+            self.maxs.pop_back();
+        }
+        self.mins.push_back((x, y));
+        self.maxs.push_back((x, y));
+        for queue in [&mut self.mins, &mut self.maxs] {
+            while queue.front().is_some_and(|p| p.0 <= start) {
+                queue.pop_front();
+            }
+        }
+
+        // irregularly sampled points weigh in by how long ago the previous one was
+        let ema = match self.ema {
+            Some((last_x, last)) => {
+                let alpha = 1.0 - (-(x - last_x) / self.window).exp();
+                last + alpha * (y - last)
+            }
+            None => y,
+        };
+        self.ema = Some((x, ema));
+
+        RollingValues {
+            mean: self.sum / self.points.len() as RangePrec,
+            ema,
+            min: self.mins[0].1,
+            max: self.maxs[0].1,
+        }
+    }
+}
+
+/// Moving aggregate of `source` written into its `outputs`, the mean, min and max of an
+/// envelope or the single trace of the other kinds
+pub struct Rolling {
+    source: DataIdx,
+    kind: RollingKind,
+    outputs: Vec<DataIdx>,
+    window: RollingWindow,
+    /// Last point of the source folded in so far
+    last_x: Option<RangePrec>,
+}
+
+impl Rolling {
+    pub fn source(&self) -> DataIdx {
+        self.source
+    }
+
+    pub fn outputs(&self) -> &[DataIdx] {
+        &self.outputs
+    }
+
+    fn values(&self, values: RollingValues) -> Vec<RangePrec> {
+        match self.kind {
+            RollingKind::Mean => vec![values.mean],
+            RollingKind::Ema => vec![values.ema],
+            RollingKind::Min => vec![values.min],
+            RollingKind::Max => vec![values.max],
+            RollingKind::Envelope => vec![values.mean, values.min, values.max],
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl DataModule {
+    /// Creates a read-only trace of a moving aggregate of `handle` over `window` x units, `kind`
+    /// being `mean`, `ema`, `min`, `max` or `envelope`. An envelope is its moving average with
+    /// the min and max around it. Points appended to the source are folded in as they come,
+    /// other changes to it recompute the whole trace.
+    pub fn rolling(
+        &mut self,
+        handle: DataIdx,
+        window: RangePrec,
+        kind: &str,
+    ) -> Result<DataIdx, JsValue> {
+        let kind = RollingKind::parse(kind)
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown rolling aggregate {}", kind)))?;
+        if !(window.is_finite() && window > 0.0) {
+            return Err(JsValue::from_str(&format!("Invalid window {}", window)));
+        }
+
+        let (id, x_type) = self
+            .get_trace(handle)
+            .map(|t| (t.id.clone(), t.x_type.clone()))
+            .ok_or_else(|| JsValue::from_str(&format!("Invalid trace handle {}", handle)))?;
+        let id = format!("{}.{}({})", id, kind.name(), window);
+
+        let outputs = match kind {
+            RollingKind::Envelope => {
+                let mean = self.create_envelope_trace(&id, &x_type);
+                let Envelope { mean, min, max } = self.get_envelope(mean).unwrap();
+                vec![mean, min, max]
+            }
+            _ => vec![self.create_trace(&id, &x_type)],
+        };
+        for output in &outputs {
+            self.traces.get_mut(output).unwrap().read_only = true;
+        }
+
+        self.rollings.insert(
+            outputs[0],
+            Rolling {
+                source: handle,
+                kind,
+                outputs: outputs.clone(),
+                window: RollingWindow::new(window),
+                last_x: None,
+            },
+        );
+        self.update_rolling(outputs[0]);
+
+        Ok(outputs[0])
+    }
+}
+
+impl DataModule {
+    pub fn get_rolling(&self, handle: DataIdx) -> Option<&Rolling> {
+        self.rollings.get(&handle)
+    }
+
+    /// Folds the points appended to the source of the rolling aggregate `handle` into it, or
+    /// recomputes it if the source changed otherwise
+    pub(super) fn update_rolling(&mut self, handle: DataIdx) {
+        let Some(rolling) = self.rollings.get_mut(&handle) else {
+            return;
+        };
+        let source = self.traces.get(&rolling.source);
+        let (from, to) = source
+            .and_then(|t| {
+                let from = t.segments.iter().map(|s| s.from()).reduce(RangePrec::min)?;
+                let to = t.segments.iter().map(|s| s.to()).reduce(RangePrec::max)?;
+                Some((from, to))
+            })
+            .unwrap_or((1.0, 0.0));
+
+        let appended = rolling.last_x.is_some_and(|last| to > last);
+        if !appended {
+            rolling.window = RollingWindow::new(rolling.window.window);
+            rolling.last_x = None;
+        }
+
+        let after = rolling.last_x.unwrap_or(RangePrec::MIN);
+        let points: Vec<_> = source
+            .into_iter()
+            .flat_map(|t| t.get_data_high_prec(after, RangePrec::MAX))
+            .filter(|&(x, y)| x > after && y.is_finite())
+            .collect();
+
+        let mut columns = vec![Vec::with_capacity(points.len()); rolling.outputs.len()];
+        for &(x, y) in &points {
+            let values = rolling.window.push(x, y);
+            for (column, value) in columns.iter_mut().zip(rolling.values(values)) {
+                column.push((x, value));
+            }
+        }
+        rolling.last_x = points.last().map(|p| p.0).or(rolling.last_x);
+
+        for (output, column) in rolling.outputs.iter().zip(columns) {
+            let Some(trace) = self.traces.get_mut(output) else {
+                continue;
+            };
+
+            match (appended, trace.segments.first_mut()) {
+                (true, Some(segment)) => {
+                    let segment = dyn_clone::rc_make_mut(segment);
+                    segment.append(&column);
+                    segment.shrink(from, RangePrec::MAX);
+                }
+                _ => {
+                    let mut segment = RollingTrace::unbounded();
+                    segment.push(&column);
+                    trace.segments = vec![Rc::new(segment) as Rc<dyn Segment>];
+                }
+            }
+        }
+    }
+}
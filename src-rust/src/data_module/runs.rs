@@ -0,0 +1,92 @@
+//! Traces of discrete states summarized as the runs of consecutive equal values
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{data::DataIdx, prelude::*};
+
+use super::DataModule;
+
+/// Span over which a state trace kept `state`
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+pub struct Run {
+    pub state: RangePrec,
+    pub start: RangePrec,
+    pub end: RangePrec,
+    pub duration: RangePrec,
+}
+
+/// Runs of the sorted `points` clipped to `from..to`, each state lasting until the next point
+/// and the last one until the last point, or `to` when none lies within. A non finite value
+/// ends the run before it without starting another. `before` is the point preceding `from`,
+/// whose state `from` starts in.
+pub fn runs(
+    before: Option<(RangePrec, RangePrec)>,
+    points: impl Iterator<Item = (RangePrec, RangePrec)>,
+    from: RangePrec,
+    to: RangePrec,
+) -> Vec<Run> {
+    let mut out = Vec::new();
+    // state and start of the run in progress
+    let mut current: Option<(RangePrec, RangePrec)> = before
+        .filter(|p| p.1.is_finite())
+        .map(|(_, state)| (state, from));
+    let mut last_x = None;
+
+    let mut close = |state, start: RangePrec, end: RangePrec| {
+        let end = end.min(to);
+
+        out.push(Run {
+            state,
+            start,
+            end,
+            duration: end - start,
+        });
+    };
+
+    for (x, state) in points.skip_while(|p| p.0 < from).take_while(|p| p.0 <= to) {
+        match current {
+            Some((current_state, _)) if current_state == state => {}
+            Some((current_state, start)) => {
+                close(current_state, start, x);
+                current = state.is_finite().then_some((state, x));
+            }
+            None => current = state.is_finite().then_some((state, x)),
+        }
+
+        last_x = Some(x);
+    }
+
+    if let Some((state, start)) = current {
+        close(state, start, last_x.unwrap_or(to));
+    }
+
+    out
+}
+
+#[wasm_bindgen]
+impl DataModule {
+    /// Consolidated `{ state, start, end, duration }` runs of a trace holding discrete states
+    /// between `x_from` and `x_to`, e.g. for tooltips and exports of state lanes
+    pub fn runs(&self, handle: DataIdx, x_from: RangePrec, x_to: RangePrec) -> JsValue {
+        let Some(trace) = self.get_trace(handle) else {
+            return JsValue::NULL;
+        };
+
+        // the state at `x_from` was set by the last point before it in the same segment
+        let before = trace
+            .segments
+            .iter()
+            .filter(|s| s.contains(x_from))
+            .filter_map(|s| {
+                s.iter_high_prec(s.from(), x_from)
+                    .take_while(|p| p.0 < x_from)
+                    .last()
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0));
+
+        let runs = runs(before, trace.get_data_high_prec(x_from, x_to), x_from, x_to);
+
+        serde_wasm_bindgen::to_value(&runs).unwrap()
+    }
+}
@@ -0,0 +1,129 @@
+//! Traces rendered as sound, their values mapped to the pitch of a sine tone
+
+use std::f64::consts::TAU;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{data::DataIdx, prelude::*};
+
+use super::DataModule;
+
+/// How values are turned into pitch, the smallest value in the range sounding at `min_freq`
+/// and the largest at `max_freq`
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct PitchMapping {
+    pub sample_rate: u32,
+    /// Frequencies in Hz
+    pub min_freq: f64,
+    pub max_freq: f64,
+    /// Maps values to musical intervals rather than to evenly spaced frequencies
+    pub logarithmic: bool,
+    /// Amplitude of the tone, from 0 to 1
+    pub volume: f32,
+}
+
+#[wasm_bindgen]
+impl PitchMapping {
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: u32, min_freq: f64, max_freq: f64) -> Self {
+        Self {
+            sample_rate,
+            min_freq,
+            max_freq,
+            logarithmic: true,
+            volume: 0.5,
+        }
+    }
+}
+
+impl Default for PitchMapping {
+    fn default() -> Self {
+        Self::new(44100, 220.0, 880.0)
+    }
+}
+
+impl PitchMapping {
+    /// Frequency of the value at `t`, 0 being the smallest value and 1 the largest
+    pub fn frequency(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+
+        if self.logarithmic && self.min_freq > 0.0 && self.max_freq > 0.0 {
+            self.min_freq * (self.max_freq / self.min_freq).powf(t)
+        } else {
+            self.min_freq + (self.max_freq - self.min_freq) * t
+        }
+    }
+}
+
+/// Audio samples playing the sorted `points` between `from` and `to` over `duration` seconds.
+/// Values are linearly interpolated, the tone falling silent outside of the points and
+/// around non finite values.
+pub fn sonify(
+    points: &[(RangePrec, RangePrec)],
+    from: RangePrec,
+    to: RangePrec,
+    duration: f64,
+    mapping: &PitchMapping,
+) -> Vec<f32> {
+    let count = (duration.max(0.0) * mapping.sample_rate as f64).round() as usize;
+    let (min, max) = points
+        .iter()
+        .map(|p| p.1)
+        .filter(|y| y.is_finite())
+        .fold((RangePrec::MAX, RangePrec::MIN), |(min, max), y| {
+            (min.min(y), max.max(y))
+        });
+    let span = if max > min { max - min } else { 1.0 };
+
+    let mut samples = Vec::with_capacity(count);
+    let mut next = 0;
+    let mut phase: f64 = 0.0;
+
+    for i in 0..count {
+        let x = from + (to - from) * i as f64 / count as f64;
+
+        while next < points.len() && points[next].0 < x {
+            next += 1;
+        }
+
+        let value = match (next.checked_sub(1).map(|i| points[i]), points.get(next)) {
+            (_, Some(&(rx, ry))) if rx == x => Some(ry),
+            (Some((lx, ly)), Some(&(rx, ry))) => Some(ly + (ry - ly) * (x - lx) / (rx - lx)),
+            _ => None,
+        };
+
+        match value.filter(|v| v.is_finite()) {
+            Some(value) => {
+                samples.push(phase.sin() as f32 * mapping.volume);
+                phase = (phase
+                    + TAU * mapping.frequency((value - min) / span) / mapping.sample_rate as f64)
+                    % TAU;
+            }
+            None => samples.push(0.0),
+        }
+    }
+
+    samples
+}
+
+#[wasm_bindgen]
+impl DataModule {
+    /// Mono audio playing the trace between `x_from` and `x_to` over `duration` seconds, its
+    /// values mapped to pitch, e.g. for filling a Web Audio buffer
+    pub fn to_audio_samples(
+        &self,
+        handle: DataIdx,
+        x_from: RangePrec,
+        x_to: RangePrec,
+        duration: f64,
+        mapping: &PitchMapping,
+    ) -> Result<Vec<f32>, JsValue> {
+        let trace = self
+            .get_trace(handle)
+            .ok_or_else(|| JsValue::from_str(&format!("Invalid trace handle {}", handle)))?;
+        let points: Vec<_> = trace.get_data_high_prec(x_from, x_to).collect();
+
+        Ok(sonify(&points, x_from, x_to, duration, mapping))
+    }
+}
@@ -0,0 +1,147 @@
+//! Summary statistics of the visible part of a trace
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{data::DataIdx, parallel, prelude::*};
+
+use super::DataModule;
+
+/// Independent accumulators the sums are spread over, so they vectorize
+const LANES: usize = 8;
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct TraceStats {
+    /// Finite values the statistics were computed from
+    pub count: usize,
+    pub min: RangePrec,
+    pub max: RangePrec,
+    pub mean: RangePrec,
+    /// Population variance
+    pub variance: RangePrec,
+    /// `(p, value)` for each requested percentile `p` from 0 to 100, interpolated linearly
+    /// between the closest ranks
+    pub percentiles: Vec<(RangePrec, RangePrec)>,
+}
+
+/// Statistics of the finite `values`, `None` when there are none. The values are reordered.
+pub fn stats(values: &mut Vec<RangePrec>, percentiles: &[RangePrec]) -> Option<TraceStats> {
+    values.retain(|v| v.is_finite());
+    if values.is_empty() {
+        return None;
+    }
+
+    let parts = parallel::split(values.len(), parallel::MIN_PART, |_| true);
+
+    let (mut min, mut max, mut sum) = (RangePrec::MAX, RangePrec::MIN, 0.0);
+    for (part_min, part_max, part_sum) in parallel::map(&parts, |part| sums(&values[part])) {
+        (min, max, sum) = (min.min(part_min), max.max(part_max), sum + part_sum);
+    }
+
+    let count = values.len();
+    let mean = sum / count as RangePrec;
+
+    // the deviations are summed in a second pass, the one pass formula cancels catastrophically
+    let squares: RangePrec = parallel::map(&parts, |part| squares(&values[part], mean))
+        .into_iter()
+        .sum();
+
+    let percentiles = percentiles
+        .iter()
+        .map(|&p| (p, percentile(values, p)))
+        .collect();
+
+    Some(TraceStats {
+        count,
+        min,
+        max,
+        mean,
+        variance: squares / count as RangePrec,
+        percentiles,
+    })
+}
+
+/// Minimum, maximum and sum of `values`
+fn sums(values: &[RangePrec]) -> (RangePrec, RangePrec, RangePrec) {
+    let (mut min, mut max, mut sum) = (
+        [RangePrec::MAX; LANES],
+        [RangePrec::MIN; LANES],
+        [0.0; LANES],
+    );
+    let chunks = values.chunks_exact(LANES);
+    let rest = chunks.remainder();
+
+    for chunk in chunks {
+        for lane in 0..LANES {
+            min[lane] = min[lane].min(chunk[lane]);
+            max[lane] = max[lane].max(chunk[lane]);
+            sum[lane] += chunk[lane];
+        }
+    }
+    for (lane, &v) in rest.iter().enumerate() {
+        min[lane] = min[lane].min(v);
+        max[lane] = max[lane].max(v);
+        sum[lane] += v;
+    }
+
+    (
+        min.into_iter().fold(RangePrec::MAX, RangePrec::min),
+        max.into_iter().fold(RangePrec::MIN, RangePrec::max),
+        sum.iter().sum(),
+    )
+}
+
+/// Sum of the squared deviations of `values` from `mean`
+fn squares(values: &[RangePrec], mean: RangePrec) -> RangePrec {
+    let mut squares = [0.0; LANES];
+    let chunks = values.chunks_exact(LANES);
+    let rest = chunks.remainder();
+
+    for chunk in chunks {
+        for lane in 0..LANES {
+            squares[lane] += (chunk[lane] - mean) * (chunk[lane] - mean);
+        }
+    }
+    for (lane, &v) in rest.iter().enumerate() {
+        squares[lane] += (v - mean) * (v - mean);
+    }
+
+    squares.iter().sum()
+}
+
+/// `p`-th percentile of the non empty `values`, selected without sorting them fully
+fn percentile(values: &mut [RangePrec], p: RangePrec) -> RangePrec {
+    let rank = p.clamp(0.0, 100.0) / 100.0 * (values.len() - 1) as RangePrec;
+    let (lo, t) = (rank.floor() as usize, rank.fract());
+
+    let (_, &mut low, above) = values.select_nth_unstable_by(lo, RangePrec::total_cmp);
+    match above.iter().copied().reduce(RangePrec::min) {
+        Some(high) if t > 0.0 => low + (high - low) * t,
+        _ => low,
+    }
+}
+
+#[wasm_bindgen]
+impl DataModule {
+    /// `{ count, min, max, mean, variance, percentiles }` of the finite values of a trace
+    /// between `from` and `to`, `percentiles` listing the ones from 0 to 100 to compute.
+    /// `null` for unknown handles and ranges without values.
+    pub fn trace_stats(
+        &self,
+        handle: DataIdx,
+        from: RangePrec,
+        to: RangePrec,
+        percentiles: &[RangePrec],
+    ) -> JsValue {
+        let Some(trace) = self.get_trace(handle) else {
+            return JsValue::NULL;
+        };
+
+        let mut values: Vec<_> = trace.get_data_high_prec(from, to).map(|p| p.1).collect();
+
+        match stats(&mut values, percentiles) {
+            Some(stats) => serde_wasm_bindgen::to_value(&stats).unwrap(),
+            None => JsValue::NULL,
+        }
+    }
+}
@@ -0,0 +1,297 @@
+//! Levels of detail of whole traces exported as tiles, so hosts can persist them and skip
+//! rebuilding them on every page load.
+//!
+//! The tiles are encoded little endian as a header followed by the tiles of every level:
+//!
+//! | field      | type | meaning                                                  |
+//! |------------|------|----------------------------------------------------------|
+//! | magic      | 4 B  | `CHTP`                                                   |
+//! | version    | u32  | `2`                                                      |
+//! | origin     | f64  | x the point x values are relative to                     |
+//! | last_x     | f64  | last x of the trace the levels were built from           |
+//! | checksum   | u64  | `checksum_points` of the trace's points                  |
+//! | tile_size  | u32  | points per tile, only the last tile of a level has fewer |
+//! | levels     | u32  | number of levels                                         |
+//! | tiles      | u32  | number of tiles following                                |
+//!
+//! Each tile is its `level` (u32, 0 being the finest), its `index` within the level (u32),
+//! its point `count` (u32) and the points as `count` pairs of f32 `x` and `y`. Version `1`
+//! exports have no `checksum` and are still read.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{data::DataIdx, prelude::*};
+
+use super::{checksum_points, m4, DataModule};
+
+/// How many times fewer buckets each level has than the one below has points
+pub const PYRAMID_FACTOR: usize = 16;
+
+const MAGIC: &[u8; 4] = b"CHTP";
+const VERSION: u32 = 2;
+const HEADER_SIZE: usize = 4 + 4 + 8 + 8 + 8 + 4 * 3;
+const TILE_HEADER_SIZE: usize = 4 * 3;
+const MAX_LEVELS: usize = 32;
+
+/// Min/max decimated levels of a trace, each reduced from the one below, the first from the
+/// trace itself. Points are relative to `origin`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pyramid {
+    pub origin: RangePrec,
+    pub last_x: RangePrec,
+    /// Checksum of the points the levels were built from, `None` for version 1 exports
+    pub checksum: Option<u64>,
+    pub levels: Vec<Vec<(DataPrec, DataPrec)>>,
+}
+
+impl Pyramid {
+    /// Levels of `points` relative to their first x, down to the last with 2 buckets or more
+    pub fn build(points: &[(RangePrec, RangePrec)]) -> Self {
+        let origin = points.first().map_or(0.0, |p| p.0);
+        let last_x = points.last().map_or(0.0, |p| p.0);
+
+        let mut level: Vec<(DataPrec, DataPrec)> = points
+            .iter()
+            .map(|&(x, y)| ((x - origin) as DataPrec, y as DataPrec))
+            .collect();
+        let mut levels = Vec::new();
+
+        loop {
+            let buckets = level.len() / PYRAMID_FACTOR;
+            if buckets < 2 {
+                break;
+            }
+
+            level = m4(&level, 0.0, (last_x - origin) as DataPrec, buckets);
+            levels.push(level.clone());
+        }
+
+        Self {
+            origin,
+            last_x,
+            checksum: Some(checksum_points(points.iter().copied())),
+            levels,
+        }
+    }
+
+    /// Points of `level` between `from` and `to`, relative to `from`
+    pub fn slice(
+        &self,
+        level: usize,
+        from: RangePrec,
+        to: RangePrec,
+    ) -> Option<Vec<(DataPrec, DataPrec)>> {
+        let points = self.levels.get(level)?;
+        let (lo, hi) = (
+            (from - self.origin) as DataPrec,
+            (to - self.origin) as DataPrec,
+        );
+        let shift = (self.origin - from) as DataPrec;
+
+        let start = points.partition_point(|p| p.0 < lo);
+        let end = points.partition_point(|p| p.0 <= hi);
+
+        Some(
+            points[start..end.max(start)]
+                .iter()
+                .map(|&(x, y)| (x + shift, y))
+                .collect(),
+        )
+    }
+
+    /// Tiles of at most `tile_size` points, see the module documentation for the layout
+    pub fn encode(&self, tile_size: usize) -> Vec<u8> {
+        let tile_size = tile_size.max(1);
+        let tiles: Vec<_> = self
+            .levels
+            .iter()
+            .enumerate()
+            .flat_map(|(level, points)| {
+                points
+                    .chunks(tile_size)
+                    .enumerate()
+                    .map(move |(index, tile)| (level, index, tile))
+            })
+            .collect();
+
+        let points: usize = self.levels.iter().map(Vec::len).sum();
+        let mut out = Vec::with_capacity(HEADER_SIZE + tiles.len() * TILE_HEADER_SIZE + points * 8);
+
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.extend_from_slice(&self.origin.to_le_bytes());
+        out.extend_from_slice(&self.last_x.to_le_bytes());
+        out.extend_from_slice(&self.checksum.unwrap_or_default().to_le_bytes());
+        out.extend_from_slice(&(tile_size as u32).to_le_bytes());
+        out.extend_from_slice(&(self.levels.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(tiles.len() as u32).to_le_bytes());
+
+        for (level, index, tile) in tiles {
+            out.extend_from_slice(&(level as u32).to_le_bytes());
+            out.extend_from_slice(&(index as u32).to_le_bytes());
+            out.extend_from_slice(&(tile.len() as u32).to_le_bytes());
+
+            for &(x, y) in tile {
+                out.extend_from_slice(&x.to_le_bytes());
+                out.extend_from_slice(&y.to_le_bytes());
+            }
+        }
+
+        out
+    }
+
+    /// Reassembles the levels from the tiles written by `encode`, in any order
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader { bytes, pos: 0 };
+
+        if reader.take(4)? != MAGIC {
+            return Err("Not a tile export".to_string());
+        }
+        let version = reader.u32()?;
+        if !(1..=VERSION).contains(&version) {
+            return Err(format!("Unsupported tile export version {}", version));
+        }
+
+        let origin = reader.f64()?;
+        let last_x = reader.f64()?;
+        let checksum = match version {
+            1 => None,
+            _ => Some(reader.u64()?),
+        };
+        let tile_size = reader.u32()? as usize;
+        let level_count = reader.u32()? as usize;
+        let tile_count = reader.u32()? as usize;
+
+        // every level has a sixteenth of the points of the one below, there can't be many
+        if level_count > MAX_LEVELS {
+            return Err(format!("Tile export claims {} levels", level_count));
+        }
+
+        let mut tiles = Vec::with_capacity(tile_count.min(bytes.len() / TILE_HEADER_SIZE));
+        for _ in 0..tile_count {
+            let (level, index, count) = (reader.u32()?, reader.u32()?, reader.u32()? as usize);
+
+            if level as usize >= level_count || count > tile_size {
+                return Err(format!("Tile {} of level {} is malformed", index, level));
+            }
+
+            let points = reader
+                .take(count.checked_mul(8).ok_or("Tile too large")?)?
+                .chunks_exact(8)
+                .map(|p| {
+                    (
+                        DataPrec::from_le_bytes(p[0..4].try_into().unwrap()),
+                        DataPrec::from_le_bytes(p[4..8].try_into().unwrap()),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            tiles.push((level, index, points));
+        }
+
+        if reader.pos != bytes.len() {
+            return Err("Trailing bytes after the last tile".to_string());
+        }
+
+        tiles.sort_by_key(|&(level, index, _)| (level, index));
+
+        let mut levels = vec![Vec::new(); level_count];
+        for (level, index, points) in tiles {
+            let level = &mut levels[level as usize];
+            if level.len() != index as usize * tile_size {
+                return Err(format!("Tile {} is missing or duplicated", index));
+            }
+
+            level.extend(points);
+        }
+
+        Ok(Self {
+            origin,
+            last_x,
+            checksum,
+            levels,
+        })
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let slice = self
+            .bytes
+            .get(self.pos..self.pos.saturating_add(len))
+            .ok_or("Tile export is truncated")?;
+        self.pos += len;
+
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f64(&mut self) -> Result<f64, String> {
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+#[wasm_bindgen]
+impl DataModule {
+    /// Levels of detail of the whole trace as tiles of `tile_points` points, in the layout
+    /// described in the `tiles` module, to be handed back to `import_tiles` later
+    pub fn export_tiles(&self, handle: DataIdx, tile_points: usize) -> Result<Vec<u8>, JsValue> {
+        let trace = self
+            .get_trace(handle)
+            .ok_or_else(|| JsValue::from_str(&format!("Invalid trace handle {}", handle)))?;
+        let points: Vec<_> = trace
+            .get_data_high_prec(RangePrec::MIN, RangePrec::MAX)
+            .collect();
+
+        Ok(Pyramid::build(&points).encode(tile_points))
+    }
+
+    /// Restores the levels of detail exported by `export_tiles`, bundles of the trace then use
+    /// them instead of building their own as long as the trace ends where it did on export.
+    /// Tiles built from other data than the trace holds are rejected.
+    pub fn import_tiles(&mut self, handle: DataIdx, tiles: &[u8]) -> Result<(), JsValue> {
+        let Some(checksum) = self.trace_checksum(handle) else {
+            return Err(JsValue::from_str(&format!(
+                "Invalid trace handle {}",
+                handle
+            )));
+        };
+
+        let pyramid = Pyramid::decode(tiles).map_err(|e| JsValue::from_str(&e))?;
+        if pyramid.checksum.is_some_and(|c| c != checksum) {
+            return Err(JsValue::from_str(
+                "The tiles were built from other data than the trace holds",
+            ));
+        }
+        self.pyramids.insert(handle, pyramid);
+
+        Ok(())
+    }
+}
+
+impl DataModule {
+    /// Imported levels of detail of `handle`, unless the trace changed its end since export
+    pub fn get_pyramid(&self, handle: DataIdx) -> Option<&Pyramid> {
+        let pyramid = self.pyramids.get(&handle)?;
+        let last = self
+            .get_trace(handle)?
+            .segments
+            .iter()
+            .map(|s| s.to())
+            .reduce(RangePrec::max)?;
+
+        (last == pyramid.last_x).then_some(pyramid)
+    }
+}
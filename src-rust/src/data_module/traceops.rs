@@ -1,22 +1,15 @@
+use std::rc::Rc;
+
 use wasm_bindgen::prelude::*;
 
 use crate::{
     data::{create_segment, DataIdx, TYPE_SIZES},
     prelude::*,
-    trace::{Segment, TraceMetas},
+    trace::{Expression, ExpressionSegment, Segment, ShiftedSegment, TraceMetas},
 };
 
 use super::DataModule;
 
-trait OptionUtils<T> {
-    fn is_none_or(self, f: impl FnOnce(T) -> bool) -> bool;
-}
-impl<T> OptionUtils<T> for Option<T> {
-    fn is_none_or(self, f: impl FnOnce(T) -> bool) -> bool {
-        self.is_none() || f(self.unwrap())
-    }
-}
-
 #[wasm_bindgen]
 impl DataModule {
     pub fn get_data_at_point(&self, ptrs: &[DataIdx], x: RangePrec) -> JsValue {
@@ -46,7 +39,7 @@ impl DataModule {
             })
             .collect();
 
-        dists.sort_by(|(_, a_delta), (_, b_delta)| a_delta.partial_cmp(&b_delta).unwrap());
+        dists.sort_by(|(_, a_delta), (_, b_delta)| a_delta.partial_cmp(b_delta).unwrap());
 
         dists.into_iter().take(n).map(|(p, _)| p).collect()
     }
@@ -123,6 +116,76 @@ impl DataModule {
             .expect("Invalid output handle in shift_clone_trace");
     }
 
+    /// Creates a trace showing `source` moved by `offset` along x, e.g. last week's data
+    /// aligned with this week's. The view shares the segments of `source` as they are
+    /// at the time of the call instead of copying them.
+    pub fn time_shifted_view(&mut self, source: DataIdx, offset: RangePrec) -> DataIdx {
+        let (id, x_type, segments) = self
+            .traces
+            .get(&source)
+            .map(|t| {
+                let segments: Vec<Rc<dyn Segment>> = t
+                    .segments
+                    .iter()
+                    .map(|s| Rc::new(ShiftedSegment::new(s.clone(), offset)) as Rc<dyn Segment>)
+                    .collect();
+
+                (format!("{}@{}", t.id, offset), t.x_type.clone(), segments)
+            })
+            .expect("Invalid source handle in time_shifted_view");
+
+        let handle = self.create_trace(&id, &x_type);
+        let trace = self.traces.get_mut(&handle).unwrap();
+
+        for seg in segments {
+            trace.push_segment(seg);
+        }
+
+        handle
+    }
+
+    /// Creates a read-only trace of `expression`, e.g. `a / b * 100`, over the traces bound to
+    /// its variables by `names` and `handles`. Values are computed per requested range when the
+    /// trace is read rather than stored, at the x of the expression's first variable. Like a
+    /// shifted view, it shares the segments of the sources as they are at the time of the call.
+    pub fn create_expression_trace(
+        &mut self,
+        id: &str,
+        expression: &str,
+        names: Vec<String>,
+        handles: &[DataIdx],
+    ) -> Result<DataIdx, JsValue> {
+        let expression = Expression::parse(expression)
+            .map_err(|err| JsValue::from_str(&format!("Invalid expression: {}", err)))?;
+
+        let mut x_type = None;
+        let mut inputs = Vec::with_capacity(expression.variables().len());
+
+        for var in expression.variables() {
+            let trace = names
+                .iter()
+                .position(|name| name == var)
+                .and_then(|i| handles.get(i))
+                .and_then(|handle| self.traces.get(handle))
+                .ok_or_else(|| JsValue::from_str(&format!("No trace is bound to {}", var)))?;
+
+            x_type.get_or_insert_with(|| trace.x_type.clone());
+            inputs.push(trace.segments.clone());
+        }
+
+        let Some(x_type) = x_type else {
+            return Err(JsValue::from_str("The expression reads no traces"));
+        };
+
+        let handle = self.create_trace(id, &x_type);
+        let trace = self.traces.get_mut(&handle).unwrap();
+
+        trace.read_only = true;
+        trace.push_segment(Rc::new(ExpressionSegment::new(expression, inputs)) as Rc<dyn Segment>);
+
+        Ok(handle)
+    }
+
     pub fn op_traces(
         &mut self,
         output: DataIdx,
@@ -153,6 +216,7 @@ impl DataModule {
                     }
                 })
                 .expect("Invalid output in op_traces");
+            self.invalidate(output);
 
             return;
         }
@@ -194,7 +258,11 @@ impl DataModule {
             let ptr = data.as_mut_ptr();
             std::mem::forget(data);
 
-            Vec::from_raw_parts(std::mem::transmute(ptr), len, cap)
+            Vec::from_raw_parts(
+                std::mem::transmute::<*mut (i32, f64), *mut u8>(ptr),
+                len,
+                cap,
+            )
         };
 
         self.traces
@@ -212,6 +280,7 @@ impl DataModule {
                 ));
             })
             .expect("Invalid output in op_traces");
+        self.invalidate(output);
     }
 
     pub fn get_closest_point(
@@ -249,10 +318,10 @@ impl DataModule {
             }
         }
 
-        return match (closest_x, closest_y) {
+        match (closest_x, closest_y) {
             (Some(x), Some(y)) => Some(vec![x, y].into_boxed_slice()),
             _ => None,
-        };
+        }
     }
 
     pub fn get_trace_metas(&self, ptr: DataIdx, from: RangePrec, to: RangePrec) -> JsValue {
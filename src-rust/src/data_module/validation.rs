@@ -0,0 +1,186 @@
+//! Plausibility checks of live data, keeping corrupt samples from being drawn as spikes
+
+use js_sys::Function;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{data::DataIdx, prelude::*};
+
+use super::DataModule;
+
+/// What happens to samples breaking the rules of their trace
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ViolationAction {
+    /// Kept as they are, only reported
+    #[default]
+    Flag = 0,
+    /// Replaced by missing values, which break the line with the trace's gap rules
+    Gap = 1,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ValidationRules {
+    /// Smallest plausible value
+    pub min: RangePrec,
+    /// Largest plausible value
+    pub max: RangePrec,
+    /// Largest plausible change from the last valid value, `0` allowing any
+    pub max_step: RangePrec,
+    pub action: ViolationAction,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationKind {
+    BelowMin,
+    AboveMax,
+    Step,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct Violation {
+    pub x: RangePrec,
+    /// Value as it was appended, before being gapped
+    pub y: RangePrec,
+    pub kind: ViolationKind,
+}
+
+/// Outcome of checking the points of an append
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ValidationReport {
+    pub handle: DataIdx,
+    /// Points checked, missing values aren't
+    pub checked: usize,
+    pub violations: Vec<Violation>,
+}
+
+#[wasm_bindgen]
+impl ValidationRules {
+    /// Rules allowing any value, to be narrowed by setting the fields
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            min: RangePrec::NEG_INFINITY,
+            max: RangePrec::INFINITY,
+            max_step: 0.0,
+            action: ViolationAction::Flag,
+        }
+    }
+}
+
+impl Default for ValidationRules {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ValidationRules {
+    /// Checks the `points` following a valid value of `last`, gapping the violating ones if
+    /// the rules say so. Returns the violations and the last valid value after the points.
+    pub fn check(
+        &self,
+        points: &mut [(RangePrec, RangePrec)],
+        mut last: Option<RangePrec>,
+    ) -> (Vec<Violation>, Option<RangePrec>) {
+        let mut violations = Vec::new();
+
+        for (x, y) in points.iter_mut() {
+            if y.is_nan() {
+                continue;
+            }
+
+            // a step is measured from the last valid value, so the sample after a spike isn't
+            // reported as well
+            let kind = if *y < self.min {
+                Some(ViolationKind::BelowMin)
+            } else if *y > self.max {
+                Some(ViolationKind::AboveMax)
+            } else if last
+                .is_some_and(|last| self.max_step > 0.0 && (*y - last).abs() > self.max_step)
+            {
+                Some(ViolationKind::Step)
+            } else {
+                None
+            };
+
+            match kind {
+                Some(kind) => {
+                    violations.push(Violation { x: *x, y: *y, kind });
+                    if self.action == ViolationAction::Gap {
+                        *y = RangePrec::NAN;
+                    }
+                }
+                None => last = Some(*y),
+            }
+        }
+
+        (violations, last)
+    }
+}
+
+/// Rules of a trace with what's needed to continue checking its appends
+pub struct Validator {
+    rules: ValidationRules,
+    last: Option<RangePrec>,
+    /// Called with the `ValidationReport` of appends breaking the rules
+    on_violation: Option<Function>,
+}
+
+#[wasm_bindgen]
+impl DataModule {
+    /// Checks the points appended to a trace by `push_rolling` against `rules`, calling
+    /// `on_violation` with `{ handle, checked, violations: [{ x, y, kind }] }` for each append
+    /// breaking them, `kind` being `below_min`, `above_max` or `step`
+    pub fn set_validation(
+        &mut self,
+        handle: DataIdx,
+        rules: ValidationRules,
+        on_violation: Option<Function>,
+    ) {
+        self.validators.insert(
+            handle,
+            Validator {
+                rules,
+                last: None,
+                on_violation,
+            },
+        );
+    }
+
+    pub fn clear_validation(&mut self, handle: DataIdx) {
+        self.validators.remove(&handle);
+    }
+}
+
+impl DataModule {
+    /// Applies the validation rules of a trace to points appended to it, `None` when it has
+    /// none. Reports with violations are passed to its callback as well.
+    pub fn validate_append(
+        &mut self,
+        handle: DataIdx,
+        points: &mut [(RangePrec, RangePrec)],
+    ) -> Option<ValidationReport> {
+        let validator = self.validators.get_mut(&handle)?;
+
+        let checked = points.iter().filter(|p| !p.1.is_nan()).count();
+        let (violations, last) = validator.rules.check(points, validator.last);
+        validator.last = last;
+
+        let report = ValidationReport {
+            handle,
+            checked,
+            violations,
+        };
+        if let (Some(callback), false) = (&validator.on_violation, report.violations.is_empty()) {
+            // the append goes through whatever the callback does
+            let _ = callback.call1(
+                &JsValue::NULL,
+                &serde_wasm_bindgen::to_value(&report).unwrap(),
+            );
+        }
+
+        Some(report)
+    }
+}
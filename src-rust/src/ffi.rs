@@ -0,0 +1,294 @@
+//! C ABI for embedding the data module, decimation, tick layout and the SVG renderer outside
+//! of JS. Every object is an opaque pointer created and freed by the functions here, the
+//! declarations are in `include/chartium.h`. Invalid arguments and panics are reported by
+//! the return value, never unwound into the caller.
+
+use std::{
+    ffi::{c_char, CStr, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use crate::{
+    data::DataIdx,
+    data_module::{downsample, DataModule, Downsampling},
+    prelude::*,
+    renderers::{ticks::calc_ticks, Renderer, SvgRenderer},
+    structs::RenderJob,
+};
+
+/// Bumped whenever a declaration of `chartium.h` changes incompatibly
+pub const ABI_VERSION: u32 = 1;
+
+/// Handle `chartium_create_trace` returns on failure
+pub const INVALID_HANDLE: usize = usize::MAX;
+
+/// Runs `f`, turning a panic into `fallback`
+fn guard<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback)
+}
+
+/// `len` items from `data`, empty when it's null
+///
+/// # Safety
+/// A non-null `data` must point at `len` initialized items.
+unsafe fn slice_of<'a, T>(data: *const T, len: usize) -> &'a [T] {
+    match data.is_null() {
+        true => &[],
+        false => slice::from_raw_parts(data, len),
+    }
+}
+
+/// # Safety
+/// A non-null `text` must be a nul terminated string.
+unsafe fn str_of<'a>(text: *const c_char) -> Option<&'a str> {
+    match text.is_null() {
+        true => None,
+        false => CStr::from_ptr(text).to_str().ok(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn chartium_abi_version() -> u32 {
+    ABI_VERSION
+}
+
+#[no_mangle]
+pub extern "C" fn chartium_module_new() -> *mut DataModule {
+    Box::into_raw(Box::new(DataModule::new()))
+}
+
+/// # Safety
+/// `module` must come from `chartium_module_new` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn chartium_module_free(module: *mut DataModule) {
+    if !module.is_null() {
+        drop(Box::from_raw(module));
+    }
+}
+
+/// Creates an empty trace, returning its handle or `INVALID_HANDLE`
+///
+/// # Safety
+/// `module` must be a live module, `id` and `x_type` nul terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn chartium_create_trace(
+    module: *mut DataModule,
+    id: *const c_char,
+    x_type: *const c_char,
+) -> usize {
+    let (Some(module), Some(id), Some(x_type)) = (module.as_mut(), str_of(id), str_of(x_type))
+    else {
+        return INVALID_HANDLE;
+    };
+
+    guard(INVALID_HANDLE, || module.create_trace(id, x_type))
+}
+
+/// Loads `len` points of the `x` and `y` columns into a trace, returning whether it exists
+/// and is writable
+///
+/// # Safety
+/// `module` must be a live module, `x` and `y` must hold `len` values each.
+#[no_mangle]
+pub unsafe extern "C" fn chartium_load_trace(
+    module: *mut DataModule,
+    handle: usize,
+    x: *const f64,
+    y: *const f64,
+    len: usize,
+) -> bool {
+    let Some(module) = module.as_mut() else {
+        return false;
+    };
+    let (x, y) = (slice_of(x, len).to_vec(), slice_of(y, len).to_vec());
+    if x.len() != y.len() {
+        return false;
+    }
+
+    guard(false, || module.load_points(handle as DataIdx, x, y))
+}
+
+/// Decimates the `len` points of `x` and `y` into `buckets` with `method`, `0` keeping all of
+/// them, `1` M4 and `2` LTTB. Writes at most `capacity` points to `out_x` and `out_y` and
+/// returns how many there are, which may be more.
+///
+/// # Safety
+/// `x` and `y` must hold `len` values each, `out_x` and `out_y` room for `capacity` values.
+#[no_mangle]
+pub unsafe extern "C" fn chartium_downsample(
+    x: *const f32,
+    y: *const f32,
+    len: usize,
+    method: u8,
+    buckets: usize,
+    out_x: *mut f32,
+    out_y: *mut f32,
+    capacity: usize,
+) -> usize {
+    let method = match method {
+        0 => Downsampling::None,
+        1 => Downsampling::M4,
+        2 => Downsampling::Lttb,
+        _ => return 0,
+    };
+    let points: Vec<(DataPrec, DataPrec)> = slice_of(x, len)
+        .iter()
+        .copied()
+        .zip(slice_of(y, len).iter().copied())
+        .collect();
+    let (Some(&(from, _)), Some(&(to, _))) = (points.first(), points.last()) else {
+        return 0;
+    };
+
+    let reduced = guard(Vec::new(), || downsample(points, method, from, to, buckets));
+
+    if !out_x.is_null() && !out_y.is_null() {
+        for (i, &(x, y)) in reduced.iter().take(capacity).enumerate() {
+            out_x.add(i).write(x);
+            out_y.add(i).write(y);
+        }
+    }
+
+    reduced.len()
+}
+
+/// Places linear ticks on `from..to`, writing at most `capacity` of their values and relative
+/// positions to `out_values` and `out_positions`. Returns how many ticks there are.
+///
+/// # Safety
+/// `out_values` and `out_positions` must have room for `capacity` values.
+#[no_mangle]
+pub unsafe extern "C" fn chartium_ticks(
+    from: f64,
+    to: f64,
+    out_values: *mut f64,
+    out_positions: *mut f64,
+    capacity: usize,
+) -> usize {
+    let ticks = calc_ticks(from, to - from);
+
+    if !out_values.is_null() && !out_positions.is_null() {
+        for (i, tick) in ticks.iter().take(capacity).enumerate() {
+            out_values.add(i).write(tick.val());
+            out_positions.add(i).write(tick.pos());
+        }
+    }
+
+    ticks.len()
+}
+
+/// # Safety
+/// `x_type` must be a nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn chartium_job_new(x_type: *const c_char) -> *mut RenderJob {
+    match str_of(x_type) {
+        Some(x_type) => Box::into_raw(Box::new(RenderJob::new(x_type.to_string(), 0, 0))),
+        None => ptr::null_mut(),
+    }
+}
+
+/// # Safety
+/// `job` must come from `chartium_job_new` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn chartium_job_free(job: *mut RenderJob) {
+    if !job.is_null() {
+        drop(Box::from_raw(job));
+    }
+}
+
+/// # Safety
+/// `job` must be a live job.
+#[no_mangle]
+pub unsafe extern "C" fn chartium_job_set_view(
+    job: *mut RenderJob,
+    x_from: f64,
+    x_to: f64,
+    y_from: f64,
+    y_to: f64,
+) {
+    if let Some(job) = job.as_mut() {
+        (job.x_from, job.x_to, job.y_from, job.y_to) = (x_from, x_to, y_from, y_to);
+    }
+}
+
+/// Sets the margin around the plot and the room left for the x and y tick labels, in pixels
+///
+/// # Safety
+/// `job` must be a live job.
+#[no_mangle]
+pub unsafe extern "C" fn chartium_job_set_margins(
+    job: *mut RenderJob,
+    margin: u32,
+    x_label_space: u32,
+    y_label_space: u32,
+) {
+    if let Some(job) = job.as_mut() {
+        (job.margin, job.x_label_space, job.y_label_space) = (margin, x_label_space, y_label_space);
+    }
+}
+
+/// Draws the trace as a `width` pixels wide line of the RGB `color`
+///
+/// # Safety
+/// `job` must be a live job and `color` hold 3 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chartium_job_add_trace(
+    job: *mut RenderJob,
+    handle: usize,
+    color: *const u8,
+    width: u32,
+) {
+    if let (Some(job), false) = (job.as_mut(), color.is_null()) {
+        job.add_trace(handle as DataIdx, slice_of(color, 3), width, false);
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn chartium_svg_renderer_new(width: u32, height: u32) -> *mut SvgRenderer {
+    Box::into_raw(Box::new(SvgRenderer::new(width, height, 0.0)))
+}
+
+/// # Safety
+/// `renderer` must come from `chartium_svg_renderer_new` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn chartium_svg_renderer_free(renderer: *mut SvgRenderer) {
+    if !renderer.is_null() {
+        drop(Box::from_raw(renderer));
+    }
+}
+
+/// Renders `job` and returns the SVG document of everything rendered since the last clearing
+/// job, to be freed with `chartium_string_free`, or null on failure
+///
+/// # Safety
+/// `renderer`, `module` and `job` must be live objects.
+#[no_mangle]
+pub unsafe extern "C" fn chartium_render_svg(
+    renderer: *mut SvgRenderer,
+    module: *const DataModule,
+    job: *const RenderJob,
+) -> *mut c_char {
+    let (Some(renderer), Some(module), Some(job)) =
+        (renderer.as_mut(), module.as_ref(), job.as_ref())
+    else {
+        return ptr::null_mut();
+    };
+
+    let svg = guard(None, || {
+        renderer.render(module, job.clone()).ok()?;
+        Some(renderer.svg())
+    });
+
+    svg.and_then(|svg| CString::new(svg).ok())
+        .map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// # Safety
+/// `text` must come from this library and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn chartium_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
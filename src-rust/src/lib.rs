@@ -4,7 +4,12 @@ use wasm_bindgen::prelude::*;
 
 pub mod data;
 pub mod data_module;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod parallel;
 pub mod prelude;
+#[cfg(feature = "python")]
+pub mod python;
 pub mod renderers;
 pub mod structs;
 pub mod trace;
@@ -0,0 +1,72 @@
+//! Data parallelism for preparing large traces. Native builds with the `threads` feature
+//! spread work over rayon's global thread pool, everything else runs on the calling thread,
+//! as do all wasm builds, which don't start a pool of wasm threads.
+
+use std::{
+    ops::Range,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use wasm_bindgen::prelude::*;
+
+/// Fewest items worth handing to another thread
+pub const MIN_PART: usize = 1 << 15;
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Lets the data module spread work over rayon's global thread pool, the default, or with
+/// `false` keeps it on the calling thread. Only native builds with the `threads` feature have
+/// a pool, elsewhere it does nothing.
+#[wasm_bindgen]
+pub fn enable_threads(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Threads work is spread over, 1 when it runs on the calling thread
+pub fn threads() -> usize {
+    #[cfg(all(feature = "threads", not(target_arch = "wasm32")))]
+    if ENABLED.load(Ordering::Relaxed) {
+        return rayon::current_num_threads();
+    }
+
+    1
+}
+
+/// Splits `0..len` into a range per thread, each at least `min_len` long unless it's the only
+/// one. Every cut is moved forward to the next index `can_cut` allows a range to start at.
+pub fn split(len: usize, min_len: usize, can_cut: impl Fn(usize) -> bool) -> Vec<Range<usize>> {
+    let parts = threads().min(len / min_len.max(1)).max(1);
+    let mut ranges = Vec::with_capacity(parts);
+    let mut start = 0;
+
+    for part in 1..parts {
+        let mut cut = (len * part / parts).max(start + 1);
+        while cut < len && !can_cut(cut) {
+            cut += 1;
+        }
+        if cut >= len {
+            break;
+        }
+
+        ranges.push(start..cut);
+        start = cut;
+    }
+    ranges.push(start..len);
+
+    ranges
+}
+
+/// `f` of every range in order, computed in parallel when threads are enabled
+pub fn map<R: Send>(
+    ranges: &[Range<usize>],
+    f: impl Fn(Range<usize>) -> R + Sync + Send,
+) -> Vec<R> {
+    #[cfg(all(feature = "threads", not(target_arch = "wasm32")))]
+    if ranges.len() > 1 {
+        use rayon::prelude::*;
+
+        return ranges.par_iter().map(|r| f(r.clone())).collect();
+    }
+
+    ranges.iter().map(|r| f(r.clone())).collect()
+}
@@ -0,0 +1,170 @@
+//! Python bindings of the data module, decimation, statistics and export, so data can be
+//! preprocessed and checked with the same code the chart runs. Built as the `libchartium`
+//! extension module with the `python` feature, e.g. through maturin.
+
+// the conversions are in the code `#[pyfunction]` expands to
+#![allow(clippy::useless_conversion)]
+
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyDict};
+
+use crate::{
+    data::DataIdx,
+    data_module::{self, Downsampling, TraceStats},
+    prelude::*,
+};
+
+fn parse_method(method: &str) -> PyResult<Downsampling> {
+    match method {
+        "none" => Ok(Downsampling::None),
+        "m4" => Ok(Downsampling::M4),
+        "lttb" => Ok(Downsampling::Lttb),
+        _ => Err(PyValueError::new_err(format!(
+            "unknown downsampling method {method:?}, expected none, m4 or lttb"
+        ))),
+    }
+}
+
+fn stats_dict<'py>(py: Python<'py>, stats: TraceStats) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new_bound(py);
+    dict.set_item("count", stats.count)?;
+    dict.set_item("min", stats.min)?;
+    dict.set_item("max", stats.max)?;
+    dict.set_item("mean", stats.mean)?;
+    dict.set_item("variance", stats.variance)?;
+    dict.set_item("percentiles", stats.percentiles)?;
+
+    Ok(dict)
+}
+
+/// `DataModule` as a Python class, bound to the thread that created it as traces share their
+/// segments through `Rc`s
+#[pyclass(name = "DataModule", unsendable)]
+#[derive(Default)]
+pub struct PyDataModule(pub data_module::DataModule);
+
+#[pymethods]
+impl PyDataModule {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn create_trace(&mut self, id: &str, x_type: &str) -> DataIdx {
+        self.0.create_trace(id, x_type)
+    }
+
+    fn dispose_trace(&mut self, handle: DataIdx) {
+        self.0.dispose_trace(handle);
+    }
+
+    /// Loads the points of the `x` and `y` columns into a trace
+    fn load_trace(
+        &mut self,
+        handle: DataIdx,
+        x: Vec<RangePrec>,
+        y: Vec<RangePrec>,
+    ) -> PyResult<()> {
+        if x.len() != y.len() {
+            return Err(PyValueError::new_err("x and y differ in length"));
+        }
+
+        match self.0.load_points(handle, x, y) {
+            true => Ok(()),
+            false => Err(PyValueError::new_err(format!(
+                "no writable trace with handle {handle}"
+            ))),
+        }
+    }
+
+    /// `(x, y)` columns of the points of a trace between `from` and `to`
+    fn points(
+        &self,
+        handle: DataIdx,
+        from: RangePrec,
+        to: RangePrec,
+    ) -> Option<(Vec<RangePrec>, Vec<RangePrec>)> {
+        let trace = self.0.get_trace(handle)?;
+
+        Some(trace.get_data_high_prec(from, to).unzip())
+    }
+
+    /// Dictionary of the statistics `DataModule.trace_stats` reports in JS, `None` for unknown
+    /// handles and ranges without values
+    #[pyo3(signature = (handle, from, to, percentiles = vec![]))]
+    fn trace_stats<'py>(
+        &self,
+        py: Python<'py>,
+        handle: DataIdx,
+        from: RangePrec,
+        to: RangePrec,
+        percentiles: Vec<RangePrec>,
+    ) -> PyResult<Option<Bound<'py, PyDict>>> {
+        let Some(trace) = self.0.get_trace(handle) else {
+            return Ok(None);
+        };
+
+        let mut values: Vec<_> = trace.get_data_high_prec(from, to).map(|p| p.1).collect();
+
+        data_module::stats(&mut values, &percentiles)
+            .map(|stats| stats_dict(py, stats))
+            .transpose()
+    }
+
+    /// Checksum of all points of a trace, the same hex digits `DataModule.checksum` gives in JS
+    fn checksum(&self, handle: DataIdx) -> Option<String> {
+        self.0
+            .trace_checksum(handle)
+            .map(|checksum| format!("{:016x}", checksum))
+    }
+
+    /// CSV of the traces' values at every whole x from `from` to `to`, as the chart exports it
+    fn to_csv(&self, handles: Vec<DataIdx>, from: RangePrec, to: RangePrec) -> String {
+        self.0.print_data_as_csv(&handles, from, to)
+    }
+}
+
+/// Decimates the points of `x` and `y` into `buckets` with `method`, one of `"none"`, `"m4"`
+/// and `"lttb"`, the way bundles are before they're drawn
+#[pyfunction]
+fn downsample(
+    x: Vec<DataPrec>,
+    y: Vec<DataPrec>,
+    method: &str,
+    buckets: usize,
+) -> PyResult<(Vec<DataPrec>, Vec<DataPrec>)> {
+    let method = parse_method(method)?;
+    if x.len() != y.len() {
+        return Err(PyValueError::new_err("x and y differ in length"));
+    }
+    let (Some(&from), Some(&to)) = (x.first(), x.last()) else {
+        return Ok((x, y));
+    };
+
+    let points = x.into_iter().zip(y).collect();
+
+    Ok(data_module::downsample(points, method, from, to, buckets)
+        .into_iter()
+        .unzip())
+}
+
+/// Statistics of the finite `values`, `None` when there are none
+#[pyfunction]
+#[pyo3(signature = (values, percentiles = vec![]))]
+fn stats(
+    py: Python<'_>,
+    mut values: Vec<RangePrec>,
+    percentiles: Vec<RangePrec>,
+) -> PyResult<Option<Bound<'_, PyDict>>> {
+    data_module::stats(&mut values, &percentiles)
+        .map(|stats| stats_dict(py, stats))
+        .transpose()
+}
+
+#[pymodule]
+pub fn libchartium(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyDataModule>()?;
+    module.add_function(wrap_pyfunction!(downsample, module)?)?;
+    module.add_function(wrap_pyfunction!(stats, module)?)?;
+
+    Ok(())
+}
@@ -0,0 +1,319 @@
+//! Bundles of the renderers that draw every frame from the module, keeping only the entries
+//! of their bundles and, where they hit test, copies of the points
+
+use std::collections::{HashMap, HashSet};
+
+use super::{
+    draw_list::{CachedFrame, DrawList, DrawListCache, JobFingerprint},
+    raise_order, BundleEntry, HitIndex, HitResult, PlotRect,
+};
+use crate::{
+    data::DataIdx,
+    data_module::DataModule,
+    prelude::*,
+    structs::{Annotation, Annotations, RenderJob},
+};
+
+/// Frames kept by `BundleStore::frame`, enough to switch back and forth between a few views
+const CACHED_FRAMES: usize = 4;
+
+/// Entries of a bundle, whose data is read from the module when rendering, with copies of
+/// their points for hit tests
+struct StoredBundle {
+    from: RangePrec,
+    to: RangePrec,
+    rows: Vec<(BundleEntry, HitIndex)>,
+}
+
+/// Bundle bookkeeping of the CPU renderers, which have no buffers to build. Bundles begun
+/// are complete right away, so `step_bundle` has nothing left to do. Along with the
+/// annotations, they make up the draw lists of frames, the recent ones of which are cached.
+pub struct BundleStore {
+    /// Whether the points of entries are copied for hit tests
+    indexed: bool,
+    counter: usize,
+    bundles: HashMap<usize, StoredBundle>,
+    /// Bundles hidden by `set_visible`
+    hidden: HashSet<usize>,
+    annotations: Annotations,
+    /// Bumped on every change to the bundles or annotations
+    generation: u64,
+    frames: DrawListCache,
+}
+
+impl BundleStore {
+    pub fn new(indexed: bool) -> Self {
+        Self {
+            indexed,
+            counter: 0,
+            bundles: HashMap::new(),
+            hidden: HashSet::new(),
+            annotations: Annotations::default(),
+            generation: 0,
+            frames: DrawListCache::new(CACHED_FRAMES),
+        }
+    }
+
+    /// A handle no bundle has had yet, which heatmaps may take as well
+    pub fn next_handle(&mut self) -> usize {
+        self.counter += 1;
+        self.counter - 1
+    }
+
+    fn rows(
+        &self,
+        module: &DataModule,
+        from: RangePrec,
+        to: RangePrec,
+        entries: &[BundleEntry],
+    ) -> Vec<(BundleEntry, HitIndex)> {
+        entries
+            .iter()
+            .map(|entry| {
+                let index = match self.indexed {
+                    true => HitIndex::of_trace(module, entry.handle, from, to),
+                    false => HitIndex::new(from, Vec::new()),
+                };
+                (entry.clone(), index)
+            })
+            .collect()
+    }
+
+    fn insert(
+        &mut self,
+        module: &DataModule,
+        handle: usize,
+        from: RangePrec,
+        to: RangePrec,
+        data: &[BundleEntry],
+    ) -> usize {
+        let rows = self.rows(module, from, to, data);
+        self.bundles.insert(handle, StoredBundle { from, to, rows });
+        self.generation += 1;
+
+        handle
+    }
+
+    pub fn create(
+        &mut self,
+        module: &DataModule,
+        from: RangePrec,
+        to: RangePrec,
+        data: &[BundleEntry],
+    ) -> usize {
+        let handle = self.next_handle();
+        self.insert(module, handle, from, to, data)
+    }
+
+    /// Stores a bundle under the handle of the one it `replaces`, or a new one if that's gone
+    pub fn begin(
+        &mut self,
+        module: &DataModule,
+        from: RangePrec,
+        to: RangePrec,
+        data: &[BundleEntry],
+        replaces: Option<usize>,
+    ) -> usize {
+        let handle = match replaces {
+            Some(bundle) if self.bundles.contains_key(&bundle) => bundle,
+            _ => self.next_handle(),
+        };
+        self.insert(module, handle, from, to, data)
+    }
+
+    pub fn rebundle(
+        &mut self,
+        module: &DataModule,
+        bundle: usize,
+        to_add: &[BundleEntry],
+        to_del: &[DataIdx],
+        to_mod: &[BundleEntry],
+    ) {
+        let Some(b) = self.bundles.get(&bundle) else {
+            return;
+        };
+        let added = self.rows(module, b.from, b.to, to_add);
+        let b = self.bundles.get_mut(&bundle).unwrap();
+
+        b.rows.extend(added);
+        b.rows.retain(|(e, _)| !to_del.contains(&e.handle));
+
+        for row in to_mod {
+            if let Some((entry, _)) = b.rows.iter_mut().find(|(e, _)| e.handle == row.handle) {
+                *entry = entry.restyled(row);
+            }
+        }
+        self.generation += 1;
+    }
+
+    pub fn dispose(&mut self, bundle: usize) {
+        self.bundles.remove(&bundle);
+        self.hidden.remove(&bundle);
+        self.generation += 1;
+    }
+
+    pub fn set_visible(&mut self, bundle: usize, visible: bool) {
+        match visible {
+            true => self.hidden.remove(&bundle),
+            false => self.hidden.insert(bundle),
+        };
+        self.generation += 1;
+    }
+
+    /// Appends `points` to the hit test copy of the entry of `handle`, the lines themselves
+    /// are drawn from the module
+    pub fn extend(&mut self, bundle: usize, handle: DataIdx, points: &[(RangePrec, RangePrec)]) {
+        self.generation += 1;
+        if !self.indexed {
+            return;
+        }
+
+        let Some(b) = self.bundles.get_mut(&bundle) else {
+            return;
+        };
+        let from = b.from;
+        let Some((_, index)) = b.rows.iter_mut().find(|(e, _)| e.handle == handle) else {
+            return;
+        };
+
+        let mut last = index.points().last().map(|p| p.0);
+        let points: Vec<(DataPrec, DataPrec)> = points
+            .iter()
+            .map(|&(x, y)| ((x - from) as DataPrec, y as DataPrec))
+            .filter(|&(x, _)| {
+                let next = last.is_none_or(|last| x > last);
+                if next {
+                    last = Some(x);
+                }
+                next
+            })
+            .collect();
+
+        if let Some(&(last_x, _)) = index.append(&points).last() {
+            b.to = b.to.max(from + last_x as RangePrec);
+        }
+    }
+
+    /// Assigns `(handle, stack_group, stack_order)` to the bundle's entries
+    pub fn restack(&mut self, bundle: usize, stacking: &[(DataIdx, u32, i32)]) {
+        let Some(b) = self.bundles.get_mut(&bundle) else {
+            return;
+        };
+
+        for &(handle, group, order) in stacking {
+            if let Some((entry, _)) = b.rows.iter_mut().find(|(e, _)| e.handle == handle) {
+                entry.stack_group = group;
+                entry.stack_order = order;
+            }
+        }
+        self.generation += 1;
+    }
+
+    pub fn add_annotation(&mut self, annotation: Annotation) -> u32 {
+        self.generation += 1;
+        self.annotations.add(annotation)
+    }
+
+    pub fn remove_annotation(&mut self, id: u32) -> bool {
+        self.generation += 1;
+        self.annotations.remove(id)
+    }
+
+    /// Bundles of `job` not hidden by `set_visible`, in its order
+    fn shown<'a>(&'a self, job: &'a RenderJob) -> impl Iterator<Item = &'a StoredBundle> + 'a {
+        job.get_bundles()
+            .iter()
+            .filter(|b| !self.hidden.contains(b))
+            .filter_map(|b| self.bundles.get(b))
+    }
+
+    /// Entries of the shown bundles followed by the traces of `job`, in the legend's order
+    pub fn entries(&self, job: &RenderJob) -> Vec<BundleEntry> {
+        self.shown(job)
+            .flat_map(|b| b.rows.iter().map(|(entry, _)| entry.clone()))
+            .chain(job.get_traces().iter().map(BundleEntry::of_trace))
+            .collect()
+    }
+
+    /// The same entries in the order they're drawn, those of each bundle by `raise_order`.
+    /// Only the drawing is reordered, the legend keeps the order of the bundles.
+    fn drawn(&self, module: &DataModule, job: &RenderJob) -> Vec<BundleEntry> {
+        self.shown(job)
+            .flat_map(|b| {
+                let values = b.rows.iter().map(|(entry, index)| {
+                    let value = job.raise_at_x.and_then(|x| match self.indexed {
+                        true => index.value_at(x),
+                        false => module.get_trace(entry.handle)?.get_data_at(x),
+                    });
+                    (entry.handle, value, entry.y_transform())
+                });
+
+                raise_order(job, module, values)
+                    .into_iter()
+                    .map(|i| b.rows[i].0.clone())
+            })
+            .chain(job.get_traces().iter().map(BundleEntry::of_trace))
+            .collect()
+    }
+
+    /// Draw list of the whole frame of `job` and the ticks it was drawn with, taken from the
+    /// cache while neither the view, the bundles nor the points in `module` changed
+    pub fn frame(
+        &mut self,
+        module: &DataModule,
+        job: &RenderJob,
+        width: u32,
+        height: u32,
+        measure: impl Fn(&str) -> f32,
+    ) -> CachedFrame {
+        let key = JobFingerprint::of(job, width, height, self.generation)
+            .with_revision(module.revision());
+        if let Some(frame) = self.frames.get(key) {
+            return frame;
+        }
+
+        let drawn = self.drawn(module, job);
+        let frame = DrawList::build(
+            module,
+            job,
+            width,
+            height,
+            &drawn,
+            &self.annotations,
+            measure,
+        );
+        self.frames.insert(key, frame)
+    }
+
+    /// Nearest point of the shown entries as `Renderer::find_closest` reports it, `None`
+    /// when the points aren't copied
+    pub fn find_closest(
+        &self,
+        job: &RenderJob,
+        rect: &PlotRect,
+        (x, y): (RangePrec, RangePrec),
+        max_px: f32,
+    ) -> Option<HitResult> {
+        self.shown(job)
+            .flat_map(|b| b.rows.iter())
+            .filter(|(entry, _)| !job.is_blacklisted(entry.handle))
+            .filter_map(|(entry, index)| {
+                let range = job.y_range(entry.handle);
+                let (scale, offset) = entry.y_transform();
+                index
+                    .nearest(job, rect, (x, y), range, (scale, offset), max_px)
+                    .map(|(i, dist)| {
+                        let (x, y) = index.point(i);
+                        (entry.handle, (x, y * scale + offset), i, dist)
+                    })
+            })
+            .min_by(|a, b| a.3.partial_cmp(&b.3).unwrap())
+            .map(|(handle, (x, y), index, px_distance)| HitResult {
+                handle,
+                index,
+                x,
+                y,
+                px_distance,
+            })
+    }
+}
@@ -0,0 +1,587 @@
+//! Path drawing into a 2D canvas, standing in for the GPU on browsers without WebGL
+
+use std::{collections::HashMap, f64::consts::TAU};
+
+use wasm_bindgen::{Clamped, JsCast, JsValue};
+use web_sys::{ImageData, OffscreenCanvas, OffscreenCanvasRenderingContext2d};
+
+use super::{
+    bundle_store::BundleStore,
+    draw_list::{Color, DrawList, Primitive},
+    geometry::{self, LineCap, LineJoin},
+    heatmap::colorize,
+    legend::{legend_items, LegendLayout},
+    text::FONT_PX,
+    Align, BundleEntry, BundleOptions, FeatureSet, HitResult, PlotRect, RenderJobResult, Renderer,
+    RendererOptions,
+};
+use crate::{
+    data::DataIdx,
+    data_module::{DataModule, Decimation, Fidelity},
+    prelude::*,
+    structs::{Annotation, Colormap, Marker, RenderJob},
+};
+
+/// Tolerance polylines are simplified with, finer than the canvas resolves
+const SIMPLIFY_PX: f32 = 0.25;
+
+/// Grid kept as an image of a pixel per cell, stretched over its extent when drawn
+struct CanvasHeatmap {
+    image: OffscreenCanvas,
+    x: (RangePrec, RangePrec),
+    y: (RangePrec, RangePrec),
+}
+
+/// Renderer drawing the frames the GPU would with the paths of a 2D context, straight into
+/// the present canvas. Slower than WebGL with many points, as every frame is drawn from the
+/// module, but available everywhere.
+pub struct Canvas2dRenderer {
+    width: u32,
+    height: u32,
+    line_join: LineJoin,
+    line_cap: LineCap,
+
+    canvas: OffscreenCanvas,
+    context: OffscreenCanvasRenderingContext2d,
+
+    bundles: BundleStore,
+    heatmaps: HashMap<usize, CanvasHeatmap>,
+}
+
+impl Canvas2dRenderer {
+    pub fn new(present_canvas: OffscreenCanvas, ropts: RendererOptions) -> Result<Self, JsValue> {
+        Ok(Self {
+            width: present_canvas.width(),
+            height: present_canvas.height(),
+            line_join: ropts.line_join,
+            line_cap: ropts.line_cap,
+            context: context_2d(&present_canvas)?,
+            canvas: present_canvas,
+            bundles: BundleStore::new(true),
+            heatmaps: HashMap::new(),
+        })
+    }
+
+    /// Canvas y of the draw list y, which grows upwards
+    fn flip(&self, y: f32) -> f64 {
+        (self.height as f32 - y) as f64
+    }
+
+    fn measure(context: &OffscreenCanvasRenderingContext2d, text: &str) -> f32 {
+        context
+            .measure_text(text)
+            .map_or(0.0, |metrics| metrics.width() as f32)
+    }
+
+    fn trace(&self, points: &[(f32, f32)]) {
+        for (i, &(x, y)) in points.iter().enumerate() {
+            match i {
+                0 => self.context.move_to(x as f64, self.flip(y)),
+                _ => self.context.line_to(x as f64, self.flip(y)),
+            }
+        }
+    }
+
+    fn stroke(&self, color: &Color, width: f32) {
+        self.context.set_stroke_style_str(&css(color));
+        self.context.set_line_width(width as f64);
+        self.context.stroke();
+    }
+
+    fn execute(&self, list: &DrawList, simplify_px: f32) {
+        let ctx = &self.context;
+        let mut clipped = false;
+
+        ctx.set_line_join(match self.line_join {
+            LineJoin::Miter => "miter",
+            LineJoin::Bevel => "bevel",
+        });
+
+        for primitive in &list.primitives {
+            match primitive {
+                Primitive::Polyline {
+                    points,
+                    color,
+                    width,
+                } => {
+                    ctx.begin_path();
+                    self.trace(&geometry::simplify(points, simplify_px));
+                    ctx.set_line_cap(match self.line_cap {
+                        LineCap::Butt => "butt",
+                        LineCap::Round => "round",
+                    });
+                    self.stroke(color, *width);
+                }
+                Primitive::Segments {
+                    points,
+                    color,
+                    width,
+                } => {
+                    ctx.begin_path();
+                    for pair in points.chunks_exact(2) {
+                        self.trace(pair);
+                    }
+                    ctx.set_line_cap("butt");
+                    self.stroke(color, *width);
+                }
+                Primitive::Triangles { points, color } => {
+                    ctx.begin_path();
+                    for tri in points.chunks_exact(3) {
+                        self.trace(tri);
+                        ctx.close_path();
+                    }
+                    ctx.set_fill_style_str(&css(color));
+                    ctx.fill();
+                }
+                Primitive::Quads {
+                    rects,
+                    color,
+                    marker,
+                    rotation,
+                } => {
+                    ctx.begin_path();
+                    for &[x, y, w, h] in rects {
+                        self.marker_path(*marker, (x + w / 2.0, y + h / 2.0), (w, h), *rotation);
+                    }
+                    ctx.set_fill_style_str(&css(color));
+                    ctx.fill();
+                }
+                Primitive::Glyphs { labels, color } => {
+                    ctx.set_fill_style_str(&css(color));
+                    ctx.set_text_baseline("middle");
+
+                    for label in labels {
+                        ctx.set_text_align(match label.align {
+                            Align::Left => "left",
+                            Align::Center => "center",
+                            Align::Right => "right",
+                        });
+
+                        // the canvas turns clockwise, its y pointing down
+                        ctx.save();
+                        let _ = ctx.translate(label.x as f64, self.flip(label.y));
+                        let _ = ctx.rotate(-label.rotation.to_radians() as f64);
+                        let _ = ctx.fill_text(&label.text, 0.0, 0.0);
+                        ctx.restore();
+                    }
+                }
+                Primitive::Clip(rect) => {
+                    if clipped {
+                        ctx.restore();
+                    }
+
+                    clipped = rect.is_some();
+                    if let Some(rect) = rect {
+                        ctx.save();
+                        ctx.begin_path();
+                        ctx.rect(
+                            rect.left as f64,
+                            self.flip(rect.top() as f32),
+                            rect.width as f64,
+                            rect.height as f64,
+                        );
+                        ctx.clip();
+                    }
+                }
+            }
+        }
+
+        if clipped {
+            ctx.restore();
+        }
+    }
+
+    /// Adds the outline of a `marker` of `size` centered at `center` to the current path,
+    /// turned counterclockwise by `rotation` degrees, shaped as the GPU draws it
+    fn marker_path(&self, marker: Marker, center: (f32, f32), size: (f32, f32), rotation: f32) {
+        let ctx = &self.context;
+        let (rx, ry) = (size.0 / 2.0, size.1 / 2.0);
+        let (sin, cos) = rotation.to_radians().sin_cos();
+        let (cx, cy) = (center.0 as f64, self.flip(center.1));
+
+        if marker == Marker::Circle {
+            ctx.move_to(cx + (rx * cos) as f64, cy - (rx * sin) as f64);
+            let _ = ctx.ellipse(
+                cx,
+                cy,
+                rx as f64,
+                ry as f64,
+                -rotation.to_radians() as f64,
+                0.0,
+                TAU,
+            );
+            return;
+        }
+
+        let polygon = |corners: &[(f32, f32)]| {
+            for (i, &(x, y)) in corners.iter().enumerate() {
+                let (x, y) = (x * cos - y * sin, x * sin + y * cos);
+                match i {
+                    0 => ctx.move_to(cx + x as f64, cy - y as f64),
+                    _ => ctx.line_to(cx + x as f64, cy - y as f64),
+                }
+            }
+            ctx.close_path();
+        };
+
+        match marker {
+            Marker::Triangle => polygon(&[(0.0, ry), (rx, -ry), (-rx, -ry)]),
+            Marker::Diamond => polygon(&[(0.0, ry), (rx, 0.0), (0.0, -ry), (-rx, 0.0)]),
+            // arms a quarter of the marker wide
+            Marker::Cross => {
+                let (ax, ay) = (rx / 4.0, ry / 4.0);
+                polygon(&[(-rx, -ay), (rx, -ay), (rx, ay), (-rx, ay)]);
+                polygon(&[(-ax, -ry), (ax, -ry), (ax, ry), (-ax, ry)]);
+            }
+            _ => polygon(&[(-rx, -ry), (rx, -ry), (rx, ry), (-rx, ry)]),
+        }
+    }
+
+    fn draw_heatmap(&self, heatmap: usize, job: &RenderJob) -> Result<(), JsValue> {
+        let Some(layer) = self.heatmaps.get(&heatmap) else {
+            return Ok(());
+        };
+
+        let rect = PlotRect::of(job, self.width, self.height);
+        let xs = job.x_axis_scale();
+        let ys = job.y_axis_scale();
+
+        let x_at =
+            |x| rect.left as f32 + rect.width as f32 * xs.position(x, job.x_from, job.x_to) as f32;
+        let y_at = |y| {
+            rect.bottom as f32 + rect.height as f32 * ys.position(y, job.y_from, job.y_to) as f32
+        };
+
+        let (x0, x1) = (x_at(layer.x.0), x_at(layer.x.1));
+        let (y0, y1) = (y_at(layer.y.0), y_at(layer.y.1));
+
+        let ctx = &self.context;
+        ctx.save();
+        ctx.begin_path();
+        ctx.rect(
+            rect.left as f64,
+            self.flip(rect.top() as f32),
+            rect.width as f64,
+            rect.height as f64,
+        );
+        ctx.clip();
+        // cells are drawn as blocks, as the GPU samples them
+        ctx.set_image_smoothing_enabled(false);
+        let drawn = ctx.draw_image_with_offscreen_canvas_and_dw_and_dh(
+            &layer.image,
+            x0.min(x1) as f64,
+            self.flip(y0.max(y1)),
+            (x1 - x0).abs() as f64,
+            (y1 - y0).abs() as f64,
+        );
+        ctx.restore();
+
+        drawn
+    }
+}
+
+fn context_2d(canvas: &OffscreenCanvas) -> Result<OffscreenCanvasRenderingContext2d, JsValue> {
+    canvas
+        .get_context("2d")?
+        .ok_or_else(|| JsValue::from_str("The 2D canvas context is not available"))?
+        .dyn_into::<OffscreenCanvasRenderingContext2d>()
+        .map_err(JsValue::from)
+}
+
+/// CSS color of the premultiplied `color`
+fn css(color: &Color) -> String {
+    let a = color[3];
+    let channel = |c: f32| {
+        let c = if a > 0.0 { c / a } else { c };
+        (c.clamp(0.0, 1.0) * 255.0).round() as u8
+    };
+
+    format!(
+        "rgba({},{},{},{})",
+        channel(color[0]),
+        channel(color[1]),
+        channel(color[2]),
+        a.clamp(0.0, 1.0)
+    )
+}
+
+impl Renderer for Canvas2dRenderer {
+    fn render(&mut self, module: &DataModule, job: RenderJob) -> Result<RenderJobResult, JsValue> {
+        if job.clear {
+            self.context
+                .clear_rect(0.0, 0.0, self.width as f64, self.height as f64);
+            if let Some(rgb) = job.clear_color() {
+                let [r, g, b] = rgb.map(|c| c as f32 / 255.0);
+                self.context.set_fill_style_str(&css(&[r, g, b, 1.0]));
+                self.context
+                    .fill_rect(0.0, 0.0, self.width as f64, self.height as f64);
+            }
+        }
+        self.context.set_font(&format!("{}px sans-serif", FONT_PX));
+
+        for &heatmap in job.get_heatmaps() {
+            self.draw_heatmap(heatmap, &job)?;
+        }
+
+        let entries = self.bundles.entries(&job);
+        let frame = self
+            .bundles
+            .frame(module, &job, self.width, self.height, |text| {
+                Self::measure(&self.context, text)
+            });
+        let mut result = frame.1.clone();
+        let simplify_px = if job.exact { 0.0 } else { SIMPLIFY_PX };
+        self.execute(&frame.0, simplify_px);
+
+        if job.render_legend {
+            let traces = entries.iter().map(|e| (e.handle, e.color, e.width));
+            let items = legend_items(module, &job, traces);
+
+            if !items.is_empty() {
+                let rect = PlotRect::of(&job, self.width, self.height);
+                let layout =
+                    LegendLayout::of(&items, &rect, |text| Self::measure(&self.context, text));
+                let mut legend = DrawList::new(self.width, self.height);
+                legend.push_legend(&job, &items, &layout);
+                self.execute(&legend, 0.0);
+
+                result.set_legend(layout.hits(&items, self.height));
+            }
+        }
+
+        let decimation = match simplify_px > 0.0 {
+            true => Decimation {
+                fidelity: Fidelity::Lossy,
+                max_error: None,
+            },
+            false => Decimation::EXACT,
+        };
+        for entry in &entries {
+            result.add_decimation(entry.handle, decimation);
+        }
+
+        Ok(result)
+    }
+
+    fn present(&mut self) -> Result<(), JsValue> {
+        // frames are drawn into the present canvas itself
+        Ok(())
+    }
+
+    fn add_annotation(&mut self, annotation: Annotation) -> u32 {
+        self.bundles.add_annotation(annotation)
+    }
+
+    fn remove_annotation(&mut self, id: u32) -> bool {
+        self.bundles.remove_annotation(id)
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn size_changed(&mut self, width: u32, height: u32) -> Result<(), JsValue> {
+        self.width = width;
+        self.height = height;
+        self.canvas.set_width(width);
+        self.canvas.set_height(height);
+
+        Ok(())
+    }
+
+    fn create_bundle(
+        &mut self,
+        module: &DataModule,
+        from: RangePrec,
+        to: RangePrec,
+        data: &[BundleEntry],
+        _opts: BundleOptions,
+    ) -> Result<usize, JsValue> {
+        Ok(self.bundles.create(module, from, to, data))
+    }
+
+    fn rebundle(
+        &mut self,
+        module: &DataModule,
+        bundle: usize,
+        to_add: &[BundleEntry],
+        to_del: &[DataIdx],
+        to_mod: &[BundleEntry],
+    ) -> Result<(), JsValue> {
+        self.bundles
+            .rebundle(module, bundle, to_add, to_del, to_mod);
+
+        Ok(())
+    }
+
+    fn begin_bundle(
+        &mut self,
+        module: &DataModule,
+        from: RangePrec,
+        to: RangePrec,
+        data: &[BundleEntry],
+        _opts: BundleOptions,
+        replaces: Option<usize>,
+    ) -> Result<usize, JsValue> {
+        Ok(self.bundles.begin(module, from, to, data, replaces))
+    }
+
+    fn step_bundle(
+        &mut self,
+        _module: &DataModule,
+        _bundle: usize,
+        _chunks: usize,
+    ) -> Result<f64, JsValue> {
+        Ok(1.0)
+    }
+
+    fn dispose_bundle(&mut self, bundle: usize) -> Result<(), JsValue> {
+        self.bundles.dispose(bundle);
+
+        Ok(())
+    }
+
+    fn set_bundle_visible(&mut self, bundle: usize, visible: bool) {
+        self.bundles.set_visible(bundle, visible);
+    }
+
+    fn extend_bundle(
+        &mut self,
+        bundle: usize,
+        handle: DataIdx,
+        points: &[(RangePrec, RangePrec)],
+    ) -> Result<(), JsValue> {
+        self.bundles.extend(bundle, handle, points);
+
+        Ok(())
+    }
+
+    fn create_heatmap(
+        &mut self,
+        values: &[f32],
+        cols: usize,
+        x: (RangePrec, RangePrec),
+        y: (RangePrec, RangePrec),
+        colormap: &Colormap,
+    ) -> Result<usize, JsValue> {
+        let cols = cols.max(1);
+        let (pixels, rows) = colorize(values, cols, colormap);
+
+        // the first row lies at the bottom, image data starts at the top
+        let flipped: Vec<u8> = pixels
+            .chunks_exact(cols * 4)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+
+        let image = OffscreenCanvas::new(cols as u32, rows.max(1) as u32)?;
+        if rows > 0 {
+            let data = ImageData::new_with_u8_clamped_array_and_sh(
+                Clamped(&flipped),
+                cols as u32,
+                rows as u32,
+            )?;
+            context_2d(&image)?.put_image_data(&data, 0.0, 0.0)?;
+        }
+
+        let handle = self.bundles.next_handle();
+        self.heatmaps.insert(handle, CanvasHeatmap { image, x, y });
+
+        Ok(handle)
+    }
+
+    fn dispose_heatmap(&mut self, heatmap: usize) -> Result<(), JsValue> {
+        self.heatmaps.remove(&heatmap);
+
+        Ok(())
+    }
+
+    fn restack_bundle(
+        &mut self,
+        _module: &DataModule,
+        bundle: usize,
+        stacking: &[(DataIdx, u32, i32)],
+    ) -> Result<(), JsValue> {
+        self.bundles.restack(bundle, stacking);
+
+        Ok(())
+    }
+
+    fn suspend(&mut self, _release_bundles: bool) -> Result<(), JsValue> {
+        Ok(())
+    }
+
+    fn resume(&mut self, _module: &DataModule) -> Result<(), JsValue> {
+        Ok(())
+    }
+
+    fn render_offscreen(
+        &mut self,
+        module: &DataModule,
+        jobs: &[RenderJob],
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<u8>, JsValue> {
+        let target = OffscreenCanvas::new(width, height)?;
+        let context = context_2d(&target)?;
+
+        // the jobs are drawn into the target as if it was the present canvas
+        let canvas = std::mem::replace(&mut self.canvas, target);
+        let context = std::mem::replace(&mut self.context, context);
+        let size = (self.width, self.height);
+        (self.width, self.height) = (width, height);
+
+        let rendered = jobs
+            .iter()
+            .try_for_each(|job| self.render(module, job.clone()).map(|_| ()));
+        let image = rendered.and_then(|_| {
+            self.context
+                .get_image_data(0.0, 0.0, width as f64, height as f64)
+        });
+
+        self.canvas = canvas;
+        self.context = context;
+        (self.width, self.height) = size;
+
+        // image data is straight alpha and starts at the top
+        Ok(image?
+            .data()
+            .chunks_exact(width as usize * 4)
+            .rev()
+            .flat_map(|row| {
+                row.chunks_exact(4).flat_map(|px| {
+                    let a = px[3] as u32;
+                    let channel = |c: u8| ((c as u32 * a + 127) / 255) as u8;
+
+                    [channel(px[0]), channel(px[1]), channel(px[2]), px[3]]
+                })
+            })
+            .collect())
+    }
+
+    fn find_closest(
+        &self,
+        job: &RenderJob,
+        x: RangePrec,
+        y: RangePrec,
+        max_px: f32,
+    ) -> Option<HitResult> {
+        let rect = PlotRect::of(job, self.width, self.height);
+        self.bundles.find_closest(job, &rect, (x, y), max_px)
+    }
+
+    fn backend(&self) -> &'static str {
+        "canvas2d"
+    }
+
+    fn features(&self) -> FeatureSet {
+        // paths take any marker shape and size, and are antialiased by the browser
+        FeatureSet {
+            dash: true,
+            instancing: true,
+            text: true,
+            ..FeatureSet::default()
+        }
+    }
+}
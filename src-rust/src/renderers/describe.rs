@@ -0,0 +1,106 @@
+//! Structured description of a rendered frame, e.g. for hosts filling in ARIA descriptions
+
+use serde::Serialize;
+
+use super::RenderJobResult;
+use crate::{data::DataIdx, data_module::DataModule, prelude::*, structs::RenderJob};
+
+#[derive(Serialize, Debug)]
+pub struct AxisDescription {
+    pub from: RangePrec,
+    pub to: RangePrec,
+    pub unit: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TraceDescription {
+    pub handle: DataIdx,
+    pub name: String,
+    /// Whether the trace is plotted against the secondary y axis
+    pub y2: bool,
+    /// Y range drawn within the visible x range
+    pub min: DataPrec,
+    pub max: DataPrec,
+    /// `(x, y)` pairs at the sample positions, `y` being `None` where the trace has no data
+    pub samples: Vec<(RangePrec, Option<RangePrec>)>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PanelDescription {
+    /// Kind of the x values, e.g. `DateTime`
+    pub x_type: String,
+    pub x: AxisDescription,
+    pub y: AxisDescription,
+    pub y2: Option<AxisDescription>,
+    pub traces: Vec<TraceDescription>,
+}
+
+/// Describes the panel `job` drew, with the traces it reported in `result` sampled at
+/// `samples` positions evenly spaced across the plot
+pub fn describe_panel(
+    module: &DataModule,
+    job: &RenderJob,
+    result: &RenderJobResult,
+    samples: usize,
+) -> PanelDescription {
+    let (x_unit, y_unit, y2_unit) = job.units();
+    let axis = |from, to, unit: &str| AxisDescription {
+        from,
+        to,
+        unit: (!unit.is_empty()).then(|| unit.to_string()),
+    };
+
+    let positions = sample_positions(job, samples);
+
+    let traces = result
+        .extents
+        .iter()
+        .filter_map(|extent| {
+            let trace = module.get_trace(extent.handle)?;
+            let transform = match job.baseline_x {
+                Some(x) => module.relative_transform(extent.handle, x)?,
+                None => (1.0, 0.0),
+            };
+
+            Some(TraceDescription {
+                handle: extent.handle,
+                name: trace.id.clone(),
+                y2: job.on_y2(extent.handle),
+                min: extent.min,
+                max: extent.max,
+                samples: positions
+                    .iter()
+                    .map(|&x| {
+                        let y = trace.get_data_at(x).filter(|y| y.is_finite());
+                        (x, y.map(|y| y * transform.0 + transform.1))
+                    })
+                    .collect(),
+            })
+        })
+        .collect();
+
+    PanelDescription {
+        x_type: job.get_x_type().clone(),
+        x: axis(job.x_from, job.x_to, x_unit),
+        y: axis(job.y_from, job.y_to, y_unit),
+        y2: job.y2_range().map(|(from, to)| axis(from, to, y2_unit)),
+        traces,
+    }
+}
+
+/// X values `samples` evenly spaced pixels apart, the first and last at the plot's edges
+fn sample_positions(job: &RenderJob, samples: usize) -> Vec<RangePrec> {
+    let scale = job.x_axis_scale();
+    let (from, to) = (scale.apply(job.x_from), scale.apply(job.x_to));
+
+    (0..samples)
+        .map(|i| {
+            let t = match samples {
+                1 => 0.5,
+                _ => i as RangePrec / (samples - 1) as RangePrec,
+            };
+
+            scale.invert(from + (to - from) * t)
+        })
+        .collect()
+}
@@ -0,0 +1,1003 @@
+//! Renderer independent description of a frame, backends only have to draw its primitives
+
+use std::{collections::VecDeque, rc::Rc};
+
+use crate::{
+    data::DataIdx,
+    data_module::DataModule,
+    prelude::*,
+    structs::{AnnotationKind, Annotations, AxisScale, Marker, RenderJob},
+};
+
+use super::{
+    geometry,
+    legend::{LegendItem, LegendLayout},
+    text::FONT_PX,
+    ticks, Align, AxisOffset, AxisTick, BundleEntry, Label, PlotRect, RenderJobResult,
+    DEFAULT_MARKER_SIZE,
+};
+
+const TICK_LEN: f32 = 4.0;
+const LABEL_GAP: f32 = 2.0;
+const ARROW_SIZE: f32 = 5.0;
+const SNAP_MARKER_SIZE: f32 = 10.0;
+
+/// Color with premultiplied alpha
+pub type Color = [f32; 4];
+
+/// Shapes in canvas pixels, with the origin in the bottom left corner
+pub enum Primitive {
+    /// Line connecting all of `points`
+    Polyline {
+        points: Vec<(f32, f32)>,
+        color: Color,
+        width: f32,
+    },
+    /// Independent lines, one for each pair of `points`
+    Segments {
+        points: Vec<(f32, f32)>,
+        color: Color,
+        width: f32,
+    },
+    /// Independent triangles, one for each three `points`
+    Triangles {
+        points: Vec<(f32, f32)>,
+        color: Color,
+    },
+    /// Rectangles as `[x, y, width, height]`, each shaped as `marker` unless it's `None` and
+    /// turned counterclockwise around its center by `rotation` degrees
+    Quads {
+        rects: Vec<[f32; 4]>,
+        color: Color,
+        marker: Marker,
+        rotation: f32,
+    },
+    /// Text labels
+    Glyphs { labels: Vec<Label>, color: Color },
+    /// Clips the following primitives to a rectangle, or stops clipping
+    Clip(Option<PlotRect>),
+}
+
+pub struct DrawList {
+    pub width: u32,
+    pub height: u32,
+    pub primitives: Vec<Primitive>,
+}
+
+impl DrawList {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            primitives: Vec::new(),
+        }
+    }
+
+    /// Whole frame of `job` drawing `entries` straight from `module`, between the bands and
+    /// the lines of `annotations`
+    pub fn build(
+        module: &DataModule,
+        job: &RenderJob,
+        width: u32,
+        height: u32,
+        entries: &[BundleEntry],
+        annotations: &Annotations,
+        measure: impl Fn(&str) -> f32,
+    ) -> (Self, RenderJobResult) {
+        let (mut list, result) = Self::overlay(job, width, height, measure);
+
+        list.push_annotation_bands(job, annotations);
+
+        for entry in entries {
+            if !job.is_blacklisted(entry.handle) {
+                list.push_series(module, job, entry);
+            }
+        }
+
+        list.push_annotation_lines(job, annotations);
+
+        if let Some(now) = job.now {
+            list.push_now(job, now);
+        }
+
+        (list, result)
+    }
+
+    /// Axes and grid of `job`, everything drawn besides the data. Tick labels are measured
+    /// with `measure` to leave out or turn the ones that would overlap.
+    pub fn overlay(
+        job: &RenderJob,
+        width: u32,
+        height: u32,
+        measure: impl Fn(&str) -> f32,
+    ) -> (Self, RenderJobResult) {
+        let mut list = Self::new(width, height);
+        let rect = list.rect(job);
+        let mut result = ticks::for_job(job, &rect);
+        ticks::lay_out_labels(&mut result, job, &rect, measure);
+
+        if job.render_axes {
+            list.push_axes(
+                job,
+                &result.x_ticks,
+                &result.y_ticks,
+                &result.y2_ticks,
+                result.x_label_rotation,
+            );
+            list.push_axis_offsets(job, &result);
+        }
+
+        if job.render_grid {
+            list.push_grid(job, &result.x_ticks, &result.y_ticks);
+        }
+
+        (list, result)
+    }
+
+    pub fn push(&mut self, primitive: Primitive) {
+        self.primitives.push(primitive);
+    }
+
+    fn rect(&self, job: &RenderJob) -> PlotRect {
+        PlotRect::of(job, self.width, self.height)
+    }
+
+    /// Axis lines, tick marks and, where the job leaves space for them, the tick labels that
+    /// aren't hidden, the x ones turned by `x_label_rotation` degrees
+    pub fn push_axes(
+        &mut self,
+        job: &RenderJob,
+        x_ticks: &[AxisTick],
+        y_ticks: &[AxisTick],
+        y2_ticks: &[AxisTick],
+        x_label_rotation: f32,
+    ) {
+        let rect = self.rect(job);
+        let (left, bottom) = (rect.left as f32, rect.bottom as f32);
+        let (right, top) = (rect.right() as f32, rect.top() as f32);
+
+        let x_at = |pos: RangePrec| left + (right - left) * pos as f32;
+        let y_at = |pos: RangePrec| bottom + (top - bottom) * pos as f32;
+
+        let mut axes = vec![
+            (left - 1.0, top),
+            (left - 1.0, bottom - 1.0),
+            (right, bottom - 1.0),
+        ];
+        if job.y2_range().is_some() {
+            axes.push((right, top));
+        }
+
+        let color = [0.3, 0.3, 0.3, 1.0];
+        self.push(Primitive::Polyline {
+            points: axes,
+            color,
+            width: 2.0,
+        });
+
+        let mut marks = Vec::new();
+        for tick in x_ticks {
+            marks.extend([
+                (x_at(tick.pos), bottom),
+                (x_at(tick.pos), bottom - TICK_LEN),
+            ]);
+        }
+        for tick in y_ticks {
+            marks.extend([(left, y_at(tick.pos)), (left - TICK_LEN, y_at(tick.pos))]);
+        }
+        for tick in y2_ticks {
+            marks.extend([(right, y_at(tick.pos)), (right + TICK_LEN, y_at(tick.pos))]);
+        }
+
+        self.push(Primitive::Segments {
+            points: marks,
+            color,
+            width: 2.0,
+        });
+
+        let label = |tick: &AxisTick, x: f32, y: f32, align: Align| Label {
+            text: tick.label.clone(),
+            x,
+            y,
+            align,
+            rotation: 0.0,
+        };
+        let mut labels = Vec::new();
+
+        if job.x_label_space > 0 {
+            // turned labels end at their tick, hanging down to the left of it
+            let (y, align) = match x_label_rotation != 0.0 {
+                true => (bottom - TICK_LEN - LABEL_GAP, Align::Right),
+                false => (bottom - TICK_LEN - LABEL_GAP - FONT_PX / 2.0, Align::Center),
+            };
+            labels.extend(x_ticks.iter().filter(|t| !t.hidden).map(|t| Label {
+                rotation: x_label_rotation,
+                ..label(t, x_at(t.pos), y, align)
+            }));
+        }
+        if job.y_label_space > 0 {
+            let x = left - TICK_LEN - LABEL_GAP;
+            labels.extend(
+                y_ticks
+                    .iter()
+                    .filter(|t| !t.hidden)
+                    .map(|t| label(t, x, y_at(t.pos), Align::Right)),
+            );
+        }
+        if job.y2_label_space > 0 {
+            let x = right + TICK_LEN + LABEL_GAP;
+            labels.extend(
+                y2_ticks
+                    .iter()
+                    .filter(|t| !t.hidden)
+                    .map(|t| label(t, x, y_at(t.pos), Align::Left)),
+            );
+        }
+
+        self.push(Primitive::Glyphs {
+            labels,
+            color: if job.dark_background() {
+                [0.8, 0.8, 0.8, 1.0]
+            } else {
+                [0.2, 0.2, 0.2, 1.0]
+            },
+        });
+    }
+
+    /// Offsets the tick labels of `result` are relative to, inside the plot by the end of their
+    /// axis, where the job leaves space for the labels
+    pub fn push_axis_offsets(&mut self, job: &RenderJob, result: &RenderJobResult) {
+        let rect = self.rect(job);
+        let (left, bottom) = (rect.left as f32, rect.bottom as f32);
+        let (right, top) = (rect.right() as f32, rect.top() as f32);
+
+        let label = |offset: &AxisOffset, x: f32, y: f32, align: Align| Label {
+            text: offset.label.clone(),
+            x,
+            y,
+            align,
+            rotation: 0.0,
+        };
+        let (x_offset, y_offset, y2_offset) = result.offsets();
+        let labels: Vec<_> = [
+            x_offset.filter(|_| job.x_label_space > 0).map(|o| {
+                label(
+                    o,
+                    right - LABEL_GAP,
+                    bottom + LABEL_GAP + FONT_PX / 2.0,
+                    Align::Right,
+                )
+            }),
+            y_offset.filter(|_| job.y_label_space > 0).map(|o| {
+                label(
+                    o,
+                    left + LABEL_GAP,
+                    top - LABEL_GAP - FONT_PX / 2.0,
+                    Align::Left,
+                )
+            }),
+            y2_offset.filter(|_| job.y2_label_space > 0).map(|o| {
+                label(
+                    o,
+                    right - LABEL_GAP,
+                    top - LABEL_GAP - FONT_PX / 2.0,
+                    Align::Right,
+                )
+            }),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        if labels.is_empty() {
+            return;
+        }
+
+        self.push(Primitive::Glyphs {
+            labels,
+            color: if job.dark_background() {
+                [0.8, 0.8, 0.8, 1.0]
+            } else {
+                [0.2, 0.2, 0.2, 1.0]
+            },
+        });
+    }
+
+    /// Grid lines at the ticks, snapped to the pixel centers
+    pub fn push_grid(&mut self, job: &RenderJob, x_ticks: &[AxisTick], y_ticks: &[AxisTick]) {
+        let rect = self.rect(job);
+        let (left, bottom) = (rect.left as f32, rect.bottom as f32);
+        let (width, height) = (rect.width as f32, rect.height as f32);
+
+        let snap = |len: f32, pos: RangePrec| (len * pos as f32 + 0.5).round() - 0.5;
+        let mut lines = Vec::with_capacity((x_ticks.len() + y_ticks.len()) * 2);
+
+        for tick in x_ticks {
+            let x = left + snap(width, tick.pos);
+            lines.extend([(x, bottom), (x, bottom + height)]);
+        }
+        for tick in y_ticks {
+            let y = bottom + snap(height, tick.pos);
+            lines.extend([(left, y), (left + width, y)]);
+        }
+
+        self.push(Primitive::Segments {
+            points: lines,
+            color: if job.dark_background() {
+                [0.3, 0.3, 0.3, 1.0]
+            } else {
+                [0.85, 0.85, 0.85, 1.0]
+            },
+            width: 1.0,
+        });
+    }
+
+    /// Crosshair through `cursor` in data coordinates and a marker on the `snapped` point,
+    /// given with the handle and color of its trace. With `ruler` a vertical line in the
+    /// trace's color runs through the snapped point.
+    pub fn push_crosshair(
+        &mut self,
+        job: &RenderJob,
+        cursor: (RangePrec, RangePrec),
+        snapped: Option<(DataIdx, [u8; 3], (RangePrec, RangePrec))>,
+        ruler: bool,
+    ) {
+        let rect = self.rect(job);
+        let (left, bottom) = (rect.left as f32, rect.bottom as f32);
+        let (right, top) = (rect.right() as f32, rect.top() as f32);
+
+        let (xs, ys) = (job.x_axis_scale(), job.y_axis_scale());
+        let x_at = |x| left + rect.width as f32 * xs.position(x, job.x_from, job.x_to) as f32;
+        let y_at = |y, (from, to)| bottom + rect.height as f32 * ys.position(y, from, to) as f32;
+        // lines 1 pixel wide are crisp through the pixel centers
+        let snap = |v: f32| (v + 0.5).round() - 0.5;
+
+        self.push(Primitive::Clip(Some(rect)));
+
+        let (x, y) = (
+            snap(x_at(cursor.0)),
+            snap(y_at(cursor.1, (job.y_from, job.y_to))),
+        );
+        self.push(Primitive::Segments {
+            points: vec![(x, bottom), (x, top), (left, y), (right, y)],
+            color: if job.dark_background() {
+                [0.6, 0.6, 0.6, 1.0]
+            } else {
+                [0.4, 0.4, 0.4, 1.0]
+            },
+            width: 1.0,
+        });
+
+        if let Some((handle, rgb, (px, py))) = snapped {
+            let rgb = rgb.map(|c| c as f32 / 255.0);
+            let (x, y) = (x_at(px), y_at(py, job.y_range(handle)));
+
+            if ruler {
+                self.push(Primitive::Segments {
+                    points: vec![(snap(x), bottom), (snap(x), top)],
+                    color: [rgb[0] * 0.5, rgb[1] * 0.5, rgb[2] * 0.5, 0.5],
+                    width: 1.0,
+                });
+            }
+
+            let half = SNAP_MARKER_SIZE / 2.0;
+            self.push(Primitive::Quads {
+                rects: vec![[x - half, y - half, SNAP_MARKER_SIZE, SNAP_MARKER_SIZE]],
+                color: [rgb[0], rgb[1], rgb[2], 1.0],
+                marker: Marker::Circle,
+                rotation: 0.0,
+            });
+        }
+
+        self.push(Primitive::Clip(None));
+    }
+
+    /// Playhead at `x` in data coordinates with the `trail` x units behind it shaded, and a
+    /// marker on each of `values`, given with the handle and color of their traces
+    pub fn push_playhead(
+        &mut self,
+        job: &RenderJob,
+        x: RangePrec,
+        trail: RangePrec,
+        values: &[(DataIdx, [u8; 3], RangePrec)],
+    ) {
+        let rect = self.rect(job);
+        let (left, bottom) = (rect.left as f32, rect.bottom as f32);
+        let top = rect.top() as f32;
+
+        let (xs, ys) = (job.x_axis_scale(), job.y_axis_scale());
+        let x_at = |x| left + rect.width as f32 * xs.position(x, job.x_from, job.x_to) as f32;
+        let y_at = |y, (from, to)| bottom + rect.height as f32 * ys.position(y, from, to) as f32;
+        let snap = |v: f32| (v + 0.5).round() - 0.5;
+
+        self.push(Primitive::Clip(Some(rect)));
+
+        let head = snap(x_at(x));
+        if trail > 0.0 {
+            let tail = x_at(x - trail);
+            self.push(Primitive::Triangles {
+                points: vec![
+                    (tail, bottom),
+                    (head, bottom),
+                    (head, top),
+                    (tail, bottom),
+                    (head, top),
+                    (tail, top),
+                ],
+                color: [0.1, 0.05, 0.0, 0.1],
+            });
+        }
+
+        self.push(Primitive::Segments {
+            points: vec![(head, bottom), (head, top)],
+            color: [0.9, 0.45, 0.0, 1.0],
+            width: 1.0,
+        });
+
+        let half = SNAP_MARKER_SIZE / 2.0;
+        for &(handle, rgb, y) in values {
+            let rgb = rgb.map(|c| c as f32 / 255.0);
+            let y = y_at(y, job.y_range(handle));
+
+            self.push(Primitive::Quads {
+                rects: vec![[head - half, y - half, SNAP_MARKER_SIZE, SNAP_MARKER_SIZE]],
+                color: [rgb[0], rgb[1], rgb[2], 1.0],
+                marker: Marker::Circle,
+                rotation: 0.0,
+            });
+        }
+
+        self.push(Primitive::Clip(None));
+    }
+
+    /// Marker of the present at `now` in data coordinates, with the plot right of it shaded
+    /// if the job shades the future. The shading starts at the left edge once `now` is left
+    /// of the view.
+    pub fn push_now(&mut self, job: &RenderJob, now: RangePrec) {
+        let rect = self.rect(job);
+        let (left, bottom) = (rect.left as f32, rect.bottom as f32);
+        let (right, top) = (rect.right() as f32, rect.top() as f32);
+
+        let rel = job.x_axis_scale().position(now, job.x_from, job.x_to) as f32;
+        if !rel.is_finite() || rel > 1.0 || (!job.shade_future && rel < 0.0) {
+            return;
+        }
+        let x = (left + (right - left) * rel.max(0.0)).round() - 0.5;
+
+        if job.shade_future {
+            self.push(Primitive::Triangles {
+                points: vec![
+                    (x, bottom),
+                    (right, bottom),
+                    (right, top),
+                    (x, bottom),
+                    (right, top),
+                    (x, top),
+                ],
+                color: match job.dark_background() {
+                    true => [0.05, 0.05, 0.05, 0.1],
+                    false => [0.0, 0.0, 0.0, 0.06],
+                },
+            });
+        }
+
+        if rel >= 0.0 {
+            self.push(Primitive::Segments {
+                points: vec![(x, bottom), (x, top)],
+                color: match job.dark_background() {
+                    true => [0.8, 0.8, 0.8, 1.0],
+                    false => [0.2, 0.2, 0.2, 1.0],
+                },
+                width: 1.0,
+            });
+        }
+    }
+
+    /// Legend box listing `items` as placed by `layout`, the hidden ones dimmed
+    pub fn push_legend(&mut self, job: &RenderJob, items: &[LegendItem], layout: &LegendLayout) {
+        let [x, y, w, h] = layout.frame;
+        let (background, text) = match job.dark_background() {
+            true => ([0.1, 0.1, 0.1, 0.85], [0.8, 0.8, 0.8]),
+            false => ([0.85, 0.85, 0.85, 0.85], [0.2, 0.2, 0.2]),
+        };
+
+        self.push(Primitive::Triangles {
+            points: vec![
+                (x, y),
+                (x + w, y),
+                (x + w, y + h),
+                (x, y),
+                (x + w, y + h),
+                (x, y + h),
+            ],
+            color: background,
+        });
+
+        for (i, item) in items.iter().enumerate() {
+            let alpha = if item.hidden { 0.3 } else { 1.0 };
+            let rgb = item.color.map(|c| c as f32 / 255.0 * alpha);
+
+            self.push(Primitive::Quads {
+                rects: vec![layout.swatches[i]],
+                color: [rgb[0], rgb[1], rgb[2], alpha],
+                marker: Marker::None,
+                rotation: 0.0,
+            });
+
+            let (x, y) = layout.label_anchor(i);
+            self.push(Primitive::Glyphs {
+                labels: vec![Label {
+                    text: item.label.clone(),
+                    x,
+                    y,
+                    align: Align::Left,
+                    rotation: 0.0,
+                }],
+                color: [text[0] * alpha, text[1] * alpha, text[2] * alpha, alpha],
+            });
+        }
+    }
+
+    /// Translucent selection rectangle between the corners `from` and `to` in data coordinates
+    pub fn push_brush(
+        &mut self,
+        job: &RenderJob,
+        from: (RangePrec, RangePrec),
+        to: (RangePrec, RangePrec),
+    ) {
+        let rect = self.rect(job);
+        let (left, bottom) = (rect.left as f32, rect.bottom as f32);
+
+        let (xs, ys) = (job.x_axis_scale(), job.y_axis_scale());
+        let x_at = |x| left + rect.width as f32 * xs.position(x, job.x_from, job.x_to) as f32;
+        let y_at = |y| bottom + rect.height as f32 * ys.position(y, job.y_from, job.y_to) as f32;
+        let snap = |v: f32| (v + 0.5).round() - 0.5;
+
+        let (x0, x1) = (x_at(from.0), x_at(to.0));
+        let (y0, y1) = (y_at(from.1), y_at(to.1));
+        let (x0, x1) = (snap(x0.min(x1)), snap(x0.max(x1)));
+        let (y0, y1) = (snap(y0.min(y1)), snap(y0.max(y1)));
+
+        self.push(Primitive::Clip(Some(rect)));
+        self.push(Primitive::Triangles {
+            points: vec![(x0, y0), (x1, y0), (x1, y1), (x0, y0), (x1, y1), (x0, y1)],
+            color: [0.03, 0.06, 0.12, 0.15],
+        });
+        self.push(Primitive::Polyline {
+            points: vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1), (x0, y0)],
+            color: [0.16, 0.32, 0.64, 0.8],
+            width: 1.0,
+        });
+        self.push(Primitive::Clip(None));
+    }
+
+    /// Shaded regions of `annotations`, drawn beneath the data
+    pub fn push_annotation_bands(&mut self, job: &RenderJob, annotations: &Annotations) {
+        let rect = self.rect(job);
+        let (left, bottom) = (rect.left as f32, rect.bottom as f32);
+        let (right, top) = (rect.right() as f32, rect.top() as f32);
+
+        let (xs, ys) = (job.x_axis_scale(), job.y_axis_scale());
+        let x_at = |x| left + rect.width as f32 * xs.position(x, job.x_from, job.x_to) as f32;
+        let y_at = |y| bottom + rect.height as f32 * ys.position(y, job.y_from, job.y_to) as f32;
+
+        for annotation in annotations.iter() {
+            let (x0, y0, x1, y1) = match annotation.kind() {
+                AnnotationKind::XBand(from, to) => (x_at(from), bottom, x_at(to), top),
+                AnnotationKind::YBand(from, to) => (left, y_at(from), right, y_at(to)),
+                _ => continue,
+            };
+
+            let (x0, x1) = (x0.min(x1).max(left), x0.max(x1).min(right));
+            let (y0, y1) = (y0.min(y1).max(bottom), y0.max(y1).min(top));
+            if !(x0 < x1 && y0 < y1) {
+                continue;
+            }
+
+            let alpha = annotation.alpha.clamp(0.0, 1.0);
+            let rgb = annotation.color().map(|c| c as f32 / 255.0 * alpha);
+            self.push(Primitive::Triangles {
+                points: vec![(x0, y0), (x1, y0), (x1, y1), (x0, y0), (x1, y1), (x0, y1)],
+                color: [rgb[0], rgb[1], rgb[2], alpha],
+            });
+        }
+    }
+
+    /// Threshold lines of `annotations` and the labels of all of them, drawn over the data
+    pub fn push_annotation_lines(&mut self, job: &RenderJob, annotations: &Annotations) {
+        let rect = self.rect(job);
+        let (left, bottom) = (rect.left as f32, rect.bottom as f32);
+        let (right, top) = (rect.right() as f32, rect.top() as f32);
+
+        let (xs, ys) = (job.x_axis_scale(), job.y_axis_scale());
+        let x_at = |x| left + rect.width as f32 * xs.position(x, job.x_from, job.x_to) as f32;
+        let y_at = |y| bottom + rect.height as f32 * ys.position(y, job.y_from, job.y_to) as f32;
+        let snap = |v: f32| (v + 0.5).round() - 0.5;
+        // label centers sitting just inside of what they're attached to
+        let offset = FONT_PX / 2.0 + LABEL_GAP;
+
+        for annotation in annotations.iter() {
+            let text = annotation.label().to_string();
+
+            let (line, label) = match annotation.kind() {
+                AnnotationKind::HLine(y) => {
+                    let y = snap(y_at(y));
+                    if !(bottom..=top).contains(&y) {
+                        continue;
+                    }
+
+                    let label = Label {
+                        text,
+                        x: right - LABEL_GAP,
+                        y: y + offset,
+                        align: Align::Right,
+                        rotation: 0.0,
+                    };
+                    (Some([(left, y), (right, y)]), label)
+                }
+                AnnotationKind::VLine(x) => {
+                    let x = snap(x_at(x));
+                    if !(left..=right).contains(&x) {
+                        continue;
+                    }
+
+                    let label = Label {
+                        text,
+                        x: x + LABEL_GAP,
+                        y: top - offset,
+                        align: Align::Left,
+                        rotation: 0.0,
+                    };
+                    (Some([(x, bottom), (x, top)]), label)
+                }
+                AnnotationKind::XBand(from, to) => {
+                    let x = x_at(from).min(x_at(to)).max(left);
+                    if x.is_nan() || x >= right {
+                        continue;
+                    }
+
+                    let label = Label {
+                        text,
+                        x: x + LABEL_GAP,
+                        y: top - offset,
+                        align: Align::Left,
+                        rotation: 0.0,
+                    };
+                    (None, label)
+                }
+                AnnotationKind::YBand(from, to) => {
+                    let y = y_at(from).max(y_at(to)).min(top);
+                    if y.is_nan() || y <= bottom {
+                        continue;
+                    }
+
+                    let label = Label {
+                        text,
+                        x: right - LABEL_GAP,
+                        y: y - offset,
+                        align: Align::Right,
+                        rotation: 0.0,
+                    };
+                    (None, label)
+                }
+            };
+
+            let rgb = annotation.color().map(|c| c as f32 / 255.0);
+
+            if let Some([a, b]) = line {
+                let alpha = annotation.alpha.clamp(0.0, 1.0);
+                self.push(Primitive::Segments {
+                    points: geometry::dash_segments(a, b, annotation.dash()),
+                    color: [rgb[0] * alpha, rgb[1] * alpha, rgb[2] * alpha, alpha],
+                    width: annotation.width,
+                });
+            }
+
+            if !label.text.is_empty() {
+                self.push(Primitive::Glyphs {
+                    labels: vec![label],
+                    color: [rgb[0], rgb[1], rgb[2], 1.0],
+                });
+            }
+        }
+    }
+
+    /// Arrows at the top or bottom edge for each of the `traces`, given as handle, color and
+    /// visible extent, that lies entirely above or below the y range it's plotted against
+    pub fn push_out_of_view(
+        &mut self,
+        job: &RenderJob,
+        traces: &[(DataIdx, [u8; 3], (DataPrec, DataPrec))],
+    ) {
+        let rect = self.rect(job);
+        let (top, bottom) = (rect.top() as f32 - 2.0, rect.bottom as f32 + 2.0);
+        let (mut above, mut below) = (0, 0);
+
+        for &(handle, rgb, (min, max)) in traces {
+            let (from, to) = job.y_range(handle);
+            let (lo, hi) = (from.min(to), from.max(to));
+
+            // arrows on the same edge are lined up from the left
+            let slot = |count: &mut usize| {
+                *count += 1;
+                rect.left as f32 + ARROW_SIZE * 3.0 * *count as f32
+            };
+
+            let points = if min as RangePrec > hi {
+                let x = slot(&mut above);
+                vec![
+                    (x, top),
+                    (x - ARROW_SIZE, top - ARROW_SIZE),
+                    (x + ARROW_SIZE, top - ARROW_SIZE),
+                ]
+            } else if (max as RangePrec) < lo {
+                let x = slot(&mut below);
+                vec![
+                    (x, bottom),
+                    (x + ARROW_SIZE, bottom + ARROW_SIZE),
+                    (x - ARROW_SIZE, bottom + ARROW_SIZE),
+                ]
+            } else {
+                continue;
+            };
+
+            let rgb = rgb.map(|c| c as f32 / 255.0);
+            self.push(Primitive::Triangles {
+                points,
+                color: [rgb[0], rgb[1], rgb[2], 1.0],
+            });
+        }
+    }
+
+    /// Trace of `entry` as displayed by `job`, with its band fill and markers
+    pub fn push_series(&mut self, module: &DataModule, job: &RenderJob, entry: &BundleEntry) {
+        let rect = self.rect(job);
+        let Some(project) = Projection::new(module, job, &rect, entry.handle) else {
+            return;
+        };
+        let project = project.with_y_transform(entry.y_transform());
+        let Some(trace) = module.get_trace(entry.handle) else {
+            return;
+        };
+
+        // the overscan is clipped away with the rest outside of the plot
+        let (from, to) = job.overscan_range();
+        let mut data: Vec<(f32, f32)> = trace
+            .get_data_with_origin(from, to, job.x_from, 0.0)
+            .collect();
+        // bands are filled point by point against their lower trace, so they keep every point
+        let breaks = match entry.fill_to {
+            Some(_) => Vec::new(),
+            None => module.get_gap_rules(entry.handle).split(&mut data, None),
+        };
+        if data.is_empty() {
+            return;
+        }
+
+        let rgb = entry.color.map(|c| c as f32 / 255.0);
+        self.push(Primitive::Clip(Some(rect)));
+
+        if let Some(lower) = entry.fill_to.and_then(|h| module.get_trace(h)) {
+            let strip =
+                geometry::band_strip(&data, lower.get_data_with_origin(from, to, job.x_from, 0.0));
+            let a = entry.fill_alpha;
+
+            self.push(Primitive::Triangles {
+                points: strip_to_triangles(&strip)
+                    .into_iter()
+                    .map(|p| project.apply(p))
+                    .collect(),
+                color: [rgb[0] * a, rgb[1] * a, rgb[2] * a, a],
+            });
+        }
+
+        let points: Vec<(f32, f32)> = data.iter().map(|&p| project.apply(p)).collect();
+        let color = [rgb[0], rgb[1], rgb[2], 1.0];
+
+        if entry.width > 0 {
+            for strip in geometry::strips(&breaks, 0, points.len()) {
+                if strip.len() > 1 {
+                    self.push(Primitive::Polyline {
+                        points: points[strip].to_vec(),
+                        color,
+                        width: entry.width as f32,
+                    });
+                }
+            }
+        }
+
+        // a lone point doesn't form a line, so it at least gets a marker
+        let marker = match entry.marker {
+            Marker::None if points.len() == 1 => Marker::Circle,
+            marker => marker,
+        };
+
+        if marker != Marker::None {
+            let size = if entry.marker_size > 0 {
+                entry.marker_size as f32
+            } else {
+                DEFAULT_MARKER_SIZE.max(entry.width as f32 * 2.0)
+            };
+
+            self.push(Primitive::Quads {
+                rects: points
+                    .iter()
+                    .map(|&(x, y)| [x - size / 2.0, y - size / 2.0, size, size])
+                    .collect(),
+                color,
+                marker,
+                rotation: job.marker_rotation,
+            });
+        }
+
+        self.push(Primitive::Clip(None));
+    }
+}
+
+/// Maps trace points relative to the job's `x_from` into canvas pixels
+pub(super) struct Projection {
+    x_origin: RangePrec,
+    x_scale: AxisScale,
+    y_scale: AxisScale,
+    x_range: (RangePrec, RangePrec),
+    y_range: (RangePrec, RangePrec),
+    transform: (RangePrec, RangePrec),
+    rect: PlotRect,
+}
+
+impl Projection {
+    pub(super) fn new(
+        module: &DataModule,
+        job: &RenderJob,
+        rect: &PlotRect,
+        handle: usize,
+    ) -> Option<Self> {
+        let transform = match job.baseline_x {
+            Some(x) => module.relative_transform(handle, x)?,
+            None => (1.0, 0.0),
+        };
+
+        let x_scale = job.x_axis_scale();
+        let y_scale = job.y_axis_scale();
+        let (y_from, y_to) = job.y_range(handle);
+        // reversed axes have their ends swapped, mapping `to` onto the left or bottom edge
+        let range = |scale: AxisScale, from, to| match scale.reversed {
+            true => (scale.apply(to), scale.apply(from)),
+            false => (scale.apply(from), scale.apply(to)),
+        };
+
+        Some(Self {
+            x_origin: job.x_from,
+            x_scale,
+            y_scale,
+            x_range: range(x_scale, job.x_from, job.x_to),
+            y_range: range(y_scale, y_from, y_to),
+            transform,
+            rect: *rect,
+        })
+    }
+
+    /// Projects points whose x is relative to `x` instead of the start of the job
+    pub(super) fn with_x_origin(self, x: RangePrec) -> Self {
+        Self {
+            x_origin: x,
+            ..self
+        }
+    }
+
+    /// Projects values also scaled and offset by the `(scale, offset)` of a bundle entry
+    pub(super) fn with_y_transform(self, (scale, offset): (RangePrec, RangePrec)) -> Self {
+        Self {
+            transform: (self.transform.0 * scale, self.transform.1 * scale + offset),
+            ..self
+        }
+    }
+
+    pub(super) fn apply(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let x = self.x_scale.apply(x as RangePrec + self.x_origin);
+        let y = self
+            .y_scale
+            .apply(y as RangePrec * self.transform.0 + self.transform.1);
+
+        (
+            self.rect.left as f32
+                + ((x - self.x_range.0) / (self.x_range.1 - self.x_range.0)) as f32
+                    * self.rect.width as f32,
+            self.rect.bottom as f32
+                + ((y - self.y_range.0) / (self.y_range.1 - self.y_range.0)) as f32
+                    * self.rect.height as f32,
+        )
+    }
+}
+
+/// Identifies the output of a job, `generation` changing whenever the drawn data does
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct JobFingerprint {
+    range: [u64; 6],
+    size: (u32, u32),
+    generation: u64,
+    revision: u64,
+    style: u64,
+}
+
+impl JobFingerprint {
+    pub fn of(job: &RenderJob, width: u32, height: u32, generation: u64) -> Self {
+        let (y2_from, y2_to) = job.y2_range().unwrap_or((0.0, 0.0));
+
+        Self {
+            range: [job.x_from, job.x_to, job.y_from, job.y_to, y2_from, y2_to].map(f64::to_bits),
+            size: (width, height),
+            generation,
+            revision: 0,
+            style: job.style_hash(),
+        }
+    }
+
+    /// The fingerprint of a frame drawing points of a module at `revision`, for renderers
+    /// reading them from the module instead of buffers of their own
+    pub fn with_revision(self, revision: u64) -> Self {
+        Self { revision, ..self }
+    }
+}
+
+/// A generated draw list together with the ticks it was generated from
+pub type CachedFrame = Rc<(DrawList, RenderJobResult)>;
+
+/// The most recently used draw lists, so that returning to a recent view skips generating them
+pub struct DrawListCache {
+    capacity: usize,
+    frames: VecDeque<(JobFingerprint, CachedFrame)>,
+}
+
+impl DrawListCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            frames: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// The cached frame for `key`, generated by `build` on a miss
+    pub fn get_or_insert_with(
+        &mut self,
+        key: JobFingerprint,
+        build: impl FnOnce() -> (DrawList, RenderJobResult),
+    ) -> CachedFrame {
+        match self.get(key) {
+            Some(frame) => frame,
+            None => self.insert(key, build()),
+        }
+    }
+
+    /// The cached frame for `key`, which becomes the most recently used one
+    pub fn get(&mut self, key: JobFingerprint) -> Option<CachedFrame> {
+        let i = self.frames.iter().position(|(k, _)| *k == key)?;
+        let hit = self.frames.remove(i).unwrap();
+        self.frames.push_front(hit);
+
+        Some(self.frames[0].1.clone())
+    }
+
+    /// Caches the `frame` of `key`, evicting the least recently used one when full
+    pub fn insert(
+        &mut self,
+        key: JobFingerprint,
+        frame: (DrawList, RenderJobResult),
+    ) -> CachedFrame {
+        if self.frames.len() >= self.capacity {
+            self.frames.pop_back();
+        }
+        self.frames.push_front((key, Rc::new(frame)));
+
+        self.frames[0].1.clone()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+}
+
+/// Unrolls a triangle strip into independent triangles
+pub fn strip_to_triangles(strip: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    strip.windows(3).flat_map(|w| [w[0], w[1], w[2]]).collect()
+}
@@ -0,0 +1,52 @@
+use std::fmt;
+
+use wasm_bindgen::JsValue;
+use web_sys::WebGl2RenderingContext as Gl;
+
+use crate::data::DataIdx;
+
+/// GL error raised while rendering, with the pass and the trace it was caught in
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RendererError {
+    pub code: u32,
+    pub pass: &'static str,
+    pub handle: Option<DataIdx>,
+}
+
+impl RendererError {
+    /// Name of the GL error `code`
+    pub fn code_name(code: u32) -> &'static str {
+        match code {
+            Gl::INVALID_ENUM => "INVALID_ENUM",
+            Gl::INVALID_VALUE => "INVALID_VALUE",
+            Gl::INVALID_OPERATION => "INVALID_OPERATION",
+            Gl::INVALID_FRAMEBUFFER_OPERATION => "INVALID_FRAMEBUFFER_OPERATION",
+            Gl::OUT_OF_MEMORY => "OUT_OF_MEMORY",
+            Gl::CONTEXT_LOST_WEBGL => "CONTEXT_LOST_WEBGL",
+            _ => "unknown GL error",
+        }
+    }
+}
+
+impl fmt::Display for RendererError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (0x{:x}) in the {} pass",
+            Self::code_name(self.code),
+            self.code,
+            self.pass
+        )?;
+
+        match self.handle {
+            Some(handle) => write!(f, " while drawing trace {}", handle),
+            None => Ok(()),
+        }
+    }
+}
+
+impl From<RendererError> for JsValue {
+    fn from(err: RendererError) -> Self {
+        JsValue::from_str(&err.to_string())
+    }
+}
@@ -0,0 +1,39 @@
+//! Encoding of frames read back from the GPU
+
+use wasm_bindgen::JsValue;
+
+/// Encodes `pixels`, premultiplied RGBA rows ordered bottom to top as GL reads them, as a PNG
+pub fn encode_png(width: u32, height: u32, pixels: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let row_len = width as usize * 4;
+    let mut image = Vec::with_capacity(pixels.len());
+
+    for row in pixels.chunks_exact(row_len).rev() {
+        image.extend(
+            row.chunks_exact(4)
+                .flat_map(|px| unpremultiply([px[0], px[1], px[2], px[3]])),
+        );
+    }
+
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let error = |err: png::EncodingError| JsValue::from_str(&err.to_string());
+    let mut writer = encoder.write_header().map_err(error)?;
+    writer.write_image_data(&image).map_err(error)?;
+    writer.finish().map_err(error)?;
+
+    Ok(out)
+}
+
+/// PNG stores straight alpha, while the canvas is premultiplied
+fn unpremultiply([r, g, b, a]: [u8; 4]) -> [u8; 4] {
+    if a == 0 || a == 255 {
+        return [r, g, b, a];
+    }
+
+    let channel = |c: u8| ((c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8;
+
+    [channel(r), channel(g), channel(b), a]
+}
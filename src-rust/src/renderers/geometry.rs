@@ -0,0 +1,384 @@
+//! Renderer independent geometry generation
+
+use std::ops::{Add, Mul, Range, Sub};
+
+use wasm_bindgen::prelude::*;
+
+use crate::parallel;
+
+/// Shape of the outer corner where two segments of a thick line meet
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum LineJoin {
+    #[default]
+    Miter,
+    Bevel,
+}
+
+/// Shape of the ends of a thick line
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum LineCap {
+    #[default]
+    Butt,
+    Round,
+}
+
+/// Longest miter relative to the half width, sharper corners are beveled instead
+const MITER_LIMIT: f32 = 4.0;
+/// Triangles approximating a half circle of a round cap
+const CAP_SEGMENTS: usize = 8;
+
+/// `value` as the sum of a coarse and a fine `f32`, for positions that need more precision
+/// than a single one has
+pub fn split_f64(value: f64) -> (f32, f32) {
+    let high = value as f32;
+
+    (high, (value - high as f64) as f32)
+}
+
+/// Whether `f32` offsets of up to `offset` are too coarse to place points within a quarter of
+/// a pixel when `visible` units span `px` pixels, so they have to be measured from closer by
+pub fn loses_precision(offset: f64, visible: f64, px: u32) -> bool {
+    let offset = (offset.abs() as f32).max(f32::MIN_POSITIVE);
+    let step = (f32::from_bits(offset.to_bits() + 1) - offset) as f64;
+
+    step > visible / px.max(1) as f64 / 4.0
+}
+
+/// Indices of the points between `from` and `to` in the `points` ascending by `x`, widened by
+/// a point on both sides so lines reach the edges of the plot
+pub fn visible_span<T>(points: &[T], x: impl Fn(&T) -> f32, from: f32, to: f32) -> Range<usize> {
+    let start = points.partition_point(|p| x(p) < from).saturating_sub(1);
+    let end = (points.partition_point(|p| x(p) <= to) + 1).min(points.len());
+
+    start..end.max(start)
+}
+
+/// Lines through the points from `first` to `end`, broken at the ascending `breaks`, each the
+/// index of a point starting a new line
+pub fn strips(breaks: &[usize], first: usize, end: usize) -> Vec<Range<usize>> {
+    let mut strips = Vec::new();
+    let mut start = first;
+
+    for &at in breaks.iter().filter(|&&at| at > first && at < end) {
+        strips.push(start..at);
+        start = at;
+    }
+    if start < end {
+        strips.push(start..end);
+    }
+
+    strips
+}
+
+/// Triangle strip filling the area between the running stack `area` and the already stacked
+/// `data` lying on top of it, afterwards `area` is raised to the top of `data`. Both are
+/// expected to share their x values; points past the shorter of the two are ignored.
+pub fn stack_area_strip(area: &mut [(f32, f32)], data: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    let len = area.len().min(data.len());
+
+    let parts = parallel::split(len, parallel::MIN_PART, |_| true);
+    let strip = parallel::map(&parts, |part| {
+        area[part.clone()]
+            .iter()
+            .zip(&data[part])
+            .flat_map(|(&bottom, &(_, top))| [bottom, (bottom.0, top)])
+            .collect::<Vec<_>>()
+    })
+    .concat();
+
+    for (bottom, &(_, top)) in area.iter_mut().zip(data) {
+        bottom.1 = top;
+    }
+
+    strip
+}
+
+/// Triangle strip filling the area between `upper` and `lower`, taking x values from `upper`
+pub fn band_strip(
+    upper: &[(f32, f32)],
+    lower: impl Iterator<Item = (f32, f32)>,
+) -> Vec<(f32, f32)> {
+    upper
+        .iter()
+        .zip(lower)
+        .flat_map(|(&upper, (_, y))| [upper, (upper.0, y)])
+        .collect()
+}
+
+/// Candlesticks as low to high wick segments and open to close body triangles
+#[derive(Default)]
+pub struct Candles {
+    pub wicks: Vec<(f32, f32)>,
+    pub bodies: Vec<(f32, f32)>,
+}
+
+/// Geometry of the rising candles, or with `rising` unset the falling ones, each candle
+/// being an `(x, [open, high, low, close])` pair
+pub fn candle_geometry(candles: &[(f32, [f32; 4])], half_width: f32, rising: bool) -> Candles {
+    let Candles {
+        mut wicks,
+        mut bodies,
+    } = Candles::default();
+
+    for &(x, [open, high, low, close]) in candles {
+        if (close >= open) != rising {
+            continue;
+        }
+
+        let (x0, x1) = (x - half_width, x + half_width);
+
+        wicks.extend([(x, low), (x, high)]);
+        bodies.extend([
+            (x0, open),
+            (x1, open),
+            (x0, close),
+            (x0, close),
+            (x1, open),
+            (x1, close),
+        ]);
+    }
+
+    Candles { wicks, bodies }
+}
+
+/// Polyline reduced by Ramer-Douglas-Peucker to the points deviating more than `tolerance`
+/// from the simplified line, the ends always kept
+pub fn simplify(points: &[(f32, f32)], tolerance: f32) -> Vec<(f32, f32)> {
+    if points.len() < 3 || tolerance <= 0.0 {
+        return points.to_vec();
+    }
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    let mut spans = vec![(0, points.len() - 1)];
+
+    while let Some((first, last)) = spans.pop() {
+        let (a, b) = (points[first], points[last]);
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let len = dx.hypot(dy);
+
+        let distance = |p: (f32, f32)| match len > 0.0 {
+            true => (dy * (p.0 - a.0) - dx * (p.1 - a.1)).abs() / len,
+            false => (p.0 - a.0).hypot(p.1 - a.1),
+        };
+
+        let farthest = (first + 1..last)
+            .map(|i| (i, distance(points[i])))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        if let Some((i, _)) = farthest.filter(|&(_, d)| d > tolerance) {
+            keep[i] = true;
+            spans.extend([(first, i), (i, last)]);
+        }
+    }
+
+    points
+        .iter()
+        .zip(keep)
+        .filter_map(|(&p, keep)| keep.then_some(p))
+        .collect()
+}
+
+/// Triangle strip of a stroked line, each vertex with its position across the line running
+/// from `-1` on one edge through `0` in the middle to `1` on the other
+#[derive(Debug, Default)]
+pub struct Stroke {
+    pub strip: Vec<(f32, f32)>,
+    pub edges: Vec<f32>,
+}
+
+impl Stroke {
+    fn push_pair(&mut self, p: Vec2, offset: Vec2) {
+        self.strip
+            .extend([(p + offset), (p - offset)].map(<(f32, f32)>::from));
+        self.edges.extend([1.0, -1.0]);
+    }
+
+    /// Appends a fan around `center` sweeping `offset` by `angle`, its triangles alternating
+    /// with degenerate ones so it stays a part of the strip
+    fn push_round_cap(&mut self, center: Vec2, offset: Vec2, angle: f32) {
+        for k in 0..=CAP_SEGMENTS {
+            let rim = center + offset.rotate(angle * k as f32 / CAP_SEGMENTS as f32);
+
+            self.strip.extend([rim, center].map(<(f32, f32)>::from));
+            self.edges.extend([1.0, 0.0]);
+        }
+    }
+}
+
+/// The polyline `points` stroked `width` wide, consecutive duplicate points being skipped.
+/// Miters longer than the limit fall back to bevels.
+pub fn stroke_strip(points: &[(f32, f32)], width: f32, join: LineJoin, cap: LineCap) -> Stroke {
+    let mut path: Vec<Vec2> = Vec::with_capacity(points.len());
+
+    for p in points.iter().map(|&p| Vec2::from(p)) {
+        let finite = p.x.is_finite() && p.y.is_finite();
+
+        if finite && path.last().is_none_or(|&last| (p - last).len_sq() > 0.0) {
+            path.push(p);
+        }
+    }
+
+    let half = width / 2.0;
+    let mut stroke = Stroke::default();
+
+    if path.len() < 2 {
+        // a lone point only shows with round caps, as a dot
+        if let (Some(&p), LineCap::Round) = (path.first(), cap) {
+            stroke.push_round_cap(p, Vec2::new(0.0, half), std::f32::consts::TAU);
+        }
+
+        return stroke;
+    }
+
+    let normals: Vec<Vec2> = path
+        .windows(2)
+        .map(|s| (s[1] - s[0]).normalize().rotate_90())
+        .collect();
+
+    if cap == LineCap::Round {
+        stroke.push_round_cap(path[0], normals[0] * -half, -std::f32::consts::PI);
+    }
+    stroke.push_pair(path[0], normals[0] * half);
+
+    for (i, &p) in path.iter().enumerate().skip(1).take(path.len() - 2) {
+        let (n0, n1) = (normals[i - 1], normals[i]);
+        let sum = n0 + n1;
+        // the miter reaches half / cos of half the turn, which is its dot with either normal
+        let cos = if sum.len_sq() > 1e-6 {
+            sum.normalize().dot(&n1)
+        } else {
+            0.0
+        };
+
+        if join == LineJoin::Miter && cos * MITER_LIMIT >= 1.0 {
+            stroke.push_pair(p, sum.normalize() * (half / cos));
+        } else {
+            stroke.push_pair(p, n0 * half);
+            stroke.push_pair(p, n1 * half);
+        }
+    }
+
+    let (&end, &normal) = (path.last().unwrap(), normals.last().unwrap());
+    stroke.push_pair(end, normal * half);
+
+    if cap == LineCap::Round {
+        stroke.push_round_cap(end, normal * half, -std::f32::consts::PI);
+    }
+
+    stroke
+}
+
+/// Dashes of the line from `a` to `b` as pairs of segment ends, `pattern` alternating the
+/// lengths of dashes and gaps and being repeated twice when it has an odd length. Patterns
+/// without a positive length draw the line solid.
+pub fn dash_segments(a: (f32, f32), b: (f32, f32), pattern: &[f32]) -> Vec<(f32, f32)> {
+    let (a, b) = (Vec2::from(a), Vec2::from(b));
+    let len = (b - a).len_sq().sqrt();
+
+    let period: f32 = pattern.iter().map(|l| l.max(0.0)).sum();
+    if period <= 0.0 || !period.is_finite() || len == 0.0 {
+        return vec![a.into(), b.into()];
+    }
+
+    let dir = (b - a) * (1.0 / len);
+    let mut segments = Vec::new();
+    let mut pos = 0.0;
+
+    // an odd pattern swaps dashes and gaps on its repetition
+    for (i, &step) in pattern
+        .iter()
+        .cycle()
+        .take(pattern.len() * 2)
+        .cycle()
+        .enumerate()
+    {
+        if pos >= len {
+            break;
+        }
+
+        let end = (pos + step.max(0.0)).min(len);
+        if i % 2 == 0 && end > pos {
+            segments.extend([a + dir * pos, a + dir * end].map(<(f32, f32)>::from));
+        }
+        pos = end;
+    }
+
+    segments
+}
+
+#[derive(Clone, Copy)]
+struct Vec2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Vec2 {
+    fn new(x: f32, y: f32) -> Self {
+        Vec2 { x, y }
+    }
+
+    fn rotate_90(self) -> Self {
+        Self::new(-self.y, self.x)
+    }
+
+    fn rotate(self, angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+
+        Self::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
+    fn len_sq(&self) -> f32 {
+        self.dot(self)
+    }
+
+    fn dot(&self, b: &Self) -> f32 {
+        self.x * b.x + self.y * b.y
+    }
+
+    fn normalize(self) -> Self {
+        let inv_len = 1.0 / self.len_sq().sqrt();
+
+        self * inv_len
+    }
+}
+
+impl From<(f32, f32)> for Vec2 {
+    fn from((x, y): (f32, f32)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl From<Vec2> for (f32, f32) {
+    fn from(v: Vec2) -> Self {
+        (v.x, v.y)
+    }
+}
+
+impl Mul<f32> for Vec2 {
+    type Output = Vec2;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Vec2::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl Add<Vec2> for Vec2 {
+    type Output = Vec2;
+
+    fn add(self, rhs: Vec2) -> Self::Output {
+        Vec2::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub<Vec2> for Vec2 {
+    type Output = Vec2;
+
+    fn sub(self, rhs: Vec2) -> Self::Output {
+        Vec2::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
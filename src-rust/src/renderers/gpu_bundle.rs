@@ -0,0 +1,135 @@
+//! Bookkeeping of bundles built into GPU buffers, apart from the buffers themselves, so a
+//! renderer can release and rebuild them, e.g. when its context is lost and restored
+
+use std::collections::{HashMap, VecDeque};
+
+use wasm_bindgen::JsValue;
+
+use super::{BundleEntry, BundleOptions};
+use crate::{data_module::DataModule, prelude::*};
+
+/// Buffers built from a bundle entry
+pub trait BuiltRow {
+    fn entry(&self) -> &BundleEntry;
+}
+
+/// Entries of a bundle built into rows of buffers
+pub struct BufferBundle<R> {
+    pub from: RangePrec,
+    pub to: RangePrec,
+    pub opts: BundleOptions,
+    pub buffers: Vec<R>,
+    /// Entries whose buffers have been released by `suspend` or a lost context, rebuilt on
+    /// `resume`
+    pub released: Option<Vec<BundleEntry>>,
+}
+
+impl<R: BuiltRow> BufferBundle<R> {
+    /// The bundle once its context is lost, released with the entries of its rows, which
+    /// are dropped without deleting their buffers as those went with the context
+    pub fn lost(self) -> Self {
+        let entries = self
+            .released
+            .unwrap_or_else(|| self.buffers.iter().map(|row| row.entry().clone()).collect());
+
+        Self {
+            buffers: Vec::new(),
+            released: Some(entries),
+            ..self
+        }
+    }
+
+    pub fn ensure_resident(&self) -> Result<(), JsValue> {
+        match self.released {
+            Some(_) => Err(JsValue::from_str(
+                "The bundle has been released by suspend or a lost context, resume the renderer \
+                 or wait for the context to be restored first",
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Bundle built a chunk of its entries at a time, swapped in once all of them are
+pub struct PendingBundle<R> {
+    pub from: RangePrec,
+    pub to: RangePrec,
+    pub opts: BundleOptions,
+    pub entries: Vec<BundleEntry>,
+    /// Indices of the entries left to build, each a single entry or a whole stack group, as
+    /// its entries are stacked on each other
+    pub chunks: VecDeque<Vec<usize>>,
+    pub built: Vec<Option<R>>,
+}
+
+impl<R> PendingBundle<R> {
+    pub fn new(
+        module: &DataModule,
+        from: RangePrec,
+        to: RangePrec,
+        opts: BundleOptions,
+        entries: Vec<BundleEntry>,
+    ) -> Self {
+        let mut chunks: Vec<Vec<usize>> = Vec::new();
+        let mut groups: HashMap<u32, usize> = HashMap::new();
+
+        for (i, entry) in entries.iter().enumerate() {
+            let stacked = entry.stack_group != 0 && module.get_ohlc_trace(entry.handle).is_none();
+
+            match groups.get(&entry.stack_group) {
+                Some(&chunk) if stacked => chunks[chunk].push(i),
+                _ => {
+                    if stacked {
+                        groups.insert(entry.stack_group, chunks.len());
+                    }
+                    chunks.push(vec![i]);
+                }
+            }
+        }
+
+        Self {
+            from,
+            to,
+            opts,
+            built: entries.iter().map(|_| None).collect(),
+            entries,
+            chunks: chunks.into(),
+        }
+    }
+
+    /// Fraction of the entries built
+    pub fn progress(&self) -> f64 {
+        match self.built.len() {
+            0 => 1.0,
+            len => self.built.iter().flatten().count() as f64 / len as f64,
+        }
+    }
+
+    /// The build started over once its context is lost, dropping the rows built so far
+    /// without deleting their buffers as those went with the context
+    pub fn restarted(self, module: &DataModule) -> Self {
+        Self::new(module, self.from, self.to, self.opts, self.entries)
+    }
+
+    /// The bundle left released, to be built along with the others once there's a context
+    pub fn released(self) -> BufferBundle<R> {
+        BufferBundle {
+            from: self.from,
+            to: self.to,
+            opts: self.opts,
+            buffers: Vec::new(),
+            released: Some(self.entries),
+        }
+    }
+
+    /// The bundle of the rows built, once no chunks are left
+    pub fn finished(self) -> BufferBundle<R> {
+        BufferBundle {
+            from: self.from,
+            to: self.to,
+            opts: self.opts,
+            buffers: self.built.into_iter().flatten().collect(),
+            released: None,
+        }
+    }
+}
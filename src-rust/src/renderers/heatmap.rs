@@ -0,0 +1,245 @@
+//! Dense 2D grids of values, drawn as a colormapped texture stretched over their extent
+
+use wasm_bindgen::JsValue;
+use web_sys::{
+    WebGl2RenderingContext, WebGlBuffer, WebGlProgram, WebGlTexture, WebGlUniformLocation,
+};
+
+use super::{webgl::webgl_utils, PlotRect};
+use crate::{
+    prelude::*,
+    structs::{Colormap, RenderJob},
+};
+
+/// Texels of colormap lookup textures
+pub const LUT_SIZE: usize = 256;
+
+/// Uploaded grid, `x` and `y` being the data ranges it covers
+pub struct HeatmapLayer {
+    values: WebGlTexture,
+    colormap: WebGlTexture,
+    x: (RangePrec, RangePrec),
+    y: (RangePrec, RangePrec),
+}
+
+impl HeatmapLayer {
+    /// Uploads the row major `values` with `cols` columns, the first row lying at `y.0`.
+    /// Values are mapped to the colormap between the grid's finite minimum and maximum.
+    pub fn new(
+        gl: &WebGl2RenderingContext,
+        values: &[f32],
+        cols: usize,
+        x: (RangePrec, RangePrec),
+        y: (RangePrec, RangePrec),
+        colormap: &Colormap,
+    ) -> Result<Self, JsValue> {
+        let cols = cols.max(1);
+        let rows = values.len() / cols;
+        let (min, max) = value_range(values);
+
+        let cells = quantize(&values[..rows * cols], min, max);
+        let lut = colormap.lut(LUT_SIZE);
+
+        Ok(Self {
+            values: webgl_utils::create_texture(
+                gl,
+                (cols as i32, rows as i32),
+                WebGl2RenderingContext::LUMINANCE_ALPHA,
+                WebGl2RenderingContext::NEAREST,
+                &cells,
+            )?,
+            colormap: webgl_utils::create_texture(
+                gl,
+                (LUT_SIZE as i32, 1),
+                WebGl2RenderingContext::RGBA,
+                WebGl2RenderingContext::LINEAR,
+                &lut,
+            )?,
+            x,
+            y,
+        })
+    }
+
+    pub fn delete(self, gl: &WebGl2RenderingContext) {
+        gl.delete_texture(Some(&self.values));
+        gl.delete_texture(Some(&self.colormap));
+    }
+}
+
+/// Smallest and largest finite value, `(0, 1)` when there are none
+pub fn value_range(values: &[f32]) -> (f32, f32) {
+    values
+        .iter()
+        .filter(|v| v.is_finite())
+        .fold(None, |range, &v| match range {
+            None => Some((v, v)),
+            Some((min, max)) => Some((v.min(min), v.max(max))),
+        })
+        .unwrap_or((0.0, 1.0))
+}
+
+/// Colormap index and opacity byte pairs, non finite values becoming transparent
+pub fn quantize(values: &[f32], min: f32, max: f32) -> Vec<u8> {
+    let span = if max > min { max - min } else { 1.0 };
+
+    values
+        .iter()
+        .flat_map(|&v| {
+            if v.is_finite() {
+                [
+                    (((v - min) / span).clamp(0.0, 1.0) * 255.0).round() as u8,
+                    255,
+                ]
+            } else {
+                [0, 0]
+            }
+        })
+        .collect()
+}
+
+/// Straight RGBA pixels of the row major `values` through `colormap`, with the number of rows.
+/// Non finite values become transparent.
+pub fn colorize(values: &[f32], cols: usize, colormap: &Colormap) -> (Vec<u8>, usize) {
+    let cols = cols.max(1);
+    let rows = values.len() / cols;
+    let (min, max) = value_range(values);
+
+    let pixels = quantize(&values[..rows * cols], min, max)
+        .chunks_exact(2)
+        .flat_map(|cell| match cell[1] {
+            0 => [0; 4],
+            _ => {
+                let [r, g, b] = colormap.sample(cell[0] as f32 / 255.0);
+                [r, g, b, 255]
+            }
+        })
+        .collect();
+
+    (pixels, rows)
+}
+
+pub struct HeatmapProgram {
+    program: WebGlProgram,
+    buffer: WebGlBuffer,
+    resolution_pos: WebGlUniformLocation,
+    values_pos: WebGlUniformLocation,
+    colormap_pos: WebGlUniformLocation,
+}
+
+impl HeatmapProgram {
+    pub fn new(gl: &WebGl2RenderingContext) -> Result<Self, JsValue> {
+        let vert_shader = webgl_utils::compile_shader(
+            gl,
+            WebGl2RenderingContext::VERTEX_SHADER,
+            r#"
+            attribute vec4 aVertex;
+
+            uniform vec2 resolution;
+            varying vec2 uv;
+
+            void main() {
+                uv = aVertex.zw;
+                gl_Position = vec4(vec2(-1, -1) + vec2(2, 2) * aVertex.xy / resolution, 0, 1);
+            }
+            "#,
+        )?;
+
+        let frag_shader = webgl_utils::compile_shader(
+            gl,
+            WebGl2RenderingContext::FRAGMENT_SHADER,
+            r#"
+            precision mediump float;
+
+            uniform sampler2D values;
+            uniform sampler2D colormap;
+            varying vec2 uv;
+
+            void main() {
+                vec4 cell = texture2D(values, uv);
+
+                if (cell.a < 0.5) {
+                    discard;
+                }
+
+                // hit the centers of the lookup texels
+                gl_FragColor = texture2D(colormap, vec2((cell.r * 255.0 + 0.5) / 256.0, 0.5));
+            }
+            "#,
+        )?;
+
+        let program = webgl_utils::link_program(gl, &vert_shader, &frag_shader)?;
+
+        Ok(Self {
+            buffer: gl
+                .create_buffer()
+                .ok_or_else(|| JsValue::from_str("Failed to allocate the heatmap buffer"))?,
+            resolution_pos: gl.get_uniform_location(&program, "resolution").unwrap(),
+            values_pos: gl.get_uniform_location(&program, "values").unwrap(),
+            colormap_pos: gl.get_uniform_location(&program, "colormap").unwrap(),
+            program,
+        })
+    }
+
+    /// Draws `layer` into the plot area of `job` on a `width` x `height` canvas
+    pub fn draw(
+        &self,
+        gl: &WebGl2RenderingContext,
+        layer: &HeatmapLayer,
+        job: &RenderJob,
+        width: u32,
+        height: u32,
+    ) {
+        let rect = PlotRect::of(job, width, height);
+        let xs = job.x_axis_scale();
+        let ys = job.y_axis_scale();
+
+        let x_at =
+            |x| rect.left as f32 + rect.width as f32 * xs.position(x, job.x_from, job.x_to) as f32;
+        let y_at = |y| {
+            rect.bottom as f32 + rect.height as f32 * ys.position(y, job.y_from, job.y_to) as f32
+        };
+
+        let (x0, x1) = (x_at(layer.x.0), x_at(layer.x.1));
+        let (y0, y1) = (y_at(layer.y.0), y_at(layer.y.1));
+
+        let data: [f32; 24] = [
+            x0, y0, 0.0, 0.0, x1, y0, 1.0, 0.0, x0, y1, 0.0, 1.0, //
+            x0, y1, 0.0, 1.0, x1, y0, 1.0, 0.0, x1, y1, 1.0, 1.0,
+        ];
+
+        gl.viewport(0, 0, width as i32, height as i32);
+        gl.enable(WebGl2RenderingContext::SCISSOR_TEST);
+        gl.scissor(
+            rect.left as i32,
+            rect.bottom as i32,
+            rect.width as i32,
+            rect.height as i32,
+        );
+
+        gl.use_program(Some(&self.program));
+        gl.uniform2f(Some(&self.resolution_pos), width as f32, height as f32);
+
+        gl.active_texture(WebGl2RenderingContext::TEXTURE0);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&layer.values));
+        gl.uniform1i(Some(&self.values_pos), 0);
+        gl.active_texture(WebGl2RenderingContext::TEXTURE1);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&layer.colormap));
+        gl.uniform1i(Some(&self.colormap_pos), 1);
+        gl.active_texture(WebGl2RenderingContext::TEXTURE0);
+
+        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&self.buffer));
+        webgl_utils::upload(
+            gl,
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            &data,
+            WebGl2RenderingContext::STATIC_DRAW,
+        );
+        gl.vertex_attrib_pointer_with_i32(0, 4, WebGl2RenderingContext::FLOAT, false, 0, 0);
+        gl.enable_vertex_attrib_array(0);
+        gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, 6);
+
+        // the other programs read attribute 0 as a vec2
+        gl.vertex_attrib_pointer_with_i32(0, 2, WebGl2RenderingContext::FLOAT, false, 0, 0);
+        gl.disable(WebGl2RenderingContext::SCISSOR_TEST);
+    }
+}
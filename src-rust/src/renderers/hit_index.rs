@@ -0,0 +1,243 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{data::DataIdx, data_module::DataModule, prelude::*, structs::RenderJob};
+
+use super::PlotRect;
+
+/// Result of a hit test, `index` is the position of the point within its bundle entry
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HitResult {
+    pub handle: DataIdx,
+    pub index: usize,
+    pub x: RangePrec,
+    pub y: RangePrec,
+    pub px_distance: f32,
+}
+
+/// Points of a bundle entry within a brushed rectangle, as ranges of their indices
+#[derive(Serialize, Debug)]
+pub struct BrushHit {
+    pub handle: DataIdx,
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// Value of a bundle entry under the playhead, interpolated between its points
+#[derive(Serialize, Debug, PartialEq)]
+pub struct PlayheadValue {
+    pub handle: DataIdx,
+    pub y: RangePrec,
+}
+
+/// Copy of the points of a bundle entry, sorted by x, for nearest point queries
+pub struct HitIndex {
+    origin: RangePrec,
+    points: Vec<(DataPrec, DataPrec)>,
+}
+
+impl HitIndex {
+    pub fn new(origin: RangePrec, points: Vec<(DataPrec, DataPrec)>) -> Self {
+        Self { origin, points }
+    }
+
+    /// Copy of the points of `handle` between `from` and `to`, relative to `from`
+    pub fn of_trace(module: &DataModule, handle: DataIdx, from: RangePrec, to: RangePrec) -> Self {
+        let points = module
+            .get_trace(handle)
+            .map(|trace| {
+                trace
+                    .get_data_high_prec(from, to)
+                    .map(|(x, y)| ((x - from) as DataPrec, y as DataPrec))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self::new(from, points)
+    }
+
+    /// Nearest point within `max_px` pixels of `(x, y)` when displayed by `job` in `rect`,
+    /// `y` being on the primary axis while the points are plotted against `y_range`, their
+    /// values drawn times `scale` plus `offset`
+    pub fn nearest(
+        &self,
+        job: &RenderJob,
+        rect: &PlotRect,
+        (x, y): (RangePrec, RangePrec),
+        y_range: (RangePrec, RangePrec),
+        (scale, offset): (RangePrec, RangePrec),
+        max_px: f32,
+    ) -> Option<(usize, f32)> {
+        let xs = job.x_axis_scale();
+        let ys = job.y_axis_scale();
+
+        let x_span = xs.apply(job.x_to) - xs.apply(job.x_from);
+        let px_per_x = rect.width as RangePrec / x_span;
+        let px_per_y = rect.height as RangePrec / (ys.apply(job.y_to) - ys.apply(job.y_from));
+        let px_per_y_range = rect.height as RangePrec / (ys.apply(y_range.1) - ys.apply(y_range.0));
+
+        let window = max_px as RangePrec / px_per_x;
+        let lo = (xs.invert(xs.apply(x) - window) - self.origin) as DataPrec;
+        let hi = (xs.invert(xs.apply(x) + window) - self.origin) as DataPrec;
+
+        let start = self.points.partition_point(|p| p.0 < lo);
+        let end = self.points.partition_point(|p| p.0 <= hi);
+
+        let cx = xs.apply(x) * px_per_x;
+        let cy = (ys.apply(y) - ys.apply(job.y_from)) * px_per_y;
+
+        self.points[start..end]
+            .iter()
+            .enumerate()
+            .map(|(i, &(px, py))| {
+                let dx = xs.apply(px as RangePrec + self.origin) * px_per_x - cx;
+                let py = py as RangePrec * scale + offset;
+                let dy = (ys.apply(py) - ys.apply(y_range.0)) * px_per_y_range - cy;
+
+                (start + i, (dx * dx + dy * dy).sqrt() as f32)
+            })
+            .filter(|&(_, dist)| dist <= max_px)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+    }
+
+    /// Smallest and largest y of the points between `from` and `to`
+    pub fn extent(&self, from: RangePrec, to: RangePrec) -> Option<(DataPrec, DataPrec)> {
+        let lo = (from - self.origin) as DataPrec;
+        let hi = (to - self.origin) as DataPrec;
+
+        let start = self.points.partition_point(|p| p.0 < lo);
+        let end = self.points.partition_point(|p| p.0 <= hi);
+
+        y_extent(self.points[start..end].iter().map(|p| p.1))
+    }
+
+    /// Runs of consecutive points with an x within `x` and a y within `y`, as ranges of their
+    /// indices
+    pub fn runs_within(
+        &self,
+        x: (RangePrec, RangePrec),
+        y: (RangePrec, RangePrec),
+    ) -> Vec<Range<usize>> {
+        let start = self.index_from(x.0);
+        let end = self
+            .points
+            .partition_point(|p| p.0 <= (x.1 - self.origin) as DataPrec);
+        let mut runs: Vec<Range<usize>> = Vec::new();
+
+        for (i, p) in self.points[start..end.max(start)].iter().enumerate() {
+            let i = start + i;
+            if !(y.0..=y.1).contains(&(p.1 as RangePrec)) {
+                continue;
+            }
+
+            match runs.last_mut() {
+                Some(run) if run.end == i => run.end += 1,
+                _ => runs.push(i..i + 1),
+            }
+        }
+
+        runs
+    }
+
+    /// y at `x` interpolated linearly between the points around it, `None` outside of them
+    pub fn value_at(&self, x: RangePrec) -> Option<RangePrec> {
+        let next = self.index_from(x);
+        let &(right_x, right_y) = self.points.get(next)?;
+        let rel = x - self.origin;
+
+        if right_x as RangePrec == rel {
+            return Some(right_y as RangePrec);
+        }
+
+        let &(left_x, left_y) = self.points.get(next.checked_sub(1)?)?;
+        let t = (rel - left_x as RangePrec) / (right_x - left_x) as RangePrec;
+
+        Some(left_y as RangePrec + (right_y - left_y) as RangePrec * t)
+    }
+
+    /// Index of the first point at or after `x`
+    pub fn index_from(&self, x: RangePrec) -> usize {
+        let x = (x - self.origin) as DataPrec;
+
+        self.points.partition_point(|p| p.0 < x)
+    }
+
+    /// All points, relative to the origin
+    pub fn points(&self) -> &[(DataPrec, DataPrec)] {
+        &self.points
+    }
+
+    /// Appends `points` past the last indexed one, the others are dropped to keep the order.
+    /// Returns the appended points.
+    pub fn append(&mut self, points: &[(DataPrec, DataPrec)]) -> &[(DataPrec, DataPrec)] {
+        let start = self.points.len();
+
+        for &point in points {
+            if self.points.last().is_none_or(|last| point.0 > last.0) {
+                self.points.push(point);
+            }
+        }
+
+        &self.points[start..]
+    }
+
+    pub fn point(&self, index: usize) -> (RangePrec, RangePrec) {
+        let (x, y) = self.points[index];
+
+        (x as RangePrec + self.origin, y as RangePrec)
+    }
+}
+
+/// Smallest and largest of the finite `ys`
+pub fn y_extent(ys: impl Iterator<Item = DataPrec>) -> Option<(DataPrec, DataPrec)> {
+    ys.filter(|y| y.is_finite())
+        .fold(None, |range, y| match range {
+            None => Some((y, y)),
+            Some((min, max)) => Some((min.min(y), max.max(y))),
+        })
+}
+
+/// Order to draw the entries of a bundle in, given their handles, values at the job's
+/// `raise_at_x` and `(scale, offset)` transforms: farthest from `raise_at_y` on screen first,
+/// so the nearest one is drawn last. Entries without a value there stay at the bottom in their
+/// order, as do all of them when the job doesn't raise entries.
+pub fn raise_order(
+    job: &RenderJob,
+    module: &DataModule,
+    values: impl IntoIterator<Item = (DataIdx, Option<RangePrec>, (RangePrec, RangePrec))>,
+) -> Vec<usize> {
+    let values = values.into_iter();
+    let Some((_, cursor)) = job.raise_at_x.zip(job.raise_at_y) else {
+        return (0..values.count()).collect();
+    };
+
+    let ys = job.y_axis_scale();
+    let cursor = ys.position(cursor, job.y_from, job.y_to);
+
+    let mut order: Vec<(usize, RangePrec)> = values
+        .enumerate()
+        .map(|(i, (handle, value, (scale, offset)))| {
+            let value = match job.baseline_x {
+                Some(x) => module
+                    .relative_transform(handle, x)
+                    .zip(value)
+                    .map(|((scale, offset), value)| value * scale + offset),
+                None => value,
+            };
+            // the entry's transform applies on top of the baseline's, as when drawn
+            let value = value.map(|value| value * scale + offset);
+            let (from, to) = job.y_range(handle);
+            let distance = value
+                .map(|value| (ys.position(value, from, to) - cursor).abs())
+                .filter(|distance| distance.is_finite())
+                .unwrap_or(RangePrec::INFINITY);
+
+            (i, distance)
+        })
+        .collect();
+
+    // the sort is stable, so entries as far away keep their order
+    order.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    order.into_iter().map(|(i, _)| i).collect()
+}
@@ -0,0 +1,139 @@
+//! Legend listing the drawn traces in a box over the top right corner of the plot
+
+use serde::{Deserialize, Serialize};
+
+use crate::{data::DataIdx, data_module::DataModule, structs::RenderJob};
+
+use super::{text::FONT_PX, PlotRect};
+
+const PADDING: f32 = 6.0;
+const SWATCH_WIDTH: f32 = 16.0;
+const SWATCH_GAP: f32 = 4.0;
+const COLUMN_GAP: f32 = 12.0;
+const ROW_HEIGHT: f32 = FONT_PX + 4.0;
+/// Distance of the box from the corner of the plot
+const OFFSET: f32 = 8.0;
+
+/// Trace listed in a legend
+pub struct LegendItem {
+    pub handle: DataIdx,
+    pub color: [u8; 3],
+    pub width: u32,
+    pub label: String,
+    /// Blacklisted traces stay listed, dimmed, so they can be toggled back on
+    pub hidden: bool,
+}
+
+/// Items of the traces drawn by `job` given as `(handle, color, width)`, named by their ids.
+/// Traces drawn more than once are listed once, unknown ones not at all.
+pub fn legend_items(
+    module: &DataModule,
+    job: &RenderJob,
+    traces: impl Iterator<Item = (DataIdx, [u8; 3], u32)>,
+) -> Vec<LegendItem> {
+    let mut items: Vec<LegendItem> = Vec::new();
+
+    for (handle, color, width) in traces {
+        if items.iter().any(|item| item.handle == handle) {
+            continue;
+        }
+
+        if let Some(trace) = module.get_trace(handle) {
+            items.push(LegendItem {
+                handle,
+                color,
+                width,
+                label: trace.id.clone(),
+                hidden: job.is_blacklisted(handle),
+            });
+        }
+    }
+
+    items
+}
+
+/// Where a legend item lies, `rect` being `[x, y, width, height]` in canvas pixels measured
+/// from the top left corner like pointer events are
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LegendHit {
+    pub handle: DataIdx,
+    pub rect: [f32; 4],
+}
+
+/// Placement of the legend items in canvas pixels with the origin in the bottom left corner,
+/// rectangles being `[x, y, width, height]`
+pub struct LegendLayout {
+    pub frame: [f32; 4],
+    pub rows: usize,
+    pub columns: usize,
+    /// Rectangle spanning each item and the line sample drawn in it
+    pub cells: Vec<[f32; 4]>,
+    pub swatches: Vec<[f32; 4]>,
+}
+
+impl LegendLayout {
+    /// Lays `items` out in columns as wide as the widest one, as many as fit into `rect`,
+    /// measuring the labels with `measure`
+    pub fn of(items: &[LegendItem], rect: &PlotRect, measure: impl Fn(&str) -> f32) -> Self {
+        let label_width = items
+            .iter()
+            .map(|item| measure(&item.label))
+            .fold(0.0, f32::max);
+        let column_width = SWATCH_WIDTH + SWATCH_GAP + label_width;
+
+        let room = rect.width as f32 - OFFSET * 2.0 - PADDING * 2.0 + COLUMN_GAP;
+        let columns =
+            ((room / (column_width + COLUMN_GAP)).floor() as usize).clamp(1, items.len().max(1));
+        let rows = items.len().div_ceil(columns);
+
+        let width = columns as f32 * (column_width + COLUMN_GAP) - COLUMN_GAP + PADDING * 2.0;
+        let height = rows as f32 * ROW_HEIGHT + PADDING * 2.0;
+        let left = rect.right() as f32 - OFFSET - width;
+        let top = rect.top() as f32 - OFFSET;
+
+        let mut cells = Vec::with_capacity(items.len());
+        let mut swatches = Vec::with_capacity(items.len());
+
+        for (i, item) in items.iter().enumerate() {
+            let (row, column) = (i / columns, i % columns);
+            let x = left + PADDING + column as f32 * (column_width + COLUMN_GAP);
+            let y = top - PADDING - (row + 1) as f32 * ROW_HEIGHT;
+
+            let thickness = (item.width as f32).clamp(1.0, ROW_HEIGHT / 2.0);
+            cells.push([x, y, column_width, ROW_HEIGHT]);
+            swatches.push([
+                x,
+                y + (ROW_HEIGHT - thickness) / 2.0,
+                SWATCH_WIDTH,
+                thickness,
+            ]);
+        }
+
+        Self {
+            frame: [left, top - height, width, height],
+            rows,
+            columns,
+            cells,
+            swatches,
+        }
+    }
+
+    /// Anchor of the label in the cell `i`, its vertical center
+    pub fn label_anchor(&self, i: usize) -> (f32, f32) {
+        let [x, y, _, height] = self.cells[i];
+
+        (x + SWATCH_WIDTH + SWATCH_GAP, y + height / 2.0)
+    }
+
+    /// Cells of `items` flipped to be measured from the top of a `canvas_height` tall canvas
+    pub fn hits(&self, items: &[LegendItem], canvas_height: u32) -> Vec<LegendHit> {
+        items
+            .iter()
+            .zip(&self.cells)
+            .map(|(item, &[x, y, width, height])| LegendHit {
+                handle: item.handle,
+                rect: [x, canvas_height as f32 - y - height, width, height],
+            })
+            .collect()
+    }
+}
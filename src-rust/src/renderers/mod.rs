@@ -1,72 +1,781 @@
+mod bundle_store;
+mod canvas2d;
+mod describe;
+pub mod draw_list;
+mod error;
+pub mod export;
+pub mod geometry;
+pub mod gpu_bundle;
+mod heatmap;
+mod hit_index;
+pub mod legend;
+#[cfg(feature = "raster")]
+mod raster;
+mod stroke;
+mod svg;
+mod text;
+pub mod ticks;
+pub mod trace_cache;
 mod webgl;
-use std::{convert::TryInto, mem::size_of};
+use std::{collections::HashMap, convert::TryInto, mem::size_of};
 
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
-use web_sys::OffscreenCanvas;
+use web_sys::{ImageBitmap, OffscreenCanvas};
 
-use crate::{data_module::DataModule, prelude::*, structs::RenderJob};
+use crate::{
+    data::DataIdx,
+    data_module::{DataModule, Decimation, Downsampling, Fidelity},
+    prelude::*,
+    structs::{
+        Annotation, Bookmark, Colormap, LinkGroup, Marker, RenderJob, TraceStyle, Versioned,
+        ViewAnimation,
+    },
+};
+pub use canvas2d::Canvas2dRenderer;
+pub use error::RendererError;
+pub use hit_index::*;
+#[cfg(feature = "raster")]
+pub use raster::RasterRenderer;
+pub use svg::SvgRenderer;
+pub use text::{Align, Label, TickFormat};
 pub use webgl::WebGlRenderer;
 
+const DEFAULT_MARKER_SIZE: f32 = 8.0;
+/// Color of falling candles when the entry doesn't set one, rising ones use the entry color
+const DEFAULT_DOWN_COLOR: [u8; 3] = [0xef, 0x53, 0x50];
+
+/// Serialized entries only need the trace, color and width, the rest has defaults
+#[derive(Clone, Serialize, Deserialize)]
 pub struct BundleEntry {
     handle: usize,
     width: u32,
     color: [u8; 3],
-    points_mode: bool,
+    #[serde(default)]
+    marker: Marker,
+    #[serde(default)]
+    marker_size: u32,
+
+    /// When set, the area between this trace and `fill_to` is filled with `fill_alpha`
+    #[serde(default)]
+    fill_to: Option<usize>,
+    #[serde(default)]
+    fill_alpha: f32,
+
+    /// Entries sharing a non-zero group are stacked on top of each other by ascending order
+    #[serde(default)]
+    stack_group: u32,
+    #[serde(default)]
+    stack_order: i32,
+
+    /// Color of falling candles when the entry is an OHLC trace
+    #[serde(default)]
+    down_color: Option<[u8; 3]>,
+    /// Trace whose values color the line through the colormap, in place of `color`
+    #[serde(default)]
+    color_by: Option<(DataIdx, Colormap)>,
+
+    /// Values are drawn times `y_scale` plus `y_offset`, e.g. to normalize traces of different
+    /// units or spread them out vertically, without touching the uploaded points. Unset in
+    /// a modification, the entry keeps the ones it has.
+    #[serde(default)]
+    y_offset: Option<RangePrec>,
+    #[serde(default)]
+    y_scale: Option<RangePrec>,
 }
 
-#[derive(Serialize, Deserialize)]
+impl BundleEntry {
+    /// Entries of a stream of `ROW_LEN` byte rows, failing on a partial row or a marker byte
+    /// naming no shape rather than misreading them
+    pub fn from_rows(stream: &[u8]) -> Result<Vec<Self>, String> {
+        if !stream.len().is_multiple_of(ROW_LEN) {
+            return Err(format!(
+                "Bundle rows are {} bytes long, {} bytes don't divide into them",
+                ROW_LEN,
+                stream.len()
+            ));
+        }
+
+        stream.chunks_exact(ROW_LEN).map(Self::from_row).collect()
+    }
+
+    fn from_row(row: &[u8]) -> Result<Self, String> {
+        let marker = Marker::from_byte(row[11])
+            .ok_or_else(|| format!("Unknown marker byte {} in a bundle row", row[11]))?;
+
+        Ok(Self {
+            handle: u32::from_be_bytes(row[0..4].try_into().unwrap()) as usize,
+            width: u32::from_be_bytes(row[4..8].try_into().unwrap()),
+            color: row[8..11].try_into().unwrap(),
+            marker,
+            marker_size: 0,
+            fill_to: None,
+            fill_alpha: 0.0,
+            stack_group: 0,
+            stack_order: 0,
+            down_color: None,
+            color_by: None,
+            y_offset: None,
+            y_scale: None,
+        })
+    }
+
+    /// The entry restyled by the `style` of a modification, keeping how it fills, stacks and
+    /// is colored by values, and its marker size and y transform when `style` has none, which
+    /// rows can't carry
+    pub(crate) fn restyled(&self, style: &BundleEntry) -> Self {
+        Self {
+            marker_size: match style.marker_size {
+                0 => self.marker_size,
+                size => size,
+            },
+            fill_to: self.fill_to,
+            fill_alpha: self.fill_alpha,
+            stack_group: self.stack_group,
+            stack_order: self.stack_order,
+            down_color: self.down_color,
+            color_by: self.color_by.clone(),
+            y_offset: style.y_offset.or(self.y_offset),
+            y_scale: style.y_scale.or(self.y_scale),
+            ..style.clone()
+        }
+    }
+
+    /// Plain line of a trace drawn by a job outside of any bundle
+    fn of_trace(trace: &TraceStyle) -> Self {
+        Self {
+            handle: trace.idx,
+            width: trace.width,
+            color: trace.color,
+            marker: Marker::None,
+            marker_size: 0,
+            fill_to: None,
+            fill_alpha: 0.0,
+            stack_group: 0,
+            stack_order: 0,
+            down_color: None,
+            color_by: None,
+            y_offset: None,
+            y_scale: None,
+        }
+    }
+
+    /// The entry with its values drawn times `scale` plus `offset`
+    pub fn with_y_transform(self, offset: RangePrec, scale: RangePrec) -> Self {
+        Self {
+            y_offset: Some(offset),
+            y_scale: Some(scale),
+            ..self
+        }
+    }
+
+    /// `(scale, offset)` the values are drawn with, composed after the job's own transform
+    pub fn y_transform(&self) -> (RangePrec, RangePrec) {
+        (self.y_scale.unwrap_or(1.0), self.y_offset.unwrap_or(0.0))
+    }
+
+    /// Entries rendering a forecast: the `bands` as `(lower, upper)` quantile pairs ordered
+    /// from the outermost to the innermost, drawn as nested fills, topped by the `center` line
+    pub fn forecast(
+        center: DataIdx,
+        bands: &[(DataIdx, DataIdx)],
+        color: [u8; 3],
+        width: u32,
+    ) -> Vec<BundleEntry> {
+        const MIN_ALPHA: f32 = 0.15;
+        const MAX_ALPHA: f32 = 0.45;
+
+        let mut entries: Vec<BundleEntry> = bands
+            .iter()
+            .enumerate()
+            .map(|(i, &(lower, upper))| BundleEntry {
+                handle: upper,
+                width: 0,
+                color,
+                marker: Marker::None,
+                marker_size: 0,
+                fill_to: Some(lower),
+                fill_alpha: MIN_ALPHA
+                    + (MAX_ALPHA - MIN_ALPHA) * i as f32 / (bands.len() - 1).max(1) as f32,
+                stack_group: 0,
+                stack_order: 0,
+                down_color: None,
+                color_by: None,
+                y_offset: None,
+                y_scale: None,
+            })
+            .collect();
+
+        entries.push(BundleEntry {
+            handle: center,
+            width,
+            color,
+            marker: Marker::None,
+            marker_size: 0,
+            fill_to: None,
+            fill_alpha: 0.0,
+            stack_group: 0,
+            stack_order: 0,
+            down_color: None,
+            color_by: None,
+            y_offset: None,
+            y_scale: None,
+        });
+
+        entries
+    }
+
+    /// Entry drawing `handle` as a line colored by the values of `color_by` at its points
+    pub fn colormapped(
+        handle: DataIdx,
+        color_by: DataIdx,
+        colormap: Colormap,
+        color: [u8; 3],
+        width: u32,
+    ) -> Self {
+        Self {
+            handle,
+            width,
+            color,
+            marker: Marker::None,
+            marker_size: 0,
+            fill_to: None,
+            fill_alpha: 0.0,
+            stack_group: 0,
+            stack_order: 0,
+            down_color: None,
+            color_by: Some((color_by, colormap)),
+            y_offset: None,
+            y_scale: None,
+        }
+    }
+
+    /// Entry drawing the OHLC trace `handle` as candlesticks with wicks `width` pixels wide
+    pub fn candles(handle: DataIdx, up_color: [u8; 3], down_color: [u8; 3], width: u32) -> Self {
+        Self {
+            handle,
+            width,
+            color: up_color,
+            marker: Marker::None,
+            marker_size: 0,
+            fill_to: None,
+            fill_alpha: 0.0,
+            stack_group: 0,
+            stack_order: 0,
+            down_color: Some(down_color),
+            color_by: None,
+            y_offset: None,
+            y_scale: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct AxisTick {
     val: RangePrec,
     pos: RangePrec,
+    label: String,
+    /// Left unlabeled so the labels around it don't overlap
+    hidden: bool,
+}
+
+impl AxisTick {
+    /// Tick labeled with its formatted value
+    pub fn numeric(val: RangePrec, pos: RangePrec) -> Self {
+        Self {
+            val,
+            pos,
+            label: text::format_tick(val),
+            hidden: false,
+        }
+    }
+
+    pub fn val(&self) -> RangePrec {
+        self.val
+    }
+
+    /// Relative position along the axis, from 0 at its start to 1 at its end
+    pub fn pos(&self) -> RangePrec {
+        self.pos
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+/// Value the tick labels of an axis are relative to, shown once next to the axis
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AxisOffset {
+    pub val: RangePrec,
+    /// Signed exponent notation of the offset, e.g. `+1.0e6`
+    pub label: String,
+}
+
+/// Y range a trace was drawn with inside the visible x range
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TraceExtent {
+    pub handle: DataIdx,
+    pub min: DataPrec,
+    pub max: DataPrec,
+}
+
+/// How faithfully a trace was drawn, its worst level of detail if drawn by multiple bundles
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DecimationReport {
+    pub handle: DataIdx,
+    pub fidelity: Fidelity,
+    /// Largest vertical distance of a data point from the drawn line, in data units, when
+    /// the bundle was built with `measure_error`
+    pub max_error: Option<DataPrec>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct RenderJobResult {
     x_ticks: Box<[AxisTick]>,
     y_ticks: Box<[AxisTick]>,
+    y2_ticks: Box<[AxisTick]>,
+    /// Offsets the tick labels of the axes are given relative to, when their values are too
+    /// large for their span to be told apart
+    x_offset: Option<AxisOffset>,
+    y_offset: Option<AxisOffset>,
+    y2_offset: Option<AxisOffset>,
+    /// Degrees the x tick labels are turned counterclockwise by
+    x_label_rotation: f32,
+    extents: Vec<TraceExtent>,
+    decimation: Vec<DecimationReport>,
+    /// Legend items for toggling the traces, when the job renders a legend
+    legend: Vec<legend::LegendHit>,
+}
+
+impl RenderJobResult {
+    /// Ticks of the x, y and secondary y axis
+    pub fn ticks(&self) -> (&[AxisTick], &[AxisTick], &[AxisTick]) {
+        (&self.x_ticks, &self.y_ticks, &self.y2_ticks)
+    }
+
+    /// Offsets of the x, y and secondary y axis
+    pub fn offsets(
+        &self,
+    ) -> (
+        Option<&AxisOffset>,
+        Option<&AxisOffset>,
+        Option<&AxisOffset>,
+    ) {
+        (
+            self.x_offset.as_ref(),
+            self.y_offset.as_ref(),
+            self.y2_offset.as_ref(),
+        )
+    }
+
+    /// Widens the extent reported for `handle`, e.g. when it's drawn by multiple bundles
+    pub fn add_extent(&mut self, handle: DataIdx, (min, max): (DataPrec, DataPrec)) {
+        match self.extents.iter_mut().find(|e| e.handle == handle) {
+            Some(extent) => {
+                extent.min = extent.min.min(min);
+                extent.max = extent.max.max(max);
+            }
+            None => self.extents.push(TraceExtent { handle, min, max }),
+        }
+    }
+
+    pub fn set_legend(&mut self, legend: Vec<legend::LegendHit>) {
+        self.legend = legend;
+    }
+
+    /// Reports how `handle` was drawn, keeping the worst fidelity and error reported for it
+    pub fn add_decimation(&mut self, handle: DataIdx, decimation: Decimation) {
+        let Decimation {
+            fidelity,
+            max_error,
+        } = decimation;
+
+        match self.decimation.iter_mut().find(|d| d.handle == handle) {
+            Some(report) => {
+                report.fidelity = report.fidelity.max(fidelity);
+                report.max_error = report.max_error.zip(max_error).map(|(a, b)| a.max(b));
+            }
+            None => self.decimation.push(DecimationReport {
+                handle,
+                fidelity,
+                max_error,
+            }),
+        }
+    }
+}
+
+/// Pixel rectangle of the plotting area, with the origin in the bottom left corner
+#[derive(Clone, Copy, Debug)]
+pub struct PlotRect {
+    pub left: u32,
+    pub bottom: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PlotRect {
+    /// Plot area of `job` on a canvas of the given size, minus its margins and label space
+    pub fn of(job: &RenderJob, canvas_width: u32, canvas_height: u32) -> Self {
+        let (region_bottom, region_height) = if job.region_height > 0 {
+            (job.region_bottom, job.region_height)
+        } else {
+            (0, canvas_height)
+        };
+
+        Self {
+            left: job.margin + job.y_label_space,
+            bottom: region_bottom + job.margin + job.x_label_space,
+            width: canvas_width
+                .saturating_sub(job.margin * 2 + job.y_label_space + job.y2_label_space),
+            height: region_height.saturating_sub(job.margin * 2 + job.x_label_space),
+        }
+    }
+
+    pub fn right(&self) -> u32 {
+        self.left + self.width
+    }
+
+    pub fn top(&self) -> u32 {
+        self.bottom + self.height
+    }
 }
 
 pub trait Renderer {
     fn render(&mut self, module: &DataModule, job: RenderJob) -> Result<RenderJobResult, JsValue>;
     fn present(&mut self) -> Result<(), JsValue>;
+    /// Adds an annotation drawn by the renders of the selected chart, returning its id
+    fn add_annotation(&mut self, annotation: Annotation) -> u32;
+    /// Removes an annotation of the selected chart, returning whether it existed
+    fn remove_annotation(&mut self, id: u32) -> bool;
     fn size_changed(&mut self, width: u32, height: u32) -> Result<(), JsValue>;
+    /// Width and height of the canvas, as last set by `size_changed`
+    fn size(&self) -> (u32, u32);
     fn create_bundle(
         &mut self,
         module: &DataModule,
         from: RangePrec,
         to: RangePrec,
         data: &[BundleEntry],
+        opts: BundleOptions,
     ) -> Result<usize, JsValue>;
     fn rebundle(
         &mut self,
         module: &DataModule,
         bundle: usize,
         to_add: &[BundleEntry],
-        to_del: &[crate::data::DataIdx],
+        to_del: &[DataIdx],
         to_mod: &[BundleEntry],
     ) -> Result<(), JsValue>;
+    /// Starts building a bundle `step_bundle` completes, returning the handle it will have.
+    /// With `replaces`, that bundle keeps being drawn until the new one takes its handle.
+    fn begin_bundle(
+        &mut self,
+        module: &DataModule,
+        from: RangePrec,
+        to: RangePrec,
+        data: &[BundleEntry],
+        opts: BundleOptions,
+        replaces: Option<usize>,
+    ) -> Result<usize, JsValue>;
+    /// Builds up to `chunks` more entries or stack groups of a bundle started by
+    /// `begin_bundle`, swapping it in once all of them are. Returns the fraction built.
+    fn step_bundle(
+        &mut self,
+        module: &DataModule,
+        bundle: usize,
+        chunks: usize,
+    ) -> Result<f64, JsValue>;
     fn dispose_bundle(&mut self, bundle: usize) -> Result<(), JsValue>;
+    /// Leaves a bundle out of frames, hit tests and legends until it's shown again, keeping
+    /// its buffers so it's back right away
+    fn set_bundle_visible(&mut self, bundle: usize, visible: bool);
+    /// Appends `points` to the entry of `handle` without rebuilding the bundle
+    fn extend_bundle(
+        &mut self,
+        bundle: usize,
+        handle: DataIdx,
+        points: &[(RangePrec, RangePrec)],
+    ) -> Result<(), JsValue>;
+    /// Uploads a row major grid of `values` with `cols` columns spanning the `x` and `y` ranges
+    fn create_heatmap(
+        &mut self,
+        values: &[f32],
+        cols: usize,
+        x: (RangePrec, RangePrec),
+        y: (RangePrec, RangePrec),
+        colormap: &Colormap,
+    ) -> Result<usize, JsValue>;
+    fn dispose_heatmap(&mut self, heatmap: usize) -> Result<(), JsValue>;
+    /// Assigns `(handle, stack_group, stack_order)` to the bundle's entries and restacks them
+    fn restack_bundle(
+        &mut self,
+        module: &DataModule,
+        bundle: usize,
+        stacking: &[(DataIdx, u32, i32)],
+    ) -> Result<(), JsValue>;
+    /// Drops cached frames and, with `release_bundles`, the GPU buffers of all bundles while
+    /// keeping what they were built from, e.g. while the chart is hidden
+    fn suspend(&mut self, release_bundles: bool) -> Result<(), JsValue>;
+    /// Rebuilds the bundles released by `suspend` from `module`
+    fn resume(&mut self, module: &DataModule) -> Result<(), JsValue>;
+    /// Whether the GPU context is lost, along with everything uploaded to it
+    fn context_lost(&self) -> bool {
+        false
+    }
+    /// Rebuilds the GPU resources from `module` once a lost context has been restored,
+    /// returning whether there was anything to rebuild
+    fn restore_context(&mut self, _module: &DataModule) -> Result<bool, JsValue> {
+        Ok(false)
+    }
+    /// Renders `jobs` into an offscreen `width` x `height` frame, returning its premultiplied
+    /// RGBA pixels bottom row first
+    fn render_offscreen(
+        &mut self,
+        module: &DataModule,
+        jobs: &[RenderJob],
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<u8>, JsValue>;
+    /// Renders `job` into a bitmap the host composites itself, e.g. as a texture of its own
+    /// GL scene, leaving the presented frame as it was
+    fn render_to_texture(
+        &mut self,
+        _module: &DataModule,
+        _job: RenderJob,
+    ) -> Result<ImageBitmap, JsValue> {
+        Err(JsValue::from_str("The renderer draws no texture"))
+    }
+    /// Adds a chart presented into `present_canvas`, sharing the resources of the others
+    fn add_chart(&mut self, _chart: u32, _present_canvas: OffscreenCanvas) -> Result<(), JsValue> {
+        Err(JsValue::from_str("The renderer draws a single chart"))
+    }
+    fn remove_chart(&mut self, _chart: u32) -> Result<(), JsValue> {
+        Err(JsValue::from_str("The renderer draws a single chart"))
+    }
+    /// Makes `chart` the target of the following renders, presents and resizes. Renderers
+    /// of a single chart only have chart 0.
+    fn select_chart(&mut self, chart: u32) -> Result<(), JsValue> {
+        match chart {
+            0 => Ok(()),
+            _ => Err(JsValue::from_str("The renderer draws a single chart")),
+        }
+    }
+    /// Sets the canvas the selected chart presents its overlay layer into, `None` removing it
+    fn set_overlay_canvas(&mut self, _canvas: Option<OffscreenCanvas>) -> Result<(), JsValue> {
+        Err(JsValue::from_str("The renderer has no overlay layer"))
+    }
+    /// Draws a selection rectangle between the corners `from` and `to` into the overlay layer
+    /// alone, returning the points of the bundles of `job` within it
+    fn render_brush(
+        &mut self,
+        _job: &RenderJob,
+        _from: (RangePrec, RangePrec),
+        _to: (RangePrec, RangePrec),
+    ) -> Result<Vec<BrushHit>, JsValue> {
+        Err(JsValue::from_str("The renderer has no overlay layer"))
+    }
+    /// Draws a crosshair through `cursor`, snapped to the nearest point of the bundles of `job`
+    /// within `max_px` pixels, into the overlay layer alone. Returns the snapped point.
+    fn render_overlay(
+        &mut self,
+        _job: &RenderJob,
+        _cursor: (RangePrec, RangePrec),
+        _ruler: bool,
+        _max_px: f32,
+    ) -> Result<Option<HitResult>, JsValue> {
+        Err(JsValue::from_str("The renderer has no overlay layer"))
+    }
+    /// Draws the now marker of `job` and the shading past it into the overlay layer alone,
+    /// which carries them instead of the frames of charts that have one
+    fn render_now(&mut self, _job: &RenderJob) -> Result<(), JsValue> {
+        Err(JsValue::from_str("The renderer has no overlay layer"))
+    }
+    /// Draws a playhead at `x` with the `trail` x units behind it shaded into the overlay layer
+    /// alone, returning the values of the bundles of `job` under it
+    fn render_playhead(
+        &mut self,
+        _job: &RenderJob,
+        _x: RangePrec,
+        _trail: RangePrec,
+    ) -> Result<Vec<PlayheadValue>, JsValue> {
+        Err(JsValue::from_str("The renderer has no overlay layer"))
+    }
+    /// Features the renderer can honor on the device it draws with
+    fn features(&self) -> FeatureSet;
+    /// Name of what the renderer draws with, e.g. for hosts reporting it
+    fn backend(&self) -> &'static str;
+    /// Document accumulated by vector renderers since it was last taken, `None` for the others
+    fn take_svg(&mut self) -> Option<String> {
+        None
+    }
+    /// Premultiplied RGBA pixels of the frame drawn by software renderers, bottom row first,
+    /// `None` for the others
+    fn frame_pixels(&self) -> Option<Vec<u8>> {
+        None
+    }
+    /// Identifies what rendering `job` into the selected chart would draw, so a frame with
+    /// the same key as the last one can be skipped. `None` when every frame has to be drawn.
+    fn frame_key(&self, _job: &RenderJob) -> Option<draw_list::JobFingerprint> {
+        None
+    }
+    /// Nearest point of the bundles rendered by `job` within `max_px` pixels of `(x, y)`
+    fn find_closest(
+        &self,
+        job: &RenderJob,
+        x: RangePrec,
+        y: RangePrec,
+        max_px: f32,
+    ) -> Option<HitResult>;
 }
 
 #[wasm_bindgen]
 pub struct RendererContainer {
     renderer: Box<dyn Renderer>,
+    now_clock: Option<js_sys::Function>,
+    /// Jobs of the last presented frame of each chart, re-rendered by exports
+    last_frames: HashMap<u32, Vec<RenderJob>>,
+    /// What the jobs of the last frames reported, for describing them
+    last_results: HashMap<u32, Vec<RenderJobResult>>,
+    /// What the last frame of each chart rendered by `render` drew, with the revision of the
+    /// module it was drawn from, so an unchanged frame isn't drawn again
+    last_keys: HashMap<u32, (draw_list::JobFingerprint, u64)>,
+    /// Callbacks of the bundles being built by `step_bundle`, given the fraction built
+    bundle_progress: HashMap<usize, js_sys::Function>,
+    /// Bookmarks of each chart in the order they were saved
+    bookmarks: HashMap<u32, Vec<(String, Bookmark)>>,
+    /// Annotations added by the bookmark last applied to each chart, removed by the next one
+    bookmark_annotations: HashMap<u32, Vec<u32>>,
+    /// Chart selected in the renderer
+    chart: u32,
 }
 
 #[wasm_bindgen]
+#[derive(Clone, Copy)]
 pub struct RendererOptions {
     pub area_chart: bool,
+    /// Corners of lines too thick for the driver to draw
+    pub line_join: geometry::LineJoin,
+    pub line_cap: geometry::LineCap,
 }
 
 #[wasm_bindgen]
 impl RendererOptions {
     #[wasm_bindgen(constructor)]
     pub fn new(area_chart: bool) -> Self {
-        Self { area_chart }
+        Self {
+            area_chart,
+            line_join: geometry::LineJoin::default(),
+            line_cap: geometry::LineCap::default(),
+        }
+    }
+}
+
+/// Optional features a host may rely on, set for the ones it wants or a renderer can honor
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeatureSet {
+    /// Dashed annotation lines
+    pub dash: bool,
+    /// Markers sized, shaped and turned freely rather than limited to the driver's points
+    pub instancing: bool,
+    /// Tick labels, legends and other text
+    pub text: bool,
+    /// Multisampled antialiasing of the lines the driver draws
+    pub msaa: bool,
+    /// Linearly filtered textures of 32 bit floats
+    pub float_textures: bool,
+}
+
+#[wasm_bindgen]
+impl FeatureSet {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn all() -> Self {
+        Self {
+            dash: true,
+            instancing: true,
+            text: true,
+            msaa: true,
+            float_textures: true,
+        }
+    }
+}
+
+impl FeatureSet {
+    /// Features set in both
+    pub fn intersect(&self, other: &FeatureSet) -> FeatureSet {
+        FeatureSet {
+            dash: self.dash && other.dash,
+            instancing: self.instancing && other.instancing,
+            text: self.text && other.text,
+            msaa: self.msaa && other.msaa,
+            float_textures: self.float_textures && other.float_textures,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub struct BundleOptions {
+    pub downsampling: Downsampling,
+    /// Number of horizontal buckets the bundle is reduced to, usually its width in pixels
+    pub resolution: u32,
+    /// Additional levels of detail, each decimated 4x more than the previous one
+    pub pyramid_levels: u32,
+    /// Measures the error of decimated entries for the render results, which costs another
+    /// pass over their data when bundling
+    pub measure_error: bool,
+    /// Fraction of the bundle's range also bundled past each of its ends, so lines don't pop
+    /// in at the edges of the view while it's panned. Renderers drawing straight from the
+    /// module read past the view by the job's `overscan` instead.
+    pub overscan: RangePrec,
+}
+
+#[wasm_bindgen]
+impl BundleOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new(downsampling: Downsampling, resolution: u32) -> Self {
+        Self {
+            downsampling,
+            resolution,
+            pyramid_levels: 0,
+            measure_error: false,
+            overscan: 0.0,
+        }
+    }
+}
+
+impl BundleOptions {
+    /// `from..to` grown by the overscan on both sides, with the options' resolution raised to
+    /// keep the buckets as wide
+    pub fn overscanned(self, from: RangePrec, to: RangePrec) -> (RangePrec, RangePrec, Self) {
+        let overscan = self.overscan.max(0.0);
+        let margin = (to - from) * overscan;
+        let resolution = (self.resolution as RangePrec * (1.0 + 2.0 * overscan)).round();
+
+        (
+            from - margin,
+            to + margin,
+            Self {
+                resolution: resolution as u32,
+                ..self
+            },
+        )
     }
 }
 
+impl Default for BundleOptions {
+    fn default() -> Self {
+        Self::new(Downsampling::None, 0)
+    }
+}
+
+/// handle: u32, width: u32, color: [u8; 3], marker: u8. Marker sizes and the other fields
+/// of entries only travel in their `{ version, data }` form.
 const ROW_LEN: usize = std::mem::size_of::<u32>() * 2 + 4;
 
 #[wasm_bindgen]
@@ -78,16 +787,217 @@ impl RendererContainer {
     ) -> Result<RendererContainer, JsValue> {
         Ok(Self {
             renderer: Box::new(WebGlRenderer::new(shared_canvas, present_canvas, opts)?),
+            now_clock: None,
+            last_frames: HashMap::new(),
+            last_results: HashMap::new(),
+            last_keys: HashMap::new(),
+            bundle_progress: HashMap::new(),
+            bookmarks: HashMap::new(),
+            bookmark_annotations: HashMap::new(),
+            chart: 0,
         })
     }
 
+    /// Renderer drawing with the paths of the present canvas' 2D context, for browsers without
+    /// WebGL. It has a single chart and no overlay layer.
+    pub fn new_canvas2d(
+        present_canvas: OffscreenCanvas,
+        opts: RendererOptions,
+    ) -> Result<RendererContainer, JsValue> {
+        Ok(Self {
+            renderer: Box::new(Canvas2dRenderer::new(present_canvas, opts)?),
+            now_clock: None,
+            last_frames: HashMap::new(),
+            last_results: HashMap::new(),
+            last_keys: HashMap::new(),
+            bundle_progress: HashMap::new(),
+            bookmarks: HashMap::new(),
+            bookmark_annotations: HashMap::new(),
+            chart: 0,
+        })
+    }
+
+    /// The best renderer available in this browser, WebGL unless it can't get a context, in
+    /// which case it falls back to `new_canvas2d`. See `backend` for the one picked.
+    pub fn new_best(
+        shared_canvas: OffscreenCanvas,
+        present_canvas: OffscreenCanvas,
+        opts: RendererOptions,
+    ) -> Result<RendererContainer, JsValue> {
+        Self::new_webgl(shared_canvas, present_canvas.clone(), opts)
+            .or_else(|_| Self::new_canvas2d(present_canvas, opts))
+    }
+
+    /// `webgl`, `canvas2d`, `svg` or `raster`, depending on what the renderer draws with
+    pub fn backend(&self) -> String {
+        self.renderer.backend().to_string()
+    }
+
+    /// Renderer writing SVG instead of drawing, with polylines simplified to `simplify_px`
+    /// pixels of tolerance, `0` keeping every point
+    pub fn new_svg(width: u32, height: u32, simplify_px: f32) -> RendererContainer {
+        Self {
+            renderer: Box::new(SvgRenderer::new(width, height, simplify_px)),
+            now_clock: None,
+            last_frames: HashMap::new(),
+            last_results: HashMap::new(),
+            last_keys: HashMap::new(),
+            bundle_progress: HashMap::new(),
+            bookmarks: HashMap::new(),
+            bookmark_annotations: HashMap::new(),
+            chart: 0,
+        }
+    }
+
+    /// Renderer drawing into a buffer of pixels on the CPU, for rendering charts without a GPU,
+    /// e.g. in Node or CI. Read the frames with `pixels` or export them with `export_png`.
+    #[cfg(feature = "raster")]
+    pub fn new_raster(width: u32, height: u32, opts: RendererOptions) -> RendererContainer {
+        Self {
+            renderer: Box::new(RasterRenderer::new(width, height, opts)),
+            now_clock: None,
+            last_frames: HashMap::new(),
+            last_results: HashMap::new(),
+            last_keys: HashMap::new(),
+            bundle_progress: HashMap::new(),
+            bookmarks: HashMap::new(),
+            bookmark_annotations: HashMap::new(),
+            chart: 0,
+        }
+    }
+
+    /// Which of the `requested` features the renderer can honor on this device, so hosts can
+    /// leave out options that would otherwise be downgraded silently
+    pub fn negotiate(&self, requested: &FeatureSet) -> FeatureSet {
+        requested.intersect(&self.renderer.features())
+    }
+
+    /// SVG document of the frames rendered since the last call, `None` unless the renderer
+    /// was created by `new_svg`
+    pub fn take_svg(&mut self) -> Option<String> {
+        self.renderer.take_svg()
+    }
+
+    /// Premultiplied RGBA pixels of the presented frame, bottom row first, `None` unless the
+    /// renderer was created by `new_raster`
+    pub fn pixels(&self) -> Option<Vec<u8>> {
+        self.renderer.frame_pixels()
+    }
+
+    /// Adds another chart drawn by this renderer, sharing its WebGL context, programs and
+    /// bundles. Charts take turns drawing into the shared canvas, each presenting the region
+    /// of its size into its own `present_canvas`. The chart passed to `new_webgl` has id 0.
+    pub fn add_chart(
+        &mut self,
+        chart: u32,
+        present_canvas: OffscreenCanvas,
+    ) -> Result<(), JsValue> {
+        self.renderer.add_chart(chart, present_canvas)
+    }
+
+    pub fn remove_chart(&mut self, chart: u32) -> Result<(), JsValue> {
+        self.renderer.remove_chart(chart)?;
+        self.last_frames.remove(&chart);
+        self.last_results.remove(&chart);
+        self.last_keys.remove(&chart);
+        self.bookmarks.remove(&chart);
+        self.bookmark_annotations.remove(&chart);
+
+        Ok(())
+    }
+
+    /// Makes `chart` the one the following renders, resizes and exports apply to
+    pub fn select_chart(&mut self, chart: u32) -> Result<(), JsValue> {
+        self.renderer.select_chart(chart)?;
+        self.chart = chart;
+
+        Ok(())
+    }
+
+    /// Sets a callback returning the current x position of the "now" marker,
+    /// used for jobs that don't specify `now` themselves
+    pub fn set_now_clock(&mut self, clock: Option<js_sys::Function>) {
+        self.now_clock = clock;
+    }
+
+    /// Renders `job` into the selected chart and presents it. When neither the job, the
+    /// bundles nor the module changed since the last frame of the chart, nothing is drawn and
+    /// the last result is returned again.
     pub fn render(&mut self, module: &DataModule, job: RenderJob) -> Result<JsValue, JsValue> {
-        let result = self.renderer.render(module, job)?;
-        self.renderer.present()?;
+        let result = self.render_selected(module, job)?;
 
         Ok(serde_wasm_bindgen::to_value(&result).unwrap())
     }
 
+    /// Renders the `jobs` into the charts of the same index in one call, e.g. for dashboards
+    /// of many small charts. The jobs are batched by chart, each presented once after all
+    /// of its jobs in their given order, and the charts drawing the same bundles are rendered
+    /// one after another. Returns the results in the order of `jobs`, the selected chart
+    /// stays selected.
+    pub fn render_many(
+        &mut self,
+        module: &DataModule,
+        charts: Vec<u32>,
+        jobs: Vec<RenderJob>,
+    ) -> Result<JsValue, JsValue> {
+        let results = self.render_batch(module, &charts, jobs)?;
+
+        Ok(serde_wasm_bindgen::to_value(&results).unwrap())
+    }
+
+    /// Renders and presents every member of `group` into its chart, returning their results
+    /// in the order of `group.charts()`. The selected chart stays selected.
+    pub fn render_linked(
+        &mut self,
+        module: &DataModule,
+        group: &LinkGroup,
+    ) -> Result<JsValue, JsValue> {
+        let results = self.render_group(module, group)?;
+
+        Ok(serde_wasm_bindgen::to_value(&results).unwrap())
+    }
+
+    /// Renders a price chart stacked over a volume chart, both sharing the x range of `price`.
+    /// `price_share` is the fraction of the canvas height given to the price panel.
+    pub fn render_combo(
+        &mut self,
+        module: &DataModule,
+        price: RenderJob,
+        volume: RenderJob,
+        price_share: f32,
+    ) -> Result<JsValue, JsValue> {
+        let (mut price, mut volume) = (price.padded(), volume.padded());
+        let (_, canvas_height) = self.renderer.size();
+        let volume_height = (canvas_height as f32 * (1.0 - price_share.clamp(0.0, 1.0))) as u32;
+
+        volume.x_from = price.x_from;
+        volume.x_to = price.x_to;
+        volume.x_scale = price.x_scale;
+        volume.x_linthresh = price.x_linthresh;
+        volume.region_bottom = 0;
+        volume.region_height = volume_height;
+
+        // the price panel sits on top of the volume panel and leaves the x labels to it, the
+        // canvas is cleared at most once by the price job
+        price.region_bottom = volume_height;
+        price.region_height = canvas_height - volume_height;
+        price.x_label_space = 0;
+        volume.clear = false;
+
+        self.last_keys.remove(&self.chart);
+        self.last_frames
+            .insert(self.chart, vec![price.clone(), volume.clone()]);
+        let price_result = self.renderer.render(module, price)?;
+        let volume_result = self.renderer.render(module, volume)?;
+        self.renderer.present()?;
+        self.last_results.insert(
+            self.chart,
+            vec![price_result.clone(), volume_result.clone()],
+        );
+
+        Ok(serde_wasm_bindgen::to_value(&[price_result, volume_result]).unwrap())
+    }
+
     pub fn size_changed(&mut self, width: u32, height: u32) -> Result<(), JsValue> {
         self.renderer.size_changed(width, height)
     }
@@ -98,19 +1008,136 @@ impl RendererContainer {
         from: RangePrec,
         to: RangePrec,
         stream: &[u8],
+        opts: Option<BundleOptions>,
+    ) -> Result<usize, JsValue> {
+        let vec = BundleEntry::from_rows(stream).map_err(|e| JsValue::from_str(&e))?;
+
+        self.renderer
+            .create_bundle(module, from, to, &vec, opts.unwrap_or_default())
+    }
+
+    /// Starts building a bundle of the entries of `stream` without blocking the worker,
+    /// returning the handle it will have. Every call of `step_bundle` then builds a few more
+    /// of its entries, e.g. one per microtask, and reports the fraction built to `progress`.
+    /// Jobs skip the bundle until it's built, or with `replaces` keep drawing that bundle,
+    /// whose handle the new one takes once built.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_bundle_async(
+        &mut self,
+        module: &DataModule,
+        from: RangePrec,
+        to: RangePrec,
+        stream: &[u8],
+        opts: Option<BundleOptions>,
+        replaces: Option<usize>,
+        progress: Option<js_sys::Function>,
     ) -> Result<usize, JsValue> {
-        let mut vec = Vec::with_capacity(stream.len() / ROW_LEN);
+        let entries = BundleEntry::from_rows(stream).map_err(|e| JsValue::from_str(&e))?;
+
+        let bundle = self.renderer.begin_bundle(
+            module,
+            from,
+            to,
+            &entries,
+            opts.unwrap_or_default(),
+            replaces,
+        )?;
+        match progress {
+            Some(progress) => self.bundle_progress.insert(bundle, progress),
+            None => self.bundle_progress.remove(&bundle),
+        };
+
+        Ok(bundle)
+    }
 
-        for row in stream.chunks_exact(ROW_LEN) {
-            vec.push(BundleEntry {
-                handle: u32::from_be_bytes(row[0..4].try_into().unwrap()) as usize,
-                width: u32::from_be_bytes(row[4..8].try_into().unwrap()),
-                color: row[8..11].try_into().unwrap(),
-                points_mode: row[11] > 0,
-            });
+    /// Builds up to `chunks` more entries of a bundle started by `create_bundle_async`, a
+    /// stack group counting as one. Returns whether the bundle is built and drawn by jobs.
+    pub fn step_bundle(
+        &mut self,
+        module: &DataModule,
+        bundle: usize,
+        chunks: usize,
+    ) -> Result<bool, JsValue> {
+        let built = match self.renderer.step_bundle(module, bundle, chunks) {
+            Ok(built) => built,
+            // the failed build is dropped, so is its progress callback
+            Err(e) => {
+                self.bundle_progress.remove(&bundle);
+                return Err(e);
+            }
+        };
+
+        if let Some(progress) = self.bundle_progress.get(&bundle) {
+            progress.call1(&JsValue::NULL, &JsValue::from_f64(built))?;
+        }
+        if built < 1.0 {
+            return Ok(false);
         }
 
-        self.renderer.create_bundle(module, from, to, &vec)
+        self.bundle_progress.remove(&bundle);
+        Ok(true)
+    }
+
+    /// Creates a bundle of the entries `{ version, data }` holds, as hosts persisting them
+    /// across upgrades saved them
+    pub fn create_bundle_from_wire(
+        &mut self,
+        module: &DataModule,
+        from: RangePrec,
+        to: RangePrec,
+        wire: JsValue,
+        opts: Option<BundleOptions>,
+    ) -> Result<usize, JsValue> {
+        let wire: Versioned<Vec<BundleEntry>> = serde_wasm_bindgen::from_value(wire)?;
+        let entries = wire.into_current().map_err(|e| JsValue::from_str(&e))?;
+
+        self.renderer
+            .create_bundle(module, from, to, &entries, opts.unwrap_or_default())
+    }
+
+    /// Creates a bundle holding a forecast, `bands` contains `(lower, upper)` handle pairs
+    /// flattened and ordered from the outermost quantiles inwards
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_forecast_bundle(
+        &mut self,
+        module: &DataModule,
+        from: RangePrec,
+        to: RangePrec,
+        center: DataIdx,
+        bands: &[DataIdx],
+        color: &[u8],
+        width: u32,
+    ) -> Result<usize, JsValue> {
+        let bands: Vec<_> = bands.chunks_exact(2).map(|b| (b[0], b[1])).collect();
+        let entries = BundleEntry::forecast(center, &bands, rgb(color)?, width);
+
+        self.renderer
+            .create_bundle(module, from, to, &entries, BundleOptions::default())
+    }
+
+    /// Creates a bundle drawing an envelope as its mean line over the band between its min
+    /// and max
+    pub fn create_envelope_bundle(
+        &mut self,
+        module: &DataModule,
+        from: RangePrec,
+        to: RangePrec,
+        envelope: DataIdx,
+        color: &[u8],
+        width: u32,
+    ) -> Result<usize, JsValue> {
+        let envelope = module
+            .get_envelope(envelope)
+            .ok_or_else(|| JsValue::from_str(&format!("Handle {} is not an envelope", envelope)))?;
+        let entries = BundleEntry::forecast(
+            envelope.mean,
+            &[(envelope.min, envelope.max)],
+            rgb(color)?,
+            width,
+        );
+
+        self.renderer
+            .create_bundle(module, from, to, &entries, BundleOptions::default())
     }
 
     pub fn rebundle(
@@ -121,37 +1148,589 @@ impl RendererContainer {
         add: &[u8],
         modif: &[u8],
     ) -> Result<(), JsValue> {
-        let mut to_add = Vec::with_capacity(add.len() / ROW_LEN);
-        let mut to_mod = Vec::with_capacity(modif.len() / ROW_LEN);
+        let to_add = BundleEntry::from_rows(add).map_err(|e| JsValue::from_str(&e))?;
+        let to_mod = BundleEntry::from_rows(modif).map_err(|e| JsValue::from_str(&e))?;
         let mut to_del = Vec::with_capacity(del.len() / size_of::<usize>());
 
         for row in del.chunks_exact(size_of::<usize>()) {
             to_del.push(usize::from_be_bytes(row.try_into().unwrap()));
         }
 
-        for row in add.chunks_exact(ROW_LEN) {
-            to_add.push(BundleEntry {
-                handle: u32::from_be_bytes(row[0..4].try_into().unwrap()) as usize,
-                width: u32::from_be_bytes(row[4..8].try_into().unwrap()),
-                color: row[8..11].try_into().unwrap(),
-                points_mode: row[11] > 0,
-            });
-        }
+        self.renderer
+            .rebundle(module, bundle, &to_add, &to_del, &to_mod)
+    }
 
-        for row in modif.chunks_exact(ROW_LEN) {
-            to_mod.push(BundleEntry {
-                handle: u32::from_be_bytes(row[0..4].try_into().unwrap()) as usize,
-                width: u32::from_be_bytes(row[4..8].try_into().unwrap()),
-                color: row[8..11].try_into().unwrap(),
-                points_mode: row[11] > 0,
-            });
+    /// `rebundle` with the added and modified entries in the `{ version, data }` form, which
+    /// holds every field of an entry, e.g. the `y_offset` and `y_scale` of normalized views
+    pub fn rebundle_from_wire(
+        &mut self,
+        module: &DataModule,
+        bundle: usize,
+        del: &[u8],
+        add: JsValue,
+        modif: JsValue,
+    ) -> Result<(), JsValue> {
+        let entries = |wire: JsValue| -> Result<Vec<BundleEntry>, JsValue> {
+            let wire: Versioned<Vec<BundleEntry>> = serde_wasm_bindgen::from_value(wire)?;
+            wire.into_current().map_err(|e| JsValue::from_str(&e))
+        };
+        let to_del: Vec<DataIdx> = del
+            .chunks_exact(size_of::<usize>())
+            .map(|row| usize::from_be_bytes(row.try_into().unwrap()))
+            .collect();
+
+        self.renderer
+            .rebundle(module, bundle, &entries(add)?, &to_del, &entries(modif)?)
+    }
+
+    /// Creates a bundle drawing the OHLC `traces` as candlesticks in the given colors
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_ohlc_bundle(
+        &mut self,
+        module: &DataModule,
+        from: RangePrec,
+        to: RangePrec,
+        traces: &[DataIdx],
+        up_color: &[u8],
+        down_color: &[u8],
+        opts: Option<BundleOptions>,
+    ) -> Result<usize, JsValue> {
+        let (up_color, down_color) = (rgb(up_color)?, rgb(down_color)?);
+        let entries: Vec<_> = traces
+            .iter()
+            .map(|&handle| BundleEntry::candles(handle, up_color, down_color, 1))
+            .collect();
+
+        self.renderer
+            .create_bundle(module, from, to, &entries, opts.unwrap_or_default())
+    }
+
+    /// Creates a bundle of the `traces` colored along their lines by the values of the traces
+    /// at the same index of `color_by`, each scaled to its range within the bundle. The lines
+    /// keep `color` where the renderer can't map them, points appended later by
+    /// `extend_bundle` aren't drawn until the bundle is rebuilt.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_colormapped_bundle(
+        &mut self,
+        module: &DataModule,
+        from: RangePrec,
+        to: RangePrec,
+        traces: &[DataIdx],
+        color_by: &[DataIdx],
+        colormap: &Colormap,
+        color: &[u8],
+        width: u32,
+        opts: Option<BundleOptions>,
+    ) -> Result<usize, JsValue> {
+        if traces.len() != color_by.len() {
+            return Err(JsValue::from_str(
+                "Every trace needs a trace to be colored by",
+            ));
         }
+        let color = rgb(color)?;
+        let entries: Vec<_> = traces
+            .iter()
+            .zip(color_by)
+            .map(|(&handle, &color_by)| {
+                BundleEntry::colormapped(handle, color_by, colormap.clone(), color, width)
+            })
+            .collect();
 
         self.renderer
-            .rebundle(module, bundle, &to_add, &to_del, &to_mod)
+            .create_bundle(module, from, to, &entries, opts.unwrap_or_default())
     }
 
     pub fn dispose_bundle(&mut self, bundle: usize) -> Result<(), JsValue> {
+        self.bundle_progress.remove(&bundle);
         self.renderer.dispose_bundle(bundle)
     }
+
+    /// Hides or shows all entries of a bundle at once, e.g. to compare runs bundled apart,
+    /// without blacklisting every trace or disposing the bundle
+    pub fn set_bundle_visible(&mut self, bundle: usize, visible: bool) {
+        self.renderer.set_bundle_visible(bundle, visible);
+    }
+
+    /// Appends live data to a bundle entry, `points` holding interleaved x and y values.
+    /// Points not past the entry's last one are dropped.
+    pub fn extend_bundle(
+        &mut self,
+        bundle: usize,
+        handle: DataIdx,
+        points: &[RangePrec],
+    ) -> Result<(), JsValue> {
+        let points: Vec<_> = points.chunks_exact(2).map(|p| (p[0], p[1])).collect();
+
+        self.renderer.extend_bundle(bundle, handle, &points)
+    }
+
+    /// Creates a heatmap from a row major grid, its first row lying at `y_from`
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_heatmap(
+        &mut self,
+        values: &[f32],
+        cols: usize,
+        x_from: RangePrec,
+        x_to: RangePrec,
+        y_from: RangePrec,
+        y_to: RangePrec,
+        colormap: &Colormap,
+    ) -> Result<usize, JsValue> {
+        self.renderer
+            .create_heatmap(values, cols, (x_from, x_to), (y_from, y_to), colormap)
+    }
+
+    pub fn dispose_heatmap(&mut self, heatmap: usize) -> Result<(), JsValue> {
+        self.renderer.dispose_heatmap(heatmap)
+    }
+
+    /// Changes how the entries of a bundle stack, `stream` holds `handle: u32, group: u32,
+    /// order: i32` rows, group 0 taking the entry out of any stack
+    pub fn restack_bundle(
+        &mut self,
+        module: &DataModule,
+        bundle: usize,
+        stream: &[u8],
+    ) -> Result<(), JsValue> {
+        const ROW: usize = size_of::<u32>() * 3;
+
+        let stacking: Vec<_> = stream
+            .chunks_exact(ROW)
+            .map(|row| {
+                (
+                    u32::from_be_bytes(row[0..4].try_into().unwrap()) as DataIdx,
+                    u32::from_be_bytes(row[4..8].try_into().unwrap()),
+                    i32::from_be_bytes(row[8..12].try_into().unwrap()),
+                )
+            })
+            .collect();
+
+        self.renderer.restack_bundle(module, bundle, &stacking)
+    }
+
+    /// Frees GPU memory of a hidden chart, bundles can't be changed until `resume` is called
+    pub fn suspend(&mut self, release_bundles: bool) -> Result<(), JsValue> {
+        self.last_keys.clear();
+        self.renderer.suspend(release_bundles)
+    }
+
+    pub fn resume(&mut self, module: &DataModule) -> Result<(), JsValue> {
+        self.last_keys.clear();
+        self.renderer.resume(module)
+    }
+
+    /// Whether the WebGL context is lost, e.g. after the GPU process restarted. Renders fail
+    /// until the browser restores it, the first one after that rebuilding the renderer.
+    pub fn is_context_lost(&self) -> bool {
+        self.renderer.context_lost()
+    }
+
+    /// Rebuilds the programs, bundles and heatmaps of a restored context right away rather
+    /// than on the next render, e.g. from a `webglcontextrestored` listener. Returns whether
+    /// the context had been lost.
+    pub fn restore_context(&mut self, module: &DataModule) -> Result<bool, JsValue> {
+        self.last_keys.clear();
+        self.renderer.restore_context(module)
+    }
+
+    /// Makes the next render of the selected chart draw even if nothing changed, e.g. after
+    /// the host drew over its canvas
+    pub fn invalidate_frame(&mut self) {
+        self.last_keys.remove(&self.chart);
+    }
+
+    /// Re-renders the last frame as a PNG of `width` x `height` times `scale` pixels, the
+    /// margins and label spaces scaled along. The frame isn't limited to the canvas size.
+    pub fn export_png(
+        &mut self,
+        module: &DataModule,
+        width: u32,
+        height: u32,
+        scale: f32,
+    ) -> Result<js_sys::Uint8Array, JsValue> {
+        let Some(last_frame) = self.last_frames.get(&self.chart) else {
+            return Err(JsValue::from_str("Nothing has been rendered to export yet"));
+        };
+
+        let (px_width, px_height) = (
+            (width as f32 * scale).round().max(1.0) as u32,
+            (height as f32 * scale).round().max(1.0) as u32,
+        );
+        let jobs: Vec<RenderJob> = last_frame.iter().map(|j| j.scaled(scale)).collect();
+
+        let pixels = self
+            .renderer
+            .render_offscreen(module, &jobs, px_width, px_height)?;
+        let png = export::encode_png(px_width, px_height, &pixels)?;
+
+        Ok(js_sys::Uint8Array::from(&png[..]))
+    }
+
+    /// Renders `job` like `render` would, but into an `ImageBitmap` of the canvas size instead
+    /// of the selected chart's canvas, for hosts compositing the chart into a scene of their
+    /// own. Uploaded with `texImage2D`, the bitmap becomes a texture of the host's context.
+    pub fn render_to_texture(
+        &mut self,
+        module: &DataModule,
+        job: RenderJob,
+    ) -> Result<ImageBitmap, JsValue> {
+        let job = self.clocked(job)?;
+        self.renderer.render_to_texture(module, job)
+    }
+
+    /// Gives the selected chart an overlay layer presented into `canvas`, which the host
+    /// stacks over the chart's canvas. `None` removes the layer.
+    pub fn set_overlay_canvas(&mut self, canvas: Option<OffscreenCanvas>) -> Result<(), JsValue> {
+        self.renderer.set_overlay_canvas(canvas)
+    }
+
+    /// Draws a crosshair through `(cursor_x, cursor_y)` in data coordinates into the overlay
+    /// layer, leaving the chart itself as it is, e.g. on every mouse move. A marker sits on
+    /// the nearest bundled point within `max_px_distance` pixels, with `ruler` also a vertical
+    /// line through it. Returns the point like `find_closest` does.
+    pub fn render_overlay(
+        &mut self,
+        job: &RenderJob,
+        cursor_x: RangePrec,
+        cursor_y: RangePrec,
+        ruler: bool,
+        max_px_distance: f32,
+    ) -> Result<JsValue, JsValue> {
+        let hit = self.renderer.render_overlay(
+            &self.clocked(job.clone())?,
+            (cursor_x, cursor_y),
+            ruler,
+            max_px_distance,
+        )?;
+
+        Ok(match hit {
+            Some(hit) => serde_wasm_bindgen::to_value(&hit)?,
+            None => JsValue::NULL,
+        })
+    }
+
+    /// Draws a selection rectangle from `(x0, y0)` to `(x1, y1)` in data coordinates into the
+    /// overlay layer in place of the crosshair, e.g. for box zoom. Returns the bundled traces
+    /// with points inside as `{ handle, ranges }`, the ranges being `{ start, end }` indices.
+    pub fn set_brush(
+        &mut self,
+        job: &RenderJob,
+        x0: RangePrec,
+        y0: RangePrec,
+        x1: RangePrec,
+        y1: RangePrec,
+    ) -> Result<JsValue, JsValue> {
+        let hits = self
+            .renderer
+            .render_brush(&self.clocked(job.clone())?, (x0, y0), (x1, y1))?;
+
+        Ok(serde_wasm_bindgen::to_value(&hits)?)
+    }
+
+    /// Draws the now marker at the time of the clock into the overlay layer in place of the
+    /// crosshair, e.g. on every tick of the clock. Charts with an overlay layer carry the
+    /// marker and the shading past it there, so moving it doesn't draw their frames again.
+    pub fn render_now(&mut self, job: &RenderJob) -> Result<(), JsValue> {
+        let job = self.clocked(job.clone())?;
+        self.renderer.render_now(&job)
+    }
+
+    /// Draws a playhead at `x` into the overlay layer in place of the crosshair, for charts
+    /// following an external clock such as a playing video. With a positive `trail` the
+    /// `trail` x units behind it are highlighted. Returns the values of the bundled traces at
+    /// `x` as `{ handle, y }`, interpolated between their points.
+    pub fn set_playhead(
+        &mut self,
+        job: &RenderJob,
+        x: RangePrec,
+        trail: RangePrec,
+    ) -> Result<JsValue, JsValue> {
+        let job = self.clocked(job.clone())?;
+        let values = self.renderer.render_playhead(&job, x, trail)?;
+
+        Ok(serde_wasm_bindgen::to_value(&values)?)
+    }
+
+    /// Adds a threshold line or shaded region to the selected chart, drawn by every following
+    /// render. Returns the id to remove it with.
+    pub fn add_annotation(&mut self, annotation: &Annotation) -> u32 {
+        self.last_keys.remove(&self.chart);
+        self.renderer.add_annotation(annotation.clone())
+    }
+
+    /// Removes an annotation of the selected chart, returning whether it existed
+    pub fn remove_annotation(&mut self, id: u32) -> bool {
+        self.last_keys.remove(&self.chart);
+        self.renderer.remove_annotation(id)
+    }
+
+    /// Saves `bookmark` under `name` for the selected chart, replacing one of the same name
+    pub fn save_bookmark(&mut self, name: String, bookmark: &Bookmark) {
+        let bookmarks = self.bookmarks.entry(self.chart).or_default();
+
+        match bookmarks.iter_mut().find(|(n, _)| *n == name) {
+            Some(saved) => saved.1 = bookmark.clone(),
+            None => bookmarks.push((name, bookmark.clone())),
+        }
+    }
+
+    /// Removes a bookmark of the selected chart, returning whether it existed
+    pub fn remove_bookmark(&mut self, name: &str) -> bool {
+        let Some(bookmarks) = self.bookmarks.get_mut(&self.chart) else {
+            return false;
+        };
+        let count = bookmarks.len();
+        bookmarks.retain(|(n, _)| n != name);
+
+        bookmarks.len() < count
+    }
+
+    /// Names of the bookmarks of the selected chart in the order they were saved
+    pub fn bookmark_names(&self) -> Vec<String> {
+        self.bookmarks
+            .get(&self.chart)
+            .map(|bookmarks| bookmarks.iter().map(|(n, _)| n.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the selected chart to a bookmark. The traces it hid are blacklisted in `job`
+    /// in place of the ones blacklisted so far, and its annotations replace those of the last
+    /// applied bookmark. The view is left to the returned animation, which moves it there
+    /// over `duration` milliseconds from `now` on.
+    pub fn apply_bookmark(
+        &mut self,
+        name: &str,
+        job: &mut RenderJob,
+        now: RangePrec,
+        duration: RangePrec,
+    ) -> Result<ViewAnimation, JsValue> {
+        let bookmark = self
+            .bookmarks
+            .get(&self.chart)
+            .and_then(|bookmarks| bookmarks.iter().find(|(n, _)| n == name))
+            .map(|(_, bookmark)| bookmark.clone())
+            .ok_or_else(|| JsValue::from_str(&format!("No bookmark named {:?}", name)))?;
+
+        job.clear_blacklist();
+        for &handle in bookmark.hidden() {
+            job.blacklist_trace(handle);
+        }
+
+        for id in self
+            .bookmark_annotations
+            .remove(&self.chart)
+            .unwrap_or_default()
+        {
+            self.renderer.remove_annotation(id);
+        }
+        let added = bookmark
+            .annotations()
+            .iter()
+            .map(|annotation| self.renderer.add_annotation(annotation.clone()))
+            .collect();
+        self.bookmark_annotations.insert(self.chart, added);
+        self.last_keys.remove(&self.chart);
+
+        Ok(ViewAnimation::of_job(job, bookmark.view(), now, duration))
+    }
+
+    /// Describes the last frame of the selected chart for screen readers, a panel per job
+    /// holding the axis ranges with their units and the drawn traces with their names, y
+    /// extents and values at `samples` positions evenly spaced across the plot
+    pub fn describe(&self, module: &DataModule, samples: usize) -> Result<JsValue, JsValue> {
+        let (Some(jobs), Some(results)) = (
+            self.last_frames.get(&self.chart),
+            self.last_results.get(&self.chart),
+        ) else {
+            return Err(JsValue::from_str(
+                "Nothing has been rendered to describe yet",
+            ));
+        };
+
+        let panels: Vec<_> = jobs
+            .iter()
+            .zip(results)
+            .map(|(job, result)| describe::describe_panel(module, job, result, samples))
+            .collect();
+
+        Ok(serde_wasm_bindgen::to_value(&panels)?)
+    }
+
+    /// Returns the handle, index and data coordinates of the point closest to `(x, y)`
+    /// as displayed by `job`, or `null` when there is none within `max_px_distance` pixels
+    pub fn find_closest(
+        &self,
+        job: &RenderJob,
+        x: RangePrec,
+        y: RangePrec,
+        max_px_distance: f32,
+    ) -> JsValue {
+        match self
+            .renderer
+            .find_closest(&job.padded(), x, y, max_px_distance)
+        {
+            Some(hit) => serde_wasm_bindgen::to_value(&hit).unwrap(),
+            None => JsValue::NULL,
+        }
+    }
+}
+
+/// `color` as the `[r, g, b]` of an entry, which the host may have passed in another form
+fn rgb(color: &[u8]) -> Result<[u8; 3], JsValue> {
+    color.try_into().map_err(|_| {
+        JsValue::from_str(&format!(
+            "Colors are 3 bytes of r, g and b, not {}",
+            color.len()
+        ))
+    })
+}
+
+/// Order to render `jobs` into the charts of the same index in, keeping the jobs of a chart
+/// together in their given order. Charts are ordered by the first bundle they draw, so ones
+/// sharing bundles follow each other, and then by their first job.
+pub fn batch_order(charts: &[u32], jobs: &[RenderJob]) -> Vec<usize> {
+    let mut first: HashMap<u32, (usize, usize)> = HashMap::new();
+    for (i, (&chart, job)) in charts.iter().zip(jobs).enumerate() {
+        let bundle = job
+            .get_bundles()
+            .iter()
+            .copied()
+            .min()
+            .unwrap_or(usize::MAX);
+        let key = first.entry(chart).or_insert((bundle, i));
+        key.0 = key.0.min(bundle);
+    }
+
+    let mut order: Vec<usize> = (0..charts.len().min(jobs.len())).collect();
+    order.sort_by_key(|&i| (first[&charts[i]], i));
+
+    order
+}
+
+impl RendererContainer {
+    /// Renders `jobs` into `charts` as `render_many` does, returning the results themselves
+    pub fn render_batch(
+        &mut self,
+        module: &DataModule,
+        charts: &[u32],
+        jobs: Vec<RenderJob>,
+    ) -> Result<Vec<RenderJobResult>, JsValue> {
+        if charts.len() != jobs.len() {
+            return Err(JsValue::from_str("Every job needs a chart to render into"));
+        }
+
+        // the clock is read once, the whole batch shows the same moment
+        let now = match &self.now_clock {
+            Some(clock) if jobs.iter().any(|j| j.now.is_none()) => {
+                clock.call0(&JsValue::NULL)?.as_f64()
+            }
+            _ => None,
+        };
+        let jobs: Vec<RenderJob> = jobs
+            .into_iter()
+            .map(|job| {
+                let mut job = job.padded();
+                job.now = job.now.or(now);
+                job
+            })
+            .collect();
+
+        let selected = self.chart;
+        let mut results = vec![None; jobs.len()];
+        let order = batch_order(charts, &jobs);
+
+        for batch in order.chunk_by(|&a, &b| charts[a] == charts[b]) {
+            if charts[batch[0]] != self.chart {
+                self.select_chart(charts[batch[0]])?;
+            }
+            self.last_keys.remove(&self.chart);
+
+            for &i in batch {
+                results[i] = Some(self.renderer.render(module, jobs[i].clone())?);
+            }
+            self.renderer.present()?;
+
+            self.last_frames
+                .insert(self.chart, batch.iter().map(|&i| jobs[i].clone()).collect());
+            self.last_results.insert(
+                self.chart,
+                batch.iter().filter_map(|&i| results[i].clone()).collect(),
+            );
+        }
+        if self.chart != selected {
+            self.select_chart(selected)?;
+        }
+
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    /// Renders the members of `group` as `render_linked` does, returning the results themselves
+    pub fn render_group(
+        &mut self,
+        module: &DataModule,
+        group: &LinkGroup,
+    ) -> Result<Vec<RenderJobResult>, JsValue> {
+        let selected = self.chart;
+        let mut results = Vec::with_capacity(group.members().len());
+
+        for (chart, job) in group.members() {
+            if *chart != self.chart {
+                self.select_chart(*chart)?;
+            }
+            results.push(self.render_selected(module, job.clone())?);
+        }
+        if self.chart != selected {
+            self.select_chart(selected)?;
+        }
+
+        Ok(results)
+    }
+
+    /// `job` padded, at the time of the now clock unless it has a `now` of its own
+    fn clocked(&self, job: RenderJob) -> Result<RenderJob, JsValue> {
+        let mut job = job.padded();
+        if job.now.is_none() {
+            if let Some(clock) = &self.now_clock {
+                job.now = clock.call0(&JsValue::NULL)?.as_f64();
+            }
+        }
+
+        Ok(job)
+    }
+
+    /// Renders `job` into the selected chart and presents it, remembering it for exports. An
+    /// unchanged frame isn't drawn again, see `render`.
+    fn render_selected(
+        &mut self,
+        module: &DataModule,
+        job: RenderJob,
+    ) -> Result<RenderJobResult, JsValue> {
+        let job = self.clocked(job)?;
+
+        let key = self
+            .renderer
+            .frame_key(&job)
+            .map(|key| (key, module.revision()));
+        if let (Some(key), Some([last])) =
+            (&key, self.last_results.get(&self.chart).map(|r| &r[..]))
+        {
+            if self.last_keys.get(&self.chart) == Some(key) {
+                // what the frame doesn't show, e.g. the units, is still described anew
+                let last = last.clone();
+                self.last_frames.insert(self.chart, vec![job]);
+                return Ok(last);
+            }
+        }
+
+        self.last_keys.remove(&self.chart);
+        self.last_frames.insert(self.chart, vec![job.clone()]);
+        let result = self.renderer.render(module, job)?;
+        self.renderer.present()?;
+        self.last_results.insert(self.chart, vec![result.clone()]);
+        if let Some(key) = key {
+            self.last_keys.insert(self.chart, key);
+        }
+
+        Ok(result)
+    }
 }
@@ -0,0 +1,723 @@
+//! Software rasterization of the same frames the GPU renders, for drawing charts where there
+//! is none, e.g. on servers or in CI
+
+use std::collections::HashMap;
+
+use wasm_bindgen::JsValue;
+
+use super::{
+    bundle_store::BundleStore,
+    draw_list::{Color, DrawList, Primitive},
+    geometry::{self, LineCap, LineJoin},
+    heatmap::colorize,
+    legend::{legend_items, LegendLayout},
+    text::{Label, FONT_PX},
+    Align, BundleEntry, BundleOptions, FeatureSet, HitResult, PlotRect, RenderJobResult, Renderer,
+    RendererOptions,
+};
+use crate::{
+    data::DataIdx,
+    data_module::{DataModule, Decimation, Fidelity},
+    prelude::*,
+    structs::{Annotation, Colormap, Marker, RenderJob},
+};
+
+/// Tolerance polylines are simplified with, finer than the coverage resolves
+const SIMPLIFY_PX: f32 = 0.25;
+/// Samples per pixel along each axis, giving edges 16 levels of coverage
+const SUBSAMPLES: usize = 4;
+/// Sides of the polygons circle markers are drawn as
+const CIRCLE_SEGMENTS: usize = 16;
+
+/// Columns and rows of the bitmap font, whose glyphs are drawn a column apart
+const GLYPH_COLS: usize = 5;
+const GLYPH_ROWS: usize = 7;
+/// Size of a pixel of the bitmap font, so its glyphs are about as tall as the GPU's
+const GLYPH_PX: f32 = FONT_PX / (GLYPH_ROWS + 1) as f32;
+
+/// Grid kept as the straight RGBA `pixels` of its cells, the first row starting at `y.0`
+struct RasterHeatmap {
+    pixels: Vec<u8>,
+    cols: usize,
+    rows: usize,
+    x: (RangePrec, RangePrec),
+    y: (RangePrec, RangePrec),
+}
+
+/// Coverage of the shapes of a primitive, blended into the frame at once so that its
+/// overlapping triangles don't darken each other
+struct Mask {
+    width: usize,
+    coverage: Vec<f32>,
+    /// Pixels shapes are clipped to as `[left, bottom, right, top)`
+    clip: [usize; 4],
+    /// Pixels covered since the last blend, in the same form
+    dirty: Option<[usize; 4]>,
+}
+
+impl Mask {
+    fn new(width: u32, height: u32) -> Self {
+        let (width, height) = (width as usize, height as usize);
+
+        Self {
+            width,
+            coverage: vec![0.0; width * height],
+            clip: [0, 0, width, height],
+            dirty: None,
+        }
+    }
+
+    fn set_clip(&mut self, rect: Option<&PlotRect>, height: u32) {
+        self.clip = match rect {
+            Some(rect) => [
+                (rect.left as usize).min(self.width),
+                (rect.bottom.min(height)) as usize,
+                (rect.right() as usize).min(self.width),
+                (rect.top().min(height)) as usize,
+            ],
+            None => [0, 0, self.width, height as usize],
+        };
+    }
+
+    /// Adds the coverage of a triangle, sampled on a grid within every pixel it touches
+    fn triangle(&mut self, a: (f32, f32), b: (f32, f32), c: (f32, f32)) {
+        let area = (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0);
+        // degenerate triangles, e.g. the ones joining parts of a strip, cover nothing
+        if area.is_nan() || area.abs() <= 1e-6 {
+            return;
+        }
+        let (b, c) = if area > 0.0 { (b, c) } else { (c, b) };
+
+        let [left, bottom, right, top] = self.clip;
+        let bound = |lo: f32, hi: f32, min: usize, max: usize| {
+            let lo = (lo.floor().max(min as f32) as usize).min(max);
+            let hi = (hi.ceil().max(0.0) as usize).clamp(lo, max);
+            (lo, hi)
+        };
+        let (x0, x1) = bound(a.0.min(b.0).min(c.0), a.0.max(b.0).max(c.0), left, right);
+        let (y0, y1) = bound(a.1.min(b.1).min(c.1), a.1.max(b.1).max(c.1), bottom, top);
+        if x0 == x1 || y0 == y1 {
+            return;
+        }
+
+        // counterclockwise, so every sample within lies left of all the edges
+        let edges = [(a, b), (b, c), (c, a)];
+        let inside = |x: f32, y: f32| {
+            edges
+                .iter()
+                .all(|&(p, q)| (q.0 - p.0) * (y - p.1) - (q.1 - p.1) * (x - p.0) >= 0.0)
+        };
+        let step = 1.0 / SUBSAMPLES as f32;
+        let weight = step * step;
+
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let mut hits = 0;
+                for sy in 0..SUBSAMPLES {
+                    for sx in 0..SUBSAMPLES {
+                        let x = px as f32 + (sx as f32 + 0.5) * step;
+                        let y = py as f32 + (sy as f32 + 0.5) * step;
+                        hits += inside(x, y) as u32;
+                    }
+                }
+
+                if hits > 0 {
+                    let coverage = &mut self.coverage[py * self.width + px];
+                    *coverage = (*coverage + hits as f32 * weight).min(1.0);
+                }
+            }
+        }
+
+        self.dirty = Some(match self.dirty {
+            Some([l, b, r, t]) => [l.min(x0), b.min(y0), r.max(x1), t.max(y1)],
+            None => [x0, y0, x1, y1],
+        });
+    }
+
+    fn strip(&mut self, strip: &[(f32, f32)]) {
+        for tri in strip.windows(3) {
+            self.triangle(tri[0], tri[1], tri[2]);
+        }
+    }
+
+    /// Adds a convex polygon as a fan of triangles
+    fn polygon(&mut self, corners: &[(f32, f32)]) {
+        for i in 2..corners.len() {
+            self.triangle(corners[0], corners[i - 1], corners[i]);
+        }
+    }
+
+    /// Composites `color` by the coverage over the premultiplied `pixels`, clearing the mask
+    fn blend(&mut self, pixels: &mut [u8], color: &Color) {
+        let Some([left, bottom, right, top]) = self.dirty.take() else {
+            return;
+        };
+
+        for y in bottom..top {
+            for x in left..right {
+                let i = y * self.width + x;
+                let k = std::mem::take(&mut self.coverage[i]);
+                if k == 0.0 {
+                    continue;
+                }
+
+                for (channel, &src) in pixels[i * 4..i * 4 + 4].iter_mut().zip(color) {
+                    let dst = *channel as f32 / 255.0;
+                    let out = src * k + dst * (1.0 - color[3] * k);
+                    *channel = (out.clamp(0.0, 1.0) * 255.0).round() as u8;
+                }
+            }
+        }
+    }
+}
+
+/// Renderer drawing the frames the GPU would into a buffer of pixels on the CPU, so charts
+/// can be rendered without a GPU or a browser. Every frame is drawn from the module, with
+/// antialiased edges and tick labels in a built-in bitmap font.
+pub struct RasterRenderer {
+    width: u32,
+    height: u32,
+    line_join: LineJoin,
+    line_cap: LineCap,
+
+    /// Premultiplied RGBA pixels of the frame, bottom row first
+    pixels: Vec<u8>,
+
+    bundles: BundleStore,
+    heatmaps: HashMap<usize, RasterHeatmap>,
+}
+
+impl RasterRenderer {
+    pub fn new(width: u32, height: u32, ropts: RendererOptions) -> Self {
+        Self {
+            width,
+            height,
+            line_join: ropts.line_join,
+            line_cap: ropts.line_cap,
+            pixels: vec![0; width as usize * height as usize * 4],
+            bundles: BundleStore::new(true),
+            heatmaps: HashMap::new(),
+        }
+    }
+
+    /// Premultiplied RGBA pixels of the frame, bottom row first as GL reads them
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    fn measure(text: &str) -> f32 {
+        text.chars().count() as f32 * (GLYPH_COLS + 1) as f32 * GLYPH_PX
+    }
+
+    fn execute(&mut self, list: &DrawList, simplify_px: f32) {
+        let mut mask = Mask::new(self.width, self.height);
+
+        for primitive in &list.primitives {
+            let color = match primitive {
+                Primitive::Polyline {
+                    points,
+                    color,
+                    width,
+                } => {
+                    let points = geometry::simplify(points, simplify_px);
+                    let stroke = geometry::stroke_strip(
+                        &points,
+                        width.max(1.0),
+                        self.line_join,
+                        self.line_cap,
+                    );
+                    mask.strip(&stroke.strip);
+                    color
+                }
+                Primitive::Segments {
+                    points,
+                    color,
+                    width,
+                } => {
+                    for pair in points.chunks_exact(2) {
+                        let stroke = geometry::stroke_strip(
+                            pair,
+                            width.max(1.0),
+                            self.line_join,
+                            LineCap::Butt,
+                        );
+                        mask.strip(&stroke.strip);
+                    }
+                    color
+                }
+                Primitive::Triangles { points, color } => {
+                    for tri in points.chunks_exact(3) {
+                        mask.triangle(tri[0], tri[1], tri[2]);
+                    }
+                    color
+                }
+                Primitive::Quads {
+                    rects,
+                    color,
+                    marker,
+                    rotation,
+                } => {
+                    for &[x, y, w, h] in rects {
+                        for outline in
+                            marker_outlines(*marker, (x + w / 2.0, y + h / 2.0), (w, h), *rotation)
+                        {
+                            mask.polygon(&outline);
+                        }
+                    }
+                    color
+                }
+                Primitive::Glyphs { labels, color } => {
+                    for label in labels {
+                        draw_label(&mut mask, label);
+                    }
+                    color
+                }
+                Primitive::Clip(rect) => {
+                    mask.set_clip(rect.as_ref(), self.height);
+                    continue;
+                }
+            };
+
+            mask.blend(&mut self.pixels, color);
+        }
+    }
+
+    fn draw_heatmap(&mut self, heatmap: usize, job: &RenderJob) {
+        let Some(layer) = self.heatmaps.get(&heatmap) else {
+            return;
+        };
+        if layer.rows == 0 {
+            return;
+        }
+
+        let rect = PlotRect::of(job, self.width, self.height);
+        let xs = job.x_axis_scale();
+        let ys = job.y_axis_scale();
+
+        let x_at =
+            |x| rect.left as f32 + rect.width as f32 * xs.position(x, job.x_from, job.x_to) as f32;
+        let y_at = |y| {
+            rect.bottom as f32 + rect.height as f32 * ys.position(y, job.y_from, job.y_to) as f32
+        };
+
+        let (x0, x1) = (x_at(layer.x.0), x_at(layer.x.1));
+        let (y0, y1) = (y_at(layer.y.0), y_at(layer.y.1));
+
+        // cells are drawn as blocks, as the GPU samples them
+        let cell = |at: f32, from: f32, to: f32, count: usize| {
+            let t = (at - from) / (to - from);
+            (0.0..1.0)
+                .contains(&t)
+                .then(|| ((t * count as f32) as usize).min(count - 1))
+        };
+
+        for py in rect.bottom..rect.top().min(self.height) {
+            let Some(row) = cell(py as f32 + 0.5, y0, y1, layer.rows) else {
+                continue;
+            };
+
+            for px in rect.left..rect.right().min(self.width) {
+                let Some(col) = cell(px as f32 + 0.5, x0, x1, layer.cols) else {
+                    continue;
+                };
+
+                let src = &layer.pixels[(row * layer.cols + col) * 4..][..4];
+                let a = src[3] as u32;
+                let i = (py * self.width + px) as usize * 4;
+
+                for (c, channel) in self.pixels[i..i + 4].iter_mut().enumerate() {
+                    let src = match c {
+                        3 => a,
+                        _ => (src[c] as u32 * a + 127) / 255,
+                    };
+                    *channel = (src + (*channel as u32 * (255 - a) + 127) / 255) as u8;
+                }
+            }
+        }
+    }
+}
+
+/// Convex outlines of a `marker` of `size` centered at `center`, turned counterclockwise by
+/// `rotation` degrees, shaped as the GPU draws it
+fn marker_outlines(
+    marker: Marker,
+    center: (f32, f32),
+    size: (f32, f32),
+    rotation: f32,
+) -> Vec<Vec<(f32, f32)>> {
+    let (rx, ry) = (size.0 / 2.0, size.1 / 2.0);
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let place = |corners: &[(f32, f32)]| -> Vec<(f32, f32)> {
+        corners
+            .iter()
+            .map(|&(x, y)| (center.0 + x * cos - y * sin, center.1 + x * sin + y * cos))
+            .collect()
+    };
+
+    match marker {
+        Marker::Circle => {
+            let rim: Vec<_> = (0..CIRCLE_SEGMENTS)
+                .map(|i| {
+                    let (sin, cos) =
+                        (std::f32::consts::TAU * i as f32 / CIRCLE_SEGMENTS as f32).sin_cos();
+                    (rx * cos, ry * sin)
+                })
+                .collect();
+            vec![place(&rim)]
+        }
+        Marker::Triangle => vec![place(&[(0.0, ry), (-rx, -ry), (rx, -ry)])],
+        Marker::Diamond => vec![place(&[(0.0, ry), (-rx, 0.0), (0.0, -ry), (rx, 0.0)])],
+        // arms a quarter of the marker wide
+        Marker::Cross => {
+            let (ax, ay) = (rx / 4.0, ry / 4.0);
+            vec![
+                place(&[(-rx, -ay), (rx, -ay), (rx, ay), (-rx, ay)]),
+                place(&[(-ax, -ry), (ax, -ry), (ax, ry), (-ax, ry)]),
+            ]
+        }
+        _ => vec![place(&[(-rx, -ry), (rx, -ry), (rx, ry), (-rx, ry)])],
+    }
+}
+
+/// Adds the glyphs of `label` to the mask, a square for every set pixel of the bitmap font
+fn draw_label(mask: &mut Mask, label: &Label) {
+    let advance = (GLYPH_COLS + 1) as f32 * GLYPH_PX;
+    let width = RasterRenderer::measure(&label.text);
+    let left = match label.align {
+        Align::Left => 0.0,
+        Align::Center => -width / 2.0,
+        Align::Right => -width,
+    };
+    let top = GLYPH_ROWS as f32 * GLYPH_PX / 2.0;
+
+    let (sin, cos) = label.rotation.to_radians().sin_cos();
+    let place = |x: f32, y: f32| (label.x + x * cos - y * sin, label.y + x * sin + y * cos);
+
+    for (i, ch) in label.text.chars().enumerate() {
+        for (col, bits) in glyph(ch).iter().enumerate() {
+            for row in (0..GLYPH_ROWS).filter(|row| bits >> row & 1 == 1) {
+                let x = left + i as f32 * advance + col as f32 * GLYPH_PX;
+                let y = top - (row + 1) as f32 * GLYPH_PX;
+
+                mask.polygon(&[
+                    place(x, y),
+                    place(x + GLYPH_PX, y),
+                    place(x + GLYPH_PX, y + GLYPH_PX),
+                    place(x, y + GLYPH_PX),
+                ]);
+            }
+        }
+    }
+}
+
+/// Columns of the glyph of `ch`, their lowest bit being the top row. The micro prefix is
+/// drawn as `u`, other characters outside of printable ASCII as `?`.
+fn glyph(ch: char) -> &'static [u8; GLYPH_COLS] {
+    let ch = if ch == 'µ' { 'u' } else { ch };
+    let index = (ch as usize).wrapping_sub(' ' as usize);
+
+    FONT.get(index)
+        .unwrap_or(&FONT['?' as usize - ' ' as usize])
+}
+
+/// 5x7 bitmap font of printable ASCII
+#[rustfmt::skip]
+const FONT: [[u8; GLYPH_COLS]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], [0x00, 0x00, 0x5F, 0x00, 0x00], // ' ' !
+    [0x00, 0x07, 0x00, 0x07, 0x00], [0x14, 0x7F, 0x14, 0x7F, 0x14], // " #
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], [0x23, 0x13, 0x08, 0x64, 0x62], // $ %
+    [0x36, 0x49, 0x55, 0x22, 0x50], [0x00, 0x05, 0x03, 0x00, 0x00], // & '
+    [0x00, 0x1C, 0x22, 0x41, 0x00], [0x00, 0x41, 0x22, 0x1C, 0x00], // ( )
+    [0x14, 0x08, 0x3E, 0x08, 0x14], [0x08, 0x08, 0x3E, 0x08, 0x08], // * +
+    [0x00, 0x50, 0x30, 0x00, 0x00], [0x08, 0x08, 0x08, 0x08, 0x08], // , -
+    [0x00, 0x60, 0x60, 0x00, 0x00], [0x20, 0x10, 0x08, 0x04, 0x02], // . /
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], [0x00, 0x42, 0x7F, 0x40, 0x00], // 0 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], [0x21, 0x41, 0x45, 0x4B, 0x31], // 2 3
+    [0x18, 0x14, 0x12, 0x7F, 0x10], [0x27, 0x45, 0x45, 0x45, 0x39], // 4 5
+    [0x3C, 0x4A, 0x49, 0x49, 0x30], [0x01, 0x71, 0x09, 0x05, 0x03], // 6 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], [0x06, 0x49, 0x49, 0x29, 0x1E], // 8 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], [0x00, 0x56, 0x36, 0x00, 0x00], // : ;
+    [0x08, 0x14, 0x22, 0x41, 0x00], [0x14, 0x14, 0x14, 0x14, 0x14], // < =
+    [0x00, 0x41, 0x22, 0x14, 0x08], [0x02, 0x01, 0x51, 0x09, 0x06], // > ?
+    [0x32, 0x49, 0x79, 0x41, 0x3E], [0x7E, 0x11, 0x11, 0x11, 0x7E], // @ A
+    [0x7F, 0x49, 0x49, 0x49, 0x36], [0x3E, 0x41, 0x41, 0x41, 0x22], // B C
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], [0x7F, 0x49, 0x49, 0x49, 0x41], // D E
+    [0x7F, 0x09, 0x09, 0x09, 0x01], [0x3E, 0x41, 0x49, 0x49, 0x7A], // F G
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], [0x00, 0x41, 0x7F, 0x41, 0x00], // H I
+    [0x20, 0x40, 0x41, 0x3F, 0x01], [0x7F, 0x08, 0x14, 0x22, 0x41], // J K
+    [0x7F, 0x40, 0x40, 0x40, 0x40], [0x7F, 0x02, 0x0C, 0x02, 0x7F], // L M
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], [0x3E, 0x41, 0x41, 0x41, 0x3E], // N O
+    [0x7F, 0x09, 0x09, 0x09, 0x06], [0x3E, 0x41, 0x51, 0x21, 0x5E], // P Q
+    [0x7F, 0x09, 0x19, 0x29, 0x46], [0x46, 0x49, 0x49, 0x49, 0x31], // R S
+    [0x01, 0x01, 0x7F, 0x01, 0x01], [0x3F, 0x40, 0x40, 0x40, 0x3F], // T U
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], [0x3F, 0x40, 0x38, 0x40, 0x3F], // V W
+    [0x63, 0x14, 0x08, 0x14, 0x63], [0x07, 0x08, 0x70, 0x08, 0x07], // X Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], [0x00, 0x7F, 0x41, 0x41, 0x00], // Z [
+    [0x02, 0x04, 0x08, 0x10, 0x20], [0x00, 0x41, 0x41, 0x7F, 0x00], // \ ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], [0x40, 0x40, 0x40, 0x40, 0x40], // ^ _
+    [0x00, 0x01, 0x02, 0x04, 0x00], [0x20, 0x54, 0x54, 0x54, 0x78], // ` a
+    [0x7F, 0x48, 0x44, 0x44, 0x38], [0x38, 0x44, 0x44, 0x44, 0x20], // b c
+    [0x38, 0x44, 0x44, 0x48, 0x7F], [0x38, 0x54, 0x54, 0x54, 0x18], // d e
+    [0x08, 0x7E, 0x09, 0x01, 0x02], [0x0C, 0x52, 0x52, 0x52, 0x3E], // f g
+    [0x7F, 0x08, 0x04, 0x04, 0x78], [0x00, 0x44, 0x7D, 0x40, 0x00], // h i
+    [0x20, 0x40, 0x44, 0x3D, 0x00], [0x7F, 0x10, 0x28, 0x44, 0x00], // j k
+    [0x00, 0x41, 0x7F, 0x40, 0x00], [0x7C, 0x04, 0x18, 0x04, 0x78], // l m
+    [0x7C, 0x08, 0x04, 0x04, 0x78], [0x38, 0x44, 0x44, 0x44, 0x38], // n o
+    [0x7C, 0x14, 0x14, 0x14, 0x08], [0x08, 0x14, 0x14, 0x18, 0x7C], // p q
+    [0x7C, 0x08, 0x04, 0x04, 0x08], [0x48, 0x54, 0x54, 0x54, 0x20], // r s
+    [0x04, 0x3F, 0x44, 0x40, 0x20], [0x3C, 0x40, 0x40, 0x20, 0x7C], // t u
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], [0x3C, 0x40, 0x30, 0x40, 0x3C], // v w
+    [0x44, 0x28, 0x10, 0x28, 0x44], [0x0C, 0x50, 0x50, 0x50, 0x3C], // x y
+    [0x44, 0x64, 0x54, 0x4C, 0x44], [0x00, 0x08, 0x36, 0x41, 0x00], // z {
+    [0x00, 0x00, 0x7F, 0x00, 0x00], [0x00, 0x41, 0x36, 0x08, 0x00], // | }
+    [0x08, 0x04, 0x08, 0x10, 0x08],                                 // ~
+];
+
+impl Renderer for RasterRenderer {
+    fn render(&mut self, module: &DataModule, job: RenderJob) -> Result<RenderJobResult, JsValue> {
+        if job.clear {
+            match job.clear_color() {
+                Some([r, g, b]) => self
+                    .pixels
+                    .chunks_exact_mut(4)
+                    .for_each(|px| px.copy_from_slice(&[r, g, b, 255])),
+                None => self.pixels.fill(0),
+            }
+        }
+
+        for &heatmap in job.get_heatmaps() {
+            self.draw_heatmap(heatmap, &job);
+        }
+
+        let entries = self.bundles.entries(&job);
+        let frame = self
+            .bundles
+            .frame(module, &job, self.width, self.height, Self::measure);
+        let mut result = frame.1.clone();
+        let simplify_px = if job.exact { 0.0 } else { SIMPLIFY_PX };
+        self.execute(&frame.0, simplify_px);
+
+        if job.render_legend {
+            let traces = entries.iter().map(|e| (e.handle, e.color, e.width));
+            let items = legend_items(module, &job, traces);
+
+            if !items.is_empty() {
+                let rect = PlotRect::of(&job, self.width, self.height);
+                let layout = LegendLayout::of(&items, &rect, Self::measure);
+                let mut legend = DrawList::new(self.width, self.height);
+                legend.push_legend(&job, &items, &layout);
+                self.execute(&legend, 0.0);
+
+                result.set_legend(layout.hits(&items, self.height));
+            }
+        }
+
+        let decimation = match simplify_px > 0.0 {
+            true => Decimation {
+                fidelity: Fidelity::Lossy,
+                max_error: None,
+            },
+            false => Decimation::EXACT,
+        };
+        for entry in &entries {
+            result.add_decimation(entry.handle, decimation);
+        }
+
+        Ok(result)
+    }
+
+    fn present(&mut self) -> Result<(), JsValue> {
+        // frames are drawn into the pixels hosts read
+        Ok(())
+    }
+
+    fn add_annotation(&mut self, annotation: Annotation) -> u32 {
+        self.bundles.add_annotation(annotation)
+    }
+
+    fn remove_annotation(&mut self, id: u32) -> bool {
+        self.bundles.remove_annotation(id)
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn size_changed(&mut self, width: u32, height: u32) -> Result<(), JsValue> {
+        self.width = width;
+        self.height = height;
+        self.pixels = vec![0; width as usize * height as usize * 4];
+
+        Ok(())
+    }
+
+    fn create_bundle(
+        &mut self,
+        module: &DataModule,
+        from: RangePrec,
+        to: RangePrec,
+        data: &[BundleEntry],
+        _opts: BundleOptions,
+    ) -> Result<usize, JsValue> {
+        Ok(self.bundles.create(module, from, to, data))
+    }
+
+    fn rebundle(
+        &mut self,
+        module: &DataModule,
+        bundle: usize,
+        to_add: &[BundleEntry],
+        to_del: &[DataIdx],
+        to_mod: &[BundleEntry],
+    ) -> Result<(), JsValue> {
+        self.bundles
+            .rebundle(module, bundle, to_add, to_del, to_mod);
+
+        Ok(())
+    }
+
+    fn begin_bundle(
+        &mut self,
+        module: &DataModule,
+        from: RangePrec,
+        to: RangePrec,
+        data: &[BundleEntry],
+        _opts: BundleOptions,
+        replaces: Option<usize>,
+    ) -> Result<usize, JsValue> {
+        Ok(self.bundles.begin(module, from, to, data, replaces))
+    }
+
+    fn step_bundle(
+        &mut self,
+        _module: &DataModule,
+        _bundle: usize,
+        _chunks: usize,
+    ) -> Result<f64, JsValue> {
+        Ok(1.0)
+    }
+
+    fn dispose_bundle(&mut self, bundle: usize) -> Result<(), JsValue> {
+        self.bundles.dispose(bundle);
+
+        Ok(())
+    }
+
+    fn set_bundle_visible(&mut self, bundle: usize, visible: bool) {
+        self.bundles.set_visible(bundle, visible);
+    }
+
+    fn extend_bundle(
+        &mut self,
+        bundle: usize,
+        handle: DataIdx,
+        points: &[(RangePrec, RangePrec)],
+    ) -> Result<(), JsValue> {
+        self.bundles.extend(bundle, handle, points);
+
+        Ok(())
+    }
+
+    fn create_heatmap(
+        &mut self,
+        values: &[f32],
+        cols: usize,
+        x: (RangePrec, RangePrec),
+        y: (RangePrec, RangePrec),
+        colormap: &Colormap,
+    ) -> Result<usize, JsValue> {
+        let cols = cols.max(1);
+        let (pixels, rows) = colorize(values, cols, colormap);
+
+        let handle = self.bundles.next_handle();
+        self.heatmaps.insert(
+            handle,
+            RasterHeatmap {
+                pixels,
+                cols,
+                rows,
+                x,
+                y,
+            },
+        );
+
+        Ok(handle)
+    }
+
+    fn dispose_heatmap(&mut self, heatmap: usize) -> Result<(), JsValue> {
+        self.heatmaps.remove(&heatmap);
+
+        Ok(())
+    }
+
+    fn restack_bundle(
+        &mut self,
+        _module: &DataModule,
+        bundle: usize,
+        stacking: &[(DataIdx, u32, i32)],
+    ) -> Result<(), JsValue> {
+        self.bundles.restack(bundle, stacking);
+
+        Ok(())
+    }
+
+    fn suspend(&mut self, _release_bundles: bool) -> Result<(), JsValue> {
+        Ok(())
+    }
+
+    fn resume(&mut self, _module: &DataModule) -> Result<(), JsValue> {
+        Ok(())
+    }
+
+    fn render_offscreen(
+        &mut self,
+        module: &DataModule,
+        jobs: &[RenderJob],
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<u8>, JsValue> {
+        // the jobs are drawn into a frame of their own, keeping the presented one
+        let frame = vec![0; width as usize * height as usize * 4];
+        let pixels = std::mem::replace(&mut self.pixels, frame);
+        let size = (self.width, self.height);
+        (self.width, self.height) = (width, height);
+
+        let rendered = jobs
+            .iter()
+            .try_for_each(|job| self.render(module, job.clone()).map(|_| ()));
+
+        let frame = std::mem::replace(&mut self.pixels, pixels);
+        (self.width, self.height) = size;
+
+        rendered.map(|_| frame)
+    }
+
+    fn find_closest(
+        &self,
+        job: &RenderJob,
+        x: RangePrec,
+        y: RangePrec,
+        max_px: f32,
+    ) -> Option<HitResult> {
+        let rect = PlotRect::of(job, self.width, self.height);
+        self.bundles.find_closest(job, &rect, (x, y), max_px)
+    }
+
+    fn backend(&self) -> &'static str {
+        "raster"
+    }
+
+    fn features(&self) -> FeatureSet {
+        // shapes are triangulated, so markers take any shape and size
+        FeatureSet {
+            dash: true,
+            instancing: true,
+            text: true,
+            ..FeatureSet::default()
+        }
+    }
+
+    fn frame_pixels(&self) -> Option<Vec<u8>> {
+        Some(self.pixels.clone())
+    }
+}
@@ -0,0 +1,128 @@
+//! Tessellated lines, their edges smoothed in the shader so they don't depend on multisampling
+
+use wasm_bindgen::JsValue;
+use web_sys::{WebGl2RenderingContext, WebGlBuffer, WebGlProgram, WebGlUniformLocation};
+
+use super::{
+    geometry::{self, LineCap, LineJoin},
+    webgl::webgl_utils,
+    PlotRect,
+};
+
+pub struct StrokeProgram {
+    program: WebGlProgram,
+    buffer: WebGlBuffer,
+    resolution_pos: WebGlUniformLocation,
+    color_pos: WebGlUniformLocation,
+    half_width_pos: WebGlUniformLocation,
+}
+
+impl StrokeProgram {
+    pub fn new(gl: &WebGl2RenderingContext) -> Result<Self, JsValue> {
+        let vert_shader = webgl_utils::compile_shader(
+            gl,
+            WebGl2RenderingContext::VERTEX_SHADER,
+            r#"
+            attribute vec3 aVertex;
+
+            uniform vec2 resolution;
+            varying float edge;
+
+            void main() {
+                edge = aVertex.z;
+                gl_Position = vec4(vec2(-1, -1) + vec2(2, 2) * aVertex.xy / resolution, 0, 1);
+            }
+            "#,
+        )?;
+
+        let frag_shader = webgl_utils::compile_shader(
+            gl,
+            WebGl2RenderingContext::FRAGMENT_SHADER,
+            r#"
+            precision mediump float;
+
+            uniform vec4 color;
+            uniform float half_width;
+            varying float edge;
+
+            void main() {
+                // pixels to the nearest edge, the outermost one fading out
+                float distance = (1.0 - abs(edge)) * half_width;
+
+                gl_FragColor = color * clamp(distance, 0.0, 1.0);
+            }
+            "#,
+        )?;
+
+        let program = webgl_utils::link_program(gl, &vert_shader, &frag_shader)?;
+
+        Ok(Self {
+            buffer: gl
+                .create_buffer()
+                .ok_or_else(|| JsValue::from_str("Failed to allocate the stroke buffer"))?,
+            resolution_pos: gl.get_uniform_location(&program, "resolution").unwrap(),
+            color_pos: gl.get_uniform_location(&program, "color").unwrap(),
+            half_width_pos: gl.get_uniform_location(&program, "half_width").unwrap(),
+            program,
+        })
+    }
+
+    /// Strokes the canvas pixel polyline `points` within `rect`, which has to be the viewport.
+    /// The stroke is a pixel wider than `width` to leave room for the smoothed edges.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &self,
+        gl: &WebGl2RenderingContext,
+        points: &[(f32, f32)],
+        width: f32,
+        join: LineJoin,
+        cap: LineCap,
+        color: [f32; 4],
+        rect: &PlotRect,
+    ) {
+        let stroke = geometry::stroke_strip(points, width + 1.0, join, cap);
+        if stroke.strip.is_empty() {
+            return;
+        }
+
+        let vertices: Vec<[f32; 3]> = stroke
+            .strip
+            .iter()
+            .zip(&stroke.edges)
+            .map(|(&(x, y), &edge)| [x - rect.left as f32, y - rect.bottom as f32, edge])
+            .collect();
+
+        gl.use_program(Some(&self.program));
+        gl.uniform2f(
+            Some(&self.resolution_pos),
+            rect.width as f32,
+            rect.height as f32,
+        );
+        gl.uniform4f(
+            Some(&self.color_pos),
+            color[0],
+            color[1],
+            color[2],
+            color[3],
+        );
+        gl.uniform1f(Some(&self.half_width_pos), (width + 1.0) / 2.0);
+
+        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&self.buffer));
+        webgl_utils::upload(
+            gl,
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            &vertices,
+            WebGl2RenderingContext::STREAM_DRAW,
+        );
+        gl.vertex_attrib_pointer_with_i32(0, 3, WebGl2RenderingContext::FLOAT, false, 0, 0);
+        gl.enable_vertex_attrib_array(0);
+        gl.draw_arrays(
+            WebGl2RenderingContext::TRIANGLE_STRIP,
+            0,
+            vertices.len() as i32,
+        );
+
+        // the other programs read attribute 0 as a vec2
+        gl.vertex_attrib_pointer_with_i32(0, 2, WebGl2RenderingContext::FLOAT, false, 0, 0);
+    }
+}
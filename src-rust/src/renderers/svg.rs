@@ -0,0 +1,620 @@
+//! Vector output of the same frames the GPU renders, e.g. for papers
+
+use std::{collections::HashMap, fmt::Write};
+
+use wasm_bindgen::JsValue;
+
+use super::{
+    bundle_store::BundleStore,
+    draw_list::{Color, DrawList, Primitive},
+    export::encode_png,
+    geometry,
+    heatmap::colorize,
+    legend::{legend_items, LegendLayout},
+    text::FONT_PX,
+    Align, BundleEntry, BundleOptions, FeatureSet, HitResult, PlotRect, RenderJobResult, Renderer,
+};
+use crate::{
+    data::DataIdx,
+    data_module::{DataModule, Decimation, Fidelity},
+    prelude::*,
+    structs::{Annotation, Colormap, Marker, RenderJob},
+};
+
+/// Typical width of a sans-serif character relative to the font size
+const AVERAGE_CHAR_WIDTH: f32 = 0.55;
+
+/// Grid kept to be embedded as an image, the row major RGBA `pixels` starting at `y.0`
+struct SvgHeatmap {
+    pixels: Vec<u8>,
+    cols: u32,
+    rows: u32,
+    x: (RangePrec, RangePrec),
+    y: (RangePrec, RangePrec),
+}
+
+/// Renderer accumulating frames into an SVG document instead of drawing them. Bundles only
+/// keep their entries, the data is read from the module when rendering.
+pub struct SvgRenderer {
+    width: u32,
+    height: u32,
+    /// Tolerance in pixels polylines are simplified with, `0` keeping every point
+    simplify_px: f32,
+
+    body: String,
+    clip_count: usize,
+
+    bundles: BundleStore,
+    heatmaps_counter: usize,
+    heatmaps: HashMap<usize, SvgHeatmap>,
+}
+
+impl SvgRenderer {
+    pub fn new(width: u32, height: u32, simplify_px: f32) -> Self {
+        Self {
+            width,
+            height,
+            simplify_px,
+            body: String::new(),
+            clip_count: 0,
+            bundles: BundleStore::new(false),
+            heatmaps_counter: 0,
+            heatmaps: HashMap::new(),
+        }
+    }
+
+    /// Document of everything rendered since the last clearing job
+    pub fn svg(&self) -> String {
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">{}</svg>"#,
+            self.body,
+            w = self.width,
+            h = self.height
+        )
+    }
+
+    /// SVG y of the canvas y, which grows upwards
+    fn flip(&self, y: f32) -> f32 {
+        self.height as f32 - y
+    }
+
+    fn path(&self, points: &[(f32, f32)]) -> String {
+        let mut d = String::with_capacity(points.len() * 12);
+
+        for (i, &(x, y)) in points.iter().enumerate() {
+            let cmd = if i == 0 { 'M' } else { 'L' };
+            let _ = write!(d, "{}{:.2} {:.2}", cmd, x, self.flip(y));
+        }
+
+        d
+    }
+
+    fn push_clip(&mut self, rect: &PlotRect) {
+        self.clip_count += 1;
+
+        let _ = write!(
+            self.body,
+            r#"<clipPath id="clip{id}"><rect x="{}" y="{}" width="{}" height="{}"/></clipPath><g clip-path="url(#clip{id})">"#,
+            rect.left,
+            self.flip(rect.top() as f32),
+            rect.width,
+            rect.height,
+            id = self.clip_count,
+        );
+    }
+
+    fn execute(&mut self, list: &DrawList, simplify_px: f32) {
+        let mut clipped = false;
+
+        for primitive in &list.primitives {
+            match primitive {
+                Primitive::Polyline {
+                    points,
+                    color,
+                    width,
+                } => {
+                    let points = geometry::simplify(points, simplify_px);
+                    let d = self.path(&points);
+
+                    let _ = write!(
+                        self.body,
+                        r#"<path d="{}" fill="none" stroke-width="{}" stroke-linejoin="round" {}/>"#,
+                        d,
+                        width,
+                        paint("stroke", color)
+                    );
+                }
+                Primitive::Segments {
+                    points,
+                    color,
+                    width,
+                } => {
+                    let d: String = points.chunks_exact(2).map(|pair| self.path(pair)).collect();
+
+                    let _ = write!(
+                        self.body,
+                        r#"<path d="{}" stroke-width="{}" {}/>"#,
+                        d,
+                        width,
+                        paint("stroke", color)
+                    );
+                }
+                Primitive::Triangles { points, color } => {
+                    let d: String = points
+                        .chunks_exact(3)
+                        .map(|tri| self.path(tri) + "Z")
+                        .collect();
+
+                    let _ = write!(self.body, r#"<path d="{}" {}/>"#, d, paint("fill", color));
+                }
+                Primitive::Quads {
+                    rects,
+                    color,
+                    marker,
+                    rotation,
+                } => {
+                    let _ = write!(self.body, "<g {}>", paint("fill", color));
+
+                    for &[x, y, w, h] in rects {
+                        let (cx, cy) = (x + w / 2.0, self.flip(y + h / 2.0));
+                        let (rx, ry) = (w / 2.0, h / 2.0);
+
+                        // svg y grows downwards, so counterclockwise turns are negative
+                        if *rotation != 0.0 {
+                            let _ = write!(
+                                self.body,
+                                r#"<g transform="rotate({} {:.2} {:.2})">"#,
+                                -rotation, cx, cy
+                            );
+                        }
+
+                        let _ = match marker {
+                            Marker::Circle => write!(
+                                self.body,
+                                r#"<ellipse cx="{:.2}" cy="{:.2}" rx="{}" ry="{}"/>"#,
+                                cx, cy, rx, ry
+                            ),
+                            Marker::Triangle => write!(
+                                self.body,
+                                r#"<path d="M{:.2} {:.2}L{:.2} {:.2}L{:.2} {:.2}Z"/>"#,
+                                cx,
+                                cy - ry,
+                                cx + rx,
+                                cy + ry,
+                                cx - rx,
+                                cy + ry
+                            ),
+                            Marker::Diamond => write!(
+                                self.body,
+                                r#"<path d="M{:.2} {:.2}L{:.2} {:.2}L{:.2} {:.2}L{:.2} {:.2}Z"/>"#,
+                                cx,
+                                cy - ry,
+                                cx + rx,
+                                cy,
+                                cx,
+                                cy + ry,
+                                cx - rx,
+                                cy
+                            ),
+                            // arms a quarter of the marker wide, as drawn by the GPU
+                            Marker::Cross => write!(
+                                self.body,
+                                r#"<path d="M{:.2} {:.2}h{}v{}h{}v{}Z M{:.2} {:.2}h{}v{}h{}v{}Z"/>"#,
+                                x,
+                                cy - ry / 4.0,
+                                w,
+                                h / 4.0,
+                                -w,
+                                -h / 4.0,
+                                cx - rx / 4.0,
+                                self.flip(y + h),
+                                w / 4.0,
+                                h,
+                                -w / 4.0,
+                                -h
+                            ),
+                            _ => write!(
+                                self.body,
+                                r#"<rect x="{:.2}" y="{:.2}" width="{}" height="{}"/>"#,
+                                x,
+                                self.flip(y + h),
+                                w,
+                                h
+                            ),
+                        };
+
+                        if *rotation != 0.0 {
+                            self.body.push_str("</g>");
+                        }
+                    }
+
+                    self.body.push_str("</g>");
+                }
+                Primitive::Glyphs { labels, color } => {
+                    let _ = write!(
+                        self.body,
+                        r#"<g font-family="sans-serif" font-size="{}" dominant-baseline="middle" {}>"#,
+                        FONT_PX,
+                        paint("fill", color)
+                    );
+
+                    for label in labels {
+                        let anchor = match label.align {
+                            Align::Left => "start",
+                            Align::Center => "middle",
+                            Align::Right => "end",
+                        };
+
+                        // svg turns clockwise, its y pointing down
+                        let (x, y) = (label.x, self.flip(label.y));
+                        let transform = match label.rotation != 0.0 {
+                            true => format!(
+                                r#" transform="rotate({:.2} {:.2} {:.2})""#,
+                                -label.rotation, x, y
+                            ),
+                            false => String::new(),
+                        };
+
+                        let _ = write!(
+                            self.body,
+                            r#"<text x="{:.2}" y="{:.2}" text-anchor="{}"{}>{}</text>"#,
+                            x,
+                            y,
+                            anchor,
+                            transform,
+                            escape(&label.text)
+                        );
+                    }
+
+                    self.body.push_str("</g>");
+                }
+                Primitive::Clip(rect) => {
+                    if clipped {
+                        self.body.push_str("</g>");
+                    }
+
+                    clipped = rect.is_some();
+                    if let Some(rect) = rect {
+                        self.push_clip(rect);
+                    }
+                }
+            }
+        }
+
+        if clipped {
+            self.body.push_str("</g>");
+        }
+    }
+
+    fn draw_heatmap(&mut self, heatmap: usize, job: &RenderJob) -> Result<(), JsValue> {
+        let Some(layer) = self.heatmaps.get(&heatmap) else {
+            return Ok(());
+        };
+
+        let rect = PlotRect::of(job, self.width, self.height);
+        let xs = job.x_axis_scale();
+        let ys = job.y_axis_scale();
+
+        let x_at =
+            |x| rect.left as f32 + rect.width as f32 * xs.position(x, job.x_from, job.x_to) as f32;
+        let y_at = |y| {
+            rect.bottom as f32 + rect.height as f32 * ys.position(y, job.y_from, job.y_to) as f32
+        };
+
+        let (x0, x1) = (x_at(layer.x.0), x_at(layer.x.1));
+        let (y0, y1) = (y_at(layer.y.0), y_at(layer.y.1));
+
+        // the first row lies at the bottom, as the rows of a GL frame do
+        let png = encode_png(layer.cols, layer.rows, &layer.pixels)?;
+        let image = format!(
+            r#"<image x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" preserveAspectRatio="none" style="image-rendering:pixelated" href="data:image/png;base64,{}"/>"#,
+            x0.min(x1),
+            self.flip(y0.max(y1)),
+            (x1 - x0).abs(),
+            (y1 - y0).abs(),
+            base64(&png)
+        );
+
+        self.push_clip(&rect);
+        self.body.push_str(&image);
+        self.body.push_str("</g>");
+
+        Ok(())
+    }
+}
+
+/// Paint attributes of the premultiplied `color`
+fn paint(attr: &str, color: &Color) -> String {
+    let a = color[3];
+    let channel = |c: f32| {
+        let c = if a > 0.0 { c / a } else { c };
+        (c.clamp(0.0, 1.0) * 255.0).round() as u8
+    };
+
+    let mut out = format!(
+        r##"{}="#{:02x}{:02x}{:02x}""##,
+        attr,
+        channel(color[0]),
+        channel(color[1]),
+        channel(color[2])
+    );
+    if a < 1.0 {
+        let _ = write!(out, r#" {}-opacity="{}""#, attr, a);
+    }
+
+    out
+}
+
+/// Width of `text` in pixels, estimated from its length as the viewer's font is unknown
+fn estimate_width(text: &str) -> f32 {
+    text.chars().count() as f32 * FONT_PX * AVERAGE_CHAR_WIDTH
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char),
+                false => out.push('='),
+            }
+        }
+    }
+
+    out
+}
+
+impl Renderer for SvgRenderer {
+    fn render(&mut self, module: &DataModule, job: RenderJob) -> Result<RenderJobResult, JsValue> {
+        if job.clear {
+            self.body.clear();
+            self.clip_count = 0;
+            if let Some(rgb) = job.clear_color() {
+                let [r, g, b] = rgb.map(|c| c as f32 / 255.0);
+                let fill = paint("fill", &[r, g, b, 1.0]);
+                let _ = write!(self.body, r#"<rect width="100%" height="100%" {}/>"#, fill);
+            }
+        }
+
+        for &heatmap in job.get_heatmaps() {
+            self.draw_heatmap(heatmap, &job)?;
+        }
+
+        let entries = self.bundles.entries(&job);
+        let frame = self
+            .bundles
+            .frame(module, &job, self.width, self.height, estimate_width);
+        let mut result = frame.1.clone();
+        let simplify_px = if job.exact { 0.0 } else { self.simplify_px };
+        self.execute(&frame.0, simplify_px);
+
+        if job.render_legend {
+            let traces = entries.iter().map(|e| (e.handle, e.color, e.width));
+            let items = legend_items(module, &job, traces);
+
+            if !items.is_empty() {
+                let rect = PlotRect::of(&job, self.width, self.height);
+                let layout = LegendLayout::of(&items, &rect, estimate_width);
+                let mut legend = DrawList::new(self.width, self.height);
+                legend.push_legend(&job, &items, &layout);
+                self.execute(&legend, 0.0);
+
+                result.set_legend(layout.hits(&items, self.height));
+            }
+        }
+
+        // simplification bounds the error in pixels rather than data units
+        let decimation = match simplify_px > 0.0 {
+            true => Decimation {
+                fidelity: Fidelity::Lossy,
+                max_error: None,
+            },
+            false => Decimation::EXACT,
+        };
+        for entry in &entries {
+            result.add_decimation(entry.handle, decimation);
+        }
+
+        Ok(result)
+    }
+
+    fn present(&mut self) -> Result<(), JsValue> {
+        Ok(())
+    }
+
+    fn add_annotation(&mut self, annotation: Annotation) -> u32 {
+        self.bundles.add_annotation(annotation)
+    }
+
+    fn remove_annotation(&mut self, id: u32) -> bool {
+        self.bundles.remove_annotation(id)
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn size_changed(&mut self, width: u32, height: u32) -> Result<(), JsValue> {
+        self.width = width;
+        self.height = height;
+
+        Ok(())
+    }
+
+    fn create_bundle(
+        &mut self,
+        module: &DataModule,
+        from: RangePrec,
+        to: RangePrec,
+        data: &[BundleEntry],
+        _opts: BundleOptions,
+    ) -> Result<usize, JsValue> {
+        Ok(self.bundles.create(module, from, to, data))
+    }
+
+    fn rebundle(
+        &mut self,
+        module: &DataModule,
+        bundle: usize,
+        to_add: &[BundleEntry],
+        to_del: &[DataIdx],
+        to_mod: &[BundleEntry],
+    ) -> Result<(), JsValue> {
+        self.bundles
+            .rebundle(module, bundle, to_add, to_del, to_mod);
+
+        Ok(())
+    }
+
+    fn begin_bundle(
+        &mut self,
+        module: &DataModule,
+        from: RangePrec,
+        to: RangePrec,
+        data: &[BundleEntry],
+        _opts: BundleOptions,
+        replaces: Option<usize>,
+    ) -> Result<usize, JsValue> {
+        Ok(self.bundles.begin(module, from, to, data, replaces))
+    }
+
+    fn step_bundle(
+        &mut self,
+        _module: &DataModule,
+        _bundle: usize,
+        _chunks: usize,
+    ) -> Result<f64, JsValue> {
+        Ok(1.0)
+    }
+
+    fn dispose_bundle(&mut self, bundle: usize) -> Result<(), JsValue> {
+        self.bundles.dispose(bundle);
+
+        Ok(())
+    }
+
+    fn set_bundle_visible(&mut self, bundle: usize, visible: bool) {
+        self.bundles.set_visible(bundle, visible);
+    }
+
+    fn extend_bundle(
+        &mut self,
+        bundle: usize,
+        handle: DataIdx,
+        points: &[(RangePrec, RangePrec)],
+    ) -> Result<(), JsValue> {
+        // the points are read from the module on every render
+        self.bundles.extend(bundle, handle, points);
+
+        Ok(())
+    }
+
+    fn create_heatmap(
+        &mut self,
+        values: &[f32],
+        cols: usize,
+        x: (RangePrec, RangePrec),
+        y: (RangePrec, RangePrec),
+        colormap: &Colormap,
+    ) -> Result<usize, JsValue> {
+        let cols = cols.max(1);
+        let (pixels, rows) = colorize(values, cols, colormap);
+
+        let handle = self.heatmaps_counter;
+        self.heatmaps_counter += 1;
+        self.heatmaps.insert(
+            handle,
+            SvgHeatmap {
+                pixels,
+                cols: cols as u32,
+                rows: rows as u32,
+                x,
+                y,
+            },
+        );
+
+        Ok(handle)
+    }
+
+    fn dispose_heatmap(&mut self, heatmap: usize) -> Result<(), JsValue> {
+        self.heatmaps.remove(&heatmap);
+
+        Ok(())
+    }
+
+    fn restack_bundle(
+        &mut self,
+        _module: &DataModule,
+        bundle: usize,
+        stacking: &[(DataIdx, u32, i32)],
+    ) -> Result<(), JsValue> {
+        self.bundles.restack(bundle, stacking);
+
+        Ok(())
+    }
+
+    fn suspend(&mut self, _release_bundles: bool) -> Result<(), JsValue> {
+        Ok(())
+    }
+
+    fn resume(&mut self, _module: &DataModule) -> Result<(), JsValue> {
+        Ok(())
+    }
+
+    fn render_offscreen(
+        &mut self,
+        _module: &DataModule,
+        _jobs: &[RenderJob],
+        _width: u32,
+        _height: u32,
+    ) -> Result<Vec<u8>, JsValue> {
+        Err(JsValue::from_str(
+            "The SVG renderer doesn't rasterize, take its output with take_svg",
+        ))
+    }
+
+    fn find_closest(
+        &self,
+        _job: &RenderJob,
+        _x: RangePrec,
+        _y: RangePrec,
+        _max_px: f32,
+    ) -> Option<HitResult> {
+        None
+    }
+
+    fn backend(&self) -> &'static str {
+        "svg"
+    }
+
+    fn features(&self) -> FeatureSet {
+        // markers are vector shapes of any size anyway, the rest is up to the viewer
+        FeatureSet {
+            dash: true,
+            text: true,
+            ..FeatureSet::default()
+        }
+    }
+
+    fn take_svg(&mut self) -> Option<String> {
+        let svg = self.svg();
+        self.body.clear();
+        self.clip_count = 0;
+
+        Some(svg)
+    }
+}
@@ -0,0 +1,448 @@
+//! Tick label text, drawn from a glyph atlas rasterized once through a 2D canvas
+
+use std::collections::HashMap;
+
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    OffscreenCanvas, OffscreenCanvasRenderingContext2d, WebGl2RenderingContext, WebGlBuffer,
+    WebGlProgram, WebGlTexture, WebGlUniformLocation,
+};
+
+use super::webgl::webgl_utils;
+use crate::{prelude::*, structs::LabelFormat};
+
+/// Printable ASCII, enough for numbers and date labels, and the micro prefix
+const CHARSET: &str =
+    " !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~µ";
+/// Prefixes of the powers of 1000 from 1e-24 on
+const SI_PREFIXES: [&str; 17] = [
+    "y", "z", "a", "f", "p", "n", "µ", "m", "", "k", "M", "G", "T", "P", "E", "Z", "Y",
+];
+const BYTE_PREFIXES: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+/// Units of spans of a minute or more, in seconds
+const DURATION_UNITS: [(RangePrec, &str); 4] =
+    [(86400.0, "d"), (3600.0, "h"), (60.0, "m"), (1.0, "s")];
+pub const FONT_PX: f32 = 12.0;
+const GLYPH_PADDING: f32 = 2.0;
+
+/// Horizontal alignment of a label relative to its anchor point
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// Label text anchored at `(x, y)` in canvas pixels, `y` being its vertical center, turned
+/// counterclockwise around the anchor by `rotation` degrees
+pub struct Label {
+    pub text: String,
+    pub x: f32,
+    pub y: f32,
+    pub align: Align,
+    pub rotation: f32,
+}
+
+#[derive(Clone, Copy)]
+struct Glyph {
+    u0: f32,
+    u1: f32,
+    width: f32,
+}
+
+pub struct TextRenderer {
+    glyphs: HashMap<char, Glyph>,
+    texture: WebGlTexture,
+    buffer: WebGlBuffer,
+
+    program: WebGlProgram,
+    resolution_pos: WebGlUniformLocation,
+    color_pos: WebGlUniformLocation,
+}
+
+impl TextRenderer {
+    pub fn new(gl: &WebGl2RenderingContext) -> Result<Self, JsValue> {
+        let height = FONT_PX + GLYPH_PADDING * 2.0;
+        let canvas = OffscreenCanvas::new(1, 1)?;
+        let ctx = Self::context_2d(&canvas)?;
+
+        let widths: Vec<f32> = CHARSET
+            .chars()
+            .map(|c| Ok(ctx.measure_text(&c.to_string())?.width() as f32))
+            .collect::<Result<_, JsValue>>()?;
+        let atlas_width: f32 = widths.iter().map(|w| w.ceil() + GLYPH_PADDING * 2.0).sum();
+
+        // resizing resets the context state, so it has to be configured afterwards
+        canvas.set_width(atlas_width as u32);
+        canvas.set_height(height as u32);
+        ctx.set_font(&format!("{}px sans-serif", FONT_PX));
+        ctx.set_text_baseline("middle");
+        ctx.set_fill_style_str("white");
+
+        let mut glyphs = HashMap::new();
+        let mut x = 0.0;
+
+        for (c, width) in CHARSET.chars().zip(widths) {
+            ctx.fill_text(
+                &c.to_string(),
+                (x + GLYPH_PADDING) as f64,
+                height as f64 / 2.0,
+            )?;
+
+            let cell = width.ceil() + GLYPH_PADDING * 2.0;
+            glyphs.insert(
+                c,
+                Glyph {
+                    u0: (x + GLYPH_PADDING) / atlas_width,
+                    u1: (x + GLYPH_PADDING + width) / atlas_width,
+                    width,
+                },
+            );
+            x += cell;
+        }
+
+        let texture = gl
+            .create_texture()
+            .ok_or_else(|| JsValue::from_str("Failed to allocate the glyph atlas texture"))?;
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&texture));
+        gl.tex_image_2d_with_u32_and_u32_and_offscreen_canvas(
+            WebGl2RenderingContext::TEXTURE_2D,
+            0,
+            WebGl2RenderingContext::RGBA as i32,
+            WebGl2RenderingContext::RGBA,
+            WebGl2RenderingContext::UNSIGNED_BYTE,
+            &canvas,
+        )?;
+
+        for (param, value) in [
+            (
+                WebGl2RenderingContext::TEXTURE_MIN_FILTER,
+                WebGl2RenderingContext::LINEAR,
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_MAG_FILTER,
+                WebGl2RenderingContext::LINEAR,
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_WRAP_S,
+                WebGl2RenderingContext::CLAMP_TO_EDGE,
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_WRAP_T,
+                WebGl2RenderingContext::CLAMP_TO_EDGE,
+            ),
+        ] {
+            gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, param, value as i32);
+        }
+
+        let program = Self::link(gl)?;
+
+        Ok(Self {
+            glyphs,
+            texture,
+            buffer: gl
+                .create_buffer()
+                .ok_or_else(|| JsValue::from_str("Failed to allocate the text buffer"))?,
+
+            resolution_pos: gl.get_uniform_location(&program, "resolution").unwrap(),
+            color_pos: gl.get_uniform_location(&program, "color").unwrap(),
+            program,
+        })
+    }
+
+    fn context_2d(canvas: &OffscreenCanvas) -> Result<OffscreenCanvasRenderingContext2d, JsValue> {
+        let ctx = canvas
+            .get_context("2d")?
+            .ok_or_else(|| JsValue::from_str("Failed to create a 2D context for the glyph atlas"))?
+            .dyn_into::<OffscreenCanvasRenderingContext2d>()?;
+        ctx.set_font(&format!("{}px sans-serif", FONT_PX));
+
+        Ok(ctx)
+    }
+
+    fn link(gl: &WebGl2RenderingContext) -> Result<WebGlProgram, JsValue> {
+        let vert_shader = webgl_utils::compile_shader(
+            gl,
+            WebGl2RenderingContext::VERTEX_SHADER,
+            r#"
+            attribute vec4 aVertex;
+
+            uniform vec2 resolution;
+            varying vec2 uv;
+
+            void main() {
+                uv = aVertex.zw;
+                gl_Position = vec4(vec2(-1, -1) + vec2(2, 2) * aVertex.xy / resolution, 0, 1);
+            }
+            "#,
+        )?;
+
+        let frag_shader = webgl_utils::compile_shader(
+            gl,
+            WebGl2RenderingContext::FRAGMENT_SHADER,
+            r#"
+            precision mediump float;
+
+            uniform sampler2D atlas;
+            uniform vec4 color;
+            varying vec2 uv;
+
+            void main() {
+                float alpha = texture2D(atlas, uv).a;
+
+                if (alpha <= 0.0) {
+                    discard;
+                }
+
+                gl_FragColor = color * alpha;
+            }
+            "#,
+        )?;
+
+        Ok(webgl_utils::link_program(gl, &vert_shader, &frag_shader)?)
+    }
+
+    /// Width of `text` in pixels, characters missing from the atlas take no space
+    pub fn measure(&self, text: &str) -> f32 {
+        text.chars()
+            .filter_map(|c| self.glyphs.get(&c))
+            .map(|g| g.width)
+            .sum()
+    }
+
+    /// Draws `labels` onto a `width` x `height` canvas, the viewport has to cover all of it
+    pub fn draw(
+        &self,
+        gl: &WebGl2RenderingContext,
+        labels: &[Label],
+        width: u32,
+        height: u32,
+        color: [f32; 3],
+    ) {
+        let half = FONT_PX / 2.0 + GLYPH_PADDING;
+        let mut data: Vec<f32> = Vec::new();
+
+        for label in labels {
+            let (anchor_x, anchor_y) = (label.x.round(), label.y.round());
+            let mut x = match label.align {
+                Align::Left => 0.0,
+                Align::Center => -self.measure(&label.text) / 2.0,
+                Align::Right => -self.measure(&label.text),
+            }
+            .round();
+            let (sin, cos) = label.rotation.to_radians().sin_cos();
+            let at = |x: f32, y: f32| [anchor_x + x * cos - y * sin, anchor_y + x * sin + y * cos];
+
+            for glyph in label.text.chars().filter_map(|c| self.glyphs.get(&c)) {
+                let (x0, x1) = (x, x + glyph.width);
+                let ([ax, ay], [bx, by]) = (at(x0, -half), at(x1, -half));
+                let ([cx, cy], [dx, dy]) = (at(x0, half), at(x1, half));
+
+                // texture rows go top to bottom, canvas pixels bottom to top
+                data.extend_from_slice(&[
+                    ax, ay, glyph.u0, 1.0, bx, by, glyph.u1, 1.0, cx, cy, glyph.u0, 0.0, //
+                    cx, cy, glyph.u0, 0.0, bx, by, glyph.u1, 1.0, dx, dy, glyph.u1, 0.0,
+                ]);
+
+                x = x1;
+            }
+        }
+
+        if data.is_empty() {
+            return;
+        }
+
+        gl.use_program(Some(&self.program));
+        gl.uniform2f(Some(&self.resolution_pos), width as f32, height as f32);
+        gl.uniform4f(Some(&self.color_pos), color[0], color[1], color[2], 1.0);
+
+        gl.active_texture(WebGl2RenderingContext::TEXTURE0);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&self.texture));
+        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&self.buffer));
+
+        webgl_utils::upload(
+            gl,
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            &data,
+            WebGl2RenderingContext::STATIC_DRAW,
+        );
+
+        gl.vertex_attrib_pointer_with_i32(0, 4, WebGl2RenderingContext::FLOAT, false, 0, 0);
+        gl.enable_vertex_attrib_array(0);
+
+        // glyph edges are blended, premultiplied like the rest of the canvas
+        gl.enable(WebGl2RenderingContext::BLEND);
+        gl.blend_func(
+            WebGl2RenderingContext::ONE,
+            WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+        );
+        gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, data.len() as i32 / 4);
+        gl.disable(WebGl2RenderingContext::BLEND);
+
+        // the other programs read attribute 0 as a vec2
+        gl.vertex_attrib_pointer_with_i32(0, 2, WebGl2RenderingContext::FLOAT, false, 0, 0);
+    }
+}
+
+/// Formats a tick value, switching to exponent notation for very large or small magnitudes
+pub fn format_tick(val: RangePrec) -> String {
+    let abs = val.abs();
+
+    if !val.is_finite() {
+        return String::new();
+    }
+    if abs == 0.0 {
+        return "0".into();
+    }
+
+    if !(1e-3..1e6).contains(&abs) {
+        let formatted = format!("{:.2e}", val);
+        let (mantissa, exp) = formatted.split_once('e').unwrap();
+
+        return format!("{}e{}", trim_zeros(mantissa), exp);
+    }
+
+    trim_zeros(&format!("{:.6}", val)).to_string()
+}
+
+/// How the tick labels of an axis show their values
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TickFormat {
+    pub format: LabelFormat,
+    /// Digits after the decimal point, at most for trimmed formats and exactly for fixed ones.
+    /// Fixed labels default to 2, the rest to 3, while automatic labels pick their own.
+    pub decimals: Option<u32>,
+}
+
+impl TickFormat {
+    pub fn new(format: LabelFormat, decimals: Option<u32>) -> Self {
+        Self { format, decimals }
+    }
+
+    /// Label of `val`, empty for values that aren't finite
+    pub fn label(&self, val: RangePrec) -> String {
+        if !val.is_finite() {
+            return String::new();
+        }
+
+        let places = self.decimals.unwrap_or(3) as usize;
+        let trimmed = |val: RangePrec| trim_zeros(&format!("{:.*}", places, val)).to_string();
+
+        match self.format {
+            LabelFormat::Auto => format_tick(val),
+            LabelFormat::Fixed => format!("{:.*}", self.decimals.unwrap_or(2) as usize, val),
+            LabelFormat::Percent => format!("{}%", trimmed(val * 100.0)),
+            LabelFormat::Si => {
+                let (mantissa, exp) = thousands(val, places, -8, 8);
+                format!("{}{}", mantissa, SI_PREFIXES[(exp + 8) as usize])
+            }
+            LabelFormat::Engineering => match thousands(val, places, i32::MIN, i32::MAX) {
+                (mantissa, 0) => mantissa,
+                (mantissa, exp) => format!("{}e{}", mantissa, exp * 3),
+            },
+            LabelFormat::Bytes => {
+                let mut scaled = val;
+                let mut prefix = 0;
+                while scaled.abs() >= 1024.0 && prefix + 1 < BYTE_PREFIXES.len() {
+                    scaled /= 1024.0;
+                    prefix += 1;
+                }
+
+                match prefix {
+                    0 => format!("{} B", trimmed(val)),
+                    _ => format!("{} {}", trimmed(scaled), BYTE_PREFIXES[prefix]),
+                }
+            }
+            LabelFormat::Duration => format_duration(val, places),
+        }
+    }
+}
+
+/// Mantissa of `val` trimmed to `places` decimals and the power of 1000 it's multiplied by,
+/// within `min..=max`. Mantissas rounding to 1000 move on to the next power.
+fn thousands(val: RangePrec, places: usize, min: i32, max: i32) -> (String, i32) {
+    if val == 0.0 {
+        return ("0".into(), 0);
+    }
+
+    let mut exp = ((val.abs().log10() / 3.0).floor() as i32).clamp(min, max);
+    let mut mantissa = val / 1000f64.powi(exp);
+    let rounded: RangePrec = format!("{:.*}", places, mantissa).parse().unwrap();
+    if rounded.abs() >= 1000.0 && exp < max {
+        exp += 1;
+        mantissa /= 1000.0;
+    }
+
+    (
+        trim_zeros(&format!("{:.*}", places, mantissa)).to_string(),
+        exp,
+    )
+}
+
+/// Formats seconds as milliseconds or microseconds below a second, as seconds below a minute
+/// and as the two largest units above, e.g. `1h 30m`
+fn format_duration(val: RangePrec, places: usize) -> String {
+    let sign = if val < 0.0 { "-" } else { "" };
+    let abs = val.abs();
+
+    let plain = |val: RangePrec, unit| {
+        format!(
+            "{}{}{}",
+            sign,
+            trim_zeros(&format!("{:.*}", places, val)),
+            unit
+        )
+    };
+    if abs == 0.0 {
+        return "0s".into();
+    }
+    if abs < 1e-3 {
+        return plain(abs * 1e6, "µs");
+    }
+    if abs < 1.0 {
+        return plain(abs * 1e3, "ms");
+    }
+    if abs < 60.0 {
+        return plain(abs, "s");
+    }
+
+    let unit = |abs: RangePrec| {
+        DURATION_UNITS
+            .iter()
+            .position(|(len, _)| abs >= *len)
+            .unwrap()
+    };
+    // rounded to whole minor units before picking them, so that 59m 59.9s is shown as 1h
+    let minor = DURATION_UNITS[unit(abs) + 1].0;
+    let abs = (abs / minor).round() * minor;
+    let i = unit(abs);
+    let (major, major_unit) = DURATION_UNITS[i];
+    let (minor, minor_unit) = DURATION_UNITS[i + 1];
+
+    let total = (abs / minor).round();
+    let per_major = (major / minor).round();
+    let (whole, rest) = ((total / per_major).floor(), total % per_major);
+
+    match rest > 0.0 {
+        true => format!("{}{}{} {}{}", sign, whole, major_unit, rest, minor_unit),
+        false => format!("{}{}{}", sign, whole, major_unit),
+    }
+}
+
+/// Formats an axis offset as a signed number in exponent notation, e.g. `+1.0e6`
+pub fn format_offset(val: RangePrec) -> String {
+    let formatted = format!("{:+e}", val);
+
+    match formatted.contains('.') {
+        true => formatted,
+        false => formatted.replacen('e', ".0e", 1),
+    }
+}
+
+fn trim_zeros(num: &str) -> &str {
+    if num.contains('.') {
+        num.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        num
+    }
+}
@@ -0,0 +1,707 @@
+//! Axis tick placement, independent of the renderer
+
+use chrono::Datelike;
+
+use crate::{
+    prelude::*,
+    structs::{AxisScale, RenderJob, Scale, TickEnds, TickMode, LOG_FLOOR},
+};
+
+use super::{
+    text::{self, FONT_PX},
+    AxisOffset, AxisTick, PlotRect, RenderJobResult, TickFormat,
+};
+
+/// Degrees x tick labels are turned by when they don't fit side by side
+pub const LABEL_ROTATION: f32 = 45.0;
+/// Least room between neighbouring tick labels in pixels
+const MIN_LABEL_GAP: f32 = 4.0;
+
+/// Share of its value a flat range is widened by on both sides, at least a unit
+const FLAT_INFLATION: RangePrec = 0.05;
+
+/// Most ticks an axis gets unless its job asks for another count
+pub const DEFAULT_MAX_TICKS: RangePrec = 10.0;
+/// Most ticks a custom step may place, beyond which the step is picked as if there was none
+const MAX_STEPPED_TICKS: RangePrec = 1000.0;
+
+/// How densely an axis is ticked
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TickDensity {
+    /// Most ticks placed at a picked step
+    pub max_ticks: RangePrec,
+    /// Distance between the ticks of linear axes in place of a picked 1, 2 or 5 step
+    pub step: Option<RangePrec>,
+}
+
+impl TickDensity {
+    /// At most `count` ticks, `0` for the default, placed `step` apart unless it's `0`
+    pub fn new(count: u32, step: RangePrec) -> Self {
+        Self {
+            max_ticks: match count {
+                0 => DEFAULT_MAX_TICKS,
+                count => count as RangePrec,
+            },
+            step: (step > 0.0 && step.is_finite()).then_some(step),
+        }
+    }
+
+    /// The density lowered so that the ticks along an axis `length` pixels long are at least
+    /// `spacing` pixels apart, `0` leaving it as it is. A custom step is kept.
+    pub fn spaced(self, length: u32, spacing: u32) -> Self {
+        let max_ticks = match spacing {
+            0 => self.max_ticks,
+            _ => self.max_ticks.min((length / spacing) as RangePrec).max(1.0),
+        };
+
+        Self { max_ticks, ..self }
+    }
+}
+
+impl Default for TickDensity {
+    fn default() -> Self {
+        Self::new(0, 0.0)
+    }
+}
+
+/// `from..to` unless it holds a single finite value, which is widened by 5% of it or a unit,
+/// whichever is more, on both sides so it can be transformed and ticked
+pub fn inflate(from: RangePrec, to: RangePrec) -> (RangePrec, RangePrec) {
+    if from != to || !from.is_finite() {
+        return (from, to);
+    }
+
+    let half = (from.abs() * FLAT_INFLATION).max(1.0);
+
+    (from - half, to + half)
+}
+
+/// Least ratio of an axis' magnitude to its span for its ticks to be labeled relative to an offset
+const OFFSET_RATIO: RangePrec = 1e3;
+
+/// Value the ticks of `from..to` are labeled relative to, when the magnitude of the range
+/// dwarfs its span so much that full labels would be long or indistinguishable. It's `from`
+/// rounded down to the power of ten above the span, leaving labels of a digit or two.
+pub fn axis_offset(from: RangePrec, to: RangePrec) -> Option<RangePrec> {
+    let span = to - from;
+    if !(span > 0.0 && span.is_finite()) || from.abs().max(to.abs()) < span * OFFSET_RATIO {
+        return None;
+    }
+
+    // dividing by a whole power keeps round offsets exact, unlike multiplying by a fraction
+    let exp = span.log10().floor() as i32 + 1;
+    let offset = match exp >= 0 {
+        true => (from / 10.0_f64.powi(exp)).floor() * 10.0_f64.powi(exp),
+        false => (from * 10.0_f64.powi(-exp)).floor() / 10.0_f64.powi(-exp),
+    };
+
+    Some(offset)
+}
+
+/// Ticks of all the axes `job` displays in `rect`, the listed ones where it has a list
+pub fn for_job(job: &RenderJob, rect: &PlotRect) -> RenderJobResult {
+    // ticks are placed along unreversed axes and mirrored once they're complete
+    let x_scale = job.x_axis_scale().reversed(false);
+    let y_scale = job.y_axis_scale().reversed(false);
+    let x_density = job
+        .x_tick_density()
+        .spaced(rect.width, job.min_tick_spacing);
+    let y_density = job
+        .y_tick_density()
+        .spaced(rect.height, job.min_tick_spacing);
+
+    let offset = |(from, to), scale: AxisScale| match scale.scale {
+        Scale::Linear => axis_offset(from, to),
+        _ => None,
+    };
+    let ends = job.tick_ends != TickEnds::Interior;
+    let numeric =
+        |ticks: Box<[AxisTick]>, (from, to), offset: Option<RangePrec>, format: TickFormat| {
+            let label = |val: RangePrec| format.label(val - offset.unwrap_or(0.0));
+            let ticks = match offset.is_some() || format != TickFormat::default() {
+                true => ticks
+                    .into_vec()
+                    .into_iter()
+                    .map(|tick| AxisTick {
+                        label: label(tick.val),
+                        ..tick
+                    })
+                    .collect(),
+                false => ticks,
+            };
+
+            match ends {
+                true => with_endpoints(ticks, from, to, label),
+                false => ticks,
+            }
+        };
+    let reported = |offset: Option<RangePrec>| {
+        offset.map(|val| AxisOffset {
+            val,
+            label: text::format_offset(val),
+        })
+    };
+
+    // times have labels of their own, as do listed ticks
+    let x_time = job.x_tick_mode == TickMode::Time && x_scale.scale == Scale::Linear;
+    let x_listed = job.x_tick_list();
+    let y_listed = job.y_tick_list();
+    let x_offset = offset(job.view().x(), x_scale).filter(|_| !x_time && x_listed.is_empty());
+    let y_offset = offset(job.view().y(), y_scale).filter(|_| y_listed.is_empty());
+    let y2_offset = job.y2_range().and_then(|y2| offset(y2, y_scale));
+
+    let x_ticks = if !x_listed.is_empty() {
+        listed_ticks(
+            x_listed,
+            job.x_from,
+            job.x_to,
+            x_scale,
+            &job.x_tick_format(),
+        )
+    } else if x_time {
+        let ticks = calc_time_ticks_with(job.x_from, job.x_to, &x_density);
+        match ends {
+            true => with_endpoints(ticks, job.x_from, job.x_to, |val| {
+                format_time(val, "%b %d %H:%M:%S")
+            }),
+            false => ticks,
+        }
+    } else {
+        numeric(
+            calc_scaled_ticks_with(job.x_from, job.x_to, x_scale, &x_density),
+            job.view().x(),
+            x_offset,
+            job.x_tick_format(),
+        )
+    };
+    let y_ticks = match y_listed.is_empty() {
+        true => numeric(
+            calc_scaled_ticks_with(job.y_from, job.y_to, y_scale, &y_density),
+            job.view().y(),
+            y_offset,
+            job.y_tick_format(),
+        ),
+        false => listed_ticks(
+            y_listed,
+            job.y_from,
+            job.y_to,
+            y_scale,
+            &job.y_tick_format(),
+        ),
+    };
+
+    let y2_ticks = match job.y2_range() {
+        Some((from, to)) => numeric(
+            calc_scaled_ticks_with(from, to, y_scale, &y_density),
+            (from, to),
+            y2_offset,
+            job.y_tick_format(),
+        ),
+        None => Box::new([]),
+    };
+
+    RenderJobResult {
+        x_ticks: mirrored(x_ticks, job.x_reversed),
+        y_ticks: mirrored(y_ticks, job.y_reversed),
+        y2_ticks: mirrored(y2_ticks, job.y_reversed),
+        x_offset: reported(x_offset),
+        y_offset: reported(y_offset),
+        y2_offset: reported(y2_offset),
+        x_label_rotation: 0.0,
+        extents: Vec::new(),
+        decimation: Vec::new(),
+        legend: Vec::new(),
+    }
+}
+
+/// `ticks` placed along a reversed axis when `reversed`, their positions still ascending
+fn mirrored(ticks: Box<[AxisTick]>, reversed: bool) -> Box<[AxisTick]> {
+    match reversed {
+        true => ticks
+            .into_vec()
+            .into_iter()
+            .rev()
+            .map(|tick| AxisTick {
+                pos: 1.0 - tick.pos,
+                ..tick
+            })
+            .collect(),
+        false => ticks,
+    }
+}
+
+/// `ticks` between ticks at `from` and `to` labeled by `label`, leaving out the ones within a
+/// quarter of their spacing of either end
+pub fn with_endpoints(
+    ticks: Box<[AxisTick]>,
+    from: RangePrec,
+    to: RangePrec,
+    label: impl Fn(RangePrec) -> String,
+) -> Box<[AxisTick]> {
+    let spacing = match &ticks[..] {
+        [a, b, ..] => b.pos - a.pos,
+        _ => 1.0,
+    };
+    let end = |val, pos| AxisTick {
+        val,
+        pos,
+        label: label(val),
+        hidden: false,
+    };
+
+    let interior = ticks
+        .into_vec()
+        .into_iter()
+        .filter(|t| t.pos > spacing / 4.0 && t.pos < 1.0 - spacing / 4.0);
+
+    std::iter::once(end(from, 0.0))
+        .chain(interior)
+        .chain(std::iter::once(end(to, 1.0)))
+        .collect()
+}
+
+/// Which tick labels are shown, as every `stride`-th one starting with the first, and the
+/// degrees they're turned counterclockwise by
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LabelLayout {
+    pub stride: usize,
+    pub rotation: f32,
+}
+
+/// Layout of the labels of `widths` pixels centered at the ascending pixel `positions` along
+/// the x axis. Labels that don't fit side by side are turned by `LABEL_ROTATION` degrees with
+/// `rotate`, and are thinned out until they fit.
+pub fn layout_x_labels(positions: &[f32], widths: &[f32], rotate: bool) -> LabelLayout {
+    let (sin, _) = LABEL_ROTATION.to_radians().sin_cos();
+    let fits = |stride: usize, gap: &dyn Fn(usize, usize) -> bool| {
+        (stride..positions.len())
+            .step_by(stride)
+            .all(|i| gap(i - stride, i))
+    };
+
+    for stride in 1..positions.len().max(1) {
+        let flat = |a: usize, b: usize| {
+            positions[b] - positions[a] >= (widths[a] + widths[b]) / 2.0 + MIN_LABEL_GAP
+        };
+        if fits(stride, &flat) {
+            return LabelLayout {
+                stride,
+                rotation: 0.0,
+            };
+        }
+
+        // turned labels lie side by side, their distance shrunk by the angle
+        let turned = |a: usize, b: usize| (positions[b] - positions[a]) * sin >= FONT_PX;
+        if rotate && fits(stride, &turned) {
+            return LabelLayout {
+                stride,
+                rotation: LABEL_ROTATION,
+            };
+        }
+    }
+
+    LabelLayout {
+        stride: positions.len().max(1),
+        rotation: 0.0,
+    }
+}
+
+/// Layout of the labels at the pixel `positions` along a y axis, which are never turned
+pub fn layout_y_labels(positions: &[f32]) -> LabelLayout {
+    let heights = vec![FONT_PX; positions.len()];
+
+    layout_x_labels(positions, &heights, false)
+}
+
+/// Hides the tick labels of `result` that would overlap in `rect`, measuring the x ones with
+/// `measure`, and turns the x ones when `job` allows it
+pub fn lay_out_labels(
+    result: &mut RenderJobResult,
+    job: &RenderJob,
+    rect: &PlotRect,
+    measure: impl Fn(&str) -> f32,
+) {
+    let hide = |ticks: &mut [AxisTick], stride: usize| {
+        for (i, tick) in ticks.iter_mut().enumerate() {
+            tick.hidden = i % stride != 0;
+        }
+    };
+
+    let positions: Vec<_> = result
+        .x_ticks
+        .iter()
+        .map(|t| t.pos as f32 * rect.width as f32)
+        .collect();
+    let widths: Vec<_> = result.x_ticks.iter().map(|t| measure(&t.label)).collect();
+    let x = layout_x_labels(&positions, &widths, job.rotate_labels);
+    hide(&mut result.x_ticks, x.stride);
+    result.x_label_rotation = x.rotation;
+
+    for ticks in [&mut result.y_ticks, &mut result.y2_ticks] {
+        let positions: Vec<_> = ticks
+            .iter()
+            .map(|t| t.pos as f32 * rect.height as f32)
+            .collect();
+        hide(ticks, layout_y_labels(&positions).stride);
+    }
+}
+
+/// Distance between the ticks of `start..start + width` and the last multiple of it before
+/// `start`
+fn tick_step(start: RangePrec, width: RangePrec, density: &TickDensity) -> (RangePrec, RangePrec) {
+    const SIZES: [RangePrec; 4] = [1.0, 2.0, 5.0, 10.0];
+
+    if let Some(step) = density
+        .step
+        .filter(|&step| width / step <= MAX_STEPPED_TICKS)
+    {
+        return ((start / step).floor() * step, step);
+    }
+
+    let mut y0: RangePrec = 0.0;
+    let mut dy: RangePrec = 1.0;
+    let max_ticks = density.max_ticks.max(1.0);
+    let order = (width / max_ticks).log10().floor();
+
+    for size in SIZES.iter() {
+        dy = 10.0_f64.powf(order) * size;
+        y0 = (start / dy).floor() * dy;
+
+        if (width + start - y0) / dy < max_ticks {
+            break;
+        }
+    }
+
+    (y0, dy)
+}
+
+pub fn calc_ticks(start: RangePrec, width: RangePrec) -> Box<[AxisTick]> {
+    calc_ticks_with(start, width, &TickDensity::default())
+}
+
+/// Ticks of `start..start + width` as dense as `density` allows
+pub fn calc_ticks_with(
+    start: RangePrec,
+    width: RangePrec,
+    density: &TickDensity,
+) -> Box<[AxisTick]> {
+    if !(width > 0.0 && width.is_finite() && start.is_finite()) {
+        return Box::new([]);
+    }
+
+    let (y0, dy) = tick_step(start, width, density);
+
+    (1..=((width + start - y0) / dy).floor() as usize)
+        .map(|i| {
+            AxisTick::numeric(
+                y0 + dy * i as RangePrec,
+                (y0 + dy * i as RangePrec - start) / width,
+            )
+        })
+        .collect()
+}
+
+/// `from..to` widened to the nearest values `calc_ticks` places ticks at
+pub fn nice_bounds(from: RangePrec, to: RangePrec) -> (RangePrec, RangePrec) {
+    nice_bounds_with(from, to, &TickDensity::default())
+}
+
+/// `from..to` widened to the nearest values `calc_ticks_with` places ticks at
+pub fn nice_bounds_with(
+    from: RangePrec,
+    to: RangePrec,
+    density: &TickDensity,
+) -> (RangePrec, RangePrec) {
+    let (_, step) = tick_step(from, to - from, density);
+
+    ((from / step).floor() * step, (to / step).ceil() * step)
+}
+
+pub fn calc_scaled_ticks(from: RangePrec, to: RangePrec, scale: AxisScale) -> Box<[AxisTick]> {
+    calc_scaled_ticks_with(from, to, scale, &TickDensity::default())
+}
+
+/// Ticks of `from..to` along `scale`, as dense as `density` allows. Custom steps only apply
+/// to linear scales.
+pub fn calc_scaled_ticks_with(
+    from: RangePrec,
+    to: RangePrec,
+    scale: AxisScale,
+    density: &TickDensity,
+) -> Box<[AxisTick]> {
+    match scale.scale {
+        Scale::Linear => calc_ticks_with(from, to - from, density),
+        Scale::Log10 => calc_log_ticks_with(from, to, density),
+        Scale::SymLog => calc_symlog_ticks_with(from, to, scale, density),
+    }
+}
+
+/// Ticks at the `listed` values within `from..to`, labeled by their labels or else by their
+/// values in `format`
+pub fn listed_ticks(
+    listed: &[(RangePrec, Option<String>)],
+    from: RangePrec,
+    to: RangePrec,
+    scale: AxisScale,
+    format: &TickFormat,
+) -> Box<[AxisTick]> {
+    let (lo, hi) = (from.min(to), from.max(to));
+
+    listed
+        .iter()
+        .filter(|(val, _)| (lo..=hi).contains(val))
+        .map(|(val, label)| AxisTick {
+            val: *val,
+            pos: scale.position(*val, from, to),
+            label: label.clone().unwrap_or_else(|| format.label(*val)),
+            hidden: false,
+        })
+        .collect()
+}
+
+/// Ticks at whole decades, with 2/5 or 2..9 sub-decade ticks when only a few decades are visible
+pub fn calc_log_ticks(from: RangePrec, to: RangePrec) -> Box<[AxisTick]> {
+    calc_log_ticks_with(from, to, &TickDensity::default())
+}
+
+/// `calc_log_ticks` skipping decades to keep the whole ones within `density`
+pub fn calc_log_ticks_with(
+    from: RangePrec,
+    to: RangePrec,
+    density: &TickDensity,
+) -> Box<[AxisTick]> {
+    let scale = AxisScale::from(Scale::Log10);
+
+    let from = from.max(LOG_FLOOR);
+    let to = to.max(from);
+    if from == to || !to.is_finite() {
+        return Box::new([]);
+    }
+
+    let lo = scale.apply(from);
+    let hi = scale.apply(to);
+    let decades = hi - lo;
+
+    let multiples: &[RangePrec] = if decades <= 1.0 {
+        &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0]
+    } else if decades <= 3.0 {
+        &[1.0, 2.0, 5.0]
+    } else {
+        &[1.0]
+    };
+    let step = (decades / density.max_ticks.max(1.0)).ceil().max(1.0) as i32;
+
+    let mut ticks = Vec::new();
+    let mut exp = lo.floor() as i32;
+    exp -= exp.rem_euclid(step);
+
+    while exp as RangePrec <= hi {
+        let base = 10.0_f64.powi(exp);
+
+        for &mul in multiples {
+            let val = base * mul;
+
+            if val >= from && val <= to {
+                ticks.push(AxisTick::numeric(val, scale.position(val, from, to)));
+            }
+        }
+
+        exp += step;
+    }
+
+    ticks.into_boxed_slice()
+}
+
+/// Ticks at calendar boundaries for `from` and `to` in epoch milliseconds, labeled in UTC
+pub fn calc_time_ticks(from: RangePrec, to: RangePrec) -> Box<[AxisTick]> {
+    calc_time_ticks_with(from, to, &TickDensity::default())
+}
+
+/// `calc_time_ticks` with at most as many ticks as `density` allows, its step being ignored
+/// as ticks keep to calendar boundaries
+pub fn calc_time_ticks_with(
+    from: RangePrec,
+    to: RangePrec,
+    density: &TickDensity,
+) -> Box<[AxisTick]> {
+    let max_ticks = density.max_ticks.max(1.0);
+
+    let span = to - from;
+    if !(span > 0.0 && span.is_finite()) {
+        return Box::new([]);
+    }
+    let step = TIME_STEPS
+        .iter()
+        .find(|step| span / step.approx_ms() <= max_ticks)
+        .copied()
+        .unwrap_or_else(|| {
+            let years = (span / TimeStep::Months(12).approx_ms() / max_ticks).ceil();
+            TimeStep::Months(12 * years as u32)
+        });
+
+    // below a second there are no calendar boundaries, so decimal milliseconds will do
+    if span / TIME_STEPS[0].approx_ms() < 2.0 {
+        let density = TickDensity {
+            step: None,
+            ..*density
+        };
+
+        return calc_ticks_with(from, span, &density)
+            .into_vec()
+            .into_iter()
+            .map(|tick| AxisTick {
+                label: format_time(tick.val, "%H:%M:%S%.3f"),
+                ..tick
+            })
+            .collect();
+    }
+
+    let mut values = Vec::new();
+
+    match step {
+        TimeStep::Fixed(ms, _) => {
+            // weeks start on monday, 4 days after the epoch
+            let offset = if ms == WEEK { 4.0 * DAY } else { 0.0 };
+            let mut val = ((from - offset) / ms).ceil() * ms + offset;
+
+            while val <= to {
+                values.push(val);
+                val += ms;
+            }
+        }
+        TimeStep::Months(months) => {
+            let month_of = |val: RangePrec| {
+                chrono::DateTime::from_timestamp_millis(val as i64)
+                    .map(|d| d.year() as i64 * 12 + d.month0() as i64)
+            };
+
+            if let (Some(first), Some(last)) = (month_of(from), month_of(to)) {
+                let months = months as i64;
+                let mut month = first.div_euclid(months) * months;
+
+                while month <= last {
+                    let val = chrono::NaiveDate::from_ymd_opt(
+                        month.div_euclid(12) as i32,
+                        month.rem_euclid(12) as u32 + 1,
+                        1,
+                    )
+                    .map(|d| {
+                        d.and_time(chrono::NaiveTime::MIN)
+                            .and_utc()
+                            .timestamp_millis()
+                    });
+
+                    if let Some(val) = val.map(|v| v as RangePrec) {
+                        if val >= from && val <= to {
+                            values.push(val);
+                        }
+                    }
+
+                    month += months;
+                }
+            }
+        }
+    }
+
+    values
+        .into_iter()
+        .map(|val| AxisTick {
+            val,
+            pos: (val - from) / span,
+            label: format_time(val, step.format()),
+            hidden: false,
+        })
+        .collect()
+}
+
+const SECOND: RangePrec = 1000.0;
+const MINUTE: RangePrec = 60.0 * SECOND;
+const HOUR: RangePrec = 60.0 * MINUTE;
+const DAY: RangePrec = 24.0 * HOUR;
+const WEEK: RangePrec = 7.0 * DAY;
+
+#[derive(Clone, Copy)]
+enum TimeStep {
+    Fixed(RangePrec, &'static str),
+    Months(u32),
+}
+
+impl TimeStep {
+    fn approx_ms(self) -> RangePrec {
+        match self {
+            TimeStep::Fixed(ms, _) => ms,
+            TimeStep::Months(months) => months as RangePrec * 30.44 * DAY,
+        }
+    }
+
+    fn format(self) -> &'static str {
+        match self {
+            TimeStep::Fixed(_, format) => format,
+            TimeStep::Months(months) if months < 12 => "%b %Y",
+            TimeStep::Months(_) => "%Y",
+        }
+    }
+}
+
+const TIME_STEPS: [TimeStep; 10] = [
+    TimeStep::Fixed(SECOND, "%H:%M:%S"),
+    TimeStep::Fixed(5.0 * SECOND, "%H:%M:%S"),
+    TimeStep::Fixed(MINUTE, "%H:%M"),
+    TimeStep::Fixed(15.0 * MINUTE, "%H:%M"),
+    TimeStep::Fixed(HOUR, "%H:%M"),
+    TimeStep::Fixed(6.0 * HOUR, "%b %d %H:%M"),
+    TimeStep::Fixed(DAY, "%b %d"),
+    TimeStep::Fixed(WEEK, "%b %d"),
+    TimeStep::Months(1),
+    TimeStep::Months(12),
+];
+
+fn format_time(ms: RangePrec, format: &str) -> String {
+    chrono::DateTime::from_timestamp_millis(ms as i64)
+        .map(|d| d.format(format).to_string())
+        .unwrap_or_default()
+}
+
+/// Ticks at zero and at whole decades past the linear threshold on either side
+pub fn calc_symlog_ticks(from: RangePrec, to: RangePrec, scale: AxisScale) -> Box<[AxisTick]> {
+    calc_symlog_ticks_with(from, to, scale, &TickDensity::default())
+}
+
+/// `calc_symlog_ticks` skipping decades to keep the ticks within `density`, half of them on
+/// either side of zero
+pub fn calc_symlog_ticks_with(
+    from: RangePrec,
+    to: RangePrec,
+    scale: AxisScale,
+    density: &TickDensity,
+) -> Box<[AxisTick]> {
+    let max_abs = from.abs().max(to.abs());
+    if max_abs == 0.0 || !max_abs.is_finite() || from >= to {
+        return Box::new([]);
+    }
+
+    let lo = scale.linthresh.log10().floor() as i32;
+    let hi = max_abs.log10().ceil() as i32;
+    let step = ((hi - lo) as RangePrec / (density.max_ticks / 2.0).max(1.0))
+        .ceil()
+        .max(1.0) as i32;
+
+    let mut values = vec![0.0];
+    let mut exp = lo;
+
+    while exp <= hi {
+        let val = 10.0_f64.powi(exp);
+        values.push(val);
+        values.push(-val);
+
+        exp += step;
+    }
+
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    values
+        .into_iter()
+        .filter(|&val| val >= from && val <= to)
+        .map(|val| AxisTick::numeric(val, scale.position(val, from, to)))
+        .collect()
+}
@@ -0,0 +1,104 @@
+//! Points of traces drawn straight from the module kept across frames, so hovering and
+//! previews don't read and upload them again for every frame
+
+use std::collections::VecDeque;
+
+use crate::{data::DataIdx, prelude::*};
+
+/// Steps of the grid cached ranges are snapped to per cached width
+const STEPS_PER_RANGE: RangePrec = 4.0;
+
+/// Identifies the points of a trace read over a range snapped to a grid, so views panned or
+/// zoomed by a little share them. `revision` is the module's, any change to the data making
+/// the points stale.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TraceKey {
+    pub handle: DataIdx,
+    /// Power of two the grid steps are
+    exponent: i32,
+    /// Step the cached range starts at
+    index: i64,
+    revision: u64,
+}
+
+impl TraceKey {
+    /// Key of the points covering `from..to`, `None` for ranges that can't be snapped
+    pub fn of(handle: DataIdx, from: RangePrec, to: RangePrec, revision: u64) -> Option<Self> {
+        let width = to - from;
+        if !(width > 0.0 && width.is_finite() && from.is_finite()) {
+            return None;
+        }
+
+        let exponent = (width / STEPS_PER_RANGE).log2().ceil() as i32;
+        let index = (from / 2f64.powi(exponent)).floor();
+
+        (index.abs() < i64::MAX as RangePrec).then_some(Self {
+            handle,
+            exponent,
+            index: index as i64,
+            revision,
+        })
+    }
+
+    /// Range the points are read over, reaching past any `from..to` the key is of by up to a
+    /// step on its right
+    pub fn range(&self) -> (RangePrec, RangePrec) {
+        let step = 2f64.powi(self.exponent);
+        let from = self.index as RangePrec * step;
+
+        (from, from + (STEPS_PER_RANGE + 1.0) * step)
+    }
+}
+
+/// The most recently used points of traces, the least recently used ones evicted beyond
+/// `capacity`
+pub struct TraceCache<V> {
+    capacity: usize,
+    entries: VecDeque<(TraceKey, V)>,
+}
+
+impl<V> TraceCache<V> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Cached value of `key`, marking it as the most recently used
+    pub fn get(&mut self, key: &TraceKey) -> Option<&V> {
+        let i = self.entries.iter().position(|(k, _)| k == key)?;
+        let hit = self.entries.remove(i).unwrap();
+        self.entries.push_front(hit);
+
+        self.entries.front().map(|(_, value)| value)
+    }
+
+    /// Caches `value` under `key`, returning the values evicted to make room for it, which
+    /// includes the one cached under `key` before
+    pub fn insert(&mut self, key: TraceKey, value: V) -> Vec<V> {
+        let mut evicted: Vec<V> = match self.entries.iter().position(|(k, _)| *k == key) {
+            Some(i) => self.entries.remove(i).map(|(_, v)| v).into_iter().collect(),
+            None => Vec::new(),
+        };
+        while !self.entries.is_empty() && self.entries.len() >= self.capacity {
+            evicted.extend(self.entries.pop_back().map(|(_, v)| v));
+        }
+        self.entries.push_front((key, value));
+
+        evicted
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Empties the cache, returning everything it held
+    pub fn drain(&mut self) -> Vec<V> {
+        self.entries.drain(..).map(|(_, v)| v).collect()
+    }
+}
@@ -1,62 +1,374 @@
 use std::{
-    collections::HashMap,
-    ops::{Add, Mul, Sub},
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    ops::Range,
+    rc::Rc,
 };
 
 use js_sys::Float32Array;
 use lazy_static::__Deref;
-use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
 use web_sys::{
-    OffscreenCanvas, WebGl2RenderingContext, WebGlBuffer, WebGlProgram, WebGlUniformLocation,
+    Event, ImageBitmap, OffscreenCanvas, WebGl2RenderingContext, WebGlBuffer, WebGlProgram,
+    WebGlTexture, WebGlUniformLocation, WebGlVertexArrayObject,
 };
 
-use crate::{data::DataIdx, data_module::DataModule, prelude::*, structs::RenderJob};
+use crate::{
+    data::DataIdx,
+    data_module::{
+        aggregate_candles, downsample, m4, max_error, resample, DataModule, Decimation,
+        Downsampling, Fidelity, GapRules, Interpolation, OhlcTrace, PYRAMID_FACTOR,
+    },
+    prelude::*,
+    structs::{Annotation, Annotations, Colormap, Marker, RenderJob, Scale},
+};
 
-use super::{AxisTick, RenderJobResult, Renderer, RendererOptions};
+use super::{
+    draw_list::{self, DrawList, DrawListCache, JobFingerprint, Primitive, Projection},
+    geometry,
+    gpu_bundle::{self, BuiltRow},
+    heatmap::{self, HeatmapLayer, HeatmapProgram},
+    legend::{legend_items, LegendLayout},
+    raise_order,
+    stroke::StrokeProgram,
+    text::TextRenderer,
+    trace_cache::{TraceCache, TraceKey},
+    y_extent, AxisTick, BrushHit, BundleEntry, BundleOptions, FeatureSet, HitIndex, HitResult,
+    PlayheadValue, PlotRect, RenderJobResult, Renderer, RendererError, RendererOptions,
+    DEFAULT_DOWN_COLOR, DEFAULT_MARKER_SIZE,
+};
 use serde::{Deserialize, Serialize};
 
+/// Directly drawn traces whose uploaded points are kept for the following frames
+const TRACE_CACHE_SIZE: usize = 16;
+
+/// Texels in a row of the palette, the `PALETTE_WIDTH` of the trace fragment shader as well.
+/// Well under any `MAX_TEXTURE_SIZE`, the palette grows by rows instead.
+const PALETTE_WIDTH: usize = 256;
+
+/// Points of a directly drawn trace uploaded for the following frames
+struct CachedTrace {
+    buffer: WebGlBuffer,
+    /// Points as uploaded, with x relative to `base`
+    data: Vec<(f32, f32)>,
+    base: RangePrec,
+}
+
 struct BufferEntry {
     points: usize,
+    /// Points `buffer` has room for, grown by doubling as the entry is extended
+    capacity: usize,
     handle: DataIdx,
     buffer: WebGlBuffer,
+    /// Attribute state drawing `buffer`, and the colormap values along it, in a single bind.
+    /// `None` on WebGL1, where the attributes are specified on every draw.
+    vao: Option<WebGlVertexArrayObject>,
     area_buffer: Option<WebGlBuffer>,
+    area_vao: Option<WebGlVertexArrayObject>,
     area_buffer_points: i32,
+    /// Set for band entries, whose `area_buffer` holds the fill to another trace
+    fill_alpha: Option<f32>,
 
     width: f32,
     color: [f32; 3],
-    points_mode: bool,
+    marker: Marker,
+    marker_size: f32,
+
+    /// Levels of detail, each decimated 4x more than the previous one
+    lods: Vec<LodLevel>,
+    /// Where the line of the trace breaks, kept to break the points it's extended by
+    gaps: GapRules,
+    /// Indices of the points of `buffer` starting a new line
+    breaks: Vec<usize>,
+    /// How faithfully `buffer` follows the trace
+    decimation: Decimation,
+    hit_index: HitIndex,
+
+    /// Description the entry was built from, needed to rebuild it when restacking
+    entry: BundleEntry,
+    /// Set for OHLC traces, whose `buffer` then only holds the close values
+    candles: Option<CandleBuffers>,
+    /// Texel of the palette holding the line color, counted row by row
+    palette_slot: usize,
+    /// Set for entries colored by another trace
+    colormap: Option<ColormapBuffers>,
 }
 
-struct BufferBundle {
-    from: RangePrec,
-    to: RangePrec,
-    buffers: Vec<BufferEntry>,
+impl BufferEntry {
+    /// Coarsest level of detail still dense enough for `px_width` pixels, along with the
+    /// indices of its points between `from` and `to` relative to the bundle start
+    fn select_lod(
+        &self,
+        visible: RangePrec,
+        px_width: u32,
+        (from, to): (DataPrec, DataPrec),
+    ) -> (
+        &WebGlBuffer,
+        Option<&WebGlVertexArrayObject>,
+        Range<usize>,
+        Decimation,
+    ) {
+        let needed = 2.0 * px_width as RangePrec;
+
+        match self
+            .lods
+            .iter()
+            .rev()
+            .find(|lod| lod.xs.len() as RangePrec * visible >= needed)
+        {
+            Some(lod) => (
+                &lod.buffer,
+                lod.vao.as_ref(),
+                geometry::visible_span(&lod.xs, |&x| x, from, to),
+                lod.decimation,
+            ),
+            None => (
+                &self.buffer,
+                self.vao.as_ref(),
+                geometry::visible_span(self.hit_index.points(), |p| p.0, from, to),
+                self.decimation,
+            ),
+        }
+    }
+}
+
+/// Candlestick geometry, split by direction as each is drawn in its own color
+struct CandleBuffers {
+    rising: CandleSide,
+    falling: CandleSide,
+    /// Lows and highs of the candles, for the reported extents
+    range: HitIndex,
+}
+
+struct CandleSide {
+    candles: usize,
+    /// Low to high segments, 2 vertices per candle
+    wicks: WebGlBuffer,
+    /// Open to close rectangles, 6 vertices per candle
+    bodies: WebGlBuffer,
+}
+
+/// Values coloring a line, normalized to `[0, 1]` and aligned with the points of its `buffer`
+struct ColormapBuffers {
+    values: WebGlBuffer,
+    points: usize,
+    texture: WebGlTexture,
+}
+
+/// Base quad of the markers, drawn once per point with the points as per-instance attributes
+struct MarkerQuad {
+    corners: WebGlBuffer,
+    /// Attribute state of the quad, the points are pointed at per draw
+    vao: WebGlVertexArrayObject,
+}
+
+impl MarkerQuad {
+    /// Quad at attribute 2, `None` on WebGL1 contexts, which can't instance
+    fn new(gl: &WebGl2RenderingContext) -> Option<Self> {
+        if !gl.is_instance_of::<WebGl2RenderingContext>() {
+            return None;
+        }
+
+        let corners = gl.create_buffer()?;
+        let vao = gl.create_vertex_array()?;
+
+        gl.bind_vertex_array(Some(&vao));
+        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&corners));
+        webgl_utils::upload(
+            gl,
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            &[[-1.0f32, -1.0], [1.0, -1.0], [-1.0, 1.0], [1.0, 1.0]],
+            WebGl2RenderingContext::STATIC_DRAW,
+        );
+        gl.vertex_attrib_pointer_with_i32(2, 2, WebGl2RenderingContext::FLOAT, false, 0, 0);
+        gl.enable_vertex_attrib_array(2);
+        // the points advance once per marker rather than per corner
+        gl.vertex_attrib_divisor(0, 1);
+        gl.vertex_attrib_divisor(1, 1);
+        gl.bind_vertex_array(None);
+
+        Some(Self { corners, vao })
+    }
+
+    fn delete(&self, gl: &WebGl2RenderingContext) {
+        gl.delete_vertex_array(Some(&self.vao));
+        gl.delete_buffer(Some(&self.corners));
+    }
+}
+
+/// A coarser copy of a `BufferEntry`'s line data
+struct LodLevel {
+    buffer: WebGlBuffer,
+    vao: Option<WebGlVertexArrayObject>,
+    /// X of the points of `buffer` relative to the bundle start, ascending, to find the
+    /// visible ones
+    xs: Vec<DataPrec>,
+    decimation: Decimation,
+}
+
+type BufferBundle = gpu_bundle::BufferBundle<BufferEntry>;
+type PendingBundle = gpu_bundle::PendingBundle<BufferEntry>;
+
+impl BuiltRow for BufferEntry {
+    fn entry(&self) -> &BundleEntry {
+        &self.entry
+    }
+}
+
+/// What a heatmap was uploaded from, to upload it again into a restored context
+struct HeatmapSource {
+    values: Vec<f32>,
+    cols: usize,
+    x: (RangePrec, RangePrec),
+    y: (RangePrec, RangePrec),
+    colormap: Colormap,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ContextStatus {
+    Live,
+    Lost,
+    /// Restored by the browser, with none of the resources rebuilt yet
+    Restored,
+}
+
+/// Follows the loss and restoration of the shared canvas' context, e.g. when the GPU process
+/// restarts, which takes all of its buffers, textures and programs along
+struct ContextWatch {
+    status: Rc<Cell<ContextStatus>>,
+    canvas: OffscreenCanvas,
+    on_lost: Closure<dyn FnMut(Event)>,
+    on_restored: Closure<dyn FnMut(Event)>,
+}
+
+impl ContextWatch {
+    fn new(canvas: &OffscreenCanvas) -> Result<Self, JsValue> {
+        let status = Rc::new(Cell::new(ContextStatus::Live));
+
+        let on_lost = {
+            let status = status.clone();
+            Closure::<dyn FnMut(Event)>::new(move |event: Event| {
+                // the browser only restores contexts whose loss was prevented from being final
+                event.prevent_default();
+                status.set(ContextStatus::Lost);
+            })
+        };
+        let on_restored = {
+            let status = status.clone();
+            Closure::<dyn FnMut(Event)>::new(move |_: Event| status.set(ContextStatus::Restored))
+        };
+
+        canvas.add_event_listener_with_callback(
+            "webglcontextlost",
+            on_lost.as_ref().unchecked_ref(),
+        )?;
+        canvas.add_event_listener_with_callback(
+            "webglcontextrestored",
+            on_restored.as_ref().unchecked_ref(),
+        )?;
+
+        Ok(Self {
+            status,
+            canvas: canvas.clone(),
+            on_lost,
+            on_restored,
+        })
+    }
+}
+
+impl Drop for ContextWatch {
+    fn drop(&mut self) {
+        let _ = self.canvas.remove_event_listener_with_callback(
+            "webglcontextlost",
+            self.on_lost.as_ref().unchecked_ref(),
+        );
+        let _ = self.canvas.remove_event_listener_with_callback(
+            "webglcontextrestored",
+            self.on_restored.as_ref().unchecked_ref(),
+        );
+    }
 }
 
 pub struct WebGlRenderer {
+    /// Size of the selected chart
     width: u32,
     height: u32,
     is_area: bool,
     line_width_limit: f32,
+    line_join: geometry::LineJoin,
+    line_cap: geometry::LineCap,
+    /// Whether the context is multisampled, lines are tessellated and smoothed otherwise
+    msaa: bool,
+    /// Whether float textures can be sampled with linear filtering
+    float_textures: bool,
 
     _canvas: OffscreenCanvas,
-    _present_canvas: OffscreenCanvas,
+    /// Present canvases of the charts sharing the context, keyed by their id
+    charts: HashMap<u32, OffscreenCanvas>,
+    /// Chart rendered to and presented next
+    chart: u32,
+    /// Canvases the charts present their crosshair overlays into
+    overlays: HashMap<u32, OffscreenCanvas>,
+    /// Threshold lines and regions of the charts
+    annotations: HashMap<u32, Annotations>,
     context: WebGl2RenderingContext,
+    context_watch: ContextWatch,
     trace_buffer: WebGlBuffer,
 
     tp_size_pos: WebGlUniformLocation,
     tp_origin_pos: WebGlUniformLocation,
+    tp_origin_lo_pos: WebGlUniformLocation,
     tp_color_pos: WebGlUniformLocation,
     tp_transform_pos: WebGlUniformLocation,
-    tp_csoffset_pos: WebGlUniformLocation,
+    tp_scales_pos: WebGlUniformLocation,
+    tp_linthresh_pos: WebGlUniformLocation,
+    tp_xbase_pos: WebGlUniformLocation,
+    tp_point_size_pos: WebGlUniformLocation,
+    tp_marker_pos: WebGlUniformLocation,
+    tp_marker_px_pos: WebGlUniformLocation,
+    tp_color_index_pos: WebGlUniformLocation,
+    tp_colormapped_pos: WebGlUniformLocation,
+    tp_colormap_pos: WebGlUniformLocation,
+    tp_palette_pos: WebGlUniformLocation,
+    tp_palette_rows_pos: WebGlUniformLocation,
+    tp_instanced_pos: WebGlUniformLocation,
+    tp_viewport_px_pos: WebGlUniformLocation,
+    tp_marker_rotation_pos: WebGlUniformLocation,
     trace_program: WebGlProgram,
+    /// Quad the markers are instanced from, `None` on WebGL1 where they're point sprites,
+    /// limited in size by the driver
+    marker_quad: Option<MarkerQuad>,
 
     ap_resolution_pos: WebGlUniformLocation,
     ap_color_pos: WebGlUniformLocation,
     axes_program: WebGlProgram,
+    text: TextRenderer,
+    heatmap_program: HeatmapProgram,
+    stroke_program: StrokeProgram,
 
     bundles_counter: usize,
     bundles: HashMap<usize, BufferBundle>,
+    /// Bundles left out of frames by `set_bundle_visible`, their buffers kept uploaded
+    hidden_bundles: HashSet<usize>,
+    /// Bundles being built by `step_bundle`, under the handles they'll be put under
+    pending: HashMap<usize, PendingBundle>,
+    heatmaps: HashMap<usize, HeatmapLayer>,
+    heatmap_sources: HashMap<usize, HeatmapSource>,
+    /// Whether `suspend` released the bundles, which a restored context leaves released
+    suspended: bool,
+    /// Bumped on every change to the bundles
+    generation: u64,
+    /// Axes and grids of recent frames, the traces being drawn from buffers of the bundles
+    overlay_cache: DrawListCache,
+    trace_cache: TraceCache<Rc<CachedTrace>>,
+
+    /// Line colors of all bundle entries, so that switching between them is a single uniform.
+    /// Rows of `PALETTE_WIDTH` texels, reallocated only once the entries outgrow them.
+    palette: Option<WebGlTexture>,
+    palette_rows: usize,
+    /// Colors last uploaded into the palette, which most changes to bundles keep
+    palette_colors: Vec<u8>,
+    palette_generation: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -78,27 +390,70 @@ impl WebGlRenderer {
         })
         .unwrap();
 
-        let context = shared_canvas
-            .get_context_with_context_options("webgl2", &opts)
-            .unwrap()
-            .unwrap()
-            .dyn_into::<WebGl2RenderingContext>()?;
+        let context = Self::acquire_context(&shared_canvas, &opts)?;
 
         let vert_shader = webgl_utils::compile_shader(
             &context,
             WebGl2RenderingContext::VERTEX_SHADER,
             r#"
             attribute vec2 aVertexPosition;
+            attribute float aColorValue;
+            // corner of the base quad markers are instanced from, in -1..1
+            attribute vec2 aCorner;
 
             uniform vec2 transform;
             uniform vec2 origin;
+            // remainder of the x origin too fine for `origin`, keeping deep zooms steady
+            uniform float origin_lo;
             uniform vec2 size;
+            uniform ivec2 scales;
+            uniform vec2 linthresh;
+            uniform float xbase;
+
+            uniform float point_size;
+            // set when markers are quads instanced over the points rather than point sprites
+            uniform float instanced;
+            uniform vec2 viewport_px;
+            uniform float marker_rotation;
+
+            varying float color_value;
+            varying vec2 corner;
+            varying float quad;
+
+            float apply_scale(float val, int scale, float c) {
+                if (scale == 1) {
+                    return log(max(val, 1e-30)) / log(10.0);
+                }
+                if (scale == 2) {
+                    return sign(val) * log(1.0 + abs(val) / c) / log(10.0);
+                }
 
-            uniform vec2 csoffset;
+                return val;
+            }
 
             void main() {
-                gl_Position = vec4(csoffset + vec2(-1,-1) + vec2(2,2) * (aVertexPosition * vec2(1,transform.x) + vec2(0, transform.y) - origin) / size, 0, 1);
-                gl_PointSize = 8.0;
+                vec2 pos = aVertexPosition * vec2(1,transform.x) + vec2(0, transform.y);
+
+                // x is stored relative to xbase, non-linear scales need the absolute value
+                if (scales.x != 0) {
+                    pos.x = apply_scale(pos.x + xbase, scales.x, linthresh.x);
+                }
+                pos.y = apply_scale(pos.y, scales.y, linthresh.y);
+
+                vec2 rel = pos - origin;
+                rel.x -= origin_lo;
+
+                gl_Position = vec4(vec2(-1,-1) + vec2(2,2) * rel / size, 0, 1);
+                gl_PointSize = point_size;
+                color_value = aColorValue;
+
+                // turned in pixels, before the viewport's aspect ratio stretches it
+                float s = sin(marker_rotation);
+                float c = cos(marker_rotation);
+                vec2 turned = vec2(c * aCorner.x - s * aCorner.y, s * aCorner.x + c * aCorner.y);
+                gl_Position.xy += instanced * turned * point_size / viewport_px;
+                corner = aCorner;
+                quad = instanced;
             }
             "#,
         )?;
@@ -108,15 +463,85 @@ impl WebGlRenderer {
             WebGl2RenderingContext::FRAGMENT_SHADER,
             r#"
             precision mediump float;
+            #define PALETTE_WIDTH 256.0
             uniform vec4 color;
 
+            // texel of the palette holding the color, negative to use `color` instead
+            uniform highp float color_index;
+            uniform sampler2D palette;
+            uniform float palette_rows;
+
+            // set to take the color from `colormap` at the value of the third data column
+            uniform float colormapped;
+            uniform sampler2D colormap;
+            varying float color_value;
+            varying vec2 corner;
+            varying float quad;
+
+            // 0 for lines and fills, otherwise the Marker drawn as a point sprite
+            uniform int marker;
+            uniform float marker_px;
+
+            float marker_distance(vec2 p) {
+                if (marker == 2) {
+                    return length(p) - 1.0;
+                }
+                if (marker == 3) {
+                    vec2 q = vec2(abs(p.x), -p.y);
+                    return max(-q.y - 0.5, 0.866 * q.x + 0.5 * q.y - 0.5);
+                }
+                if (marker == 4) {
+                    return max(min(abs(p.x), abs(p.y)) - 0.25, max(abs(p.x), abs(p.y)) - 1.0);
+                }
+                if (marker == 5) {
+                    return (abs(p.x) + abs(p.y) - 1.0) * 0.7071;
+                }
+
+                return max(abs(p.x), abs(p.y)) - 1.0;
+            }
+
+            vec4 base_color() {
+                if (colormapped > 0.5) {
+                    return texture2D(colormap, vec2(color_value, 0.5));
+                }
+                if (color_index < 0.0) {
+                    return color;
+                }
+
+                highp float row = floor(color_index / PALETTE_WIDTH);
+                highp float column = color_index - row * PALETTE_WIDTH;
+                return texture2D(
+                    palette,
+                    vec2((column + 0.5) / PALETTE_WIDTH, (row + 0.5) / palette_rows)
+                );
+            }
+
             void main() {
-                gl_FragColor = color;
+                if (marker == 0) {
+                    gl_FragColor = base_color();
+                    return;
+                }
+
+                // point sprite coordinates grow downwards, unlike the quad's corners
+                vec2 p = quad > 0.5 ? vec2(corner.x, -corner.y) : gl_PointCoord * 2.0 - 1.0;
+                float d = marker_distance(p);
+                float alpha = clamp(0.5 - d * marker_px * 0.5, 0.0, 1.0);
+
+                if (alpha <= 0.0) {
+                    discard;
+                }
+
+                gl_FragColor = base_color() * alpha;
             }
             "#,
         )?;
 
-        let program = webgl_utils::link_program(&context, &vert_shader, &frag_shader)?;
+        let program = webgl_utils::link_program_with_attributes(
+            &context,
+            &vert_shader,
+            &frag_shader,
+            &["aVertexPosition", "aColorValue", "aCorner"],
+        )?;
 
         let axes_program = {
             let vert_shader = webgl_utils::compile_shader(
@@ -152,21 +577,62 @@ impl WebGlRenderer {
         let width_range = context
             .get_parameter(WebGl2RenderingContext::ALIASED_LINE_WIDTH_RANGE)?
             .dyn_into::<Float32Array>()?;
+        let context_watch = ContextWatch::new(&shared_canvas)?;
 
-        Ok(WebGlRenderer {
+        let renderer = WebGlRenderer {
             width: present_canvas.width(),
             height: present_canvas.height(),
             _canvas: shared_canvas,
-            _present_canvas: present_canvas,
+            charts: HashMap::from([(0, present_canvas)]),
+            chart: 0,
+            overlays: HashMap::new(),
+            annotations: HashMap::new(),
             is_area: ropts.area_chart,
             line_width_limit: width_range.get_index(1),
+            line_join: ropts.line_join,
+            line_cap: ropts.line_cap,
+            msaa: context
+                .get_parameter(WebGl2RenderingContext::SAMPLES)?
+                .as_f64()
+                .is_some_and(|samples| samples > 0.0),
+            // WebGL1 has no float textures without an extension of its own
+            float_textures: context.get_extension("OES_texture_float_linear")?.is_some()
+                && (context.is_instance_of::<WebGl2RenderingContext>()
+                    || context.get_extension("OES_texture_float")?.is_some()),
 
             tp_origin_pos: context.get_uniform_location(&program, "origin").unwrap(),
+            tp_origin_lo_pos: context.get_uniform_location(&program, "origin_lo").unwrap(),
             tp_size_pos: context.get_uniform_location(&program, "size").unwrap(),
             tp_color_pos: context.get_uniform_location(&program, "color").unwrap(),
             tp_transform_pos: context.get_uniform_location(&program, "transform").unwrap(),
-            tp_csoffset_pos: context.get_uniform_location(&program, "csoffset").unwrap(),
+            tp_scales_pos: context.get_uniform_location(&program, "scales").unwrap(),
+            tp_linthresh_pos: context.get_uniform_location(&program, "linthresh").unwrap(),
+            tp_xbase_pos: context.get_uniform_location(&program, "xbase").unwrap(),
+            tp_point_size_pos: context
+                .get_uniform_location(&program, "point_size")
+                .unwrap(),
+            tp_marker_pos: context.get_uniform_location(&program, "marker").unwrap(),
+            tp_marker_px_pos: context.get_uniform_location(&program, "marker_px").unwrap(),
+            tp_color_index_pos: context
+                .get_uniform_location(&program, "color_index")
+                .unwrap(),
+            tp_colormapped_pos: context
+                .get_uniform_location(&program, "colormapped")
+                .unwrap(),
+            tp_colormap_pos: context.get_uniform_location(&program, "colormap").unwrap(),
+            tp_palette_pos: context.get_uniform_location(&program, "palette").unwrap(),
+            tp_palette_rows_pos: context
+                .get_uniform_location(&program, "palette_rows")
+                .unwrap(),
+            tp_instanced_pos: context.get_uniform_location(&program, "instanced").unwrap(),
+            tp_viewport_px_pos: context
+                .get_uniform_location(&program, "viewport_px")
+                .unwrap(),
+            tp_marker_rotation_pos: context
+                .get_uniform_location(&program, "marker_rotation")
+                .unwrap(),
             trace_program: program,
+            marker_quad: MarkerQuad::new(&context),
 
             ap_resolution_pos: context
                 .get_uniform_location(&axes_program, "resolution")
@@ -175,543 +641,2237 @@ impl WebGlRenderer {
                 .get_uniform_location(&axes_program, "color")
                 .unwrap(),
             axes_program,
+            text: TextRenderer::new(&context)?,
+            heatmap_program: HeatmapProgram::new(&context)?,
+            stroke_program: StrokeProgram::new(&context)?,
 
             trace_buffer: context.create_buffer().unwrap(),
+            context_watch,
             context,
 
             bundles_counter: 0,
             bundles: HashMap::new(),
-        })
-    }
+            hidden_bundles: HashSet::new(),
+            pending: HashMap::new(),
+            heatmaps: HashMap::new(),
+            heatmap_sources: HashMap::new(),
+            suspended: false,
+            generation: 0,
+            overlay_cache: DrawListCache::new(8),
+            trace_cache: TraceCache::new(TRACE_CACHE_SIZE),
+
+            palette: None,
+            palette_rows: 0,
+            palette_colors: Vec::new(),
+            palette_generation: None,
+        };
+        renderer.fit_shared_canvas();
 
-    pub fn clear(&self) {
-        self.context.clear_color(0.0, 0.0, 0.0, 0.0);
-        self.context.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+        Ok(renderer)
     }
 
-    pub fn render_axes(&self, job: &RenderJob, x_ticks: &[AxisTick], y_ticks: &[AxisTick]) {
-        let gl = &self.context;
-
-        gl.viewport(0, 0, self.width as i32, self.height as i32);
+    /// WebGL2 context of `canvas`, with the `webgl1` feature falling back to WebGL1 if needed
+    fn acquire_context(
+        canvas: &OffscreenCanvas,
+        opts: &JsValue,
+    ) -> Result<WebGl2RenderingContext, JsValue> {
+        if let Some(context) = canvas.get_context_with_context_options("webgl2", opts)? {
+            return context
+                .dyn_into::<WebGl2RenderingContext>()
+                .map_err(JsValue::from);
+        }
 
-        gl.use_program(Some(&self.axes_program));
-        gl.uniform2f(
-            Some(&self.ap_resolution_pos),
-            self.width as f32,
-            self.height as f32,
-        );
-        gl.uniform4f(Some(&self.ap_color_pos), 0.3, 0.3, 0.3, 1.0);
-        gl.bind_buffer(
-            WebGl2RenderingContext::ARRAY_BUFFER,
-            Some(&self.trace_buffer),
-        );
-        gl.line_width(2.0);
+        #[cfg(feature = "webgl1")]
+        if let Some(context) = canvas.get_context_with_context_options("webgl", opts)? {
+            // only the WebGL1 subset of the API is used and the bindings call methods by name,
+            // so the context can stand in for a WebGL2 one
+            let context = context.unchecked_into::<WebGl2RenderingContext>();
 
-        let graph_left = (job.y_label_space + job.margin) as f32;
-        let graph_bottom = (job.x_label_space + job.margin) as f32;
-        let graph_top = (self.height - job.margin) as f32;
-        let graph_right = (self.width - job.margin) as f32;
+            for extension in ["OES_element_index_uint", "OES_standard_derivatives"] {
+                context.get_extension(extension)?;
+            }
 
-        unsafe {
-            let data: Vec<f32> = vec![
-                graph_left - 1.0,
-                graph_top,
-                graph_left - 1.0,
-                graph_bottom - 1.0,
-                graph_right,
-                graph_bottom - 1.0,
-            ];
-
-            let vert_array = js_sys::Float32Array::view(&data);
-
-            gl.buffer_data_with_array_buffer_view(
-                WebGl2RenderingContext::ARRAY_BUFFER,
-                &vert_array,
-                WebGl2RenderingContext::STATIC_DRAW,
-            );
+            return Ok(context);
         }
 
-        gl.vertex_attrib_pointer_with_i32(0, 2, WebGl2RenderingContext::FLOAT, false, 0, 0);
-        gl.enable_vertex_attrib_array(0);
-        gl.draw_arrays(WebGl2RenderingContext::LINE_STRIP, 0, 3);
+        Err(JsValue::from_str("WebGL2 is not available"))
+    }
 
-        const TICK_LEN: f32 = 4.0;
-        let points = (x_ticks.len() + y_ticks.len()) * 2;
+    /// Whether the resources of the context are gone, until `restore_context` rebuilds them
+    fn lost(&self) -> bool {
+        self.context_watch.status.get() != ContextStatus::Live || self.context.is_context_lost()
+    }
 
-        fn lerp(from: f32, to: f32, val: f32) -> f32 {
-            from + (to - from) * val
+    fn ensure_context(&self) -> Result<(), JsValue> {
+        match self.lost() {
+            true => Err(JsValue::from_str(
+                "The WebGL context is lost, the renderer is rebuilt once it's restored",
+            )),
+            false => Ok(()),
         }
+    }
 
-        unsafe {
-            let mut data: Vec<f32> = Vec::with_capacity(2 * points);
+    /// Grows the shared canvas to hold a full frame. Other renderers draw through it as well,
+    /// so it never shrinks. Resizing drops the drawing buffer's content, not the GL resources.
+    fn fit_shared_canvas(&self) {
+        let (width, height) = (self._canvas.width(), self._canvas.height());
 
-            for tick in x_ticks {
-                data.push(lerp(graph_left, graph_right, tick.pos as f32));
-                data.push(graph_bottom);
-                data.push(lerp(graph_left, graph_right, tick.pos as f32));
-                data.push(graph_bottom - TICK_LEN);
-            }
+        if width < self.width || height < self.height {
+            self._canvas.set_width(width.max(self.width));
+            self._canvas.set_height(height.max(self.height));
+        }
+
+        self.context
+            .viewport(0, 0, self.width as i32, self.height as i32);
+    }
 
-            for tick in y_ticks {
-                data.push(graph_left);
-                data.push(lerp(graph_bottom, graph_top, tick.pos as f32));
-                data.push(graph_left - TICK_LEN);
-                data.push(lerp(graph_bottom, graph_top, tick.pos as f32));
+    /// Drops the unfinished build of `bundle` and the entries it has built, leaving a bundle
+    /// under the same handle alone
+    fn abandon_pending(&mut self, bundle: usize) {
+        if let Some(pending) = self.pending.remove(&bundle) {
+            for row in pending.built.into_iter().flatten() {
+                WebGlRenderer::delete_entry(&self.context, row);
             }
+        }
+    }
 
-            let vert_array = js_sys::Float32Array::view(&data);
+    /// Clears the selected chart's region of the shared canvas to `color`, or transparent
+    /// without one, leaving the rest untouched
+    pub fn clear(&self, color: Option<[u8; 3]>) {
+        let gl = &self.context;
+        let [r, g, b, a] = match color {
+            Some(rgb) => [rgb[0], rgb[1], rgb[2], 255].map(|c| c as f32 / 255.0),
+            None => [0.0; 4],
+        };
 
-            gl.buffer_data_with_array_buffer_view(
-                WebGl2RenderingContext::ARRAY_BUFFER,
-                &vert_array,
-                WebGl2RenderingContext::STATIC_DRAW,
-            );
-        }
+        gl.enable(WebGl2RenderingContext::SCISSOR_TEST);
+        gl.scissor(0, 0, self.width as i32, self.height as i32);
+        gl.clear_color(r, g, b, a);
+        gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+        gl.disable(WebGl2RenderingContext::SCISSOR_TEST);
+    }
 
-        gl.draw_arrays(WebGl2RenderingContext::LINES, 0, points as i32);
+    pub fn render_axes(
+        &self,
+        job: &RenderJob,
+        x_ticks: &[AxisTick],
+        y_ticks: &[AxisTick],
+        y2_ticks: &[AxisTick],
+    ) {
+        let mut list = DrawList::new(self.width, self.height);
+        list.push_axes(job, x_ticks, y_ticks, y2_ticks, 0.0);
+
+        self.execute(&list);
     }
 
     pub fn render_grid(&self, job: &RenderJob, x_ticks: &[AxisTick], y_ticks: &[AxisTick]) {
+        let mut list = DrawList::new(self.width, self.height);
+        list.push_grid(job, x_ticks, y_ticks);
+
+        self.execute(&list);
+    }
+
+    /// Draws the primitives of `list`, shaped markers are approximated by their quads
+    pub fn execute(&self, list: &DrawList) {
         let gl = &self.context;
 
-        let width = (self.width - job.margin * 2 - job.y_label_space) as i32;
-        let height = (self.height - job.margin * 2 - job.x_label_space) as i32;
+        gl.viewport(0, 0, list.width as i32, list.height as i32);
+
+        for primitive in &list.primitives {
+            match primitive {
+                Primitive::Polyline {
+                    points,
+                    color,
+                    width,
+                } => {
+                    gl.line_width(*width);
+                    self.draw_pixels(WebGl2RenderingContext::LINE_STRIP, points, color, list);
+                }
+                Primitive::Segments {
+                    points,
+                    color,
+                    width,
+                } => {
+                    gl.line_width(*width);
+                    self.draw_pixels(WebGl2RenderingContext::LINES, points, color, list);
+                }
+                Primitive::Triangles { points, color } => {
+                    self.draw_pixels(WebGl2RenderingContext::TRIANGLES, points, color, list);
+                }
+                Primitive::Quads {
+                    rects,
+                    color,
+                    rotation,
+                    ..
+                } => {
+                    let (sin, cos) = rotation.to_radians().sin_cos();
+                    let points: Vec<(f32, f32)> = rects
+                        .iter()
+                        .flat_map(|&[x, y, w, h]| {
+                            let (cx, cy, rx, ry) = (x + w / 2.0, y + h / 2.0, w / 2.0, h / 2.0);
+                            let corner = |dx: f32, dy: f32| {
+                                (cx + dx * cos - dy * sin, cy + dx * sin + dy * cos)
+                            };
+
+                            [
+                                corner(-rx, -ry),
+                                corner(rx, -ry),
+                                corner(-rx, ry),
+                                corner(-rx, ry),
+                                corner(rx, -ry),
+                                corner(rx, ry),
+                            ]
+                        })
+                        .collect();
+
+                    self.draw_pixels(WebGl2RenderingContext::TRIANGLES, &points, color, list);
+                }
+                Primitive::Glyphs { labels, color } => {
+                    let color = [color[0], color[1], color[2]];
+                    self.text.draw(gl, labels, list.width, list.height, color);
+                }
+                Primitive::Clip(Some(rect)) => {
+                    gl.enable(WebGl2RenderingContext::SCISSOR_TEST);
+                    gl.scissor(
+                        rect.left as i32,
+                        rect.bottom as i32,
+                        rect.width as i32,
+                        rect.height as i32,
+                    );
+                }
+                Primitive::Clip(None) => gl.disable(WebGl2RenderingContext::SCISSOR_TEST),
+            }
+        }
+    }
+
+    fn draw_pixels(
+        &self,
+        mode: u32,
+        points: &[(f32, f32)],
+        color: &draw_list::Color,
+        list: &DrawList,
+    ) {
+        if points.is_empty() {
+            return;
+        }
 
-        let data_width = job.x_to - job.x_from;
-        let data_height = job.y_to - job.y_from;
+        let gl = &self.context;
+        let vertices: Vec<[f32; 2]> = points.iter().map(|&(x, y)| [x, y]).collect();
 
-        gl.viewport(
-            (job.margin + job.y_label_space) as i32,
-            (job.margin + job.x_label_space) as i32,
+        gl.use_program(Some(&self.axes_program));
+        gl.uniform2f(
+            Some(&self.ap_resolution_pos),
+            list.width as f32,
+            list.height as f32,
+        );
+        gl.uniform4f(
+            Some(&self.ap_color_pos),
+            color[0],
+            color[1],
+            color[2],
+            color[3],
+        );
+
+        gl.bind_buffer(
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            Some(&self.trace_buffer),
+        );
+        webgl_utils::upload(
+            gl,
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            &vertices,
+            WebGl2RenderingContext::STATIC_DRAW,
+        );
+        gl.vertex_attrib_pointer_with_i32(0, 2, WebGl2RenderingContext::FLOAT, false, 0, 0);
+        gl.enable_vertex_attrib_array(0);
+        gl.draw_arrays(mode, 0, points.len() as i32);
+    }
+
+    /// Strokes the canvas pixel polyline `points` within `rect`, which has to be the viewport,
+    /// leaving the trace program in use
+    fn draw_stroke(&self, points: &[(f32, f32)], width: f32, color: [f32; 3], rect: &PlotRect) {
+        self.stroke_program.draw(
+            &self.context,
+            points,
             width,
-            height,
+            self.line_join,
+            self.line_cap,
+            [color[0], color[1], color[2], 1.0],
+            rect,
         );
+        self.context.use_program(Some(&self.trace_program));
+    }
 
-        gl.use_program(Some(&self.trace_program));
-        gl.uniform2f(Some(&self.tp_origin_pos), 0.0, 0.0);
-        gl.uniform2f(Some(&self.tp_size_pos), width as f32, height as f32);
-        gl.uniform2f(Some(&self.tp_transform_pos), 1.0, 0.0);
+    /// Draws the "now" marker as a vertical line, or with `shade` only the region past it
+    pub fn render_now_marker(&self, job: &RenderJob, now: RangePrec, shade: bool) {
+        let gl = &self.context;
 
-        if job.dark_mode {
-            gl.uniform4f(Some(&self.tp_color_pos), 0.3, 0.3, 0.3, 1.0);
-        } else {
-            gl.uniform4f(Some(&self.tp_color_pos), 0.85, 0.85, 0.85, 1.0);
+        let rect = PlotRect::of(job, self.width, self.height);
+        let graph_left = rect.left as f32;
+        let graph_bottom = rect.bottom as f32;
+        let graph_top = rect.top() as f32;
+        let graph_right = rect.right() as f32;
+
+        let rel = job.x_axis_scale().position(now, job.x_from, job.x_to) as f32;
+        if !rel.is_finite() || rel > 1.0 || (!shade && rel < 0.0) {
+            return;
         }
 
-        gl.line_width(1.0);
+        let x = (graph_left + (graph_right - graph_left) * rel.max(0.0)).round() - 0.5;
 
+        gl.viewport(0, 0, self.width as i32, self.height as i32);
+        gl.use_program(Some(&self.axes_program));
+        gl.uniform2f(
+            Some(&self.ap_resolution_pos),
+            self.width as f32,
+            self.height as f32,
+        );
         gl.bind_buffer(
             WebGl2RenderingContext::ARRAY_BUFFER,
             Some(&self.trace_buffer),
         );
-        let points = (x_ticks.len() + y_ticks.len()) * 2;
-
-        unsafe {
-            let mut data: Vec<f32> = Vec::with_capacity(2 * points);
-
-            for tick in x_ticks {
-                let x = ((width as RangePrec * (tick.val - job.x_from) / data_width) as f32 + 0.5)
-                    .round()
-                    - 0.5;
 
-                data.push(x);
-                data.push(0.0);
-                data.push(x);
-                data.push(height as f32);
+        let (mode, points) = if shade {
+            // premultiplied alpha, same as the area fills
+            if job.dark_background() {
+                gl.uniform4f(Some(&self.ap_color_pos), 0.05, 0.05, 0.05, 0.1);
+            } else {
+                gl.uniform4f(Some(&self.ap_color_pos), 0.0, 0.0, 0.0, 0.06);
             }
 
-            for tick in y_ticks {
-                let y = ((height as RangePrec * (tick.val - job.y_from) / data_height) as f32
-                    + 0.5)
-                    .round()
-                    - 0.5;
-
-                data.push(0.0);
-                data.push(y);
-                data.push(width as f32);
-                data.push(y);
+            (
+                WebGl2RenderingContext::TRIANGLE_STRIP,
+                vec![
+                    x,
+                    graph_bottom,
+                    x,
+                    graph_top,
+                    graph_right,
+                    graph_bottom,
+                    graph_right,
+                    graph_top,
+                ],
+            )
+        } else {
+            if job.dark_background() {
+                gl.uniform4f(Some(&self.ap_color_pos), 0.8, 0.8, 0.8, 1.0);
+            } else {
+                gl.uniform4f(Some(&self.ap_color_pos), 0.2, 0.2, 0.2, 1.0);
             }
+            gl.line_width(1.0);
 
-            let vert_array = js_sys::Float32Array::view(&data);
+            (
+                WebGl2RenderingContext::LINES,
+                vec![x, graph_bottom, x, graph_top],
+            )
+        };
 
-            gl.buffer_data_with_array_buffer_view(
-                WebGl2RenderingContext::ARRAY_BUFFER,
-                &vert_array,
-                WebGl2RenderingContext::STATIC_DRAW,
-            );
-        }
+        webgl_utils::upload(
+            gl,
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            &points,
+            WebGl2RenderingContext::STATIC_DRAW,
+        );
 
         gl.vertex_attrib_pointer_with_i32(0, 2, WebGl2RenderingContext::FLOAT, false, 0, 0);
         gl.enable_vertex_attrib_array(0);
-        gl.draw_arrays(WebGl2RenderingContext::LINES, 0, points as i32);
+        gl.draw_arrays(mode, 0, points.len() as i32 / 2);
     }
 
-    fn allocate_bundle_entry(
+    /// Draws the now marker of `job` and the shading past it into the cleared overlay layer
+    fn render_now_layer(&self, job: &RenderJob) -> Result<(), JsValue> {
+        if let Some(now) = job.now {
+            if job.shade_future {
+                self.render_now_marker(job, now, true);
+            }
+            self.render_now_marker(job, now, false);
+            webgl_utils::check(&self.context, "now marker", None)?;
+        }
+
+        Ok(())
+    }
+
+    /// Builds the buffers of `entries` in their order, stacking each non-zero `stack_group`
+    fn build_entries(
         context: &WebGl2RenderingContext,
         module: &DataModule,
         from: RangePrec,
         to: RangePrec,
-        entry: &super::BundleEntry,
-        area_add: Option<&mut Vec<(f32, f32)>>,
-    ) -> Result<BufferEntry, JsValue> {
-        let buffer =
-            match context.create_buffer() {
-                Some(b) => b,
-                _ => return Result::Err(JsValue::from_str(
-                    "Failed to allocate a buffer, perhaps the WebGL context has been destroyed.",
-                )),
-            };
+        opts: BundleOptions,
+        entries: &[BundleEntry],
+    ) -> Result<Vec<BufferEntry>, JsValue> {
+        let mut built: Vec<Option<BufferEntry>> = entries.iter().map(|_| None).collect();
+
+        let mut stacked: Vec<usize> = (0..entries.len())
+            .filter(|&i| entries[i].stack_group != 0)
+            .filter(|&i| module.get_ohlc_trace(entries[i].handle).is_none())
+            .collect();
+        stacked.sort_by_key(|&i| (entries[i].stack_group, entries[i].stack_order));
+
+        // the running top of the current group, its x values taken from the group's first entry
+        let mut area: Option<(u32, Vec<(f32, f32)>)> = None;
+
+        for i in stacked {
+            let entry = &entries[i];
+
+            if area.as_ref().map(|(group, _)| *group) != Some(entry.stack_group) {
+                let base = module
+                    .get_trace(entry.handle)
+                    .map(|t| {
+                        t.get_data_with_origin(from, to, from, 0.0)
+                            .map(|(x, _)| (x, 0.0))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                area = Some((entry.stack_group, base));
+            }
 
-        let mut data: Vec<(f32, f32)> = module
-            .get_trace(entry.handle)
-            .map(|t| t.get_data_with_origin(from, to, from, 0.0).collect())
-            .expect("Invalid entry handle during bundling");
+            built[i] = Some(Self::allocate_bundle_entry(
+                context,
+                module,
+                from,
+                to,
+                entry,
+                opts,
+                area.as_mut().map(|(_, area)| area),
+            )?);
+            webgl_utils::check(context, "bundle upload", Some(entry.handle))?;
+        }
 
-        if let Some(ref area) = area_add {
-            for (data, area) in data.iter_mut().zip(area.deref()) {
-                data.1 += area.1;
+        for (i, entry) in entries.iter().enumerate() {
+            if built[i].is_none() {
+                built[i] = Some(Self::allocate_bundle_entry(
+                    context, module, from, to, entry, opts, None,
+                )?);
+                webgl_utils::check(context, "bundle upload", Some(entry.handle))?;
             }
         }
 
-        context.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&buffer));
-        context.buffer_data_with_array_buffer_view(
-            WebGl2RenderingContext::ARRAY_BUFFER,
-            unsafe {
-                &js_sys::Float32Array::view(core::slice::from_raw_parts(
-                    std::mem::transmute(data.as_ptr()),
-                    data.len() * 2,
-                ))
-            },
-            WebGl2RenderingContext::STATIC_DRAW,
-        );
-
-        let area_buffer = if let Some(area) = area_add {
-            let a_buffer = match context.create_buffer() {
-                Some(b) => b,
-                _ => return Result::Err(JsValue::from_str(
-                    "Failed to allocate a buffer, perhaps the WebGL context has been destroyed.",
-                )),
-            };
-
-            let mut to_push: Vec<(f32, f32)> = vec![area[0]];
+        Ok(built.into_iter().map(Option::unwrap).collect())
+    }
 
-            for i in 0..(area.len() - 1) {
-                let (x0, _) = area[i];
-                let (x1, prev1) = area[i + 1];
-                let (_, y0) = data[i];
-                let (_, y1) = data[i + 1];
+    /// Rebuilds the stacked entries of `bundle` after their stacking has changed
+    fn restack(&mut self, module: &DataModule, bundle: usize) -> Result<(), JsValue> {
+        let Some(b) = self.bundles.get_mut(&bundle) else {
+            return Ok(());
+        };
+        self.generation += 1;
+
+        let stacked: Vec<usize> = (0..b.buffers.len())
+            .filter(|&i| b.buffers[i].entry.stack_group != 0 || b.buffers[i].area_buffer.is_some())
+            .filter(|&i| b.buffers[i].entry.fill_to.is_none())
+            .collect();
+        let entries: Vec<BundleEntry> = stacked
+            .iter()
+            .map(|&i| b.buffers[i].entry.clone())
+            .collect();
+
+        let rebuilt = Self::build_entries(&self.context, module, b.from, b.to, b.opts, &entries)?;
+
+        for (i, entry) in stacked.into_iter().zip(rebuilt) {
+            let old = std::mem::replace(&mut b.buffers[i], entry);
+            Self::delete_entry(&self.context, old);
+        }
 
-                to_push.extend([(x1, prev1), (x0, y0), (x1, y1), (x1, prev1)]);
+        Ok(())
+    }
 
-                area[i] = (x0, y0);
+    fn delete_entry(context: &WebGl2RenderingContext, entry: BufferEntry) {
+        context.delete_buffer(Some(&entry.buffer));
+        if entry.area_buffer.is_some() {
+            context.delete_buffer(entry.area_buffer.as_ref());
+        }
+        for vao in [entry.vao, entry.area_vao].iter().flatten() {
+            context.delete_vertex_array(Some(vao));
+        }
+        for lod in entry.lods {
+            Self::delete_lod(context, lod);
+        }
+        if let Some(candles) = entry.candles {
+            for side in [candles.rising, candles.falling] {
+                context.delete_buffer(Some(&side.wicks));
+                context.delete_buffer(Some(&side.bodies));
             }
+        }
+        if let Some(colormap) = entry.colormap {
+            context.delete_buffer(Some(&colormap.values));
+            context.delete_texture(Some(&colormap.texture));
+        }
+    }
 
-            {
-                let last_idx = area.len() - 1;
-                area[last_idx] = (area[last_idx].0, area[last_idx].1 + data[last_idx].1);
-            }
+    fn delete_lod(context: &WebGl2RenderingContext, lod: LodLevel) {
+        context.delete_buffer(Some(&lod.buffer));
+        if let Some(vao) = &lod.vao {
+            context.delete_vertex_array(Some(vao));
+        }
+    }
 
-            context.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&a_buffer));
-            context.buffer_data_with_array_buffer_view(
-                WebGl2RenderingContext::ARRAY_BUFFER,
-                unsafe {
-                    &js_sys::Float32Array::view(core::slice::from_raw_parts(
-                        std::mem::transmute(to_push.as_ptr()),
-                        to_push.len() * 2,
-                    ))
-                },
-                WebGl2RenderingContext::STATIC_DRAW,
-            );
+    fn allocate_bundle_entry(
+        context: &WebGl2RenderingContext,
+        module: &DataModule,
+        from: RangePrec,
+        to: RangePrec,
+        entry: &BundleEntry,
+        opts: BundleOptions,
+        area_add: Option<&mut Vec<(f32, f32)>>,
+    ) -> Result<BufferEntry, JsValue> {
+        if let Some(trace) = module.get_ohlc_trace(entry.handle) {
+            return Self::allocate_candle_entry(context, trace, from, to, entry, opts);
+        }
 
-            Some(a_buffer)
+        // stacking needs all entries to share their x values, which decimation doesn't keep
+        let downsampling = if area_add.is_some() || entry.fill_to.is_some() {
+            Downsampling::None
         } else {
+            opts.downsampling
+        };
+
+        let trace = module
+            .get_trace(entry.handle)
+            .expect("Invalid entry handle during bundling");
+        let full =
+            || -> Vec<(f32, f32)> { trace.get_data_with_origin(from, to, from, 0.0).collect() };
+
+        let original = full();
+        let original_len = original.len();
+        let mut data = downsample(
+            original,
+            downsampling,
+            0.0,
+            (to - from) as f32,
+            opts.resolution as usize,
+        );
+
+        // the full data is fetched again instead of kept, as it's only needed when measuring
+        let original = opts.measure_error.then(full);
+        let decimation_of = |fidelity, drawn: &[(f32, f32)]| Decimation {
+            fidelity,
+            max_error: original.as_ref().map(|o| max_error(o, drawn)),
+        };
+        let decimation =
+            decimation_of(Fidelity::of(downsampling, data.len() < original_len), &data);
+
+        // bands are filled on their own and never take part in area stacking
+        let area_add = if entry.fill_to.is_some() {
             None
+        } else {
+            area_add
         };
 
+        // entries sampled at other x than the first of their group are interpolated onto its
+        // x, adding nothing outside of their own points
+        if let Some(ref area) = area_add {
+            let points: Vec<_> = data
+                .iter()
+                .map(|&(x, y)| (x as RangePrec, y as RangePrec))
+                .collect();
+            let grid: Vec<_> = area.iter().map(|p| p.0 as RangePrec).collect();
+
+            data = resample(&points, &grid, Interpolation::Linear)
+                .into_iter()
+                .zip(area.deref())
+                .map(|(y, &(x, bottom))| (x, bottom + if y.is_nan() { 0.0 } else { y as f32 }))
+                .collect();
+        }
+
+        // stacked and filled entries are matched point by point, so only lines break
+        let gaps = module.get_gap_rules(entry.handle);
+        let breaks = match area_add.is_none() && entry.fill_to.is_none() {
+            true => gaps.split(&mut data, None),
+            false => Vec::new(),
+        };
+
+        // the fill is looked up first, no buffers are left behind when it's gone
+        let strip = if let Some(lower) = entry.fill_to {
+            let trace = module
+                .get_trace(lower)
+                .ok_or_else(|| JsValue::from_str(&format!("Invalid fill handle {}", lower)))?;
+            Some(geometry::band_strip(
+                &data,
+                trace.get_data_with_origin(from, to, from, 0.0),
+            ))
+        } else {
+            area_add.map(|area| geometry::stack_area_strip(area, &data))
+        };
+
+        let buffer = WebGlRenderer::create_points_buffer(context, &data)?;
+
+        let area_buffer_points = strip.as_ref().map(|s| s.len() as i32).unwrap_or(0);
+        let area_buffer = match strip {
+            Some(strip) if !strip.is_empty() => {
+                Some(WebGlRenderer::create_points_buffer(context, &strip)?)
+            }
+            _ => None,
+        };
+
+        let colormap = Self::allocate_colormap(context, module, from, entry, &data)?;
+        let mut lods = Vec::new();
+
+        // the colormap values and breaks follow the full points, so the lines can't switch
+        // levels
+        if area_buffer.is_none() && colormap.is_none() && breaks.is_empty() {
+            let imported = module.get_pyramid(entry.handle);
+            let mut level = data.clone();
+
+            for i in 0..opts.pyramid_levels as usize {
+                // imported levels span the whole trace, so they're cut to the bundle
+                level = match imported.and_then(|p| p.slice(i, from, to)) {
+                    Some(imported) if !imported.is_empty() => imported,
+                    Some(_) => break,
+                    None => {
+                        let buckets = level.len() / PYRAMID_FACTOR;
+                        if buckets < 2 {
+                            break;
+                        }
+
+                        m4(&level, 0.0, (to - from) as f32, buckets)
+                    }
+                };
+
+                let buffer = WebGlRenderer::create_points_buffer(context, &level)?;
+
+                lods.push(LodLevel {
+                    vao: webgl_utils::create_vertex_array(context, &buffer, None),
+                    buffer,
+                    xs: level.iter().map(|p| p.0).collect(),
+                    decimation: decimation_of(decimation.fidelity.max(Fidelity::MinMax), &level),
+                });
+            }
+        }
+
+        let vao = webgl_utils::create_vertex_array(
+            context,
+            &buffer,
+            colormap.as_ref().map(|c| &c.values),
+        );
+        let area_vao = area_buffer
+            .as_ref()
+            .and_then(|area| webgl_utils::create_vertex_array(context, area, None));
+
         Ok(BufferEntry {
             points: data.len(),
+            capacity: data.len(),
             handle: entry.handle,
             buffer,
+            vao,
             area_buffer,
-            area_buffer_points: (data.len() as i32 - 1) * 4 + 1,
+            area_vao,
+            area_buffer_points,
+            fill_alpha: entry.fill_to.map(|_| entry.fill_alpha),
             width: entry.width as f32,
             color: [
                 entry.color[0] as f32 / 255.0,
                 entry.color[1] as f32 / 255.0,
                 entry.color[2] as f32 / 255.0,
             ],
-            points_mode: entry.points_mode,
+            marker: entry.marker,
+            marker_size: entry.marker_size as f32,
+            lods,
+            gaps,
+            breaks,
+            decimation,
+            hit_index: HitIndex::new(from, data),
+            entry: entry.clone(),
+            candles: None,
+            palette_slot: 0,
+            colormap,
         })
     }
-}
 
-impl Renderer for WebGlRenderer {
-    fn render(&mut self, module: &DataModule, job: RenderJob) -> Result<RenderJobResult, JsValue> {
+    /// Samples the trace coloring `entry` at the x values of its bundled `data`, scaling them
+    /// to the range of the samples
+    fn allocate_colormap(
+        context: &WebGl2RenderingContext,
+        module: &DataModule,
+        from: RangePrec,
+        entry: &BundleEntry,
+        data: &[(f32, f32)],
+    ) -> Result<Option<ColormapBuffers>, JsValue> {
+        let Some((color_by, colormap)) = &entry.color_by else {
+            return Ok(None);
+        };
+        let trace = module
+            .get_trace(*color_by)
+            .ok_or_else(|| JsValue::from_str(&format!("Invalid color handle {}", color_by)))?;
+
+        let values: Vec<f32> = data
+            .iter()
+            .map(|&(x, _)| {
+                trace
+                    .get_data_at(x as RangePrec + from)
+                    .map_or(f32::NAN, |v| v as f32)
+            })
+            .collect();
+        let (min, max) = heatmap::value_range(&values);
+        let span = if max > min { max - min } else { 1.0 };
+        // values the coloring trace has no data for take the start of the colormap
+        let values: Vec<f32> = values
+            .iter()
+            .map(|&v| if v.is_finite() { (v - min) / span } else { 0.0 })
+            .collect();
+
+        let buffer = context
+            .create_buffer()
+            .ok_or_else(|| JsValue::from_str("Failed to allocate the colormap values buffer"))?;
+        context.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&buffer));
+        webgl_utils::upload(
+            context,
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            &values,
+            WebGl2RenderingContext::STATIC_DRAW,
+        );
+
+        Ok(Some(ColormapBuffers {
+            values: buffer,
+            points: values.len(),
+            texture: webgl_utils::create_texture(
+                context,
+                (heatmap::LUT_SIZE as i32, 1),
+                WebGl2RenderingContext::RGBA,
+                WebGl2RenderingContext::LINEAR,
+                &colormap.lut(heatmap::LUT_SIZE),
+            )?,
+        }))
+    }
+
+    /// Builds the candlesticks of an OHLC trace, downsampling merges neighbouring candles
+    fn allocate_candle_entry(
+        context: &WebGl2RenderingContext,
+        trace: &OhlcTrace,
+        from: RangePrec,
+        to: RangePrec,
+        entry: &BundleEntry,
+        opts: BundleOptions,
+    ) -> Result<BufferEntry, JsValue> {
+        let mut candles: Vec<_> = trace.iter_with_origin(from, to, from).collect();
+        let loaded = candles.len();
+
+        if opts.downsampling != Downsampling::None {
+            candles =
+                aggregate_candles(&candles, 0.0, (to - from) as f32, opts.resolution as usize);
+        }
+
+        // merged candles keep the extremes of the ones they replace
+        let decimation = match candles.len() < loaded {
+            true => Decimation {
+                fidelity: Fidelity::MinMax,
+                max_error: None,
+            },
+            false => Decimation::EXACT,
+        };
+
+        // bodies take 70% of the narrowest gap, a lone candle a hundredth of the bundle
+        let gap = candles
+            .windows(2)
+            .map(|w| w[1].0 - w[0].0)
+            .filter(|&d| d > 0.0)
+            .fold(f32::INFINITY, f32::min);
+        let half_width = if gap.is_finite() {
+            gap * 0.35
+        } else {
+            (to - from) as f32 / 200.0
+        };
+
+        let side = |rising| -> Result<CandleSide, JsValue> {
+            let geometry::Candles { wicks, bodies } =
+                geometry::candle_geometry(&candles, half_width, rising);
+
+            Ok(CandleSide {
+                candles: wicks.len() / 2,
+                wicks: WebGlRenderer::create_points_buffer(context, &wicks)?,
+                bodies: WebGlRenderer::create_points_buffer(context, &bodies)?,
+            })
+        };
+
+        let close: Vec<(f32, f32)> = candles.iter().map(|&(x, v)| (x, v[3])).collect();
+        let range = candles
+            .iter()
+            .flat_map(|&(x, v)| [(x, v[2]), (x, v[1])])
+            .collect();
+
+        Ok(BufferEntry {
+            points: close.len(),
+            capacity: close.len(),
+            handle: entry.handle,
+            buffer: WebGlRenderer::create_points_buffer(context, &close)?,
+            // only the candles are drawn, which have buffers of their own
+            vao: None,
+            area_buffer: None,
+            area_vao: None,
+            area_buffer_points: 0,
+            fill_alpha: None,
+            width: entry.width as f32,
+            color: [
+                entry.color[0] as f32 / 255.0,
+                entry.color[1] as f32 / 255.0,
+                entry.color[2] as f32 / 255.0,
+            ],
+            marker: Marker::None,
+            marker_size: 0.0,
+            lods: Vec::new(),
+            gaps: GapRules::default(),
+            breaks: Vec::new(),
+            decimation,
+            hit_index: HitIndex::new(from, close),
+            entry: entry.clone(),
+            candles: Some(CandleBuffers {
+                rising: side(true)?,
+                falling: side(false)?,
+                range: HitIndex::new(from, range),
+            }),
+            palette_slot: 0,
+            colormap: None,
+        })
+    }
+
+    /// Draws the wicks and bodies of an OHLC entry, expects the trace program to be in use
+    fn draw_candles(&self, row: &BufferEntry, candles: &CandleBuffers) {
+        let gl = &self.context;
+        let down = row
+            .entry
+            .down_color
+            .unwrap_or(DEFAULT_DOWN_COLOR)
+            .map(|c| c as f32 / 255.0);
+
+        gl.line_width(row.width.clamp(1.0, self.line_width_limit));
+        gl.uniform1f(Some(&self.tp_color_index_pos), -1.0);
+
+        for (side, color) in [(&candles.rising, row.color), (&candles.falling, down)] {
+            if side.candles == 0 {
+                continue;
+            }
+
+            gl.uniform4f(Some(&self.tp_color_pos), color[0], color[1], color[2], 1.0);
+
+            // bodies go last to cover the wicks between open and close
+            for (buffer, mode, vertices) in [
+                (&side.wicks, WebGl2RenderingContext::LINES, 2),
+                (&side.bodies, WebGl2RenderingContext::TRIANGLES, 6),
+            ] {
+                gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(buffer));
+                gl.vertex_attrib_pointer_with_i32(0, 2, WebGl2RenderingContext::FLOAT, false, 0, 0);
+                gl.enable_vertex_attrib_array(0);
+                gl.draw_arrays(mode, 0, (side.candles * vertices) as i32);
+            }
+        }
+    }
+
+    /// With an area chart, entries without explicit stacking stack in their order in group 1,
+    /// following the `existing` entries of the bundle
+    fn default_stacking(&self, entries: &[BundleEntry], existing: usize) -> Vec<BundleEntry> {
+        entries
+            .iter()
+            .enumerate()
+            .map(|(i, e)| {
+                let mut e = e.clone();
+
+                if self.is_area && e.stack_group == 0 && e.fill_to.is_none() {
+                    e.stack_group = 1;
+                    e.stack_order = (existing + i) as i32;
+                }
+
+                e
+            })
+            .collect()
+    }
+
+    /// Uploads the line colors of all bundle entries into the palette, assigning their slots.
+    /// The texture is only reallocated when it has no row left for them, and nothing is
+    /// uploaded when their colors haven't changed.
+    fn update_palette(&mut self) -> Result<(), JsValue> {
+        if self.palette_generation == Some(self.generation) {
+            return Ok(());
+        }
+
+        let mut handles: Vec<usize> = self.bundles.keys().copied().collect();
+        handles.sort_unstable();
+
+        let mut colors: Vec<u8> = Vec::new();
+        for handle in handles {
+            for row in &mut self.bundles.get_mut(&handle).unwrap().buffers {
+                row.palette_slot = colors.len() / 4;
+                colors.extend(row.entry.color);
+                colors.push(255);
+            }
+        }
+        self.palette_generation = Some(self.generation);
+
+        if self.palette.is_some() && colors == self.palette_colors {
+            return Ok(());
+        }
+
         let gl = &self.context;
+        let rows = (colors.len() / 4).div_ceil(PALETTE_WIDTH).max(1);
+        let mut texels = colors.clone();
+        texels.resize(rows * PALETTE_WIDTH * 4, 0);
+
+        match &self.palette {
+            Some(palette) if rows <= self.palette_rows => {
+                gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(palette));
+                gl.pixel_storei(WebGl2RenderingContext::UNPACK_ALIGNMENT, 1);
+                gl.tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_opt_u8_array(
+                    WebGl2RenderingContext::TEXTURE_2D,
+                    0,
+                    0,
+                    0,
+                    PALETTE_WIDTH as i32,
+                    rows as i32,
+                    WebGl2RenderingContext::RGBA,
+                    WebGl2RenderingContext::UNSIGNED_BYTE,
+                    Some(&texels),
+                )?;
+            }
+            _ => {
+                if let Some(old) = self.palette.take() {
+                    gl.delete_texture(Some(&old));
+                }
+
+                // doubled, so a growing dashboard reallocates a few times at most
+                let capacity = rows.next_power_of_two();
+                texels.resize(capacity * PALETTE_WIDTH * 4, 0);
+                self.palette = Some(webgl_utils::create_texture(
+                    gl,
+                    (PALETTE_WIDTH as i32, capacity as i32),
+                    WebGl2RenderingContext::RGBA,
+                    WebGl2RenderingContext::NEAREST,
+                    &texels,
+                )?);
+                self.palette_rows = capacity;
+            }
+        }
+        self.palette_colors = colors;
+        webgl_utils::check(gl, "palette upload", None)?;
 
-        let y_from = job.y_from as f32;
+        Ok(())
+    }
+
+    /// Points the trace program at `origin_x` and the y range of the axis `handle` is plotted
+    /// against, the origin split in two floats to not lose its precision
+    fn set_trace_range(&self, job: &RenderJob, handle: DataIdx, origin_x: RangePrec, x_size: f32) {
+        let y_scale = job.y_axis_scale();
+        let (from, to) = job.y_range(handle);
+        let (from, to) = match y_scale.reversed {
+            true => (y_scale.apply(to) as f32, y_scale.apply(from) as f32),
+            false => (y_scale.apply(from) as f32, y_scale.apply(to) as f32),
+        };
+        // a reversed x starts at the right edge, from where the negative size reaches the left
+        let (origin_x, x_size) = match job.x_reversed {
+            true => (origin_x + x_size as RangePrec, -x_size),
+            false => (origin_x, x_size),
+        };
+        let (origin_x, origin_lo) = geometry::split_f64(origin_x);
+
+        self.context
+            .uniform2f(Some(&self.tp_origin_pos), origin_x, from);
+        self.context
+            .uniform1f(Some(&self.tp_origin_lo_pos), origin_lo);
+        self.context
+            .uniform2f(Some(&self.tp_size_pos), x_size, to - from);
+    }
+
+    /// Draws `count` of the points of `buffer`, which has to be bound to attribute 0, from
+    /// `first` on as markers colored by the colormap `values` along them if given. Expects the
+    /// trace program to be in use, and leaves the default vertex array bound.
+    fn draw_markers(
+        &self,
+        marker: Marker,
+        size: f32,
+        (buffer, values): (&WebGlBuffer, Option<&WebGlBuffer>),
+        first: i32,
+        count: i32,
+    ) {
+        const POINT_BYTES: i32 = 2 * std::mem::size_of::<f32>() as i32;
+        const VALUE_BYTES: i32 = std::mem::size_of::<f32>() as i32;
 
-        let x_ticks = webgl_utils::calc_ticks(job.x_from, job.x_to - job.x_from);
-        let y_ticks = webgl_utils::calc_ticks(job.y_from, job.y_to - job.y_from);
+        let gl = &self.context;
+
+        gl.uniform1f(Some(&self.tp_point_size_pos), size);
+        gl.uniform1f(Some(&self.tp_marker_px_pos), size);
+        gl.uniform1i(Some(&self.tp_marker_pos), marker as i32);
+
+        match &self.marker_quad {
+            Some(quad) => {
+                // instances can't start past the first one, so the pointers skip to `first`
+                gl.bind_vertex_array(Some(&quad.vao));
+                if let Some(values) = values {
+                    gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(values));
+                    gl.vertex_attrib_pointer_with_i32(
+                        1,
+                        1,
+                        WebGl2RenderingContext::FLOAT,
+                        false,
+                        0,
+                        first * VALUE_BYTES,
+                    );
+                    gl.enable_vertex_attrib_array(1);
+                } else {
+                    gl.disable_vertex_attrib_array(1);
+                }
+                gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(buffer));
+                gl.vertex_attrib_pointer_with_i32(
+                    0,
+                    2,
+                    WebGl2RenderingContext::FLOAT,
+                    false,
+                    0,
+                    first * POINT_BYTES,
+                );
+                gl.enable_vertex_attrib_array(0);
+
+                gl.uniform1f(Some(&self.tp_instanced_pos), 1.0);
+                gl.draw_arrays_instanced(WebGl2RenderingContext::TRIANGLE_STRIP, 0, 4, count);
+                gl.uniform1f(Some(&self.tp_instanced_pos), 0.0);
+                gl.bind_vertex_array(None);
+            }
+            None => gl.draw_arrays(WebGl2RenderingContext::POINTS, first, count),
+        }
+
+        gl.uniform1i(Some(&self.tp_marker_pos), 0);
+    }
+
+    /// Points attribute 0 at `buffer`, by binding its vertex array when it has one
+    fn bind_points(&self, buffer: &WebGlBuffer, vao: Option<&WebGlVertexArrayObject>) {
+        let gl = &self.context;
+
+        match vao {
+            Some(vao) => gl.bind_vertex_array(Some(vao)),
+            None => {
+                gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(buffer));
+                gl.vertex_attrib_pointer_with_i32(0, 2, WebGl2RenderingContext::FLOAT, false, 0, 0);
+                gl.enable_vertex_attrib_array(0);
+            }
+        }
+    }
+
+    /// Returns to the default vertex array after `bind_points`, the rest of the renderer
+    /// specifies its attributes in it
+    fn unbind_points(&self, vao: Option<&WebGlVertexArrayObject>) {
+        if vao.is_some() {
+            self.context.bind_vertex_array(None);
+        }
+    }
+
+    /// Bundles of `job` not hidden by `set_bundle_visible`, in its order
+    fn shown_bundles<'a>(
+        &'a self,
+        job: &'a RenderJob,
+    ) -> impl Iterator<Item = &'a BufferBundle> + 'a {
+        job.get_bundles()
+            .iter()
+            .filter(|b| !self.hidden_bundles.contains(b))
+            .filter_map(|b| self.bundles.get(b))
+    }
+
+    /// Points of a directly drawn trace over the range of `key`, read and uploaded on a miss.
+    /// Evicted points have their buffers deleted.
+    fn cached_trace(
+        cache: &mut TraceCache<Rc<CachedTrace>>,
+        context: &WebGl2RenderingContext,
+        module: &DataModule,
+        key: TraceKey,
+    ) -> Result<Rc<CachedTrace>, JsValue> {
+        if let Some(hit) = cache.get(&key) {
+            return Ok(hit.clone());
+        }
+
+        let (from, to) = key.range();
+        let data = direct_points(module, key.handle, from, to, from);
+        let cached = Rc::new(CachedTrace {
+            buffer: Self::create_points_buffer(context, &data)?,
+            data,
+            base: from,
+        });
+
+        for evicted in cache.insert(key, cached.clone()) {
+            context.delete_buffer(Some(&evicted.buffer));
+        }
+
+        Ok(cached)
+    }
+
+    fn create_points_buffer(
+        context: &WebGl2RenderingContext,
+        data: &[(f32, f32)],
+    ) -> Result<WebGlBuffer, JsValue> {
+        let buffer = context.create_buffer().ok_or_else(|| {
+            JsValue::from_str(
+                "Failed to allocate a buffer, perhaps the WebGL context has been destroyed.",
+            )
+        })?;
+
+        // tuples have no guaranteed layout, so they can't be reinterpreted as a float slice
+        let vertices: Vec<[f32; 2]> = data.iter().map(|&(x, y)| [x, y]).collect();
+
+        context.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&buffer));
+        webgl_utils::upload(
+            context,
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            &vertices,
+            WebGl2RenderingContext::STATIC_DRAW,
+        );
+
+        Ok(buffer)
+    }
+
+    /// Draws the frame of `job`, with the now marker and the shading past it unless the
+    /// overlay layer carries them
+    fn render_frame(
+        &mut self,
+        module: &DataModule,
+        job: RenderJob,
+        now_marker: bool,
+    ) -> Result<RenderJobResult, JsValue> {
+        self.restore_context(module)?;
+        self.ensure_context()?;
+
+        // errors left over from outside of rendering aren't blamed on the first pass
+        webgl_utils::check(&self.context, "pre-render", None)?;
+        self.update_palette()?;
+
+        // the axes and grid don't show the now marker, a moving clock keeps hitting the cache
+        let (width, height) = (self.width, self.height);
+        let mut key_job = job.clone();
+        key_job.now = None;
+        let overlay = self.overlay_cache.get_or_insert_with(
+            JobFingerprint::of(&key_job, width, height, self.generation),
+            || DrawList::overlay(&job, width, height, |text| self.text.measure(text)),
+        );
+
+        let gl = &self.context;
+        let mut result = overlay.1.clone();
+        let mut colors: Vec<(DataIdx, [u8; 3])> = Vec::new();
+
+        let x_scale = job.x_axis_scale();
+
+        // with a linear x the buffers are positioned relative to the bundle start for precision,
+        // other scales have to work with absolute values rebuilt from `xbase`
+        let x_linear = x_scale.scale == Scale::Linear;
+        let x_size = x_scale.apply(job.x_to) - x_scale.apply(job.x_from);
 
         if job.clear {
-            self.clear();
+            self.clear(job.clear_color());
         }
 
-        if job.render_axes {
-            self.render_axes(&job, &x_ticks[..], &y_ticks[..]);
+        self.execute(&overlay.0);
+        webgl_utils::check(gl, "overlay", None)?;
+
+        for heatmap in job.get_heatmaps() {
+            if let Some(layer) = self.heatmaps.get(heatmap) {
+                self.heatmap_program
+                    .draw(gl, layer, &job, self.width, self.height);
+                webgl_utils::check(gl, "heatmap", None)?;
+            }
         }
 
-        if job.render_grid {
-            self.render_grid(&job, &x_ticks[..], &y_ticks[..]);
+        if let (Some(now), true) = (job.now, job.shade_future && now_marker) {
+            self.render_now_marker(&job, now, true);
         }
 
+        if let Some(annotations) = self.annotations.get(&self.chart) {
+            let mut bands = DrawList::new(self.width, self.height);
+            bands.push_annotation_bands(&job, annotations);
+            self.execute(&bands);
+            webgl_utils::check(gl, "annotation bands", None)?;
+        }
+
+        let rect = PlotRect::of(&job, self.width, self.height);
+
         gl.viewport(
-            (job.margin + job.y_label_space) as i32,
-            (job.margin + job.x_label_space) as i32,
-            (self.width - job.margin * 2 - job.y_label_space) as i32,
-            (self.height - job.margin * 2 - job.x_label_space) as i32,
+            rect.left as i32,
+            rect.bottom as i32,
+            rect.width as i32,
+            rect.height as i32,
         );
 
         gl.use_program(Some(&self.trace_program));
+        gl.uniform2f(Some(&self.tp_transform_pos), 1.0, 0.0);
+        gl.uniform1f(Some(&self.tp_color_index_pos), -1.0);
+
+        // a unit of its own, the text and heatmaps rebind the lower ones
+        gl.active_texture(WebGl2RenderingContext::TEXTURE2);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, self.palette.as_ref());
+        gl.active_texture(WebGl2RenderingContext::TEXTURE0);
+        gl.uniform1i(Some(&self.tp_palette_pos), 2);
+        gl.uniform1f(Some(&self.tp_palette_rows_pos), self.palette_rows as f32);
         gl.uniform2f(
-            Some(&self.tp_size_pos),
-            (job.x_to - job.x_from) as f32,
-            (job.y_to - job.y_from) as f32,
+            Some(&self.tp_viewport_px_pos),
+            rect.width as f32,
+            rect.height as f32,
         );
-        gl.uniform2f(Some(&self.tp_transform_pos), 1.0, 0.0);
+        gl.uniform1f(
+            Some(&self.tp_marker_rotation_pos),
+            job.marker_rotation.to_radians(),
+        );
+        gl.uniform2i(
+            Some(&self.tp_scales_pos),
+            job.x_scale as i32,
+            job.y_scale as i32,
+        );
+        gl.uniform2f(
+            Some(&self.tp_linthresh_pos),
+            job.x_linthresh as f32,
+            job.y_linthresh as f32,
+        );
+        webgl_utils::check(gl, "trace setup", None)?;
 
-        if !job.get_bundles().is_empty() {
-            for bundle in self.bundles.values() {
-                gl.uniform2f(
-                    Some(&self.tp_origin_pos),
-                    (job.x_from - bundle.from) as f32,
-                    y_from,
-                );
+        let px_width = rect.width;
+
+        for bundle in job.get_bundles() {
+            if self.hidden_bundles.contains(bundle) {
+                continue;
+            }
+            if let Some(bundle) = self.bundles.get(bundle) {
+                let visible = ((job.x_to - job.x_from) / (bundle.to - bundle.from)).min(1.0);
+
+                let origin_x = if x_linear {
+                    job.x_from - bundle.from
+                } else {
+                    gl.uniform1f(Some(&self.tp_xbase_pos), bundle.from as f32);
+                    x_scale.apply(job.x_from)
+                };
+
+                // zoomed in too far for offsets from the bundle start, the visible points are
+                // uploaded relative to the view instead
+                let offset = (job.x_from - bundle.from)
+                    .abs()
+                    .max((job.x_to - bundle.from).abs());
+                let focused =
+                    x_linear && geometry::loses_precision(offset, job.x_to - job.x_from, px_width);
+
+                let values = bundle.buffers.iter().map(|row| {
+                    let value = job.raise_at_x.and_then(|x| row.hit_index.value_at(x));
+                    (row.handle, value, row.entry.y_transform())
+                });
+
+                for row in raise_order(&job, module, values)
+                    .into_iter()
+                    .map(|i| &bundle.buffers[i])
+                {
+                    if job.is_blacklisted(row.handle) || row.points == 0 {
+                        continue;
+                    }
+
+                    self.set_trace_range(&job, row.handle, origin_x, x_size as f32);
+
+                    let transform = match job.baseline_x {
+                        Some(x) => match module.relative_transform(row.handle, x) {
+                            Some(transform) => transform,
+                            None => continue,
+                        },
+                        None => (1.0, 0.0),
+                    };
+                    // the entry's own transform applies to the values relative to the baseline
+                    let (scale, offset) = row.entry.y_transform();
+                    let transform = (transform.0 * scale, transform.1 * scale + offset);
+                    gl.uniform2f(
+                        Some(&self.tp_transform_pos),
+                        transform.0 as f32,
+                        transform.1 as f32,
+                    );
+
+                    // points evicted from a rolling trace since bundling are skipped
+                    let retained = module.get_trace(row.handle).and_then(|t| t.retained_from());
+                    let first = retained.map_or(0, |x| row.hit_index.index_from(x));
+                    if first >= row.points {
+                        continue;
+                    }
+
+                    let x_from = job.x_from.max(retained.unwrap_or(RangePrec::MIN));
+                    let extent = match &row.candles {
+                        Some(candles) => candles.range.extent(x_from, job.x_to),
+                        None => row.hit_index.extent(x_from, job.x_to),
+                    };
+                    if let Some(extent) = extent {
+                        result.add_extent(row.handle, transform_extent(extent, transform));
+                        colors.push((row.handle, row.entry.color));
+                    }
 
-                for row in &bundle.buffers {
-                    if job.is_blacklisted(row.handle) {
+                    if let Some(candles) = &row.candles {
+                        result.add_decimation(row.handle, row.decimation);
+                        self.draw_candles(row, candles);
+                        webgl_utils::check(gl, "candles", Some(row.handle))?;
                         continue;
                     }
 
-                    if self.is_area && row.area_buffer.is_some() {
+                    gl.uniform1f(Some(&self.tp_color_index_pos), -1.0);
+
+                    if row.area_buffer.is_some() {
+                        let alpha = row.fill_alpha.unwrap_or(0.5);
+
                         gl.uniform4f(
                             Some(&self.tp_color_pos),
-                            row.color[0] * 0.5,
-                            row.color[1] * 0.5,
-                            row.color[2] * 0.5,
-                            0.5,
+                            row.color[0] * alpha,
+                            row.color[1] * alpha,
+                            row.color[2] * alpha,
+                            alpha,
+                        );
+
+                        if let Some(area_buffer) = &row.area_buffer {
+                            self.bind_points(area_buffer, row.area_vao.as_ref());
+                        }
+                        gl.draw_arrays(
+                            WebGl2RenderingContext::TRIANGLE_STRIP,
+                            0,
+                            row.area_buffer_points,
+                        );
+                        self.unbind_points(row.area_vao.as_ref());
+                        webgl_utils::check(gl, "area fill", Some(row.handle))?;
+                    }
+
+                    if row.width <= 0.0 && row.marker == Marker::None {
+                        continue;
+                    }
+
+                    gl.uniform1f(Some(&self.tp_color_index_pos), row.palette_slot as f32);
+
+                    // the decimated buffer won't do, the stored points are uploaded for the
+                    // frame, already without the evicted ones. Focused ones only need the view
+                    // and its neighbourhood, measured from the view's start.
+                    let focus = focused && row.colormap.is_none();
+                    let (data_from, data_to, data_origin) = match focus {
+                        true => {
+                            let visible = job.x_to - job.x_from;
+                            let from = (job.x_from - visible).max(bundle.from);
+
+                            (from, (job.x_to + visible).min(bundle.to), job.x_from)
+                        }
+                        false => (bundle.from, bundle.to, bundle.from),
+                    };
+                    let exact: Option<(Vec<[f32; 2]>, Vec<usize>)> = (focus
+                        || job.exact
+                            && row.decimation.fidelity != Fidelity::Exact
+                            && row.colormap.is_none())
+                    .then(|| {
+                        let mut data: Vec<(f32, f32)> = module
+                            .get_trace(row.handle)
+                            .map(|t| {
+                                t.get_data_with_origin(data_from, data_to, data_origin, 0.0)
+                                    .collect()
+                            })
+                            .unwrap_or_default();
+                        let breaks = row.gaps.split(&mut data, None);
+
+                        (data.into_iter().map(|(x, y)| [x, y]).collect(), breaks)
+                    });
+                    let (exact_data, breaks) = match exact {
+                        Some((data, breaks)) => (Some(data), breaks),
+                        None => (None, row.breaks.clone()),
+                    };
+                    if focus {
+                        self.set_trace_range(&job, row.handle, 0.0, x_size as f32);
+                    }
+
+                    // only the points in view are submitted, found in the ascending x of each
+                    // buffer relative to where it starts
+                    let view = |origin: RangePrec| {
+                        (
+                            (x_from - origin) as DataPrec,
+                            (job.x_to - origin) as DataPrec,
+                        )
+                    };
+
+                    // levels of detail don't line up with the full data, so skipping needs it
+                    let (buffer, vao, span, decimation) = if let Some(data) = &exact_data {
+                        gl.bind_buffer(
+                            WebGl2RenderingContext::ARRAY_BUFFER,
+                            Some(&self.trace_buffer),
+                        );
+                        webgl_utils::upload(
+                            gl,
+                            WebGl2RenderingContext::ARRAY_BUFFER,
+                            data,
+                            WebGl2RenderingContext::STREAM_DRAW,
+                        );
+
+                        let (from, to) = view(data_origin);
+                        let span = geometry::visible_span(data, |p| p[0], from, to);
+
+                        (&self.trace_buffer, None, span, Decimation::EXACT)
+                    } else if first > 0 || job.exact || row.colormap.is_some() {
+                        // colormap values are only aligned with the bundled points
+                        let points = row
+                            .colormap
+                            .as_ref()
+                            .map_or(row.points, |c| row.points.min(c.points));
+                        let (from, to) = view(bundle.from);
+                        let kept = &row.hit_index.points()[first..points.max(first)];
+                        let span = geometry::visible_span(kept, |p| p.0, from, to);
+
+                        let span = span.start + first..span.end + first;
+                        (&row.buffer, row.vao.as_ref(), span, row.decimation)
+                    } else {
+                        row.select_lod(visible, px_width, view(bundle.from))
+                    };
+                    result.add_decimation(row.handle, decimation);
+                    if span.is_empty() {
+                        continue;
+                    }
+                    let count = span.len() as i32;
+
+                    self.bind_points(buffer, vao);
+
+                    if let Some(colormap) = &row.colormap {
+                        gl.active_texture(WebGl2RenderingContext::TEXTURE3);
+                        gl.bind_texture(
+                            WebGl2RenderingContext::TEXTURE_2D,
+                            Some(&colormap.texture),
                         );
+                        gl.active_texture(WebGl2RenderingContext::TEXTURE0);
+                        gl.uniform1i(Some(&self.tp_colormap_pos), 3);
+                        gl.uniform1f(Some(&self.tp_colormapped_pos), 1.0);
+                    }
 
+                    // the vertex array already holds the colormap values
+                    if let (Some(colormap), None) = (&row.colormap, vao) {
                         gl.bind_buffer(
                             WebGl2RenderingContext::ARRAY_BUFFER,
-                            row.area_buffer.as_ref(),
+                            Some(&colormap.values),
                         );
                         gl.vertex_attrib_pointer_with_i32(
-                            0,
-                            2,
+                            1,
+                            1,
                             WebGl2RenderingContext::FLOAT,
                             false,
                             0,
                             0,
                         );
-                        gl.enable_vertex_attrib_array(0);
-                        gl.draw_arrays(
-                            WebGl2RenderingContext::TRIANGLE_STRIP,
-                            0,
-                            row.area_buffer_points,
-                        );
+                        gl.enable_vertex_attrib_array(1);
+                        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(buffer));
                     }
 
-                    gl.uniform4f(
-                        Some(&self.tp_color_pos),
-                        row.color[0],
-                        row.color[1],
-                        row.color[2],
-                        1.0,
-                    );
-
-                    gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&row.buffer));
-                    gl.vertex_attrib_pointer_with_i32(
-                        0,
-                        2,
-                        WebGl2RenderingContext::FLOAT,
-                        false,
-                        0,
-                        0,
-                    );
-                    gl.enable_vertex_attrib_array(0);
-
-                    if row.width < self.line_width_limit + 0.1 {
-                        gl.line_width(row.width);
-                        gl.draw_arrays(WebGl2RenderingContext::LINE_STRIP, 0, row.points as i32);
-                    } else {
-                        gl.line_width(1.0);
-                        let start_offset = row.width / 2.0 - 0.5;
-                        let amount = row.width.round() as usize;
-
-                        for i in 0..amount {
-                            gl.uniform2f(
-                                Some(&self.tp_csoffset_pos),
-                                0.0,
-                                2.0 * (start_offset + i as f32) / self.height as f32,
-                            );
+                    if row.width <= 0.0 {
+                        // scatter only, markers without a connecting line
+                    } else if row.colormap.is_some()
+                        || (self.msaa && row.width < self.line_width_limit + 0.1)
+                    {
+                        // tessellated lines have a single color, so colormapped lines are
+                        // left to the driver however wide they are
+                        gl.line_width(row.width.min(self.line_width_limit));
+                        for strip in geometry::strips(&breaks, span.start, span.end) {
                             gl.draw_arrays(
                                 WebGl2RenderingContext::LINE_STRIP,
-                                0,
-                                row.points as i32,
+                                strip.start as i32,
+                                strip.len() as i32,
                             );
                         }
+                    } else {
+                        // too thick for the driver or without multisampling to smooth it, the
+                        // line is tessellated in pixels instead
+                        let (from, to) = (
+                            (x_from - data_origin) as f32,
+                            (job.x_to - data_origin) as f32,
+                        );
+                        let (pixels, span): (Vec<(f32, f32)>, _) = match &exact_data {
+                            Some(data) => {
+                                let span = geometry::visible_span(data, |p| p[0], from, to);
+                                (data.iter().map(|p| (p[0], p[1])).collect(), span)
+                            }
+                            None => {
+                                let points = row.hit_index.points();
+                                let span =
+                                    geometry::visible_span(&points[first..], |p| p.0, from, to);
+                                let span = span.start + first..span.end + first;
+                                (points.to_vec(), span)
+                            }
+                        };
+
+                        // the stroke program specifies attributes of its own, which mustn't
+                        // end up in the vertex array
+                        self.unbind_points(vao);
+                        if let Some(projection) = Projection::new(module, &job, &rect, row.handle) {
+                            let projection = projection
+                                .with_x_origin(data_origin)
+                                .with_y_transform(row.entry.y_transform());
+
+                            for strip in geometry::strips(&breaks, span.start, span.end) {
+                                let pixels: Vec<_> =
+                                    pixels[strip].iter().map(|&p| projection.apply(p)).collect();
+
+                                self.draw_stroke(&pixels, row.width, row.color, &rect);
+                            }
+                        }
+
+                        self.bind_points(buffer, vao);
                     }
 
-                    if row.points_mode {
-                        gl.draw_arrays(WebGl2RenderingContext::POINTS, 0, row.points as i32);
+                    // a lone point doesn't form a line, so it at least gets a marker
+                    let marker = match row.marker {
+                        Marker::None if count == 1 => Marker::Circle,
+                        marker => marker,
+                    };
+
+                    if marker != Marker::None {
+                        let size = if row.marker_size > 0.0 {
+                            row.marker_size
+                        } else {
+                            DEFAULT_MARKER_SIZE.max(row.width * 2.0)
+                        };
+
+                        let values = row.colormap.as_ref().map(|c| &c.values);
+                        self.draw_markers(marker, size, (buffer, values), span.start as i32, count);
                     }
+
+                    if row.colormap.is_some() {
+                        if vao.is_none() {
+                            gl.disable_vertex_attrib_array(1);
+                        }
+                        gl.uniform1f(Some(&self.tp_colormapped_pos), 0.0);
+                    }
+                    self.unbind_points(vao);
+
+                    webgl_utils::check(gl, "bundled traces", Some(row.handle))?;
+                }
+            }
+        }
+
+        gl.uniform1f(Some(&self.tp_color_index_pos), -1.0);
+
+        for trace in job.get_traces() {
+            let mut transform = (1.0, 0.0);
+            if let Some(x) = job.baseline_x {
+                match module.relative_transform(trace.idx, x) {
+                    Some(t) => transform = t,
+                    None => continue,
                 }
             }
+
+            // at least a unit of x past the view, so integer x still reaches its edges
+            let (from, to) = job.overscan_range();
+            let (from, to) = (from.min(job.x_from - 1.), to.max(job.x_to + 1.));
+            let cached = match TraceKey::of(trace.idx, from, to, module.revision()) {
+                Some(key) => Self::cached_trace(&mut self.trace_cache, &self.context, module, key)?,
+                // ranges that can't be snapped to the grid are read for this frame only
+                None => {
+                    let data = direct_points(module, trace.idx, from, to, job.x_from);
+                    gl.bind_buffer(
+                        WebGl2RenderingContext::ARRAY_BUFFER,
+                        Some(&self.trace_buffer),
+                    );
+                    let vertices: Vec<[f32; 2]> = data.iter().map(|&(x, y)| [x, y]).collect();
+                    webgl_utils::upload(
+                        gl,
+                        WebGl2RenderingContext::ARRAY_BUFFER,
+                        &vertices,
+                        WebGl2RenderingContext::STATIC_DRAW,
+                    );
+
+                    Rc::new(CachedTrace {
+                        buffer: self.trace_buffer.clone(),
+                        data,
+                        base: job.x_from,
+                    })
+                }
+            };
+
+            let origin_x = if x_linear {
+                job.x_from - cached.base
+            } else {
+                gl.uniform1f(Some(&self.tp_xbase_pos), cached.base as f32);
+                x_scale.apply(job.x_from)
+            };
+            self.set_trace_range(&job, trace.idx, origin_x, x_size as f32);
+            gl.uniform2f(
+                Some(&self.tp_transform_pos),
+                transform.0 as f32,
+                transform.1 as f32,
+            );
+
+            gl.uniform4f(
+                Some(&self.tp_color_pos),
+                trace.color[0] as f32 / 255.0,
+                trace.color[1] as f32 / 255.0,
+                trace.color[2] as f32 / 255.0,
+                1.0,
+            );
+            gl.line_width(trace.width as f32);
+
+            let data = &cached.data;
+            let n = data.len();
+
+            // the points reach past the view on both sides, the extent doesn't
+            let visible = data
+                .iter()
+                .filter(|p| (job.x_from..=job.x_to).contains(&(p.0 as RangePrec + cached.base)));
+            if let Some(extent) = y_extent(visible.map(|p| p.1)) {
+                result.add_extent(trace.idx, transform_extent(extent, transform));
+                colors.push((trace.idx, trace.color));
+            }
+            result.add_decimation(trace.idx, Decimation::EXACT);
+
+            gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&cached.buffer));
+            gl.vertex_attrib_pointer_with_i32(0, 2, WebGl2RenderingContext::FLOAT, false, 0, 0);
+            gl.enable_vertex_attrib_array(0);
+
+            match n {
+                0 => {}
+                1 => self.draw_markers(
+                    Marker::Circle,
+                    DEFAULT_MARKER_SIZE.max(trace.width as f32 * 2.0),
+                    (&cached.buffer, None),
+                    0,
+                    1,
+                ),
+                _ if self.msaa && trace.width as f32 <= self.line_width_limit => {
+                    gl.draw_arrays(WebGl2RenderingContext::LINE_STRIP, 0, n as i32)
+                }
+                _ => {
+                    if let Some(projection) = Projection::new(module, &job, &rect, trace.idx) {
+                        let projection = projection.with_x_origin(cached.base);
+                        let pixels: Vec<_> = data.iter().map(|&p| projection.apply(p)).collect();
+                        let color = trace.color.map(|c| c as f32 / 255.0);
+
+                        self.draw_stroke(&pixels, trace.width as f32, color, &rect);
+                    }
+                }
+            }
+
+            webgl_utils::check(gl, "direct traces", Some(trace.idx))?;
+        }
+
+        let out_of_view: Vec<_> = result
+            .extents
+            .iter()
+            .filter_map(|e| {
+                let (_, color) = colors.iter().find(|(handle, _)| *handle == e.handle)?;
+                Some((e.handle, *color, (e.min, e.max)))
+            })
+            .collect();
+
+        if let Some(annotations) = self.annotations.get(&self.chart) {
+            let mut lines = DrawList::new(self.width, self.height);
+            lines.push_annotation_lines(&job, annotations);
+            self.execute(&lines);
+            webgl_utils::check(gl, "annotation lines", None)?;
+        }
+
+        let mut arrows = DrawList::new(self.width, self.height);
+        arrows.push_out_of_view(&job, &out_of_view);
+        self.execute(&arrows);
+        webgl_utils::check(gl, "out of view arrows", None)?;
+
+        if let (Some(now), true) = (job.now, now_marker) {
+            self.render_now_marker(&job, now, false);
+            webgl_utils::check(gl, "now marker", None)?;
+        }
+
+        if job.render_legend {
+            let traces = self
+                .shown_bundles(&job)
+                .flat_map(|b| b.buffers.iter())
+                .map(|row| (row.handle, row.entry.color, row.entry.width))
+                .chain(job.get_traces().iter().map(|t| (t.idx, t.color, t.width)));
+            let items = legend_items(module, &job, traces);
+
+            if !items.is_empty() {
+                let layout = LegendLayout::of(&items, &rect, |text| self.text.measure(text));
+                let mut legend = DrawList::new(self.width, self.height);
+                legend.push_legend(&job, &items, &layout);
+                self.execute(&legend);
+                webgl_utils::check(gl, "legend", None)?;
+
+                result.set_legend(layout.hits(&items, self.height));
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl Renderer for WebGlRenderer {
+    fn render(&mut self, module: &DataModule, job: RenderJob) -> Result<RenderJobResult, JsValue> {
+        // charts with an overlay layer draw the now marker there, see `render_now`
+        let now_marker = !self.overlays.contains_key(&self.chart);
+        self.render_frame(module, job, now_marker)
+    }
+
+    fn backend(&self) -> &'static str {
+        "webgl"
+    }
+
+    fn features(&self) -> FeatureSet {
+        FeatureSet {
+            dash: true,
+            instancing: self.marker_quad.is_some(),
+            text: true,
+            msaa: self.msaa,
+            float_textures: self.float_textures,
+        }
+    }
+
+    fn frame_key(&self, job: &RenderJob) -> Option<JobFingerprint> {
+        // the now marker of charts with an overlay layer moves without changing the frame
+        let mut job = job.clone();
+        if self.overlays.contains_key(&self.chart) {
+            job.now = None;
+        }
+
+        Some(JobFingerprint::of(
+            &job,
+            self.width,
+            self.height,
+            self.generation,
+        ))
+    }
+
+    fn add_annotation(&mut self, annotation: Annotation) -> u32 {
+        self.annotations
+            .entry(self.chart)
+            .or_default()
+            .add(annotation)
+    }
+
+    fn remove_annotation(&mut self, id: u32) -> bool {
+        self.annotations
+            .get_mut(&self.chart)
+            .is_some_and(|a| a.remove(id))
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn size_changed(&mut self, width: u32, height: u32) -> Result<(), JsValue> {
+        self.width = width;
+        self.height = height;
+
+        let present = &self.charts[&self.chart];
+        present.set_width(width);
+        present.set_height(height);
+        self.fit_shared_canvas();
+
+        Ok(())
+    }
+
+    fn add_chart(&mut self, chart: u32, present_canvas: OffscreenCanvas) -> Result<(), JsValue> {
+        if self.charts.contains_key(&chart) {
+            return Err(JsValue::from_str(&format!(
+                "Chart {} already exists",
+                chart
+            )));
+        }
+
+        self.charts.insert(chart, present_canvas);
+
+        Ok(())
+    }
+
+    fn remove_chart(&mut self, chart: u32) -> Result<(), JsValue> {
+        if chart == self.chart {
+            return Err(JsValue::from_str("The selected chart can't be removed"));
+        }
+
+        self.charts.remove(&chart);
+        self.overlays.remove(&chart);
+        self.annotations.remove(&chart);
+
+        Ok(())
+    }
+
+    fn select_chart(&mut self, chart: u32) -> Result<(), JsValue> {
+        let present = self
+            .charts
+            .get(&chart)
+            .ok_or_else(|| JsValue::from_str(&format!("Chart {} doesn't exist", chart)))?;
+
+        (self.width, self.height) = (present.width(), present.height());
+        self.chart = chart;
+        self.fit_shared_canvas();
+
+        Ok(())
+    }
+
+    fn set_overlay_canvas(&mut self, canvas: Option<OffscreenCanvas>) -> Result<(), JsValue> {
+        match canvas {
+            Some(canvas) => self.overlays.insert(self.chart, canvas),
+            None => self.overlays.remove(&self.chart),
+        };
+
+        Ok(())
+    }
+
+    fn render_overlay(
+        &mut self,
+        job: &RenderJob,
+        cursor: (RangePrec, RangePrec),
+        ruler: bool,
+        max_px: f32,
+    ) -> Result<Option<HitResult>, JsValue> {
+        let Some(overlay) = self.overlays.get(&self.chart) else {
+            return Err(JsValue::from_str("The chart has no overlay canvas"));
+        };
+
+        let hit = self.find_closest(job, cursor.0, cursor.1, max_px);
+        let snapped = hit.as_ref().and_then(|hit| {
+            let row = self
+                .bundles
+                .values()
+                .flat_map(|b| b.buffers.iter())
+                .find(|row| row.handle == hit.handle)?;
+
+            Some((hit.handle, row.entry.color, (hit.x, hit.y)))
+        });
+
+        // the layer is drawn in the shared canvas like a frame, then presented on its own
+        let mut list = DrawList::new(self.width, self.height);
+        list.push_crosshair(job, cursor, snapped, ruler);
+
+        self.clear(None);
+        self.render_now_layer(job)?;
+        self.execute(&list);
+        webgl_utils::check(&self.context, "crosshair", None)?;
+
+        render_between(&self._canvas, overlay, self.width, self.height);
+
+        Ok(hit)
+    }
+
+    fn render_brush(
+        &mut self,
+        job: &RenderJob,
+        from: (RangePrec, RangePrec),
+        to: (RangePrec, RangePrec),
+    ) -> Result<Vec<BrushHit>, JsValue> {
+        let Some(overlay) = self.overlays.get(&self.chart) else {
+            return Err(JsValue::from_str("The chart has no overlay canvas"));
+        };
+
+        let x = (from.0.min(to.0), from.0.max(to.0));
+        let ys = job.y_axis_scale();
+        // the brush is in primary axis coordinates, the traces on the secondary one aren't
+        let to_axis = |y, (lo, hi): (RangePrec, RangePrec)| {
+            let t = ys.reversed(false).position(y, job.y_from, job.y_to);
+            ys.invert(ys.apply(lo) + (ys.apply(hi) - ys.apply(lo)) * t)
+        };
+
+        let hits = self
+            .shown_bundles(job)
+            .flat_map(|b| b.buffers.iter())
+            .filter(|row| !job.is_blacklisted(row.handle))
+            .filter_map(|row| {
+                let range = job.y_range(row.handle);
+                // and the points are stored before the entry's transform
+                let (scale, offset) = row.entry.y_transform();
+                let stored = |y| (to_axis(y, range) - offset) / scale;
+                let (y0, y1) = (stored(from.1), stored(to.1));
+                let ranges = row.hit_index.runs_within(x, (y0.min(y1), y0.max(y1)));
+
+                (!ranges.is_empty()).then_some(BrushHit {
+                    handle: row.handle,
+                    ranges,
+                })
+            })
+            .collect();
+
+        let mut list = DrawList::new(self.width, self.height);
+        list.push_brush(job, from, to);
+
+        self.clear(None);
+        self.render_now_layer(job)?;
+        self.execute(&list);
+        webgl_utils::check(&self.context, "brush", None)?;
+
+        render_between(&self._canvas, overlay, self.width, self.height);
+
+        Ok(hits)
+    }
+
+    fn render_playhead(
+        &mut self,
+        job: &RenderJob,
+        x: RangePrec,
+        trail: RangePrec,
+    ) -> Result<Vec<PlayheadValue>, JsValue> {
+        let Some(overlay) = self.overlays.get(&self.chart) else {
+            return Err(JsValue::from_str("The chart has no overlay canvas"));
+        };
+
+        let rows: Vec<_> = self
+            .shown_bundles(job)
+            .flat_map(|b| b.buffers.iter())
+            .filter(|row| !job.is_blacklisted(row.handle))
+            .filter_map(|row| Some((row.handle, row.entry.color, row.hit_index.value_at(x)?)))
+            .collect();
+
+        let mut list = DrawList::new(self.width, self.height);
+        list.push_playhead(job, x, trail, &rows);
+
+        self.clear(None);
+        self.render_now_layer(job)?;
+        self.execute(&list);
+        webgl_utils::check(&self.context, "playhead", None)?;
+
+        render_between(&self._canvas, overlay, self.width, self.height);
+
+        Ok(rows
+            .into_iter()
+            .map(|(handle, _, y)| PlayheadValue { handle, y })
+            .collect())
+    }
+
+    fn render_now(&mut self, job: &RenderJob) -> Result<(), JsValue> {
+        let Some(overlay) = self.overlays.get(&self.chart) else {
+            return Err(JsValue::from_str("The chart has no overlay canvas"));
+        };
+
+        self.clear(None);
+        self.render_now_layer(job)?;
+
+        render_between(&self._canvas, overlay, self.width, self.height);
+
+        Ok(())
+    }
+
+    fn create_bundle(
+        &mut self,
+        module: &DataModule,
+        from: RangePrec,
+        to: RangePrec,
+        data: &[BundleEntry],
+        opts: BundleOptions,
+    ) -> Result<usize, JsValue> {
+        let (from, to, opts) = opts.overscanned(from, to);
+        let entries = self.default_stacking(data, 0);
+        // without a context the bundle is kept as released, to be built once it's restored
+        let (vec, released) = match self.lost() {
+            true => (Vec::new(), Some(entries)),
+            false => (
+                WebGlRenderer::build_entries(&self.context, module, from, to, opts, &entries)?,
+                None,
+            ),
+        };
+
+        let handle = self.bundles_counter;
+        self.bundles_counter += 1;
+        self.generation += 1;
+        self.bundles.insert(
+            handle,
+            BufferBundle {
+                from,
+                to,
+                opts,
+                buffers: vec,
+                released,
+            },
+        );
+
+        Ok(handle)
+    }
+
+    fn begin_bundle(
+        &mut self,
+        module: &DataModule,
+        from: RangePrec,
+        to: RangePrec,
+        data: &[BundleEntry],
+        opts: BundleOptions,
+        replaces: Option<usize>,
+    ) -> Result<usize, JsValue> {
+        let handle = match replaces {
+            Some(bundle) if self.bundles.contains_key(&bundle) => bundle,
+            _ => {
+                self.bundles_counter += 1;
+                self.bundles_counter - 1
+            }
+        };
+
+        let (from, to, opts) = opts.overscanned(from, to);
+        let entries = self.default_stacking(data, 0);
+        let pending = PendingBundle::new(module, from, to, opts, entries);
+        // an unfinished build of the same bundle is superseded
+        if let Some(previous) = self.pending.insert(handle, pending) {
+            for row in previous.built.into_iter().flatten() {
+                WebGlRenderer::delete_entry(&self.context, row);
+            }
+        }
+
+        Ok(handle)
+    }
+
+    fn step_bundle(
+        &mut self,
+        module: &DataModule,
+        bundle: usize,
+        chunks: usize,
+    ) -> Result<f64, JsValue> {
+        if !self.pending.contains_key(&bundle) {
+            return Ok(1.0);
+        }
+
+        // without a context the rest is built along with the other bundles once it's restored
+        if self.lost() {
+            let pending = self.pending.remove(&bundle).unwrap();
+            self.generation += 1;
+            self.bundles.insert(bundle, pending.released());
+
+            return Ok(1.0);
+        }
+
+        let pending = self.pending.get_mut(&bundle).unwrap();
+        for _ in 0..chunks.max(1) {
+            let Some(chunk) = pending.chunks.pop_front() else {
+                break;
+            };
+            let entries: Vec<_> = chunk.iter().map(|&i| pending.entries[i].clone()).collect();
+
+            let built = WebGlRenderer::build_entries(
+                &self.context,
+                module,
+                pending.from,
+                pending.to,
+                pending.opts,
+                &entries,
+            );
+            let built = match built {
+                Ok(built) => built,
+                Err(e) => {
+                    // the bundle being replaced keeps its handle and goes on being drawn
+                    self.abandon_pending(bundle);
+                    return Err(e);
+                }
+            };
+
+            for (i, row) in chunk.into_iter().zip(built) {
+                pending.built[i] = Some(row);
+            }
+        }
+
+        if !pending.chunks.is_empty() {
+            return Ok(pending.progress());
+        }
+
+        let built = self.pending.remove(&bundle).unwrap().finished();
+
+        self.generation += 1;
+        if let Some(replaced) = self.bundles.insert(bundle, built) {
+            for row in replaced.buffers {
+                WebGlRenderer::delete_entry(&self.context, row);
+            }
+        }
+
+        Ok(1.0)
+    }
+
+    fn dispose_bundle(&mut self, bundle: usize) -> Result<(), JsValue> {
+        self.abandon_pending(bundle);
+
+        if !self.bundles.contains_key(&bundle) {
+            return Ok(());
+        }
+
+        self.hidden_bundles.remove(&bundle);
+        let bundle = self.bundles.remove(&bundle).unwrap();
+        self.generation += 1;
+
+        for row in bundle.buffers {
+            WebGlRenderer::delete_entry(&self.context, row);
+        }
+
+        Ok(())
+    }
+
+    fn set_bundle_visible(&mut self, bundle: usize, visible: bool) {
+        let changed = match visible {
+            true => self.hidden_bundles.remove(&bundle),
+            false => self.hidden_bundles.insert(bundle),
+        };
+        if changed {
+            self.generation += 1;
         }
+    }
 
-        gl.uniform2f(Some(&self.tp_origin_pos), 0.0, y_from);
+    fn extend_bundle(
+        &mut self,
+        bundle: usize,
+        handle: DataIdx,
+        points: &[(RangePrec, RangePrec)],
+    ) -> Result<(), JsValue> {
+        const POINT_BYTES: usize = 2 * std::mem::size_of::<f32>();
 
-        if !job.get_traces().is_empty() {
-            gl.bind_buffer(
-                WebGl2RenderingContext::ARRAY_BUFFER,
-                Some(&self.trace_buffer),
-            );
+        let Some(b) = self.bundles.get_mut(&bundle) else {
+            return Ok(());
+        };
+        b.ensure_resident()?;
+        let from = b.from;
+        let Some(row) = b.buffers.iter_mut().find(|e| e.handle == handle) else {
+            return Ok(());
+        };
 
-            for trace in job.get_traces() {
-                let n;
+        if row.area_buffer.is_some() || row.candles.is_some() {
+            return Err(JsValue::from_str(
+                "Filled, stacked and OHLC entries can't be extended, rebundle them instead",
+            ));
+        }
 
-                gl.uniform4f(
-                    Some(&self.tp_color_pos),
-                    trace.color[0] as f32 / 255.0,
-                    trace.color[1] as f32 / 255.0,
-                    trace.color[2] as f32 / 255.0,
-                    1.0,
-                );
-                gl.line_width(trace.width as f32);
-
-                unsafe {
-                    let data = module
-                        .get_trace(trace.idx)
-                        .map(|t| {
-                            t.get_data_with_origin(job.x_from - 1., job.x_to + 1., job.x_from, 0.0)
-                                .flat_map(|(x, y)| [x, y])
-                                .collect::<Vec<_>>()
-                        })
-                        .expect("Invalid entry handle during bundling");
+        // points out of order are dropped before the breaks are found, so they line up
+        let mut last = row.hit_index.points().last().map(|p| p.0);
+        let mut points: Vec<(f32, f32)> = points
+            .iter()
+            .map(|&(x, y)| ((x - from) as f32, y as f32))
+            .filter(|&(x, _)| {
+                let next = last.is_none_or(|last| x > last);
+                if next {
+                    last = Some(x);
+                }
+                next
+            })
+            .collect();
+        let breaks = row
+            .gaps
+            .split(&mut points, row.hit_index.points().last().copied());
+        row.breaks
+            .extend(breaks.into_iter().map(|i| row.points + i));
+
+        let appended = row.hit_index.append(&points);
+        let Some(&(last_x, _)) = appended.last() else {
+            return Ok(());
+        };
+        let (start, count) = (row.points, appended.len());
 
-                    n = data.len() / 2;
-                    let vert_array = js_sys::Float32Array::view(&data);
+        let gl = &self.context;
+        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&row.buffer));
 
-                    gl.buffer_data_with_array_buffer_view(
-                        WebGl2RenderingContext::ARRAY_BUFFER,
-                        &vert_array,
-                        WebGl2RenderingContext::STATIC_DRAW,
-                    );
-                }
+        if start + count > row.capacity {
+            row.capacity = (start + count).max(row.capacity * 2);
+            gl.buffer_data_with_i32(
+                WebGl2RenderingContext::ARRAY_BUFFER,
+                (row.capacity * POINT_BYTES) as i32,
+                WebGl2RenderingContext::DYNAMIC_DRAW,
+            );
 
-                gl.vertex_attrib_pointer_with_i32(0, 2, WebGl2RenderingContext::FLOAT, false, 0, 0);
-                gl.enable_vertex_attrib_array(0);
-                gl.draw_arrays(WebGl2RenderingContext::LINE_STRIP, 0, n as i32);
-            }
+            let vertices: Vec<[f32; 2]> = row
+                .hit_index
+                .points()
+                .iter()
+                .map(|&(x, y)| [x, y])
+                .collect();
+            webgl_utils::upload_sub(gl, WebGl2RenderingContext::ARRAY_BUFFER, 0, &vertices);
+        } else {
+            let vertices: Vec<[f32; 2]> = appended.iter().map(|&(x, y)| [x, y]).collect();
+            webgl_utils::upload_sub(
+                gl,
+                WebGl2RenderingContext::ARRAY_BUFFER,
+                start * POINT_BYTES,
+                &vertices,
+            );
         }
 
-        Ok(RenderJobResult { x_ticks, y_ticks })
-    }
+        row.points = start + count;
 
-    fn size_changed(&mut self, width: u32, height: u32) -> Result<(), JsValue> {
-        self.width = width;
-        self.height = height;
+        // the levels of detail no longer cover the entry, it's drawn in full until rebundled
+        for lod in row.lods.drain(..) {
+            WebGlRenderer::delete_lod(gl, lod);
+        }
 
-        self._present_canvas.set_width(width);
-        self._present_canvas.set_height(height);
+        b.to = b.to.max(from + last_x as RangePrec);
+        self.generation += 1;
+        webgl_utils::check(gl, "bundle extension", Some(handle))?;
 
         Ok(())
     }
 
-    fn create_bundle(
+    fn create_heatmap(
         &mut self,
-        module: &DataModule,
-        from: RangePrec,
-        to: RangePrec,
-        data: &[super::BundleEntry],
+        values: &[f32],
+        cols: usize,
+        x: (RangePrec, RangePrec),
+        y: (RangePrec, RangePrec),
+        colormap: &Colormap,
     ) -> Result<usize, JsValue> {
-        let mut vec = Vec::with_capacity(data.len());
-        let mut area_adder = if self.is_area && !data.is_empty() {
-            module.get_trace(data[0].handle).map(|d| {
-                d.get_data_with_origin(from, to, from, 0.0)
-                    .map(|p| (p.0, 0.0))
-                    .collect()
-            })
-        } else {
-            None
-        };
-
-        for row in data {
-            vec.push(WebGlRenderer::allocate_bundle_entry(
-                &self.context,
-                module,
-                from,
-                to,
-                row,
-                area_adder.as_mut(),
-            )?);
+        let handle = self.bundles_counter;
+        if !self.lost() {
+            let layer = HeatmapLayer::new(&self.context, values, cols, x, y, colormap)?;
+            self.heatmaps.insert(handle, layer);
         }
 
-        let handle = self.bundles_counter;
         self.bundles_counter += 1;
-        self.bundles.insert(
+        self.generation += 1;
+        self.heatmap_sources.insert(
             handle,
-            BufferBundle {
-                from,
-                to,
-                buffers: vec,
+            HeatmapSource {
+                values: values.to_vec(),
+                cols,
+                x,
+                y,
+                colormap: colormap.clone(),
             },
         );
 
         Ok(handle)
     }
 
-    fn dispose_bundle(&mut self, bundle: usize) -> Result<(), JsValue> {
-        if !self.bundles.contains_key(&bundle) {
-            return Ok(());
+    fn dispose_heatmap(&mut self, heatmap: usize) -> Result<(), JsValue> {
+        self.heatmap_sources.remove(&heatmap);
+        if let Some(layer) = self.heatmaps.remove(&heatmap) {
+            layer.delete(&self.context);
+            self.generation += 1;
         }
 
-        let bundle = self.bundles.remove(&bundle).unwrap();
+        Ok(())
+    }
 
-        for row in bundle.buffers {
-            self.context.delete_buffer(Some(&row.buffer));
-            if row.area_buffer.is_some() {
-                self.context.delete_buffer(row.area_buffer.as_ref());
+    fn restack_bundle(
+        &mut self,
+        module: &DataModule,
+        bundle: usize,
+        stacking: &[(DataIdx, u32, i32)],
+    ) -> Result<(), JsValue> {
+        self.ensure_context()?;
+        let Some(b) = self.bundles.get_mut(&bundle) else {
+            return Ok(());
+        };
+        b.ensure_resident()?;
+
+        for &(handle, group, order) in stacking {
+            if let Some(row) = b.buffers.iter_mut().find(|e| e.handle == handle) {
+                row.entry.stack_group = group;
+                row.entry.stack_order = order;
             }
         }
 
-        Ok(())
+        self.restack(module, bundle)
     }
 
     fn rebundle(
         &mut self,
         module: &DataModule,
         bundle: usize,
-        to_add: &[super::BundleEntry],
+        to_add: &[BundleEntry],
         to_del: &[DataIdx],
-        to_mod: &[super::BundleEntry],
+        to_mod: &[BundleEntry],
     ) -> Result<(), JsValue> {
+        let changed = !to_add.is_empty() || !to_del.is_empty();
+        self.ensure_context()?;
+        self.bundles.get(&bundle).unwrap().ensure_resident()?;
+        self.generation += 1;
+        let existing = self.bundles.get(&bundle).unwrap().buffers.len();
+        let to_add = self.default_stacking(to_add, existing);
         let b = self.bundles.get_mut(&bundle).unwrap();
 
-        for row in to_add {
-            b.buffers.push(WebGlRenderer::allocate_bundle_entry(
-                &self.context,
-                module,
-                b.from,
-                b.to,
-                row,
-                None,
-            )?);
+        // added entries are built unstacked, the restack below puts them in place
+        let unstacked: Vec<BundleEntry> = to_add
+            .iter()
+            .map(|e| BundleEntry {
+                stack_group: 0,
+                ..e.clone()
+            })
+            .collect();
+        let added =
+            WebGlRenderer::build_entries(&self.context, module, b.from, b.to, b.opts, &unstacked)?;
+
+        for (mut row, entry) in added.into_iter().zip(to_add) {
+            row.entry = entry;
+            b.buffers.push(row);
         }
 
-        b.buffers.retain(|e| !to_del.iter().any(|t| *t == e.handle));
+        let (deleted, kept) = std::mem::take(&mut b.buffers)
+            .into_iter()
+            .partition(|e| to_del.contains(&e.handle));
+        b.buffers = kept;
+
+        for row in deleted {
+            WebGlRenderer::delete_entry(&self.context, row);
+        }
 
         for row in to_mod {
             if let Some(buffer) = b.buffers.iter_mut().find(|e| e.handle == row.handle) {
@@ -721,38 +2881,488 @@ impl Renderer for WebGlRenderer {
                     row.color[1] as f32 / 255.0,
                     row.color[2] as f32 / 255.0,
                 ];
-                buffer.points_mode = row.points_mode;
+                // the style changes, how the entry fills and stacks stays
+                buffer.entry = buffer.entry.restyled(row);
+                buffer.marker = buffer.entry.marker;
+                buffer.marker_size = buffer.entry.marker_size as f32;
             }
         }
 
+        if changed {
+            self.restack(module, bundle)?;
+        }
+
         Result::Ok(())
     }
 
+    fn suspend(&mut self, release_bundles: bool) -> Result<(), JsValue> {
+        self.overlay_cache.clear();
+        for cached in self.trace_cache.drain() {
+            self.context.delete_buffer(Some(&cached.buffer));
+        }
+
+        // the scratch buffer of direct traces grows to the largest trace drawn
+        self.context.bind_buffer(
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            Some(&self.trace_buffer),
+        );
+        self.context.buffer_data_with_i32(
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            0,
+            WebGl2RenderingContext::STATIC_DRAW,
+        );
+
+        if !release_bundles {
+            return Ok(());
+        }
+
+        self.suspended = true;
+        for bundle in self.bundles.values_mut() {
+            if bundle.released.is_some() {
+                continue;
+            }
+
+            let rows = std::mem::take(&mut bundle.buffers);
+            bundle.released = Some(rows.iter().map(|row| row.entry.clone()).collect());
+
+            for row in rows {
+                WebGlRenderer::delete_entry(&self.context, row);
+            }
+        }
+
+        self.generation += 1;
+
+        Ok(())
+    }
+
+    fn resume(&mut self, module: &DataModule) -> Result<(), JsValue> {
+        // a lost context builds the bundles once it's restored
+        self.suspended = false;
+        if self.lost() {
+            return Ok(());
+        }
+
+        for bundle in self.bundles.values_mut() {
+            if let Some(entries) = bundle.released.take() {
+                bundle.buffers = WebGlRenderer::build_entries(
+                    &self.context,
+                    module,
+                    bundle.from,
+                    bundle.to,
+                    bundle.opts,
+                    &entries,
+                )?;
+            }
+        }
+
+        self.generation += 1;
+
+        Ok(())
+    }
+
+    fn context_lost(&self) -> bool {
+        self.lost()
+    }
+
+    fn restore_context(&mut self, module: &DataModule) -> Result<bool, JsValue> {
+        if self.context_watch.status.get() != ContextStatus::Restored
+            || self.context.is_context_lost()
+        {
+            return Ok(false);
+        }
+
+        // programs, uniforms and the resources shared by all bundles are set up anew, the
+        // same way as for a new renderer
+        let opts = RendererOptions {
+            area_chart: self.is_area,
+            line_join: self.line_join,
+            line_cap: self.line_cap,
+        };
+        let present = self.charts[&self.chart].clone();
+        let fresh = WebGlRenderer::new(self._canvas.clone(), present, opts)?;
+        let mut lost = std::mem::replace(self, fresh);
+
+        self.charts = std::mem::take(&mut lost.charts);
+        self.chart = lost.chart;
+        (self.width, self.height) = (lost.width, lost.height);
+        self.overlays = std::mem::take(&mut lost.overlays);
+        self.annotations = std::mem::take(&mut lost.annotations);
+        self.bundles_counter = lost.bundles_counter;
+        self.hidden_bundles = std::mem::take(&mut lost.hidden_bundles);
+        self.generation = lost.generation + 1;
+        self.suspended = lost.suspended;
+        self.fit_shared_canvas();
+
+        // the objects of the lost context went with it, they're dropped without deleting
+        self.bundles = lost
+            .bundles
+            .drain()
+            .map(|(handle, bundle)| (handle, bundle.lost()))
+            .collect();
+        self.pending = lost
+            .pending
+            .drain()
+            .map(|(handle, pending)| (handle, pending.restarted(module)))
+            .collect();
+        lost.heatmaps.clear();
+        lost.trace_cache.drain();
+        lost.palette = None;
+        lost.marker_quad = None;
+
+        self.heatmap_sources = std::mem::take(&mut lost.heatmap_sources);
+        for (&handle, source) in &self.heatmap_sources {
+            let layer = HeatmapLayer::new(
+                &self.context,
+                &source.values,
+                source.cols,
+                source.x,
+                source.y,
+                &source.colormap,
+            )?;
+            self.heatmaps.insert(handle, layer);
+        }
+
+        if !self.suspended {
+            self.resume(module)?;
+        }
+
+        Ok(true)
+    }
+
+    fn find_closest(
+        &self,
+        job: &RenderJob,
+        x: RangePrec,
+        y: RangePrec,
+        max_px: f32,
+    ) -> Option<HitResult> {
+        let rect = PlotRect::of(job, self.width, self.height);
+
+        self.shown_bundles(job)
+            .flat_map(|b| b.buffers.iter())
+            .filter(|row| !job.is_blacklisted(row.handle))
+            .filter_map(|row| {
+                let range = job.y_range(row.handle);
+                row.hit_index
+                    .nearest(job, &rect, (x, y), range, row.entry.y_transform(), max_px)
+                    .map(|(index, dist)| (row, index, dist))
+            })
+            .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap())
+            .map(|(row, index, px_distance)| {
+                let (x, y) = row.hit_index.point(index);
+                let (scale, offset) = row.entry.y_transform();
+
+                HitResult {
+                    handle: row.handle,
+                    index,
+                    x,
+                    y: y * scale + offset,
+                    px_distance,
+                }
+            })
+    }
+
+    fn render_offscreen(
+        &mut self,
+        module: &DataModule,
+        jobs: &[RenderJob],
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<u8>, JsValue> {
+        self.restore_context(module)?;
+        self.ensure_context()?;
+        let gl = self.context.clone();
+
+        let max_size = gl
+            .get_parameter(WebGl2RenderingContext::MAX_RENDERBUFFER_SIZE)?
+            .as_f64()
+            .unwrap_or(0.0) as u32;
+        if width > max_size || height > max_size {
+            return Err(JsValue::from_str(&format!(
+                "Can't export {}x{} pixels, the GPU allows at most {} per side",
+                width, height, max_size
+            )));
+        }
+
+        // a texture target works with WebGL1 too, unlike 8 bit renderbuffers
+        let target = webgl_utils::create_texture(
+            &gl,
+            (width as i32, height as i32),
+            WebGl2RenderingContext::RGBA,
+            WebGl2RenderingContext::NEAREST,
+            &vec![0; width as usize * height as usize * 4],
+        )?;
+        let framebuffer = gl
+            .create_framebuffer()
+            .ok_or_else(|| JsValue::from_str("Failed to allocate a framebuffer"))?;
+
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&framebuffer));
+        gl.framebuffer_texture_2d(
+            WebGl2RenderingContext::FRAMEBUFFER,
+            WebGl2RenderingContext::COLOR_ATTACHMENT0,
+            WebGl2RenderingContext::TEXTURE_2D,
+            Some(&target),
+            0,
+        );
+
+        let (screen_width, screen_height) = (self.width, self.height);
+        (self.width, self.height) = (width, height);
+
+        let mut frame = || -> Result<Vec<u8>, JsValue> {
+            let status = gl.check_framebuffer_status(WebGl2RenderingContext::FRAMEBUFFER);
+            if status != WebGl2RenderingContext::FRAMEBUFFER_COMPLETE {
+                return Err(JsValue::from_str(&format!(
+                    "The export framebuffer is incomplete (0x{:x})",
+                    status
+                )));
+            }
+
+            for job in jobs {
+                self.render_frame(module, job.clone(), true)?;
+            }
+
+            let mut pixels = vec![0; width as usize * height as usize * 4];
+            gl.pixel_storei(WebGl2RenderingContext::PACK_ALIGNMENT, 1);
+            gl.read_pixels_with_opt_u8_array(
+                0,
+                0,
+                width as i32,
+                height as i32,
+                WebGl2RenderingContext::RGBA,
+                WebGl2RenderingContext::UNSIGNED_BYTE,
+                Some(&mut pixels),
+            )?;
+            webgl_utils::check(&gl, "export", None)?;
+
+            Ok(pixels)
+        };
+        let pixels = frame();
+
+        (self.width, self.height) = (screen_width, screen_height);
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        gl.delete_framebuffer(Some(&framebuffer));
+        gl.delete_texture(Some(&target));
+        gl.viewport(0, 0, screen_width as i32, screen_height as i32);
+
+        pixels
+    }
+
+    fn render_to_texture(
+        &mut self,
+        module: &DataModule,
+        job: RenderJob,
+    ) -> Result<ImageBitmap, JsValue> {
+        self.render_frame(module, job, true)?;
+
+        frame_bitmap(&self._canvas, self.width, self.height)
+    }
+
     #[allow(unused_unsafe)]
     fn present(&mut self) -> Result<(), JsValue> {
         unsafe {
-            render_between(&self._canvas, &self._present_canvas);
+            render_between(
+                &self._canvas,
+                &self.charts[&self.chart],
+                self.width,
+                self.height,
+            );
         }
 
         Ok(())
     }
 }
 
+/// Points of a trace between `from` and `to` with x relative to `base`, as drawn directly
+fn direct_points(
+    module: &DataModule,
+    handle: DataIdx,
+    from: RangePrec,
+    to: RangePrec,
+    base: RangePrec,
+) -> Vec<(f32, f32)> {
+    module
+        .get_trace(handle)
+        .map(|t| t.get_data_with_origin(from, to, base, 0.0).collect())
+        .expect("Invalid entry handle during bundling")
+}
+
+/// Extent after the `(scale, offset)` transform of a relative view
+fn transform_extent(
+    (min, max): (DataPrec, DataPrec),
+    (scale, offset): (RangePrec, RangePrec),
+) -> (DataPrec, DataPrec) {
+    let a = (min as RangePrec * scale + offset) as DataPrec;
+    let b = (max as RangePrec * scale + offset) as DataPrec;
+
+    (a.min(b), a.max(b))
+}
+
 impl Drop for WebGlRenderer {
     fn drop(&mut self) {
-        let bundles: Vec<usize> = self.bundles.keys().cloned().collect();
+        let mut bundles: Vec<usize> = self.bundles.keys().cloned().collect();
+        bundles.extend(self.pending.keys());
 
         for handle in bundles {
             self.dispose_bundle(handle)
                 .expect("Failed to dispose a bundle");
         }
+
+        for (_, layer) in self.heatmaps.drain() {
+            layer.delete(&self.context);
+        }
+
+        for cached in self.trace_cache.drain() {
+            self.context.delete_buffer(Some(&cached.buffer));
+        }
+
+        if let Some(palette) = self.palette.take() {
+            self.context.delete_texture(Some(&palette));
+        }
+
+        if let Some(quad) = self.marker_quad.take() {
+            quad.delete(&self.context);
+        }
     }
 }
 
-mod webgl_utils {
-    use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlShader};
+pub(super) mod webgl_utils {
+    use bytemuck::Pod;
+    use wasm_bindgen::{JsCast, JsValue};
+    use web_sys::{
+        WebGl2RenderingContext, WebGlBuffer, WebGlProgram, WebGlShader, WebGlTexture,
+        WebGlVertexArrayObject,
+    };
+
+    use super::RendererError;
+    use crate::data::DataIdx;
+
+    /// With the `gl-debug` feature, drains the GL error flags raised since the last check and
+    /// reports the first one as caused by `pass`. Otherwise it's a no-op, as `get_error` stalls
+    /// the pipeline.
+    #[allow(unused_variables)]
+    pub fn check(
+        gl: &WebGl2RenderingContext,
+        pass: &'static str,
+        handle: Option<DataIdx>,
+    ) -> Result<(), RendererError> {
+        #[cfg(feature = "gl-debug")]
+        {
+            let mut first = None;
+
+            // one flag is kept per error kind, each call clears one of them
+            loop {
+                match gl.get_error() {
+                    WebGl2RenderingContext::NO_ERROR => break,
+                    code => {
+                        first.get_or_insert(code);
+
+                        // a lost context keeps reporting, there's nothing more to learn
+                        if code == WebGl2RenderingContext::CONTEXT_LOST_WEBGL {
+                            break;
+                        }
+                    }
+                }
+            }
+
+            if let Some(code) = first {
+                return Err(RendererError { code, pass, handle });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Uploads `data` into the buffer bound to `target`. This is the only place viewing wasm
+    /// memory from JS, any allocation would invalidate the view, so it lives for a single call.
+    pub fn upload<T: Pod>(gl: &WebGl2RenderingContext, target: u32, data: &[T], usage: u32) {
+        let floats: &[f32] = bytemuck::cast_slice(data);
+
+        // SAFETY: the view is consumed by `buffer_data` right away, with no allocation in between
+        let view = unsafe { js_sys::Float32Array::view(floats) };
+        gl.buffer_data_with_array_buffer_view(target, &view, usage);
+    }
+
+    /// Overwrites the buffer bound to `target` with `data` from `offset` bytes on, without
+    /// reallocating it
+    pub fn upload_sub<T: Pod>(gl: &WebGl2RenderingContext, target: u32, offset: usize, data: &[T]) {
+        let floats: &[f32] = bytemuck::cast_slice(data);
+
+        // SAFETY: same as in `upload`
+        let view = unsafe { js_sys::Float32Array::view(floats) };
+        gl.buffer_sub_data_with_i32_and_array_buffer_view(target, offset as i32, &view);
+    }
+
+    /// Vertex array reading `points` as pairs of floats at attribute 0 and, when given,
+    /// `values` as single floats at attribute 1. WebGL1 contexts have no vertex arrays.
+    pub fn create_vertex_array(
+        gl: &WebGl2RenderingContext,
+        points: &WebGlBuffer,
+        values: Option<&WebGlBuffer>,
+    ) -> Option<WebGlVertexArrayObject> {
+        if !gl.is_instance_of::<WebGl2RenderingContext>() {
+            return None;
+        }
+        let vao = gl.create_vertex_array()?;
+
+        gl.bind_vertex_array(Some(&vao));
+        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(points));
+        gl.vertex_attrib_pointer_with_i32(0, 2, WebGl2RenderingContext::FLOAT, false, 0, 0);
+        gl.enable_vertex_attrib_array(0);
+
+        if let Some(values) = values {
+            gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(values));
+            gl.vertex_attrib_pointer_with_i32(1, 1, WebGl2RenderingContext::FLOAT, false, 0, 0);
+            gl.enable_vertex_attrib_array(1);
+        }
+        gl.bind_vertex_array(None);
+
+        Some(vao)
+    }
 
-    use crate::{prelude::*, renderers::AxisTick};
+    /// Texture holding `data` in `format`, clamped at its edges
+    pub fn create_texture(
+        gl: &WebGl2RenderingContext,
+        (width, height): (i32, i32),
+        format: u32,
+        filter: u32,
+        data: &[u8],
+    ) -> Result<WebGlTexture, JsValue> {
+        let texture = gl
+            .create_texture()
+            .ok_or_else(|| JsValue::from_str("Failed to allocate a texture"))?;
+
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&texture));
+        // rows of odd widths aren't padded to 4 bytes
+        gl.pixel_storei(WebGl2RenderingContext::UNPACK_ALIGNMENT, 1);
+        gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            WebGl2RenderingContext::TEXTURE_2D,
+            0,
+            format as i32,
+            width,
+            height,
+            0,
+            format,
+            WebGl2RenderingContext::UNSIGNED_BYTE,
+            Some(data),
+        )?;
+
+        for (param, value) in [
+            (WebGl2RenderingContext::TEXTURE_MIN_FILTER, filter),
+            (WebGl2RenderingContext::TEXTURE_MAG_FILTER, filter),
+            (
+                WebGl2RenderingContext::TEXTURE_WRAP_S,
+                WebGl2RenderingContext::CLAMP_TO_EDGE,
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_WRAP_T,
+                WebGl2RenderingContext::CLAMP_TO_EDGE,
+            ),
+        ] {
+            gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, param, value as i32);
+        }
+
+        Ok(texture)
+    }
 
     pub fn compile_shader(
         context: &WebGl2RenderingContext,
@@ -782,6 +3392,16 @@ mod webgl_utils {
         context: &WebGl2RenderingContext,
         vert_shader: &WebGlShader,
         frag_shader: &WebGlShader,
+    ) -> Result<WebGlProgram, String> {
+        link_program_with_attributes(context, vert_shader, frag_shader, &[])
+    }
+
+    /// Links a program whose `attributes` are bound to the locations of their indices
+    pub fn link_program_with_attributes(
+        context: &WebGl2RenderingContext,
+        vert_shader: &WebGlShader,
+        frag_shader: &WebGlShader,
+        attributes: &[&str],
     ) -> Result<WebGlProgram, String> {
         let program = context
             .create_program()
@@ -789,6 +3409,9 @@ mod webgl_utils {
 
         context.attach_shader(&program, vert_shader);
         context.attach_shader(&program, frag_shader);
+        for (location, name) in attributes.iter().enumerate() {
+            context.bind_attrib_location(&program, location as u32, name);
+        }
         context.link_program(&program);
 
         if context
@@ -803,98 +3426,16 @@ mod webgl_utils {
                 .unwrap_or_else(|| String::from("Unknown error creating program object")))
         }
     }
-
-    pub fn calc_ticks(start: RangePrec, width: RangePrec) -> Box<[AxisTick]> {
-        const SIZES: [RangePrec; 4] = [1.0, 2.0, 5.0, 10.0];
-
-        let mut y0: RangePrec = 0.0;
-        let mut dy: RangePrec = 1.0;
-
-        {
-            let order = width.log10().floor() - 1.0;
-
-            for size in SIZES.iter() {
-                dy = 10.0_f64.powf(order) * size;
-                y0 = (start / dy).floor() * dy;
-
-                if (width + start - y0) / dy < 10.0 {
-                    break;
-                }
-            }
-        }
-
-        (1..=((width + start - y0) / dy).floor() as usize)
-            .map(|i| AxisTick {
-                val: y0 + dy * i as RangePrec,
-                pos: (y0 + dy * i as RangePrec - start) / width,
-            })
-            .collect()
-    }
 }
 
 use wasm_bindgen::prelude::*;
 #[wasm_bindgen(module = "/src/renderers/webgl.ts")]
 extern "C" {
-    fn render_between(source: &OffscreenCanvas, target: &OffscreenCanvas);
-}
-
-#[derive(Clone, Copy)]
-struct Vec2 {
-    pub x: f32,
-    pub y: f32,
-}
-
-#[allow(dead_code)]
-impl Vec2 {
-    fn new(x: f32, y: f32) -> Self {
-        Vec2 { x, y }
-    }
-
-    fn rotate_90(self) -> Self {
-        Self::new(-self.y, self.x)
-    }
-
-    fn len_sq(&self) -> f32 {
-        self.dot(self)
-    }
-
-    fn dot(&self, b: &Self) -> f32 {
-        self.x * b.x + self.y * b.y
-    }
-
-    fn normalize(self) -> Self {
-        let inv_len = 1.0 / self.len_sq().sqrt();
-
-        self * inv_len
-    }
-}
-
-impl From<(f32, f32)> for Vec2 {
-    fn from((x, y): (f32, f32)) -> Self {
-        Self::new(x, y)
-    }
-}
-
-impl Mul<f32> for Vec2 {
-    type Output = Vec2;
-
-    fn mul(self, rhs: f32) -> Self::Output {
-        Vec2::new(self.x * rhs, self.y * rhs)
-    }
-}
-
-impl Add<Vec2> for Vec2 {
-    type Output = Vec2;
-
-    fn add(self, rhs: Vec2) -> Self::Output {
-        Vec2::new(self.x + rhs.x, self.y + rhs.y)
-    }
-}
-
-impl Sub<Vec2> for Vec2 {
-    type Output = Vec2;
-
-    fn sub(self, rhs: Vec2) -> Self::Output {
-        Vec2::new(self.x - rhs.x, self.y - rhs.y)
-    }
+    fn render_between(source: &OffscreenCanvas, target: &OffscreenCanvas, width: u32, height: u32);
+    #[wasm_bindgen(catch)]
+    fn frame_bitmap(
+        source: &OffscreenCanvas,
+        width: u32,
+        height: u32,
+    ) -> Result<ImageBitmap, JsValue>;
 }
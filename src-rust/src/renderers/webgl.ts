@@ -1,9 +1,25 @@
 
-export function render_between(source: OffscreenCanvas, target: OffscreenCanvas) {
+/** Copies the bottom left `width` x `height` region of `source`, where the chart was drawn */
+export function render_between(source: OffscreenCanvas, target: OffscreenCanvas, width: number, height: number) {
     const ctxt = target.getContext('2d');
 
     if (!ctxt) return;
     
     ctxt.clearRect(0, 0, target.width, target.height);
-    ctxt.drawImage(source, 0, target.height - source.height);
-}
\ No newline at end of file
+    ctxt.drawImage(source, 0, source.height - height, width, height, 0, 0, width, height);
+}
+
+/**
+ * The bottom left `width` x `height` region of `source` as a bitmap. A canvas of just that size
+ * hands over its drawing buffer, larger ones are copied from.
+ */
+export function frame_bitmap(source: OffscreenCanvas, width: number, height: number): ImageBitmap {
+    if (source.width === width && source.height === height) {
+        return source.transferToImageBitmap();
+    }
+
+    const target = new OffscreenCanvas(width, height);
+    render_between(source, target, width, height);
+
+    return target.transferToImageBitmap();
+}
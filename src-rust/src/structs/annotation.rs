@@ -0,0 +1,111 @@
+use wasm_bindgen::prelude::*;
+
+use crate::prelude::*;
+
+/// Where an annotation lies, in data coordinates of the primary axes
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AnnotationKind {
+    /// Horizontal line at a y value
+    HLine(RangePrec),
+    /// Vertical line at an x value
+    VLine(RangePrec),
+    /// Region between two x values spanning the whole height
+    XBand(RangePrec, RangePrec),
+    /// Region between two y values spanning the whole width
+    YBand(RangePrec, RangePrec),
+}
+
+/// Threshold line or shaded region drawn along with the data, e.g. alarm limits
+#[wasm_bindgen]
+#[derive(Clone, PartialEq, Debug)]
+pub struct Annotation {
+    kind: AnnotationKind,
+    color: [u8; 3],
+    /// Opacity, bands default to a translucent shade
+    pub alpha: f32,
+    pub width: f32,
+    dash: Vec<f32>,
+    label: String,
+}
+
+#[wasm_bindgen]
+impl Annotation {
+    pub fn h_line(y: RangePrec, color: &[u8], label: String) -> Annotation {
+        Self::new(AnnotationKind::HLine(y), color, 1.0, label)
+    }
+
+    pub fn v_line(x: RangePrec, color: &[u8], label: String) -> Annotation {
+        Self::new(AnnotationKind::VLine(x), color, 1.0, label)
+    }
+
+    pub fn x_band(from: RangePrec, to: RangePrec, color: &[u8], label: String) -> Annotation {
+        Self::new(AnnotationKind::XBand(from, to), color, 0.2, label)
+    }
+
+    pub fn y_band(from: RangePrec, to: RangePrec, color: &[u8], label: String) -> Annotation {
+        Self::new(AnnotationKind::YBand(from, to), color, 0.2, label)
+    }
+
+    /// Alternating lengths in pixels of the dashes and gaps of a line, empty for a solid one
+    pub fn set_dash(&mut self, dash: &[f32]) {
+        self.dash = dash.to_vec();
+    }
+}
+
+impl Annotation {
+    fn new(kind: AnnotationKind, color: &[u8], alpha: f32, label: String) -> Self {
+        Self {
+            kind,
+            color: color.try_into().unwrap(),
+            alpha,
+            width: 1.0,
+            dash: Vec::new(),
+            label,
+        }
+    }
+
+    pub fn kind(&self) -> AnnotationKind {
+        self.kind
+    }
+
+    pub fn color(&self) -> [u8; 3] {
+        self.color
+    }
+
+    pub fn dash(&self) -> &[f32] {
+        &self.dash
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+/// Annotations of a chart in the order they were added, under their ids
+#[derive(Default, Debug)]
+pub struct Annotations {
+    counter: u32,
+    entries: Vec<(u32, Annotation)>,
+}
+
+impl Annotations {
+    pub fn add(&mut self, annotation: Annotation) -> u32 {
+        let id = self.counter;
+        self.counter += 1;
+        self.entries.push((id, annotation));
+
+        id
+    }
+
+    /// Removes the annotation of `id`, returning whether there was one
+    pub fn remove(&mut self, id: u32) -> bool {
+        let len = self.entries.len();
+        self.entries.retain(|(i, _)| *i != id);
+
+        self.entries.len() != len
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Annotation> {
+        self.entries.iter().map(|(_, a)| a)
+    }
+}
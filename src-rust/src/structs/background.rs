@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Background of the `Theme` variant in the light and the dark mode
+pub const LIGHT_BACKGROUND: [u8; 3] = [0xff, 0xff, 0xff];
+pub const DARK_BACKGROUND: [u8; 3] = [0x1a, 0x1a, 0x1a];
+
+/// What the frames of a `RenderJob` are cleared to, on screen and in exports alike
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Background {
+    /// Nothing, letting the page or the host's scene show through
+    #[default]
+    Transparent = 0,
+    /// The job's background color
+    Solid = 1,
+    /// White, or near black in the dark mode
+    Theme = 2,
+}
@@ -0,0 +1,46 @@
+use wasm_bindgen::prelude::*;
+
+use crate::data::DataIdx;
+
+use super::{Annotation, RenderJob, View};
+
+/// Snapshot of what a chart shows, its view, hidden traces and annotations, saved under a
+/// name by `RendererContainer.save_bookmark` to return to it later
+#[wasm_bindgen]
+#[derive(Clone, PartialEq, Debug)]
+pub struct Bookmark {
+    view: View,
+    hidden: Vec<DataIdx>,
+    annotations: Vec<Annotation>,
+}
+
+#[wasm_bindgen]
+impl Bookmark {
+    /// Snapshot of the view and blacklisted traces of `job`, without annotations
+    pub fn of_job(job: &RenderJob) -> Bookmark {
+        Self {
+            view: job.view(),
+            hidden: job.blacklist(),
+            annotations: Vec::new(),
+        }
+    }
+
+    /// Adds an annotation shown while the bookmark is applied
+    pub fn add_annotation(&mut self, annotation: &Annotation) {
+        self.annotations.push(annotation.clone());
+    }
+}
+
+impl Bookmark {
+    pub fn view(&self) -> View {
+        self.view
+    }
+
+    pub fn hidden(&self) -> &[DataIdx] {
+        &self.hidden
+    }
+
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.annotations
+    }
+}
@@ -6,6 +6,8 @@ use crate::{
     data_module::DataModule,
 };
 
+use super::{decoder, DecodedRows};
+
 #[wasm_bindgen]
 pub struct Bulkloader {
     ptrs: Vec<DataIdx>,
@@ -66,6 +68,40 @@ impl Bulkloader {
         })
     }
 
+    /// Reads a stream of a custom wire format through the decoder registered as `decoder`,
+    /// which converts it into the rows `from_stream` reads
+    pub async fn from_decoded_stream(
+        ptrs: Vec<DataIdx>,
+        x_type: String,
+        y_type: String,
+        stream: wasm_streams::readable::sys::ReadableStream,
+        decoder_name: String,
+    ) -> Result<Bulkloader, JsValue> {
+        let x_desc = TYPE_SIZES.get(x_type.as_str()).unwrap();
+        let y_desc = TYPE_SIZES.get(y_type.as_str()).unwrap();
+        let row_len_bytes = x_desc.size + y_desc.size * ptrs.len();
+
+        let decoder = decoder(&decoder_name).ok_or_else(|| {
+            JsValue::from_str(&format!("No decoder is registered as {}", decoder_name))
+        })?;
+        let mut rows = DecodedRows::new(decoder, row_len_bytes);
+
+        let mut stream = wasm_streams::ReadableStream::from_raw(stream);
+        let mut reader = stream.try_get_reader()?;
+
+        while let Some(chunk) = reader.read().await? {
+            rows.push(&Uint8Array::from(chunk).to_vec())
+                .map_err(|e| JsValue::from_str(&e))?;
+        }
+
+        Ok(Self {
+            ptrs,
+            x_desc,
+            y_desc,
+            data: rows.finish().map_err(|e| JsValue::from_str(&e))?,
+        })
+    }
+
     pub async fn from_array(
         ptrs: Vec<DataIdx>,
         x_type: String,
@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Continuous mapping of `[0, 1]` to colors, linearly interpolated between its stops
+#[wasm_bindgen]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Colormap {
+    stops: Vec<(f32, [u8; 3])>,
+}
+
+const VIRIDIS: [u32; 10] = [
+    0x440154, 0x482878, 0x3e4989, 0x31688e, 0x26828e, 0x1f9e89, 0x35b779, 0x6ece58, 0xb5de2b,
+    0xfde725,
+];
+
+const INFERNO: [u32; 10] = [
+    0x000004, 0x1b0c41, 0x4a0c6b, 0x781c6d, 0xa52c60, 0xcf4446, 0xed6925, 0xfb9b06, 0xf7d13d,
+    0xfcffa4,
+];
+
+#[wasm_bindgen]
+impl Colormap {
+    pub fn viridis() -> Colormap {
+        Self::evenly_spaced(&VIRIDIS)
+    }
+
+    pub fn inferno() -> Colormap {
+        Self::evenly_spaced(&INFERNO)
+    }
+
+    /// Custom colormap, `colors` holding an RGB triplet for each of the `positions`
+    pub fn from_stops(positions: &[f32], colors: &[u8]) -> Colormap {
+        let mut stops: Vec<_> = positions
+            .iter()
+            .zip(colors.chunks_exact(3))
+            .map(|(&pos, rgb)| (pos.clamp(0.0, 1.0), [rgb[0], rgb[1], rgb[2]]))
+            .collect();
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        if stops.is_empty() {
+            stops.push((0.0, [0, 0, 0]));
+        }
+
+        Colormap { stops }
+    }
+}
+
+impl Colormap {
+    fn evenly_spaced(colors: &[u32]) -> Self {
+        let last = (colors.len() - 1) as f32;
+
+        Self {
+            stops: colors
+                .iter()
+                .enumerate()
+                .map(|(i, &rgb)| {
+                    let [_, r, g, b] = rgb.to_be_bytes();
+                    (i as f32 / last, [r, g, b])
+                })
+                .collect(),
+        }
+    }
+
+    /// Color at `t`, values outside of `[0, 1]` are clamped
+    pub fn sample(&self, t: f32) -> [u8; 3] {
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let next = self.stops.partition_point(|s| s.0 < t);
+
+        if next == 0 {
+            return self.stops[0].1;
+        }
+        if next == self.stops.len() {
+            return self.stops[next - 1].1;
+        }
+
+        let (p0, c0) = self.stops[next - 1];
+        let (p1, c1) = self.stops[next];
+        let f = if p1 > p0 { (t - p0) / (p1 - p0) } else { 1.0 };
+
+        [0, 1, 2].map(|i| (c0[i] as f32 + (c1[i] as f32 - c0[i] as f32) * f).round() as u8)
+    }
+
+    /// `size` evenly spaced samples as RGBA bytes, e.g. for a lookup texture
+    pub fn lut(&self, size: usize) -> Vec<u8> {
+        let last = (size.max(2) - 1) as f32;
+
+        (0..size)
+            .flat_map(|i| {
+                let [r, g, b] = self.sample(i as f32 / last);
+                [r, g, b, 255]
+            })
+            .collect()
+    }
+}
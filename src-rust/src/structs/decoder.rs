@@ -0,0 +1,125 @@
+//! Decoders registered by hosts to turn chunks of their own wire formats into the rows the
+//! `Bulkloader` reads, its x followed by a y for each trace in the types it was given
+
+use std::{cell::RefCell, collections::HashMap};
+
+use js_sys::{Function, Uint8Array};
+use wasm_bindgen::prelude::*;
+
+/// Converts the chunks of a stream into rows, in as many or as few pieces as it likes. Records
+/// split across chunks are the decoder's to keep until the rest arrives.
+pub trait ChunkDecoder {
+    fn decode(&mut self, chunk: &[u8], rows: &mut Vec<u8>) -> Result<(), String>;
+
+    /// Called once the stream ended, to write out anything still held back
+    fn finish(&mut self, _rows: &mut Vec<u8>) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// JS callback or WebAssembly export taking a chunk as an `Uint8Array` and returning the
+/// decoded rows as one, called with an empty array once the stream ended
+struct JsDecoder(Function);
+
+impl JsDecoder {
+    fn call(&self, chunk: &[u8], rows: &mut Vec<u8>) -> Result<(), String> {
+        let decoded = self
+            .0
+            .call1(&JsValue::NULL, &Uint8Array::from(chunk))
+            .map_err(|err| format!("Decoder failed: {:?}", err))?;
+
+        let decoded = decoded
+            .dyn_into::<Uint8Array>()
+            .map_err(|_| "Decoder returned something else than an Uint8Array")?;
+        rows.extend(decoded.to_vec());
+
+        Ok(())
+    }
+}
+
+impl ChunkDecoder for JsDecoder {
+    fn decode(&mut self, chunk: &[u8], rows: &mut Vec<u8>) -> Result<(), String> {
+        self.call(chunk, rows)
+    }
+
+    fn finish(&mut self, rows: &mut Vec<u8>) -> Result<(), String> {
+        self.call(&[], rows)
+    }
+}
+
+#[derive(Clone)]
+enum Registered {
+    Js(Function),
+    Native(fn() -> Box<dyn ChunkDecoder>),
+}
+
+thread_local! {
+    static DECODERS: RefCell<HashMap<String, Registered>> = RefCell::new(HashMap::new());
+}
+
+/// Registers `callback` as the decoder `name`, replacing any registered before. See
+/// `Bulkloader.from_decoded_stream` for how it's called.
+#[wasm_bindgen]
+pub fn register_decoder(name: &str, callback: Function) {
+    DECODERS.with(|d| {
+        d.borrow_mut()
+            .insert(name.to_string(), Registered::Js(callback))
+    });
+}
+
+#[wasm_bindgen]
+pub fn unregister_decoder(name: &str) -> bool {
+    DECODERS.with(|d| d.borrow_mut().remove(name).is_some())
+}
+
+/// Registers a decoder compiled into the module as `name`, `create` making one per stream
+pub fn register_native_decoder(name: &str, create: fn() -> Box<dyn ChunkDecoder>) {
+    DECODERS.with(|d| {
+        d.borrow_mut()
+            .insert(name.to_string(), Registered::Native(create))
+    });
+}
+
+/// New instance of the decoder registered as `name`
+pub fn decoder(name: &str) -> Option<Box<dyn ChunkDecoder>> {
+    let registered = DECODERS.with(|d| d.borrow().get(name).cloned())?;
+
+    Some(match registered {
+        Registered::Js(callback) => Box::new(JsDecoder(callback)),
+        Registered::Native(create) => create(),
+    })
+}
+
+/// Rows decoded from a stream so far
+pub struct DecodedRows {
+    decoder: Box<dyn ChunkDecoder>,
+    row_len: usize,
+    rows: Vec<u8>,
+}
+
+impl DecodedRows {
+    pub fn new(decoder: Box<dyn ChunkDecoder>, row_len: usize) -> Self {
+        Self {
+            decoder,
+            row_len,
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) -> Result<(), String> {
+        self.decoder.decode(chunk, &mut self.rows)
+    }
+
+    /// All the rows, which have to add up to whole ones
+    pub fn finish(mut self) -> Result<Vec<u8>, String> {
+        self.decoder.finish(&mut self.rows)?;
+
+        match self.rows.len() % self.row_len {
+            0 => Ok(self.rows),
+            rest => Err(format!(
+                "Decoder left {} bytes of an incomplete {} byte row",
+                rest, self.row_len
+            )),
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use wasm_bindgen::prelude::*;
+
+use crate::prelude::*;
+
+use super::{RenderJob, ViewLock};
+
+/// Charts sharing their x axis, e.g. a stack of panels that pan and zoom together. Every
+/// member has its own job, the x range and axis settings of which are kept the same, so
+/// `RendererContainer.render_linked` draws them all with matching ticks in one call.
+#[wasm_bindgen]
+#[derive(Clone, Default)]
+pub struct LinkGroup {
+    members: Vec<(u32, RenderJob)>,
+}
+
+#[wasm_bindgen]
+impl LinkGroup {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `chart` drawn by `job`, or replaces its job. The x axis of `job` is replaced by
+    /// the group's unless it's the first member.
+    pub fn set_member(&mut self, chart: u32, mut job: RenderJob) {
+        if let Some((_, lead)) = self.members.iter().find(|(c, _)| *c != chart) {
+            share_x(lead, &mut job);
+        }
+
+        match self.members.iter_mut().find(|(c, _)| *c == chart) {
+            Some(member) => member.1 = job,
+            None => self.members.push((chart, job)),
+        }
+    }
+
+    pub fn remove_member(&mut self, chart: u32) -> bool {
+        let count = self.members.len();
+        self.members.retain(|(c, _)| *c != chart);
+
+        self.members.len() < count
+    }
+
+    pub fn charts(&self) -> Vec<u32> {
+        self.members.iter().map(|(c, _)| *c).collect()
+    }
+
+    /// Shows `from..to` along the x axis of every member
+    pub fn set_x_range(&mut self, from: RangePrec, to: RangePrec) {
+        for (_, job) in &mut self.members {
+            (job.x_from, job.x_to) = (from, to);
+        }
+    }
+
+    /// Zooms the x axis of every member by `factor` around `x`, leaving their y ranges be
+    pub fn zoom_x_at(&mut self, factor: RangePrec, x: RangePrec) {
+        self.update_x(|job| {
+            let mut lock = ViewLock::new();
+            let _ = lock.lock_y(job.y_from, job.y_to);
+            job.zoom_at(factor, x, job.y_from, &lock);
+        });
+    }
+
+    /// Moves the x axis of every member by `dx` data units
+    pub fn pan_x_by(&mut self, dx: RangePrec) {
+        self.update_x(|job| job.pan_by(dx, 0.0, &ViewLock::new()));
+    }
+}
+
+impl LinkGroup {
+    pub fn members(&self) -> &[(u32, RenderJob)] {
+        &self.members
+    }
+
+    pub fn job(&self, chart: u32) -> Option<&RenderJob> {
+        self.members.iter().find(|(c, _)| *c == chart).map(|m| &m.1)
+    }
+
+    /// Applies `update` to the first member and shares the resulting x axis with the others
+    fn update_x(&mut self, update: impl FnOnce(&mut RenderJob)) {
+        let Some(((_, lead), rest)) = self.members.split_first_mut() else {
+            return;
+        };
+
+        update(lead);
+        for (_, job) in rest {
+            share_x(lead, job);
+        }
+    }
+}
+
+/// Copies the x range and axis settings of `from` over to `to`
+fn share_x(from: &RenderJob, to: &mut RenderJob) {
+    to.x_from = from.x_from;
+    to.x_to = from.x_to;
+    to.x_scale = from.x_scale;
+    to.x_linthresh = from.x_linthresh;
+    to.x_tick_mode = from.x_tick_mode;
+    to.x_padding = from.x_padding;
+}
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Shape drawn at every data point of a trace
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Marker {
+    #[default]
+    None = 0,
+    Square = 1,
+    Circle = 2,
+    Triangle = 3,
+    Cross = 4,
+    Diamond = 5,
+}
+
+impl Marker {
+    /// Decodes the marker byte of bundle rows, where the legacy `points_mode` flag of 1 is a
+    /// square. `None` for bytes naming no shape.
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Marker::None),
+            1 => Some(Marker::Square),
+            2 => Some(Marker::Circle),
+            3 => Some(Marker::Triangle),
+            4 => Some(Marker::Cross),
+            5 => Some(Marker::Diamond),
+            _ => None,
+        }
+    }
+}
@@ -14,8 +14,8 @@ impl MetaCounter {
             sums: vec![0.0; len],
             lens: vec![0; len],
             nz_lens: vec![0; len],
-            mins: vec![std::f64::INFINITY; len],
-            maxs: vec![std::f64::NEG_INFINITY; len],
+            mins: vec![f64::INFINITY; len],
+            maxs: vec![f64::NEG_INFINITY; len],
         }
     }
 
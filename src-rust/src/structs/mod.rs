@@ -1,9 +1,29 @@
+mod annotation;
+mod background;
+mod bookmark;
 mod bulkloader;
+mod colormap;
+mod decoder;
+mod link_group;
+mod marker;
 mod meta_counter;
 mod render_job;
+mod scale;
 mod seg_numeric;
+mod view;
+mod wire;
 
+pub use annotation::*;
+pub use background::*;
+pub use bookmark::Bookmark;
 pub use bulkloader::*;
+pub use colormap::*;
+pub use decoder::*;
+pub use link_group::LinkGroup;
+pub use marker::*;
 pub use meta_counter::*;
-pub use render_job::RenderJob;
+pub use render_job::{RenderJob, TraceStyle};
+pub use scale::*;
 pub use seg_numeric::*;
+pub use view::*;
+pub use wire::*;
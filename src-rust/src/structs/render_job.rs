@@ -1,10 +1,29 @@
-use std::{collections::HashSet, convert::TryInto, mem::size_of};
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    convert::TryInto,
+    hash::{Hash, Hasher},
+    mem::size_of,
+};
 
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
-use crate::{data::DataIdx, prelude::*};
+use crate::{
+    data::DataIdx,
+    prelude::*,
+    renderers::{ticks, TickFormat},
+};
 
+use super::{
+    AxisScale, Background, LabelFormat, PaddingUnit, Scale, TickEnds, TickMode, Versioned, View,
+    ViewLock, DARK_BACKGROUND, LIGHT_BACKGROUND,
+};
+
+/// Fields missing from serialized jobs, e.g. ones written before the field existed, take the
+/// values of a new job
 #[wasm_bindgen]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RenderJob {
     pub clear: bool,
 
@@ -12,20 +31,105 @@ pub struct RenderJob {
 
     pub x_from: RangePrec,
     pub x_to: RangePrec,
+    pub x_scale: Scale,
+    pub x_linthresh: RangePrec,
+    pub x_tick_mode: TickMode,
+    /// Which ticks the ends of all the axes get
+    pub tick_ends: TickEnds,
     pub y_from: RangePrec,
     pub y_to: RangePrec,
+    pub y_scale: Scale,
+    pub y_linthresh: RangePrec,
+    /// Whether the x axis grows leftwards, e.g. for time running right to left
+    pub x_reversed: bool,
+    /// Whether the y axes grow downwards, e.g. for depth profiles
+    pub y_reversed: bool,
+
+    /// Most ticks on the x and y axes, `0` for the default of 10
+    pub x_tick_count: u32,
+    pub y_tick_count: u32,
+    /// Fewest pixels between neighbouring ticks, lowering the tick counts of short axes
+    pub min_tick_spacing: u32,
+    /// Distance between the ticks of linear axes in data units, `0` picking a 1, 2 or 5 step
+    pub x_tick_step: RangePrec,
+    pub y_tick_step: RangePrec,
+    /// How the labels of decimal x and y ticks show their values, the secondary y axis
+    /// sharing the primary one's
+    pub x_label_format: LabelFormat,
+    pub y_label_format: LabelFormat,
+    /// Digits after the decimal point of the labels, `None` for the format's default
+    pub x_label_decimals: Option<u32>,
+    pub y_label_decimals: Option<u32>,
+
+    /// Range of the secondary y axis on the right, sharing the primary axis' scale
+    pub y2_from: Option<RangePrec>,
+    pub y2_to: Option<RangePrec>,
+
+    /// Room added around the ranges on both sides, so nothing touches the edges of the plot.
+    /// Views from `fit_view` and `suggest_range` can be assigned as they are.
+    pub x_padding: RangePrec,
+    pub y_padding: RangePrec,
+    pub padding_unit: PaddingUnit,
 
     pub dark_mode: bool,
+    /// What frames are cleared to, the axes and grid contrasting with a solid background
+    /// whatever `dark_mode` says
+    pub background: Background,
+    /// Color of a `Background::Solid`, set with `set_background_color`
+    background_color: [u8; 3],
     pub render_grid: bool,
     pub render_axes: bool,
 
     pub margin: u32,
     pub x_label_space: u32,
     pub y_label_space: u32,
+    pub y2_label_space: u32,
+    /// Turns the x tick labels when they'd overlap, rather than only leaving more of them out
+    pub rotate_labels: bool,
+
+    /// Vertical slice of the canvas the job draws into, the whole canvas when `region_height` is 0
+    pub region_bottom: u32,
+    pub region_height: u32,
+
+    /// Position of the "now" marker, when `None` the renderer's clock is queried
+    pub now: Option<RangePrec>,
+    /// Shade the region right of the "now" marker, e.g. the forecast part of a chart
+    pub shade_future: bool,
+
+    /// Displays every trace as percent change from its value at this x
+    pub baseline_x: Option<RangePrec>,
+
+    /// Draws every stored point, bypassing decimation and levels of detail whatever the cost
+    pub exact: bool,
+    /// Fraction of the view's width traces drawn straight from the module are read past each
+    /// of its edges, so lines reach them rather than ending at the last point within the view
+    pub overscan: RangePrec,
+
+    /// Degrees the trace markers are turned counterclockwise by
+    pub marker_rotation: f32,
+
+    /// Cursor the entries of each bundle are reordered around every frame, those farthest from
+    /// it in y being drawn first so the nearest one ends up on top. Set both to raise entries.
+    pub raise_at_x: Option<RangePrec>,
+    pub raise_at_y: Option<RangePrec>,
+
+    /// Lists the traces in a box over the plot, reporting where they lie for toggling them
+    pub render_legend: bool,
+
+    /// Units of the axes for descriptions of the frame, empty when unknown
+    x_unit: String,
+    y_unit: String,
+    y2_unit: String,
+
+    /// Values ticked in place of the generated ticks, with their labels if they have one
+    x_tick_list: Vec<(RangePrec, Option<String>)>,
+    y_tick_list: Vec<(RangePrec, Option<String>)>,
 
     traces: Vec<TraceStyle>,
     bundles: Vec<usize>,
+    heatmaps: Vec<usize>,
     bundle_blacklist: HashSet<usize>,
+    y2_traces: HashSet<DataIdx>,
 }
 
 #[wasm_bindgen]
@@ -39,20 +143,76 @@ impl RenderJob {
 
             x_from: 0.0,
             x_to: 0.0,
+            x_scale: Scale::Linear,
+            x_linthresh: 1.0,
+            x_tick_mode: TickMode::Decimal,
+            tick_ends: TickEnds::Interior,
             y_from: 0.0,
             y_to: 0.0,
+            y_scale: Scale::Linear,
+            y_linthresh: 1.0,
+            x_reversed: false,
+            y_reversed: false,
+
+            x_tick_count: 0,
+            y_tick_count: 0,
+            min_tick_spacing: 0,
+            x_tick_step: 0.0,
+            y_tick_step: 0.0,
+            x_label_format: LabelFormat::Auto,
+            y_label_format: LabelFormat::Auto,
+            x_label_decimals: None,
+            y_label_decimals: None,
+
+            y2_from: None,
+            y2_to: None,
+
+            x_padding: 0.0,
+            y_padding: 0.0,
+            padding_unit: PaddingUnit::Data,
 
             dark_mode: false,
+            background: Background::Transparent,
+            background_color: LIGHT_BACKGROUND,
             render_axes: true,
             render_grid: true,
 
             margin: 0,
             x_label_space: 0,
             y_label_space: 0,
+            y2_label_space: 0,
+            rotate_labels: false,
+
+            region_bottom: 0,
+            region_height: 0,
+
+            now: None,
+            shade_future: false,
+
+            baseline_x: None,
+
+            exact: false,
+            overscan: 0.0,
+
+            marker_rotation: 0.0,
+
+            raise_at_x: None,
+            raise_at_y: None,
+
+            render_legend: false,
+
+            x_unit: String::new(),
+            y_unit: String::new(),
+            y2_unit: String::new(),
+
+            x_tick_list: Vec::new(),
+            y_tick_list: Vec::new(),
 
             traces: Vec::with_capacity(trace_count),
             bundles: Vec::with_capacity(bundle_count),
+            heatmaps: Vec::new(),
             bundle_blacklist: HashSet::new(),
+            y2_traces: HashSet::new(),
         }
     }
 
@@ -65,14 +225,81 @@ impl RenderJob {
         });
     }
 
+    /// Color of a `Background::Solid` as `[r, g, b]`
+    pub fn set_background_color(&mut self, color: &[u8]) {
+        self.background_color = color.try_into().unwrap();
+    }
+
+    /// Units of the x, y and secondary y axis reported by `describe`
+    pub fn set_units(&mut self, x_unit: String, y_unit: String, y2_unit: String) {
+        (self.x_unit, self.y_unit, self.y2_unit) = (x_unit, y_unit, y2_unit);
+    }
+
+    /// Ticks the x axis at `values` only, labeled by `labels` where given and otherwise by the
+    /// formatted values. No values bring back the generated ticks.
+    pub fn set_x_ticks(&mut self, values: Vec<f64>, labels: Vec<String>) {
+        self.x_tick_list = tick_list(values, labels);
+    }
+
+    /// Ticks the primary y axis at `values` only, as `set_x_ticks` does the x axis
+    pub fn set_y_ticks(&mut self, values: Vec<f64>, labels: Vec<String>) {
+        self.y_tick_list = tick_list(values, labels);
+    }
+
     pub fn add_bundle(&mut self, idx: usize) {
         self.bundles.push(idx);
     }
 
+    /// Draws the heatmap below the bundles and traces
+    pub fn add_heatmap(&mut self, idx: usize) {
+        self.heatmaps.push(idx);
+    }
+
     pub fn blacklist_trace(&mut self, handle: DataIdx) {
         self.bundle_blacklist.insert(handle);
     }
 
+    /// Shows all blacklisted traces again
+    pub fn clear_blacklist(&mut self) {
+        self.bundle_blacklist.clear();
+    }
+
+    /// Plots the trace against the secondary y axis
+    pub fn assign_y2(&mut self, handle: DataIdx) {
+        self.y2_traces.insert(handle);
+    }
+
+    /// Zooms the view by `factor` around `(x, y)` in data coordinates, e.g. on mouse wheel.
+    /// Axes held by `lock` stay put, as does the whole view when the zoom would ruin it.
+    pub fn zoom_at(&mut self, factor: RangePrec, x: RangePrec, y: RangePrec, lock: &ViewLock) {
+        let scales = (self.x_axis_scale(), self.y_axis_scale());
+        self.set_view(self.view().zoom(factor, (x, y), scales, lock));
+    }
+
+    /// Moves the view by `dx` and `dy` in data units, e.g. while dragging. Axes held by
+    /// `lock` stay put.
+    pub fn pan_by(&mut self, dx: RangePrec, dy: RangePrec, lock: &ViewLock) {
+        self.set_view(self.view().pan(dx, dy, lock));
+    }
+
+    /// `{ x_from, x_to, y_from, y_to }` actually shown, the ranges grown by the padding, e.g.
+    /// for mapping pointer positions to data coordinates
+    pub fn displayed_view(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.padded().view()).unwrap()
+    }
+
+    /// `{ version, data }` holding the whole job, for hosts persisting it across upgrades
+    pub fn to_wire(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&Versioned::new(self)).unwrap()
+    }
+
+    /// Job saved by `to_wire` of this or an older version
+    pub fn from_wire(wire: JsValue) -> Result<RenderJob, JsValue> {
+        let wire: Versioned<RenderJob> = serde_wasm_bindgen::from_value(wire)?;
+
+        wire.into_current().map_err(|e| JsValue::from_str(&e))
+    }
+
     pub fn deserialize_traces(&mut self, data: &[u8]) {
         const TRACE_ROW_SIZE: usize = 2 * size_of::<u32>() + 4;
 
@@ -95,8 +322,71 @@ impl RenderJob {
     }
 }
 
+impl Default for RenderJob {
+    fn default() -> Self {
+        Self::new("f64".to_string(), 0, 0)
+    }
+}
+
 // unbound methods
 impl RenderJob {
+    pub fn view(&self) -> View {
+        View::new((self.x_from, self.x_to), (self.y_from, self.y_to))
+    }
+
+    pub fn set_view(&mut self, view: View) {
+        (self.x_from, self.x_to) = view.x();
+        (self.y_from, self.y_to) = view.y();
+    }
+
+    /// Copy of the job with flat ranges inflated, its ranges grown by its padding, which is
+    /// then zero, and widened to nice bounds if its `tick_ends` asks for them. Renderers are given padded jobs, so
+    /// the transform, ticks and hit tests all agree.
+    pub fn padded(&self) -> RenderJob {
+        let (x_scale, y_scale) = (self.x_axis_scale(), self.y_axis_scale());
+        let nice =
+            |range: (RangePrec, RangePrec),
+             scale: AxisScale,
+             mode: TickMode,
+             density: ticks::TickDensity| match (self.tick_ends, scale.scale, mode) {
+                (TickEnds::NiceBounds, Scale::Linear, TickMode::Decimal) if range.0 < range.1 => {
+                    ticks::nice_bounds_with(range.0, range.1, &density)
+                }
+                _ => range,
+            };
+
+        let flat = |(from, to)| ticks::inflate(from, to);
+
+        let x = x_scale.pad(flat(self.view().x()), self.x_padding, self.padding_unit);
+        let (x_from, x_to) = nice(x, x_scale, self.x_tick_mode, self.x_tick_density());
+        let y = y_scale.pad(flat(self.view().y()), self.y_padding, self.padding_unit);
+        let (y_from, y_to) = nice(y, y_scale, TickMode::Decimal, self.y_tick_density());
+        let y2 = self.y2_range().map(|y2| {
+            let y2 = y_scale.pad(flat(y2), self.y_padding, self.padding_unit);
+            nice(y2, y_scale, TickMode::Decimal, self.y_tick_density())
+        });
+
+        RenderJob {
+            x_from,
+            x_to,
+            y_from,
+            y_to,
+            y2_from: y2.map(|y2| y2.0),
+            y2_to: y2.map(|y2| y2.1),
+            x_padding: 0.0,
+            y_padding: 0.0,
+            ..self.clone()
+        }
+    }
+
+    /// x range traces drawn straight from the module are read over, the view grown by the
+    /// overscan
+    pub fn overscan_range(&self) -> (RangePrec, RangePrec) {
+        let margin = (self.x_to - self.x_from) * self.overscan.max(0.0);
+
+        (self.x_from - margin, self.x_to + margin)
+    }
+
     pub fn get_traces(&self) -> &Vec<TraceStyle> {
         &self.traces
     }
@@ -105,19 +395,225 @@ impl RenderJob {
         &self.bundles
     }
 
+    pub fn get_heatmaps(&self) -> &Vec<usize> {
+        &self.heatmaps
+    }
+
     pub fn get_x_type(&self) -> &String {
         &self.x_type
     }
 
+    /// Color frames are cleared to, `None` leaving them transparent
+    pub fn clear_color(&self) -> Option<[u8; 3]> {
+        match self.background {
+            Background::Transparent => None,
+            Background::Solid => Some(self.background_color),
+            Background::Theme if self.dark_mode => Some(DARK_BACKGROUND),
+            Background::Theme => Some(LIGHT_BACKGROUND),
+        }
+    }
+
+    /// Whether axes, grid and labels have to stand out against a dark background, which a
+    /// solid one is when its luminance is below half
+    pub fn dark_background(&self) -> bool {
+        match self.background {
+            Background::Solid => {
+                let [r, g, b] = self.background_color.map(|c| c as f32 / 255.0);
+                0.2126 * r + 0.7152 * g + 0.0722 * b < 0.5
+            }
+            _ => self.dark_mode,
+        }
+    }
+
+    pub fn x_axis_scale(&self) -> AxisScale {
+        AxisScale::new(self.x_scale, self.x_linthresh).reversed(self.x_reversed)
+    }
+
+    pub fn y_axis_scale(&self) -> AxisScale {
+        AxisScale::new(self.y_scale, self.y_linthresh).reversed(self.y_reversed)
+    }
+
+    /// How densely the x axis is ticked, before its length is taken into account
+    pub fn x_tick_density(&self) -> ticks::TickDensity {
+        ticks::TickDensity::new(self.x_tick_count, self.x_tick_step)
+    }
+
+    /// How densely both y axes are ticked, before their length is taken into account
+    pub fn y_tick_density(&self) -> ticks::TickDensity {
+        ticks::TickDensity::new(self.y_tick_count, self.y_tick_step)
+    }
+
+    pub fn x_tick_format(&self) -> TickFormat {
+        TickFormat::new(self.x_label_format, self.x_label_decimals)
+    }
+
+    pub fn y_tick_format(&self) -> TickFormat {
+        TickFormat::new(self.y_label_format, self.y_label_decimals)
+    }
+
+    /// Values set by `set_x_ticks`, empty when the ticks are generated
+    pub fn x_tick_list(&self) -> &[(RangePrec, Option<String>)] {
+        &self.x_tick_list
+    }
+
+    pub fn y_tick_list(&self) -> &[(RangePrec, Option<String>)] {
+        &self.y_tick_list
+    }
+
+    /// Hash of everything affecting the output besides the displayed ranges
+    pub fn style_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        let mut blacklist: Vec<_> = self.bundle_blacklist.iter().collect();
+        blacklist.sort();
+        let mut y2_traces: Vec<_> = self.y2_traces.iter().collect();
+        y2_traces.sort();
+
+        (
+            self.clear,
+            &self.x_type,
+            self.dark_mode,
+            self.background as u8,
+            self.background_color,
+            self.render_grid,
+            self.render_axes,
+            self.render_legend,
+        )
+            .hash(&mut hasher);
+        (
+            self.x_scale as u8,
+            self.y_scale as u8,
+            self.x_tick_mode as u8,
+            self.tick_ends as u8,
+            self.x_reversed,
+            self.y_reversed,
+        )
+            .hash(&mut hasher);
+        (self.x_linthresh.to_bits(), self.y_linthresh.to_bits()).hash(&mut hasher);
+        (
+            self.x_tick_count,
+            self.y_tick_count,
+            self.min_tick_spacing,
+            self.x_tick_step.to_bits(),
+            self.y_tick_step.to_bits(),
+        )
+            .hash(&mut hasher);
+        (
+            self.x_label_format as u8,
+            self.y_label_format as u8,
+            self.x_label_decimals,
+            self.y_label_decimals,
+        )
+            .hash(&mut hasher);
+        for (val, label) in self.x_tick_list.iter().chain(&self.y_tick_list) {
+            (val.to_bits(), label).hash(&mut hasher);
+        }
+        (self.x_tick_list.len(), self.y_tick_list.len()).hash(&mut hasher);
+        (
+            self.margin,
+            self.x_label_space,
+            self.y_label_space,
+            self.y2_label_space,
+            self.rotate_labels,
+        )
+            .hash(&mut hasher);
+        (
+            self.region_bottom,
+            self.region_height,
+            self.shade_future,
+            self.exact,
+            self.overscan.to_bits(),
+        )
+            .hash(&mut hasher);
+        (
+            self.now.map(f64::to_bits),
+            self.baseline_x.map(f64::to_bits),
+            self.marker_rotation.to_bits(),
+        )
+            .hash(&mut hasher);
+        (
+            self.raise_at_x.map(f64::to_bits),
+            self.raise_at_y.map(f64::to_bits),
+        )
+            .hash(&mut hasher);
+        (&self.bundles, &self.heatmaps, blacklist, y2_traces).hash(&mut hasher);
+
+        for trace in &self.traces {
+            (trace.idx, trace.color, trace.width, trace.points_mode).hash(&mut hasher);
+        }
+
+        hasher.finish()
+    }
+
+    /// Range of the secondary y axis, when it is shown
+    pub fn y2_range(&self) -> Option<(RangePrec, RangePrec)> {
+        self.y2_from.zip(self.y2_to)
+    }
+
+    /// Range of the y axis the trace is plotted against
+    pub fn y_range(&self, handle: DataIdx) -> (RangePrec, RangePrec) {
+        match self.y2_range() {
+            Some(range) if self.on_y2(handle) => range,
+            _ => (self.y_from, self.y_to),
+        }
+    }
+
+    /// Whether the trace is plotted against the shown secondary y axis
+    pub fn on_y2(&self, handle: DataIdx) -> bool {
+        self.y2_range().is_some() && self.y2_traces.contains(&handle)
+    }
+
+    pub fn units(&self) -> (&str, &str, &str) {
+        (&self.x_unit, &self.y_unit, &self.y2_unit)
+    }
+
     pub fn is_blacklisted(&self, handle: DataIdx) -> bool {
         self.bundle_blacklist.contains(&handle)
     }
+
+    /// Handles of the blacklisted traces in ascending order
+    pub fn blacklist(&self) -> Vec<DataIdx> {
+        let mut handles: Vec<_> = self.bundle_blacklist.iter().copied().collect();
+        handles.sort();
+
+        handles
+    }
+
+    /// Copy of the job with its margins and regions, given in pixels, multiplied by `scale`
+    pub fn scaled(&self, scale: f32) -> RenderJob {
+        let px = |v: u32| (v as f32 * scale).round() as u32;
+
+        RenderJob {
+            margin: px(self.margin),
+            x_label_space: px(self.x_label_space),
+            y_label_space: px(self.y_label_space),
+            y2_label_space: px(self.y2_label_space),
+            min_tick_spacing: px(self.min_tick_spacing),
+            region_bottom: px(self.region_bottom),
+            region_height: px(self.region_height),
+            ..self.clone()
+        }
+    }
+}
+
+/// `values` paired with the `labels` there are, ordered by value
+fn tick_list(values: Vec<f64>, labels: Vec<String>) -> Vec<(RangePrec, Option<String>)> {
+    let mut list: Vec<_> = values
+        .into_iter()
+        .zip(labels.into_iter().map(Some).chain(std::iter::repeat(None)))
+        .filter(|(val, _)| val.is_finite())
+        .collect();
+    list.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    list
 }
 
 // #[wasm_bindgen]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct TraceStyle {
     pub idx: usize,
     pub color: [u8; 3],
     pub width: u32,
+    #[serde(default)]
     pub points_mode: bool,
 }
@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::prelude::*;
+
+/// Smallest value representable on a logarithmic axis, anything below is clamped to it
+pub const LOG_FLOOR: RangePrec = 1e-30;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scale {
+    Linear = 0,
+    Log10 = 1,
+    /// Linear around zero, logarithmic past the axis' `linthresh` in both directions
+    SymLog = 2,
+}
+
+/// A [`Scale`] together with its parameters
+#[derive(Clone, Copy, Debug)]
+pub struct AxisScale {
+    pub scale: Scale,
+    pub linthresh: RangePrec,
+    /// Whether the axis runs from its `to` to its `from`, e.g. for depths growing downwards
+    pub reversed: bool,
+}
+
+impl From<Scale> for AxisScale {
+    fn from(scale: Scale) -> Self {
+        Self {
+            scale,
+            linthresh: 1.0,
+            reversed: false,
+        }
+    }
+}
+
+impl AxisScale {
+    pub fn new(scale: Scale, linthresh: RangePrec) -> Self {
+        Self {
+            scale,
+            linthresh,
+            reversed: false,
+        }
+    }
+
+    /// The same scale running the other way when `reversed`
+    pub fn reversed(self, reversed: bool) -> Self {
+        Self { reversed, ..self }
+    }
+
+    /// Maps a data value into the space the axis is linear in
+    pub fn apply(self, val: RangePrec) -> RangePrec {
+        match self.scale {
+            Scale::Linear => val,
+            Scale::Log10 => val.max(LOG_FLOOR).log10(),
+            Scale::SymLog => val.signum() * (1.0 + val.abs() / self.linthresh).log10(),
+        }
+    }
+
+    /// Inverse of [`AxisScale::apply`]
+    pub fn invert(self, val: RangePrec) -> RangePrec {
+        match self.scale {
+            Scale::Linear => val,
+            Scale::Log10 => 10.0_f64.powf(val),
+            Scale::SymLog => val.signum() * self.linthresh * (10.0_f64.powf(val.abs()) - 1.0),
+        }
+    }
+
+    /// `(from, to)` grown by `padding` on both sides, in data units or in percent of the range
+    /// as the axis shows it
+    pub fn pad(
+        self,
+        (from, to): (RangePrec, RangePrec),
+        padding: RangePrec,
+        unit: PaddingUnit,
+    ) -> (RangePrec, RangePrec) {
+        if !(padding.is_finite() && padding > 0.0) {
+            return (from, to);
+        }
+
+        match unit {
+            PaddingUnit::Data => (from - padding, to + padding),
+            PaddingUnit::Percent => {
+                let (from, to) = (self.apply(from), self.apply(to));
+                let padding = (to - from) * padding / 100.0;
+
+                (self.invert(from - padding), self.invert(to + padding))
+            }
+        }
+    }
+
+    /// Relative position of `val` within `from..to` along the axis, `to` lying at 0 on
+    /// reversed axes
+    pub fn position(self, val: RangePrec, from: RangePrec, to: RangePrec) -> RangePrec {
+        let from = self.apply(from);
+        let pos = (self.apply(val) - from) / (self.apply(to) - from);
+
+        match self.reversed {
+            true => 1.0 - pos,
+            false => pos,
+        }
+    }
+}
+
+/// What the view padding of a `RenderJob` is measured in
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaddingUnit {
+    #[default]
+    Data = 0,
+    /// Percent of the range on each side
+    Percent = 1,
+}
+
+/// Which ticks the ends of an axis get
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TickEnds {
+    /// Only the ticks falling inside the range, which may leave its ends unlabeled
+    #[default]
+    Interior = 0,
+    /// Labeled ticks at both ends of the range, interior ticks crowding them left out
+    Endpoints = 1,
+    /// Linear decimal ranges widened to the nearest ticks, which then sit at both ends
+    NiceBounds = 2,
+}
+
+/// How the ticks of an axis are placed and labeled
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TickMode {
+    /// Multiples of 1, 2 and 5 times a power of ten
+    Decimal = 0,
+    /// Calendar boundaries in UTC, values being epoch milliseconds
+    Time = 1,
+}
+
+/// How the tick labels of a decimal axis show their values
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LabelFormat {
+    /// Plain decimals, switching to exponent notation for very large or small magnitudes
+    #[default]
+    Auto = 0,
+    /// SI prefixes, e.g. `1.5k` or `20µ`
+    Si = 1,
+    /// Exponents that are multiples of three, e.g. `15e3`
+    Engineering = 2,
+    /// The same number of decimals on every label
+    Fixed = 3,
+    /// Fractions as percent, e.g. `0.25` as `25%`
+    Percent = 4,
+    /// Byte counts with binary prefixes, e.g. `1.5 KiB`
+    Bytes = 5,
+    /// Seconds as spans of time, e.g. `250ms` or `1h 30m`
+    Duration = 6,
+}
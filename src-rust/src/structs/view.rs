@@ -0,0 +1,230 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::prelude::*;
+
+use super::{AxisScale, RenderJob};
+
+/// Narrowest span relative to the magnitude of its bounds a zoom may reach, below it the
+/// positions along the axis lose their precision
+const MIN_RELATIVE_SPAN: RangePrec = 1e-9;
+
+/// Ranges of a `RenderJob` under the names of its fields
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+pub struct View {
+    pub x_from: RangePrec,
+    pub x_to: RangePrec,
+    pub y_from: RangePrec,
+    pub y_to: RangePrec,
+}
+
+impl View {
+    pub fn new(x: (RangePrec, RangePrec), y: (RangePrec, RangePrec)) -> Self {
+        Self {
+            x_from: x.0,
+            x_to: x.1,
+            y_from: y.0,
+            y_to: y.1,
+        }
+    }
+
+    pub fn x(&self) -> (RangePrec, RangePrec) {
+        (self.x_from, self.x_to)
+    }
+
+    pub fn y(&self) -> (RangePrec, RangePrec) {
+        (self.y_from, self.y_to)
+    }
+
+    /// Both ranges are finite and hold more than a single value
+    pub fn is_valid(&self) -> bool {
+        [self.x(), self.y()]
+            .iter()
+            .all(|&(from, to)| from.is_finite() && to.is_finite() && from < to)
+    }
+
+    /// View `factor` times as wide and high around `center`, as seen on axes of the scales
+    /// `x_scale` and `y_scale`, the axes held by `lock` staying put. A factor that isn't
+    /// positive or zooms in past the precision of the axes leaves the view as it is.
+    pub fn zoom(
+        self,
+        factor: RangePrec,
+        center: (RangePrec, RangePrec),
+        (x_scale, y_scale): (AxisScale, AxisScale),
+        lock: &ViewLock,
+    ) -> Self {
+        if !(factor.is_finite() && factor > 0.0) {
+            return self;
+        }
+
+        let zoom = |(from, to), center, scale: AxisScale| {
+            let (from, to, center) = (scale.apply(from), scale.apply(to), scale.apply(center));
+
+            (
+                scale.invert(center + (from - center) * factor),
+                scale.invert(center + (to - center) * factor),
+            )
+        };
+
+        let x = zoom(self.x(), center.0, x_scale);
+        let y = zoom(self.y(), center.1, y_scale);
+
+        self.replaced_by(lock.apply(Self::new(x, y)))
+    }
+
+    /// View moved by `dx` and `dy` in data units, the axes held by `lock` staying put
+    pub fn pan(self, dx: RangePrec, dy: RangePrec, lock: &ViewLock) -> Self {
+        let moved = Self::new(
+            (self.x_from + dx, self.x_to + dx),
+            (self.y_from + dy, self.y_to + dy),
+        );
+
+        self.replaced_by(lock.apply(moved))
+    }
+
+    /// View `t` of the way from `self` to `to`, interpolated along the axes of `x_scale` and
+    /// `y_scale` so that log axes move evenly
+    pub fn lerp(self, to: View, t: RangePrec, (x_scale, y_scale): (AxisScale, AxisScale)) -> Self {
+        let lerp = |a: RangePrec, b: RangePrec, scale: AxisScale| {
+            let (a, b) = (scale.apply(a), scale.apply(b));
+            scale.invert(a + (b - a) * t)
+        };
+
+        match t >= 1.0 {
+            true => to,
+            false => Self::new(
+                (
+                    lerp(self.x_from, to.x_from, x_scale),
+                    lerp(self.x_to, to.x_to, x_scale),
+                ),
+                (
+                    lerp(self.y_from, to.y_from, y_scale),
+                    lerp(self.y_to, to.y_to, y_scale),
+                ),
+            ),
+        }
+    }
+
+    /// `next` unless it's invalid or narrower than the axes can resolve
+    fn replaced_by(self, next: Self) -> Self {
+        let resolvable = [next.x(), next.y()]
+            .iter()
+            .all(|&(from, to)| to - from >= from.abs().max(to.abs()).max(1.0) * MIN_RELATIVE_SPAN);
+
+        match next.is_valid() && resolvable {
+            true => next,
+            false => self,
+        }
+    }
+}
+
+/// Ranges held fixed by view computations, e.g. a percentage always shown from 0 to 100
+/// while x is zoomed freely
+#[wasm_bindgen]
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct ViewLock {
+    x: Option<(RangePrec, RangePrec)>,
+    y: Option<(RangePrec, RangePrec)>,
+}
+
+#[wasm_bindgen]
+impl ViewLock {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Holds x at `from..to`, which has to be finite and not empty
+    pub fn lock_x(&mut self, from: RangePrec, to: RangePrec) -> Result<(), String> {
+        self.x = Some(Self::validate(from, to)?);
+
+        Ok(())
+    }
+
+    /// Holds y at `from..to`, which has to be finite and not empty
+    pub fn lock_y(&mut self, from: RangePrec, to: RangePrec) -> Result<(), String> {
+        self.y = Some(Self::validate(from, to)?);
+
+        Ok(())
+    }
+
+    pub fn unlock_x(&mut self) {
+        self.x = None;
+    }
+
+    pub fn unlock_y(&mut self) {
+        self.y = None;
+    }
+}
+
+impl ViewLock {
+    fn validate(from: RangePrec, to: RangePrec) -> Result<(RangePrec, RangePrec), String> {
+        match from.is_finite() && to.is_finite() && from < to {
+            true => Ok((from, to)),
+            false => Err(format!("Can't lock an axis to {}..{}", from, to)),
+        }
+    }
+
+    pub fn x(&self) -> Option<(RangePrec, RangePrec)> {
+        self.x
+    }
+
+    pub fn y(&self) -> Option<(RangePrec, RangePrec)> {
+        self.y
+    }
+
+    /// `view` with the locked axes replaced by their ranges
+    pub fn apply(&self, view: View) -> View {
+        View::new(self.x.unwrap_or(view.x()), self.y.unwrap_or(view.y()))
+    }
+}
+
+/// Transition of a job's view to another one, eased in and out over `duration` milliseconds.
+/// Hosts step it with the time of every animation frame until it's done.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct ViewAnimation {
+    from: View,
+    to: View,
+    scales: (AxisScale, AxisScale),
+    start: RangePrec,
+    duration: RangePrec,
+}
+
+#[wasm_bindgen]
+impl ViewAnimation {
+    /// Sets the view of `job` to the one at `now`, in the milliseconds the animation was started
+    /// at, returning whether it has reached its end
+    pub fn step(&self, job: &mut RenderJob, now: RangePrec) -> bool {
+        job.set_view(self.view_at(now));
+
+        self.is_done(now)
+    }
+
+    pub fn is_done(&self, now: RangePrec) -> bool {
+        now >= self.start + self.duration
+    }
+}
+
+impl ViewAnimation {
+    /// Animation from the view of `job` to `to` along its axes, starting at `start`
+    pub fn of_job(job: &RenderJob, to: View, start: RangePrec, duration: RangePrec) -> Self {
+        Self {
+            from: job.view(),
+            to,
+            scales: (job.x_axis_scale(), job.y_axis_scale()),
+            start,
+            duration: duration.max(0.0),
+        }
+    }
+
+    pub fn view_at(&self, now: RangePrec) -> View {
+        let t = match self.duration > 0.0 {
+            true => ((now - self.start) / self.duration).clamp(0.0, 1.0),
+            false => 1.0,
+        };
+
+        self.from
+            .lerp(self.to, t * t * (3.0 - 2.0 * t), self.scales)
+    }
+}
@@ -0,0 +1,33 @@
+//! Versioned serialized form of jobs and bundle entries, for hosts that persist them. Fields
+//! missing from documents of older versions take their defaults, so these keep loading.
+
+use serde::{Deserialize, Serialize};
+
+/// Bumped whenever a serialized field changes meaning, rather than only being added
+pub const WIRE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Versioned<T> {
+    pub version: u32,
+    pub data: T,
+}
+
+impl<T> Versioned<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            version: WIRE_VERSION,
+            data,
+        }
+    }
+
+    /// The data, unless it was written by a newer version this one can't interpret
+    pub fn into_current(self) -> Result<T, String> {
+        match self.version {
+            1..=WIRE_VERSION => Ok(self.data),
+            version => Err(format!(
+                "Wire format version {} is not supported, this build reads 1 to {}",
+                version, WIRE_VERSION
+            )),
+        }
+    }
+}
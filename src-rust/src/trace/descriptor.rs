@@ -1,3 +1,5 @@
+use std::rc::Rc;
+
 use crate::prelude::*;
 
 use super::Segment;
@@ -5,8 +7,10 @@ use super::Segment;
 pub struct TraceDescriptor {
     pub id: String,
     pub x_type: String,
+    /// Traces pinned from another module share its segments and can't be loaded into
+    pub read_only: bool,
 
-    pub segments: Vec<Box<dyn Segment>>,
+    pub segments: Vec<Rc<dyn Segment>>,
 }
 
 impl TraceDescriptor {
@@ -18,7 +22,7 @@ impl TraceDescriptor {
         &self,
         from: RangePrec,
         to: RangePrec,
-    ) -> impl Iterator<Item = &Box<dyn Segment>> {
+    ) -> impl Iterator<Item = &Rc<dyn Segment>> {
         self.segments
             .iter()
             .filter(move |&x| x.intersects(from, to))
@@ -53,6 +57,14 @@ impl TraceDescriptor {
             .flat_map(move |seg| seg.iter_with_origin(from, to, x_orig, y_orig))
     }
 
+    /// Oldest x still stored when the trace evicts old data, e.g. a `RollingTrace`
+    pub fn retained_from(&self) -> Option<RangePrec> {
+        self.segments
+            .iter()
+            .filter_map(|s| s.retained_from())
+            .reduce(RangePrec::min)
+    }
+
     pub fn get_data_at(&self, x: RangePrec) -> Option<RangePrec> {
         let seg = self
             .segments
@@ -62,7 +74,9 @@ impl TraceDescriptor {
 
         seg.flatten()
     }
-    pub fn push_segment(&mut self, seg: Box<dyn Segment>) {
+    pub fn push_segment(&mut self, seg: impl Into<Rc<dyn Segment>>) {
+        let seg = seg.into();
+
         // If this interval is already loaded, cancel the push
         if self.segments.iter().any(|d| {
             seg.from() >= d.from()
@@ -82,7 +96,8 @@ impl TraceDescriptor {
             .iter_mut()
             .find(|d| d.from() < seg.from() && d.to() > seg.from())
         {
-            lead.shrink(lead.from(), seg.from());
+            let from = lead.from();
+            dyn_clone::rc_make_mut(lead).shrink(from, seg.from());
         }
 
         if let Some(trail) = self
@@ -90,7 +105,8 @@ impl TraceDescriptor {
             .iter_mut()
             .find(|d| d.from() < seg.to() && d.to() > seg.to())
         {
-            trail.shrink(seg.to(), trail.to());
+            let to = trail.to();
+            dyn_clone::rc_make_mut(trail).shrink(seg.to(), to);
         }
 
         self.segments.push(seg);
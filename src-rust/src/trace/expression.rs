@@ -0,0 +1,321 @@
+//! Tiny arithmetic expressions over named traces, compiled to a stack program
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Func {
+    Abs,
+    Sqrt,
+    Ln,
+    Log10,
+    Exp,
+    Min,
+    Max,
+}
+
+impl Func {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "abs" => Func::Abs,
+            "sqrt" => Func::Sqrt,
+            "ln" => Func::Ln,
+            "log10" => Func::Log10,
+            "exp" => Func::Exp,
+            "min" => Func::Min,
+            "max" => Func::Max,
+            _ => return None,
+        })
+    }
+
+    fn arity(self) -> usize {
+        match self {
+            Func::Min | Func::Max => 2,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Const(f64),
+    Var(usize),
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Neg,
+    Call(Func),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token<'a> {
+    Number(f64),
+    Ident(&'a str),
+    Symbol(char),
+}
+
+fn tokenize(src: &str) -> Result<Vec<Token<'_>>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = src.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        let len = if c.is_ascii_digit() || c == '.' {
+            let mut len = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+
+            // exponent, e.g. 1e-3
+            if rest[len..].starts_with(['e', 'E']) {
+                let exp = &rest[len + 1..];
+                let sign = exp.starts_with(['+', '-']) as usize;
+                let digits = exp[sign..]
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(exp.len() - sign);
+
+                if digits > 0 {
+                    len += 1 + sign + digits;
+                }
+            }
+
+            let number = &rest[..len];
+            tokens.push(Token::Number(
+                number
+                    .parse()
+                    .map_err(|_| format!("Invalid number {}", number))?,
+            ));
+            len
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+
+            tokens.push(Token::Ident(&rest[..len]));
+            len
+        } else if "+-*/^(),".contains(c) {
+            tokens.push(Token::Symbol(c));
+            1
+        } else {
+            return Err(format!("Unexpected character {:?}", c));
+        };
+
+        rest = rest[len..].trim_start();
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser emitting the program in postfix order
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    ops: Vec<Op>,
+    vars: Vec<String>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        let found = self.peek() == Some(Token::Symbol(symbol));
+        self.pos += found as usize;
+        found
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), String> {
+        match self.eat(symbol) {
+            true => Ok(()),
+            false => Err(format!("Expected {:?}", symbol)),
+        }
+    }
+
+    /// expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Result<(), String> {
+        self.term()?;
+
+        loop {
+            let op = match self.peek() {
+                Some(Token::Symbol('+')) => Op::Add,
+                Some(Token::Symbol('-')) => Op::Sub,
+                _ => return Ok(()),
+            };
+            self.pos += 1;
+            self.term()?;
+            self.ops.push(op);
+        }
+    }
+
+    /// term := unary (('*' | '/') unary)*
+    fn term(&mut self) -> Result<(), String> {
+        self.unary()?;
+
+        loop {
+            let op = match self.peek() {
+                Some(Token::Symbol('*')) => Op::Mul,
+                Some(Token::Symbol('/')) => Op::Div,
+                _ => return Ok(()),
+            };
+            self.pos += 1;
+            self.unary()?;
+            self.ops.push(op);
+        }
+    }
+
+    /// unary := '-' unary | power
+    fn unary(&mut self) -> Result<(), String> {
+        if self.eat('-') {
+            self.unary()?;
+            self.ops.push(Op::Neg);
+            return Ok(());
+        }
+
+        self.power()
+    }
+
+    /// power := atom ('^' unary)?, right associative
+    fn power(&mut self) -> Result<(), String> {
+        self.atom()?;
+
+        if self.eat('^') {
+            self.unary()?;
+            self.ops.push(Op::Pow);
+        }
+
+        Ok(())
+    }
+
+    /// atom := number | name | function '(' expr (',' expr)* ')' | '(' expr ')'
+    fn atom(&mut self) -> Result<(), String> {
+        let token = self.peek().ok_or("Unexpected end of the expression")?;
+        self.pos += 1;
+
+        match token {
+            Token::Number(value) => self.ops.push(Op::Const(value)),
+            Token::Ident(name) if self.peek() == Some(Token::Symbol('(')) => {
+                let func = Func::parse(name).ok_or_else(|| format!("Unknown function {}", name))?;
+                self.pos += 1;
+
+                for i in 0..func.arity() {
+                    if i > 0 {
+                        self.expect(',')?;
+                    }
+                    self.expr()?;
+                }
+                self.expect(')')?;
+                self.ops.push(Op::Call(func));
+            }
+            Token::Ident(name) => {
+                let index = match self.vars.iter().position(|v| v == name) {
+                    Some(index) => index,
+                    None => {
+                        self.vars.push(name.to_string());
+                        self.vars.len() - 1
+                    }
+                };
+                self.ops.push(Op::Var(index));
+            }
+            Token::Symbol('(') => {
+                self.expr()?;
+                self.expect(')')?;
+            }
+            Token::Symbol(c) => return Err(format!("Unexpected {:?}", c)),
+        }
+
+        Ok(())
+    }
+}
+
+/// Compiled expression such as `a / b * 100`, with `+ - * / ^`, parentheses and the
+/// functions `abs`, `sqrt`, `ln`, `log10`, `exp`, `min` and `max`
+#[derive(Clone, Debug)]
+pub struct Expression {
+    ops: Vec<Op>,
+    vars: Vec<String>,
+    depth: usize,
+}
+
+impl Expression {
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            tokens: tokenize(src)?,
+            pos: 0,
+            ops: Vec::new(),
+            vars: Vec::new(),
+        };
+
+        parser.expr()?;
+        if let Some(token) = parser.peek() {
+            return Err(format!("Unexpected {:?} after the expression", token));
+        }
+
+        let mut depth: usize = 0;
+        let mut max_depth = 0;
+        for op in &parser.ops {
+            depth = match op {
+                Op::Const(_) | Op::Var(_) => depth + 1,
+                Op::Neg => depth,
+                Op::Call(func) => depth + 1 - func.arity(),
+                _ => depth - 1,
+            };
+            max_depth = max_depth.max(depth);
+        }
+
+        Ok(Self {
+            ops: parser.ops,
+            vars: parser.vars,
+            depth: max_depth,
+        })
+    }
+
+    /// Names the expression reads, in the order of their first use
+    pub fn variables(&self) -> &[String] {
+        &self.vars
+    }
+
+    /// Value for `values` of the `variables`, reusing `stack` between calls
+    pub fn eval_with(&self, values: &[f64], stack: &mut Vec<f64>) -> f64 {
+        stack.clear();
+        stack.reserve(self.depth);
+
+        for op in &self.ops {
+            let value = match *op {
+                Op::Const(value) => value,
+                Op::Var(index) => values[index],
+                Op::Neg => -stack.pop().unwrap(),
+                Op::Call(func) => {
+                    let a = stack.pop().unwrap();
+
+                    match func {
+                        Func::Abs => a.abs(),
+                        Func::Sqrt => a.sqrt(),
+                        Func::Ln => a.ln(),
+                        Func::Log10 => a.log10(),
+                        Func::Exp => a.exp(),
+                        Func::Min => stack.pop().unwrap().min(a),
+                        Func::Max => stack.pop().unwrap().max(a),
+                    }
+                }
+                binary => {
+                    let b = stack.pop().unwrap();
+                    let a = stack.pop().unwrap();
+
+                    match binary {
+                        Op::Add => a + b,
+                        Op::Sub => a - b,
+                        Op::Mul => a * b,
+                        Op::Div => a / b,
+                        _ => a.powf(b),
+                    }
+                }
+            };
+
+            stack.push(value);
+        }
+
+        stack.pop().unwrap()
+    }
+
+    pub fn eval(&self, values: &[f64]) -> f64 {
+        self.eval_with(values, &mut Vec::new())
+    }
+}
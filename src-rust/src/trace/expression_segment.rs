@@ -0,0 +1,167 @@
+use std::rc::Rc;
+
+use crate::prelude::*;
+
+use super::{Expression, Segment};
+
+/// Points of `segments` between `from` and `to`
+fn points_in(
+    segments: &[Rc<dyn Segment>],
+    from: RangePrec,
+    to: RangePrec,
+) -> impl Iterator<Item = (RangePrec, RangePrec)> + '_ {
+    segments
+        .iter()
+        .filter(move |s| s.intersects(from, to))
+        .flat_map(move |s| s.iter_high_prec(from, to))
+}
+
+fn value_at(segments: &[Rc<dyn Segment>], x: RangePrec) -> Option<RangePrec> {
+    segments.iter().find(|s| s.contains(x))?.value_at(x)
+}
+
+/// Virtual segment evaluating an expression over the segments of other traces whenever it's
+/// read, storing no values. Points lie at the x of the first variable's trace, the others
+/// being interpolated there.
+#[derive(Clone)]
+pub struct ExpressionSegment {
+    expression: Rc<Expression>,
+    /// Segments of the trace bound to each variable
+    inputs: Rc<Vec<Vec<Rc<dyn Segment>>>>,
+    from: RangePrec,
+    to: RangePrec,
+    shift: (RangePrec, RangePrec),
+}
+
+impl ExpressionSegment {
+    /// `inputs` holds the segments for each of the expression's variables, in their order
+    pub fn new(expression: Expression, inputs: Vec<Vec<Rc<dyn Segment>>>) -> Self {
+        // defined only where every input has data
+        let (from, to) = inputs
+            .iter()
+            .map(|segments| {
+                let from = segments.iter().map(|s| s.from()).reduce(RangePrec::min);
+                let to = segments.iter().map(|s| s.to()).reduce(RangePrec::max);
+
+                from.zip(to).unwrap_or((1.0, 0.0))
+            })
+            .fold((RangePrec::MIN, RangePrec::MAX), |(from, to), (f, t)| {
+                (from.max(f), to.min(t))
+            });
+
+        Self {
+            expression: Rc::new(expression),
+            inputs: Rc::new(inputs),
+            from,
+            to,
+            shift: (0.0, 0.0),
+        }
+    }
+}
+
+impl Segment for ExpressionSegment {
+    fn from(&self) -> RangePrec {
+        self.from + self.shift.0
+    }
+
+    fn to(&self) -> RangePrec {
+        self.to + self.shift.0
+    }
+
+    fn iter_in<'a>(
+        &'a self,
+        from: RangePrec,
+        to: RangePrec,
+    ) -> Box<dyn Iterator<Item = (DataPrec, DataPrec)> + 'a> {
+        Box::new(
+            self.iter_high_prec(from, to)
+                .map(|(x, y)| (x as DataPrec, y as DataPrec)),
+        )
+    }
+
+    fn iter_with_origin<'a>(
+        &'a self,
+        from: RangePrec,
+        to: RangePrec,
+        x_orig: RangePrec,
+        y_orig: RangePrec,
+    ) -> Box<dyn Iterator<Item = (DataPrec, DataPrec)> + 'a> {
+        Box::new(
+            self.iter_high_prec(from, to)
+                .map(move |(x, y)| ((x - x_orig) as DataPrec, (y - y_orig) as DataPrec)),
+        )
+    }
+
+    fn iter_high_prec<'a>(
+        &'a self,
+        from: RangePrec,
+        to: RangePrec,
+    ) -> Box<dyn Iterator<Item = (RangePrec, RangePrec)> + 'a> {
+        let from = (from - self.shift.0).max(self.from);
+        let to = (to - self.shift.0).min(self.to);
+
+        let Some((driver, others)) = self.inputs.split_first().filter(|_| from <= to) else {
+            return Box::new(std::iter::empty());
+        };
+
+        // the other inputs are walked along with the driver, the last point passed and the
+        // next one bracketing each x
+        let mut cursors: Vec<_> = others
+            .iter()
+            .map(|segments| (None, points_in(segments, from, to).peekable()))
+            .collect();
+        let mut values = vec![0.0; self.inputs.len()];
+        let mut stack = Vec::new();
+
+        Box::new(points_in(driver, from, to).filter_map(move |(x, y)| {
+            values[0] = y;
+
+            for (i, (prev, next)) in cursors.iter_mut().enumerate() {
+                while let Some(&point) = next.peek().filter(|p| p.0 <= x) {
+                    *prev = Some(point);
+                    next.next();
+                }
+
+                values[i + 1] = match (*prev, next.peek()) {
+                    (Some((px, py)), _) if px == x => py,
+                    (Some((px, py)), Some(&(nx, ny))) => py + (ny - py) * (x - px) / (nx - px),
+                    // only at the ends of the range, where a bracketing point lies outside
+                    _ => value_at(&others[i], x)?,
+                };
+            }
+
+            let value = self.expression.eval_with(&values, &mut stack);
+
+            // e.g. divisions by zero
+            value
+                .is_finite()
+                .then_some((x + self.shift.0, value + self.shift.1))
+        }))
+    }
+
+    fn value_at(&self, x: RangePrec) -> Option<RangePrec> {
+        let x = x - self.shift.0;
+        if x < self.from || x > self.to {
+            return None;
+        }
+
+        let values = self
+            .inputs
+            .iter()
+            .map(|segments| value_at(segments, x))
+            .collect::<Option<Vec<_>>>()?;
+        let value = self.expression.eval(&values);
+
+        value.is_finite().then_some(value + self.shift.1)
+    }
+
+    fn shrink(&mut self, from: RangePrec, to: RangePrec) {
+        self.from = self.from.max(from - self.shift.0);
+        self.to = self.to.min(to - self.shift.0);
+    }
+
+    fn shift(&mut self, shift_x: RangePrec, shift_y: RangePrec) {
+        self.shift.0 += shift_x;
+        self.shift.1 += shift_y;
+    }
+}
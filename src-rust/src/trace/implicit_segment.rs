@@ -0,0 +1,134 @@
+use std::{ops::Range, rc::Rc};
+
+use crate::prelude::*;
+
+use super::Segment;
+
+/// Regularly sampled segment storing only y, the x of the i-th value being `start + i * dx`
+#[derive(Clone)]
+pub struct ImplicitSegment {
+    start: RangePrec,
+    dx: RangePrec,
+    y: Rc<Vec<f64>>,
+    shift_y: RangePrec,
+}
+
+impl ImplicitSegment {
+    /// Panics unless `dx` is positive
+    pub fn new(start: RangePrec, dx: RangePrec, y: Rc<Vec<f64>>) -> Self {
+        assert!(
+            dx > 0.0,
+            "The sampling interval has to be positive, got {}",
+            dx
+        );
+
+        Self {
+            start,
+            dx,
+            y,
+            shift_y: 0.0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.y.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.y.is_empty()
+    }
+
+    fn x_at(&self, i: usize) -> RangePrec {
+        self.start + i as RangePrec * self.dx
+    }
+
+    fn y_at(&self, i: usize) -> RangePrec {
+        self.y[i] + self.shift_y
+    }
+
+    /// Indices of the samples between `from` and `to`, computed without a search
+    fn indices_in(&self, from: RangePrec, to: RangePrec) -> Range<usize> {
+        let index = |x: RangePrec| ((x - self.start) / self.dx).clamp(0.0, self.len() as RangePrec);
+
+        let start = index(from).ceil() as usize;
+        let end = (index(to).floor() as usize + 1).min(self.len());
+
+        start..end.max(start)
+    }
+}
+
+impl Segment for ImplicitSegment {
+    fn from(&self) -> RangePrec {
+        match self.is_empty() {
+            true => 1.0,
+            false => self.start,
+        }
+    }
+
+    fn to(&self) -> RangePrec {
+        match self.len() {
+            0 => 0.0,
+            len => self.x_at(len - 1),
+        }
+    }
+
+    fn iter_in<'a>(
+        &'a self,
+        from: RangePrec,
+        to: RangePrec,
+    ) -> Box<dyn Iterator<Item = (DataPrec, DataPrec)> + 'a> {
+        Box::new(
+            self.iter_high_prec(from, to)
+                .map(|(x, y)| (x as DataPrec, y as DataPrec)),
+        )
+    }
+
+    fn iter_with_origin<'a>(
+        &'a self,
+        from: RangePrec,
+        to: RangePrec,
+        x_orig: RangePrec,
+        y_orig: RangePrec,
+    ) -> Box<dyn Iterator<Item = (DataPrec, DataPrec)> + 'a> {
+        Box::new(
+            self.iter_high_prec(from, to)
+                .map(move |(x, y)| ((x - x_orig) as DataPrec, (y - y_orig) as DataPrec)),
+        )
+    }
+
+    fn iter_high_prec<'a>(
+        &'a self,
+        from: RangePrec,
+        to: RangePrec,
+    ) -> Box<dyn Iterator<Item = (RangePrec, RangePrec)> + 'a> {
+        Box::new(
+            self.indices_in(from, to)
+                .map(|i| (self.x_at(i), self.y_at(i))),
+        )
+    }
+
+    fn value_at(&self, x: RangePrec) -> Option<RangePrec> {
+        if self.is_empty() || !self.contains(x) {
+            return None;
+        }
+
+        let pos = (x - self.start) / self.dx;
+        let left = (pos.floor() as usize).min(self.len() - 1);
+        let right = (left + 1).min(self.len() - 1);
+        let t = pos - left as RangePrec;
+
+        Some(self.y_at(left) * (1.0 - t) + self.y_at(right) * t)
+    }
+
+    fn shrink(&mut self, from: RangePrec, to: RangePrec) {
+        let indices = self.indices_in(from, to);
+
+        self.start = self.x_at(indices.start);
+        self.y = Rc::new(self.y[indices].to_vec());
+    }
+
+    fn shift(&mut self, shift_x: RangePrec, shift_y: RangePrec) {
+        self.start += shift_x;
+        self.shift_y += shift_y;
+    }
+}
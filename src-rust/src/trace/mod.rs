@@ -1,11 +1,21 @@
 mod descriptor;
+mod expression;
+mod expression_segment;
+mod implicit_segment;
 mod metas;
+mod rolling_trace;
 mod segment;
 mod shared_segment;
+mod shifted_segment;
 mod tuple_segment;
 
 pub use descriptor::*;
+pub use expression::*;
+pub use expression_segment::*;
+pub use implicit_segment::*;
 pub use metas::*;
+pub use rolling_trace::*;
 pub use segment::*;
 pub use shared_segment::*;
+pub use shifted_segment::*;
 pub use tuple_segment::*;
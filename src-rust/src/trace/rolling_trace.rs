@@ -0,0 +1,159 @@
+use std::collections::VecDeque;
+
+use crate::prelude::*;
+
+use super::Segment;
+
+/// Ring buffer of the latest points of a live trace. Once `capacity` points are stored, or
+/// points fall more than `window` behind the newest one, the oldest points are evicted.
+#[derive(Clone)]
+pub struct RollingTrace {
+    points: VecDeque<(RangePrec, RangePrec)>,
+    capacity: usize,
+    /// Retention window in x units, `0` keeping points until the capacity is reached
+    window: RangePrec,
+}
+
+impl RollingTrace {
+    pub fn new(capacity: usize, window: RangePrec) -> Self {
+        Self {
+            points: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            window,
+        }
+    }
+
+    /// Buffer evicting nothing by itself, for traces trimmed by whoever fills them
+    pub fn unbounded() -> Self {
+        Self {
+            points: VecDeque::new(),
+            capacity: usize::MAX,
+            window: 0.0,
+        }
+    }
+
+    /// Appends points past the newest one, points out of order are dropped
+    pub fn push(&mut self, points: &[(RangePrec, RangePrec)]) {
+        for &point in points {
+            if self.points.back().is_some_and(|last| point.0 <= last.0) {
+                continue;
+            }
+
+            if self.points.len() == self.capacity {
+                self.points.pop_front();
+            }
+            self.points.push_back(point);
+        }
+
+        if let (true, Some(&(last, _))) = (self.window > 0.0, self.points.back()) {
+            while self
+                .points
+                .front()
+                .is_some_and(|p| p.0 < last - self.window)
+            {
+                self.points.pop_front();
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Retained points between `from` and `to`, in order across the wrap of the buffer
+    fn range(
+        &self,
+        from: RangePrec,
+        to: RangePrec,
+    ) -> impl Iterator<Item = &(RangePrec, RangePrec)> + '_ {
+        let start = self.points.partition_point(|p| p.0 < from);
+        let end = self.points.partition_point(|p| p.0 <= to).max(start);
+
+        self.points.range(start..end)
+    }
+}
+
+impl Segment for RollingTrace {
+    fn from(&self) -> RangePrec {
+        self.points.front().map(|p| p.0).unwrap_or(1.0)
+    }
+
+    fn to(&self) -> RangePrec {
+        self.points.back().map(|p| p.0).unwrap_or(0.0)
+    }
+
+    fn iter_in<'a>(
+        &'a self,
+        from: RangePrec,
+        to: RangePrec,
+    ) -> Box<dyn Iterator<Item = (DataPrec, DataPrec)> + 'a> {
+        Box::new(
+            self.range(from, to)
+                .map(|&(x, y)| (x as DataPrec, y as DataPrec)),
+        )
+    }
+
+    fn iter_with_origin<'a>(
+        &'a self,
+        from: RangePrec,
+        to: RangePrec,
+        x_orig: RangePrec,
+        y_orig: RangePrec,
+    ) -> Box<dyn Iterator<Item = (DataPrec, DataPrec)> + 'a> {
+        Box::new(
+            self.range(from, to)
+                .map(move |&(x, y)| ((x - x_orig) as DataPrec, (y - y_orig) as DataPrec)),
+        )
+    }
+
+    fn iter_high_prec<'a>(
+        &'a self,
+        from: RangePrec,
+        to: RangePrec,
+    ) -> Box<dyn Iterator<Item = (RangePrec, RangePrec)> + 'a> {
+        Box::new(self.range(from, to).copied())
+    }
+
+    fn value_at(&self, x: RangePrec) -> Option<RangePrec> {
+        let next = self.points.partition_point(|p| p.0 < x);
+        let &(right_x, right_y) = self.points.get(next)?;
+
+        if right_x == x {
+            return Some(right_y);
+        }
+
+        let &(left_x, left_y) = self.points.get(next.checked_sub(1)?)?;
+
+        Some(((right_x - x) * left_y + (x - left_x) * right_y) / (right_x - left_x))
+    }
+
+    fn retained_from(&self) -> Option<RangePrec> {
+        Some(self.from())
+    }
+
+    fn append(&mut self, points: &[(RangePrec, RangePrec)]) -> bool {
+        self.push(points);
+        true
+    }
+
+    fn shrink(&mut self, from: RangePrec, to: RangePrec) {
+        // the points are ordered, only the ends have to go
+        while self.points.front().is_some_and(|p| p.0 < from) {
+            self.points.pop_front();
+        }
+        while self.points.back().is_some_and(|p| p.0 > to) {
+            self.points.pop_back();
+        }
+    }
+
+    fn shift(&mut self, shift_x: RangePrec, shift_y: RangePrec) {
+        for p in &mut self.points {
+            p.0 += shift_x;
+            p.1 += shift_y;
+        }
+    }
+}
@@ -35,6 +35,16 @@ pub trait Segment: DynClone {
     // ! FIXME perform a binary search
     fn value_at(&self, x: RangePrec) -> Option<RangePrec>;
 
+    /// Oldest x still stored by segments evicting their old points, `None` for the others
+    fn retained_from(&self) -> Option<RangePrec> {
+        None
+    }
+
+    /// Appends points after the last one, `false` for segments of a fixed extent
+    fn append(&mut self, _points: &[(RangePrec, RangePrec)]) -> bool {
+        false
+    }
+
     fn shrink(&mut self, from: RangePrec, to: RangePrec);
     fn shift(&mut self, shift_x: RangePrec, shift_y: RangePrec);
 }
@@ -4,25 +4,26 @@ use crate::{prelude::*, structs::SegmentNumeric};
 
 use super::Segment;
 
+/// Segment whose x values can be shared with the segments of other traces loaded together
 #[derive(Clone)]
-pub struct SharedSegment {
-    pub x: Rc<Vec<u64>>,
+pub struct SharedSegment<X = u64> {
+    pub x: Rc<Vec<X>>,
     pub y: Rc<Vec<f64>>,
 
     from: RangePrec,
     to: RangePrec,
 }
 
-impl SharedSegment {
-    pub fn new(x: Rc<Vec<u64>>, y: Rc<Vec<f64>>) -> Self {
-        let from = x.first().copied().unwrap_or(1) as RangePrec;
-        let to = x.last().copied().unwrap_or(0) as RangePrec;
+impl<X: SegmentNumeric + Copy> SharedSegment<X> {
+    pub fn new(x: Rc<Vec<X>>, y: Rc<Vec<f64>>) -> Self {
+        let from = x.first().map_or(1.0, |x| x.to_rangeprec());
+        let to = x.last().map_or(0.0, |x| x.to_rangeprec());
 
         Self { x, y, from, to }
     }
 }
 
-impl Segment for SharedSegment {
+impl<X: SegmentNumeric + Copy + 'static> Segment for SharedSegment<X> {
     fn from(&self) -> RangePrec {
         self.from
     }
@@ -40,9 +41,9 @@ impl Segment for SharedSegment {
             self.x
                 .iter()
                 .zip(self.y.iter())
-                .skip_while(move |(&x, _)| (x as RangePrec) < from)
-                .take_while(move |(&x, _)| (x as RangePrec) <= to)
-                .map(|(&x, &y)| (x as DataPrec, y as DataPrec)),
+                .skip_while(move |(&x, _)| x.to_rangeprec() < from)
+                .take_while(move |(&x, _)| x.to_rangeprec() <= to)
+                .map(|(&x, &y)| (x.to_dataprec(), y as DataPrec)),
         )
     }
 
@@ -57,11 +58,11 @@ impl Segment for SharedSegment {
             self.x
                 .iter()
                 .enumerate()
-                .skip_while(move |(_, x)| (**x as RangePrec) < from)
-                .take_while(move |(_, x)| (**x as RangePrec) <= to)
+                .skip_while(move |(_, x)| x.to_rangeprec() < from)
+                .take_while(move |(_, x)| x.to_rangeprec() <= to)
                 .map(move |(i, &x)| {
                     (
-                        (x as RangePrec - x_orig) as DataPrec,
+                        (x.to_rangeprec() - x_orig) as DataPrec,
                         (self.y[i] - y_orig) as DataPrec,
                     )
                 }),
@@ -77,9 +78,9 @@ impl Segment for SharedSegment {
             self.x
                 .iter()
                 .enumerate()
-                .skip_while(move |(_, x)| (**x as RangePrec) < from)
-                .take_while(move |(_, x)| (**x as RangePrec) <= to)
-                .map(|(i, &x)| (x as RangePrec, self.y[i] as RangePrec)),
+                .skip_while(move |(_, x)| x.to_rangeprec() < from)
+                .take_while(move |(_, x)| x.to_rangeprec() <= to)
+                .map(|(i, &x)| (x.to_rangeprec(), self.y[i] as RangePrec)),
         )
     }
 
@@ -136,7 +137,7 @@ impl Segment for SharedSegment {
             self.x
                 .iter()
                 .copied()
-                .map(|x| (x as RangePrec + shift_x) as u64)
+                .map(|x| X::from_rangeprec(x.to_rangeprec() + shift_x))
                 .collect(),
         );
         self.y = Rc::new(self.y.iter().copied().map(|y| y + shift_y).collect());
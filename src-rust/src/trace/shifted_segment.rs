@@ -0,0 +1,83 @@
+use std::rc::Rc;
+
+use crate::prelude::*;
+
+use super::Segment;
+
+/// A view of another segment moved along the x axis, sharing its storage
+#[derive(Clone)]
+pub struct ShiftedSegment {
+    inner: Rc<dyn Segment>,
+    shift_x: RangePrec,
+}
+
+impl ShiftedSegment {
+    pub fn new(inner: Rc<dyn Segment>, shift_x: RangePrec) -> Self {
+        Self { inner, shift_x }
+    }
+}
+
+impl Segment for ShiftedSegment {
+    fn from(&self) -> RangePrec {
+        self.inner.from() + self.shift_x
+    }
+
+    fn to(&self) -> RangePrec {
+        self.inner.to() + self.shift_x
+    }
+
+    fn iter_in<'a>(
+        &'a self,
+        from: RangePrec,
+        to: RangePrec,
+    ) -> Box<dyn Iterator<Item = (DataPrec, DataPrec)> + 'a> {
+        Box::new(
+            self.inner
+                .iter_high_prec(from - self.shift_x, to - self.shift_x)
+                .map(move |(x, y)| ((x + self.shift_x) as DataPrec, y as DataPrec)),
+        )
+    }
+
+    fn iter_with_origin<'a>(
+        &'a self,
+        from: RangePrec,
+        to: RangePrec,
+        x_orig: RangePrec,
+        y_orig: RangePrec,
+    ) -> Box<dyn Iterator<Item = (DataPrec, DataPrec)> + 'a> {
+        self.inner.iter_with_origin(
+            from - self.shift_x,
+            to - self.shift_x,
+            x_orig - self.shift_x,
+            y_orig,
+        )
+    }
+
+    fn iter_high_prec<'a>(
+        &'a self,
+        from: RangePrec,
+        to: RangePrec,
+    ) -> Box<dyn Iterator<Item = (RangePrec, RangePrec)> + 'a> {
+        Box::new(
+            self.inner
+                .iter_high_prec(from - self.shift_x, to - self.shift_x)
+                .map(move |(x, y)| (x + self.shift_x, y)),
+        )
+    }
+
+    fn value_at(&self, x: RangePrec) -> Option<RangePrec> {
+        self.inner.value_at(x - self.shift_x)
+    }
+
+    fn shrink(&mut self, from: RangePrec, to: RangePrec) {
+        dyn_clone::rc_make_mut(&mut self.inner).shrink(from - self.shift_x, to - self.shift_x);
+    }
+
+    fn shift(&mut self, shift_x: RangePrec, shift_y: RangePrec) {
+        if shift_y != 0.0 {
+            dyn_clone::rc_make_mut(&mut self.inner).shift(0.0, shift_y);
+        }
+
+        self.shift_x += shift_x;
+    }
+}
@@ -0,0 +1,36 @@
+use libchartium::{
+    renderers::{
+        ticks::{axis_offset, for_job},
+        PlotRect,
+    },
+    structs::RenderJob,
+};
+
+#[test]
+fn large_values_with_tiny_spans_get_an_offset() {
+    assert_eq!(axis_offset(1_000_001.0, 1_000_009.0), Some(1_000_000.0));
+    assert_eq!(axis_offset(-1_000_009.0, -1_000_001.0), Some(-1_000_010.0));
+    assert_eq!(axis_offset(1000.012, 1000.019), Some(1000.01));
+
+    assert_eq!(axis_offset(0.0, 100.0), None);
+    assert_eq!(axis_offset(1000.0, 1010.0), None);
+    assert_eq!(axis_offset(5.0, 5.0), None);
+}
+
+#[test]
+fn offset_ticks_are_labeled_as_deltas() {
+    let mut job = RenderJob::new("f64".to_string(), 0, 0);
+    (job.x_from, job.x_to, job.y_from, job.y_to) = (0.0, 10.0, 1_000_001.0, 1_000_009.0);
+
+    let result = for_job(&job, &PlotRect::of(&job, 800, 600));
+    let (x_offset, y_offset, y2_offset) = result.offsets();
+    assert!(x_offset.is_none() && y2_offset.is_none());
+
+    let y_offset = y_offset.unwrap();
+    assert_eq!((y_offset.val, y_offset.label.as_str()), (1e6, "+1.0e6"));
+
+    let (_, y_ticks, _) = result.ticks();
+    assert_eq!(y_ticks[0].val(), 1_000_002.0);
+    let labels: Vec<_> = y_ticks.iter().map(|t| t.label()).collect();
+    assert_eq!(labels, ["2", "3", "4", "5", "6", "7", "8", "9"]);
+}
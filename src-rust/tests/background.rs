@@ -0,0 +1,67 @@
+use libchartium::{
+    data_module::DataModule,
+    renderers::{Renderer, SvgRenderer},
+    structs::{Background, RenderJob},
+};
+
+fn job() -> RenderJob {
+    let mut job = RenderJob::new("f64".to_string(), 0, 0);
+    (job.x_from, job.x_to, job.y_from, job.y_to) = (0.0, 10.0, 0.0, 10.0);
+    job
+}
+
+#[test]
+fn backgrounds_pick_the_clear_color_and_contrast() {
+    let mut job = job();
+    assert_eq!(job.clear_color(), None);
+
+    job.dark_mode = true;
+    job.background = Background::Theme;
+    assert_eq!(job.clear_color(), Some([0x1a, 0x1a, 0x1a]));
+    assert!(job.dark_background());
+
+    // a solid background decides the contrast on its own
+    let hash = job.style_hash();
+    job.background = Background::Solid;
+    job.set_background_color(&[0xf0, 0xf0, 0xe0]);
+    assert_ne!(job.style_hash(), hash);
+    assert_eq!(job.clear_color(), Some([0xf0, 0xf0, 0xe0]));
+    assert!(!job.dark_background());
+
+    job.dark_mode = false;
+    job.set_background_color(&[0x20, 0x00, 0x40]);
+    assert!(job.dark_background());
+}
+
+#[test]
+fn svg_exports_fill_their_background() {
+    let module = DataModule::new();
+    let mut renderer = SvgRenderer::new(200, 100, 0.0);
+
+    renderer.render(&module, job()).unwrap();
+    assert!(!renderer.take_svg().unwrap().contains("<rect"));
+
+    let mut job = job();
+    job.background = Background::Theme;
+    renderer.render(&module, job).unwrap();
+    let svg = renderer.take_svg().unwrap();
+    assert!(svg.contains(r##"<rect width="100%" height="100%" fill="#ffffff"/>"##));
+}
+
+#[cfg(feature = "raster")]
+#[test]
+fn raster_frames_are_cleared_to_the_background() {
+    use libchartium::renderers::{RasterRenderer, RendererOptions};
+
+    let module = DataModule::new();
+    let mut job = job();
+    (job.render_axes, job.render_grid) = (false, false);
+    job.background = Background::Solid;
+    job.set_background_color(&[0x10, 0x20, 0x30]);
+
+    let mut renderer = RasterRenderer::new(40, 20, RendererOptions::new(false));
+    let frame = renderer.render_offscreen(&module, &[job], 40, 20).unwrap();
+    assert!(frame
+        .chunks_exact(4)
+        .all(|px| px == [0x10, 0x20, 0x30, 0xff]));
+}
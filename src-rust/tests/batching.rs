@@ -0,0 +1,27 @@
+use libchartium::{renderers::batch_order, structs::RenderJob};
+
+fn job(bundles: &[usize]) -> RenderJob {
+    let mut job = RenderJob::new("f64".to_string(), 0, bundles.len());
+    for &bundle in bundles {
+        job.add_bundle(bundle);
+    }
+
+    job
+}
+
+#[test]
+fn jobs_of_a_chart_stay_together_in_order() {
+    let charts = [1, 2, 1, 2];
+    let jobs = [job(&[5]), job(&[7]), job(&[6]), job(&[8])];
+
+    assert_eq!(batch_order(&charts, &jobs), [0, 2, 1, 3]);
+}
+
+#[test]
+fn charts_sharing_bundles_are_adjacent() {
+    let charts = [1, 2, 3];
+    let jobs = [job(&[4]), job(&[9]), job(&[2, 4])];
+
+    assert_eq!(batch_order(&charts, &jobs), [2, 0, 1]);
+    assert_eq!(batch_order(&[1, 2], &[job(&[]), job(&[])]), [0, 1]);
+}
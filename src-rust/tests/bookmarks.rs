@@ -0,0 +1,87 @@
+use libchartium::{
+    renderers::RendererContainer,
+    structs::{Annotation, Bookmark, RenderJob, Scale, View},
+};
+
+fn job(view: View) -> RenderJob {
+    let mut job = RenderJob::new("f64".to_string(), 0, 0);
+    job.set_view(view);
+    job
+}
+
+#[test]
+fn bookmarks_are_saved_by_name_per_chart() {
+    let mut renderer = RendererContainer::new_svg(200, 100, 0.0);
+    let bookmark = Bookmark::of_job(&job(View::new((0.0, 10.0), (0.0, 1.0))));
+
+    renderer.save_bookmark("run a".to_string(), &bookmark);
+    renderer.save_bookmark("run b".to_string(), &bookmark);
+    renderer.save_bookmark("run a".to_string(), &bookmark);
+    assert_eq!(renderer.bookmark_names(), ["run a", "run b"]);
+
+    assert!(renderer.remove_bookmark("run a"));
+    assert!(!renderer.remove_bookmark("run a"));
+    assert_eq!(renderer.bookmark_names(), ["run b"]);
+}
+
+#[test]
+fn applying_restores_hidden_traces_and_animates_the_view() {
+    let mut renderer = RendererContainer::new_svg(200, 100, 0.0);
+    let mut saved = job(View::new((0.0, 10.0), (0.0, 1.0)));
+    saved.blacklist_trace(3);
+    renderer.save_bookmark("spike".to_string(), &Bookmark::of_job(&saved));
+
+    let mut current = job(View::new((100.0, 200.0), (0.0, 1.0)));
+    current.blacklist_trace(1);
+    let animation = renderer
+        .apply_bookmark("spike", &mut current, 1000.0, 500.0)
+        .unwrap();
+    assert_eq!(current.blacklist(), [3]);
+
+    assert!(!animation.step(&mut current, 1250.0));
+    assert_eq!(current.view().x(), (50.0, 105.0));
+    assert!(animation.step(&mut current, 1500.0));
+    assert_eq!(current.view(), saved.view());
+}
+
+#[test]
+fn log_axes_move_evenly_in_decades() {
+    let mut from = job(View::new((1.0, 10.0), (1.0, 10.0)));
+    from.x_scale = Scale::Log10;
+    let to = View::new((100.0, 1000.0), (1.0, 10.0));
+
+    let mut renderer = RendererContainer::new_svg(200, 100, 0.0);
+    renderer.save_bookmark("zoomed".to_string(), &Bookmark::of_job(&job(to)));
+    let animation = renderer
+        .apply_bookmark("zoomed", &mut from, 0.0, 100.0)
+        .unwrap();
+
+    animation.step(&mut from, 50.0);
+    let (x_from, x_to) = from.view().x();
+    assert!((x_from - 10.0).abs() < 1e-9 && (x_to - 100.0).abs() < 1e-9);
+}
+
+#[test]
+fn annotations_of_the_last_bookmark_are_replaced() {
+    let mut renderer = RendererContainer::new_svg(200, 100, 0.0);
+    let mut with_limit = Bookmark::of_job(&job(View::new((0.0, 10.0), (0.0, 1.0))));
+    with_limit.add_annotation(&Annotation::h_line(0.8, &[255, 0, 0], "limit".to_string()));
+    renderer.save_bookmark("limit".to_string(), &with_limit);
+    renderer.save_bookmark(
+        "plain".to_string(),
+        &Bookmark::of_job(&job(View::new((0.0, 10.0), (0.0, 1.0)))),
+    );
+
+    let mut current = job(View::new((0.0, 10.0), (0.0, 1.0)));
+    let own = renderer.add_annotation(&Annotation::v_line(5.0, &[0, 0, 255], "own".to_string()));
+    renderer
+        .apply_bookmark("limit", &mut current, 0.0, 0.0)
+        .unwrap();
+    renderer
+        .apply_bookmark("plain", &mut current, 0.0, 0.0)
+        .unwrap();
+
+    // the bookmark's annotation is gone, the host's own one stays
+    assert!(!renderer.remove_annotation(own + 1));
+    assert!(renderer.remove_annotation(own));
+}
@@ -0,0 +1,47 @@
+use libchartium::{
+    data_module::DataModule,
+    renderers::{BundleEntry, BundleOptions, Renderer, SvgRenderer},
+    structs::RenderJob,
+};
+
+fn draw(renderer: &mut SvgRenderer, module: &DataModule, bundle: usize) -> String {
+    let mut job = RenderJob::new("f64".to_string(), 0, 1);
+    (job.x_from, job.x_to, job.y_from, job.y_to) = (0.0, 3.0, 0.0, 5.0);
+    job.add_bundle(bundle);
+
+    renderer.render(module, job).unwrap();
+    renderer.svg()
+}
+
+#[test]
+fn replacing_bundle_takes_over_its_handle() {
+    let mut module = DataModule::new();
+    let trace = module.create_trace("load", "f64");
+    module.load_points(trace, vec![0.0, 1.0, 2.0, 3.0], vec![1.0, 3.0, 2.0, 4.0]);
+
+    let mut renderer = SvgRenderer::new(200, 100, 0.0);
+    let red = BundleEntry::forecast(trace, &[], [0xff, 0, 0], 2);
+    let blue = BundleEntry::forecast(trace, &[], [0, 0, 0xff], 2);
+    let opts = BundleOptions::default();
+
+    let bundle = renderer
+        .begin_bundle(&module, 0.0, 3.0, &red, opts, None)
+        .unwrap();
+    assert_eq!(renderer.step_bundle(&module, bundle, 1).unwrap(), 1.0);
+    assert!(draw(&mut renderer, &module, bundle).contains("#ff0000"));
+
+    let replacement = renderer
+        .begin_bundle(&module, 0.0, 3.0, &blue, opts, Some(bundle))
+        .unwrap();
+    assert_eq!(replacement, bundle);
+    assert_eq!(renderer.step_bundle(&module, bundle, 1).unwrap(), 1.0);
+    let svg = draw(&mut renderer, &module, bundle);
+    assert!(svg.contains("#0000ff") && !svg.contains("#ff0000"));
+
+    // a disposed bundle isn't replaced, the new one gets a handle of its own
+    renderer.dispose_bundle(bundle).unwrap();
+    let fresh = renderer
+        .begin_bundle(&module, 0.0, 3.0, &red, opts, Some(bundle))
+        .unwrap();
+    assert_ne!(fresh, bundle);
+}
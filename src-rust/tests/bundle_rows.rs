@@ -0,0 +1,32 @@
+use libchartium::{renderers::BundleEntry, structs::Marker};
+
+fn row(handle: u32, marker: u8) -> Vec<u8> {
+    let mut row = handle.to_be_bytes().to_vec();
+    row.extend(2u32.to_be_bytes());
+    row.extend([0xff, 0x80, 0x00, marker]);
+    row
+}
+
+#[test]
+fn rows_keep_their_twelve_bytes() {
+    let stream: Vec<u8> = [row(3, 1), row(4, 5)].concat();
+    let entries = BundleEntry::from_rows(&stream).unwrap();
+
+    let entries = serde_json::to_value(&entries).unwrap();
+    assert_eq!(entries[0]["handle"], 3);
+    assert_eq!(entries[0]["color"], serde_json::json!([0xff, 0x80, 0x00]));
+    assert_eq!(entries[0]["marker"], "square");
+    assert_eq!(entries[1]["marker"], "diamond");
+    assert_eq!(entries[1]["marker_size"], 0);
+}
+
+#[test]
+fn partial_rows_and_unknown_markers_are_rejected() {
+    let mut stream = row(3, 0);
+    stream.extend(7u32.to_be_bytes());
+    assert!(BundleEntry::from_rows(&stream).is_err());
+
+    assert!(BundleEntry::from_rows(&row(3, 9)).is_err());
+    assert_eq!(Marker::from_byte(9), None);
+    assert_eq!(Marker::from_byte(1), Some(Marker::Square));
+}
@@ -0,0 +1,44 @@
+use libchartium::{
+    data_module::DataModule,
+    renderers::{BundleEntry, BundleOptions, Renderer, SvgRenderer},
+    structs::RenderJob,
+};
+
+fn draw(renderer: &mut SvgRenderer, module: &DataModule, bundles: &[usize]) -> String {
+    let mut job = RenderJob::new("f64".to_string(), 0, bundles.len());
+    (job.x_from, job.x_to, job.y_from, job.y_to) = (0.0, 3.0, 0.0, 5.0);
+    for &bundle in bundles {
+        job.add_bundle(bundle);
+    }
+
+    renderer.render(module, job).unwrap();
+    renderer.take_svg().unwrap()
+}
+
+#[test]
+fn hidden_bundles_are_left_out_until_shown() {
+    let mut module = DataModule::new();
+    let run_a = module.create_trace("a", "f64");
+    let run_b = module.create_trace("b", "f64");
+    module.load_points(run_a, vec![0.0, 1.0, 2.0, 3.0], vec![1.0, 3.0, 2.0, 4.0]);
+    module.load_points(run_b, vec![0.0, 1.0, 2.0, 3.0], vec![2.0, 1.0, 3.0, 2.0]);
+
+    let mut renderer = SvgRenderer::new(200, 100, 0.0);
+    let opts = BundleOptions::default();
+    let red = BundleEntry::forecast(run_a, &[], [0xff, 0, 0], 2);
+    let blue = BundleEntry::forecast(run_b, &[], [0, 0, 0xff], 2);
+    let a = renderer
+        .create_bundle(&module, 0.0, 3.0, &red, opts)
+        .unwrap();
+    let b = renderer
+        .create_bundle(&module, 0.0, 3.0, &blue, opts)
+        .unwrap();
+
+    renderer.set_bundle_visible(a, false);
+    let svg = draw(&mut renderer, &module, &[a, b]);
+    assert!(!svg.contains("#ff0000") && svg.contains("#0000ff"));
+
+    renderer.set_bundle_visible(a, true);
+    let svg = draw(&mut renderer, &module, &[a, b]);
+    assert!(svg.contains("#ff0000") && svg.contains("#0000ff"));
+}
@@ -0,0 +1,23 @@
+use libchartium::data_module::{checksum_points, DataModule};
+
+#[test]
+fn equal_data_has_equal_checksums() {
+    let mut module = DataModule::new();
+    let a = module.create_trace("a", "f64");
+    let b = module.create_trace("b", "f64");
+    let c = module.create_trace("c", "f64");
+
+    module.load_points(a, vec![0.0, 1.0, 2.0], vec![1.5, -2.0, 4.0]);
+    module.load_points(b, vec![0.0, 1.0, 2.0], vec![1.5, -2.0, 4.0]);
+    module.load_points(c, vec![0.0, 1.0, 2.0], vec![1.5, -2.0, 4.5]);
+
+    let checksum = module.trace_checksum(a).unwrap();
+    assert_eq!(module.trace_checksum(b), Some(checksum));
+    assert_ne!(module.trace_checksum(c), Some(checksum));
+    assert_eq!(
+        checksum,
+        checksum_points([(0.0, 1.5), (1.0, -2.0), (2.0, 4.0)])
+    );
+
+    assert_eq!(module.trace_checksum(99), None);
+}
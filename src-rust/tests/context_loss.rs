@@ -0,0 +1,86 @@
+use libchartium::{
+    data_module::DataModule,
+    renderers::{
+        gpu_bundle::{BufferBundle, BuiltRow, PendingBundle},
+        BundleEntry, BundleOptions, RendererContainer,
+    },
+};
+
+struct Row(BundleEntry);
+
+impl BuiltRow for Row {
+    fn entry(&self) -> &BundleEntry {
+        &self.0
+    }
+}
+
+fn entry(handle: usize, stack_group: u32) -> BundleEntry {
+    let wire = serde_json::json!({
+        "handle": handle,
+        "width": 1,
+        "color": [0, 0, 0],
+        "stack_group": stack_group,
+    });
+    serde_json::from_value(wire).unwrap()
+}
+
+fn handles(entries: &[BundleEntry]) -> Vec<u64> {
+    entries
+        .iter()
+        .map(|e| serde_json::to_value(e).unwrap()["handle"].as_u64().unwrap())
+        .collect()
+}
+
+#[test]
+fn renderers_without_gpu_context_have_nothing_to_restore() {
+    let module = DataModule::new();
+    let mut renderer = RendererContainer::new_svg(200, 100, 0.0);
+
+    assert!(!renderer.is_context_lost());
+    assert_eq!(renderer.restore_context(&module).ok(), Some(false));
+}
+
+#[test]
+fn lost_bundles_are_released_with_their_entries() {
+    let bundle = |buffers, released| BufferBundle {
+        from: 0.0,
+        to: 10.0,
+        opts: BundleOptions::default(),
+        buffers,
+        released,
+    };
+
+    let lost = bundle(vec![Row(entry(1, 0)), Row(entry(2, 0))], None).lost();
+    assert!(lost.buffers.is_empty());
+    assert_eq!(handles(lost.released.as_deref().unwrap()), vec![1, 2]);
+
+    // bundles released by suspend keep the entries they were released with
+    let lost = bundle(Vec::new(), Some(vec![entry(3, 0)])).lost();
+    assert_eq!(handles(lost.released.as_deref().unwrap()), vec![3]);
+}
+
+#[test]
+fn pending_bundles_are_restarted() {
+    let module = DataModule::new();
+    let entries = vec![entry(1, 0), entry(2, 5), entry(3, 5)];
+    let mut pending: PendingBundle<Row> =
+        PendingBundle::new(&module, 0.0, 10.0, BundleOptions::default(), entries);
+    // the stacked entries are built together
+    assert_eq!(pending.chunks.len(), 2);
+
+    for i in pending.chunks.pop_front().unwrap() {
+        pending.built[i] = Some(Row(pending.entries[i].clone()));
+    }
+    assert!(pending.progress() > 0.0);
+
+    let restarted = pending.restarted(&module);
+    assert_eq!(restarted.progress(), 0.0);
+    assert_eq!(restarted.chunks.len(), 2);
+
+    let released = restarted.released();
+    assert!(released.buffers.is_empty());
+    assert_eq!(
+        handles(released.released.as_deref().unwrap()),
+        vec![1, 2, 3]
+    );
+}
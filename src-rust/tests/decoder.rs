@@ -0,0 +1,61 @@
+use libchartium::structs::{decoder, register_native_decoder, ChunkDecoder, DecodedRows};
+
+/// Lines of `x y` text decoded into rows of a f64 x and a f64 y
+#[derive(Default)]
+struct TextRows {
+    pending: Vec<u8>,
+}
+
+impl TextRows {
+    fn write_line(line: &[u8], rows: &mut Vec<u8>) -> Result<(), String> {
+        let line = std::str::from_utf8(line).map_err(|e| e.to_string())?;
+        for value in line.split_whitespace() {
+            let value: f64 = value
+                .parse()
+                .map_err(|_| format!("{} isn't a number", value))?;
+            rows.extend_from_slice(&value.to_le_bytes());
+        }
+
+        Ok(())
+    }
+}
+
+impl ChunkDecoder for TextRows {
+    fn decode(&mut self, chunk: &[u8], rows: &mut Vec<u8>) -> Result<(), String> {
+        self.pending.extend_from_slice(chunk);
+
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            Self::write_line(&line, rows)?;
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self, rows: &mut Vec<u8>) -> Result<(), String> {
+        Self::write_line(&std::mem::take(&mut self.pending), rows)
+    }
+}
+
+#[test]
+fn registered_decoders_produce_whole_rows() {
+    register_native_decoder("text", || Box::<TextRows>::default());
+
+    let mut rows = DecodedRows::new(decoder("text").unwrap(), 16);
+    for chunk in ["1 10\n2 ", "20\n3", " 30"] {
+        rows.push(chunk.as_bytes()).unwrap();
+    }
+
+    let rows = rows.finish().unwrap();
+    let values: Vec<f64> = rows
+        .chunks_exact(8)
+        .map(|v| f64::from_le_bytes(v.try_into().unwrap()))
+        .collect();
+    assert_eq!(values, [1.0, 10.0, 2.0, 20.0, 3.0, 30.0]);
+
+    let mut partial = DecodedRows::new(decoder("text").unwrap(), 16);
+    partial.push(b"1 10 2").unwrap();
+    assert!(partial.finish().is_err());
+
+    assert!(decoder("missing").is_none());
+}
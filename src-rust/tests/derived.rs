@@ -0,0 +1,34 @@
+use libchartium::data_module::DataModule;
+
+fn values(module: &DataModule, handle: usize) -> Vec<(f64, f64)> {
+    module
+        .get_trace(handle)
+        .unwrap()
+        .get_data_high_prec(f64::MIN, f64::MAX)
+        .collect()
+}
+
+#[test]
+fn derived_traces_follow_their_inputs() {
+    let mut module = DataModule::new();
+    let a = module.create_rolling_trace("a", "f64", 16, 0.0);
+    let b = module.create_rolling_trace("b", "f64", 16, 0.0);
+    module.push_rolling(a, &[0.0, 1.0], &[4.0, 6.0]);
+    module.push_rolling(b, &[0.0, 1.0], &[2.0, 2.0]);
+
+    let ratio = module.create_derived("(a - b) / b", &[a, b]).unwrap();
+    let doubled = module.create_derived("x * 2", &[ratio]).unwrap();
+    assert!(module.get_trace(ratio).unwrap().read_only);
+    assert_eq!(values(&module, ratio), [(0.0, 1.0), (1.0, 2.0)]);
+
+    module.push_rolling(a, &[2.0], &[10.0]);
+    module.push_rolling(b, &[2.0], &[5.0]);
+    assert_eq!(values(&module, ratio), [(0.0, 1.0), (1.0, 2.0), (2.0, 1.0)]);
+    assert_eq!(
+        values(&module, doubled),
+        [(0.0, 2.0), (1.0, 4.0), (2.0, 2.0)]
+    );
+
+    module.dispose_trace(b);
+    assert!(values(&module, doubled).is_empty());
+}
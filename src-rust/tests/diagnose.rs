@@ -0,0 +1,50 @@
+use libchartium::data_module::{diagnose, Gap};
+
+#[test]
+fn reports_interval_gaps_and_disorder() {
+    let points = [
+        (0.0, 1.0),
+        (1.0, 1.0),
+        (2.0, f64::NAN),
+        (2.0, 1.0),
+        (3.0, 1.0),
+        (10.0, 1.0),
+        (9.0, 1.0),
+        (10.5, 1.0),
+        (15.0, 1.0),
+    ];
+    let diagnosis = diagnose(points.into_iter());
+
+    assert_eq!(diagnosis.points, 9);
+    assert_eq!(diagnosis.interval, Some(1.0));
+    assert_eq!(diagnosis.duplicates, 1);
+    assert_eq!(diagnosis.out_of_order, 1);
+    assert_eq!(diagnosis.first_out_of_order, Some(9.0));
+    assert_eq!(diagnosis.non_finite, 1);
+    assert_eq!(
+        diagnosis.gaps,
+        vec![
+            Gap {
+                from: 3.0,
+                to: 10.0,
+                length: 7.0
+            },
+            Gap {
+                from: 10.5,
+                to: 15.0,
+                length: 4.5
+            },
+        ]
+    );
+}
+
+#[test]
+fn regular_sampling_has_no_jitter() {
+    let diagnosis = diagnose((0..100).map(|i| (i as f64 * 0.5, 0.0)));
+
+    assert_eq!(diagnosis.interval, Some(0.5));
+    assert_eq!(diagnosis.jitter, Some(0.0));
+    assert!(diagnosis.gaps.is_empty());
+
+    assert_eq!(diagnose(std::iter::once((0.0, 0.0))).interval, None);
+}
@@ -0,0 +1,19 @@
+use libchartium::data_module::{downsample, max_error, Downsampling, Fidelity};
+
+#[test]
+fn m4_keeps_extrema_within_the_reported_error() {
+    let data: Vec<(f32, f32)> = (0..1000)
+        .map(|i| (i as f32, (i as f32 * 0.1).sin()))
+        .collect();
+    let reduced = downsample(data.clone(), Downsampling::M4, 0.0, 1000.0, 10);
+
+    assert!(reduced.len() < data.len());
+    assert_eq!(
+        Fidelity::of(Downsampling::M4, true).max(Fidelity::of(Downsampling::None, true)),
+        Fidelity::MinMax
+    );
+
+    let error = max_error(&data, &reduced);
+    assert!(error > 0.0 && error <= 2.0, "error {}", error);
+    assert_eq!(max_error(&data, &data), 0.0);
+}
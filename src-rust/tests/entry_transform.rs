@@ -0,0 +1,63 @@
+use libchartium::{
+    data_module::DataModule,
+    renderers::{BundleEntry, BundleOptions, Renderer, SvgRenderer},
+    structs::RenderJob,
+};
+
+fn draw(renderer: &mut SvgRenderer, module: &DataModule, bundle: usize) -> String {
+    let mut job = RenderJob::new("f64".to_string(), 0, 1);
+    (job.x_from, job.x_to, job.y_from, job.y_to) = (0.0, 10.0, 0.0, 10.0);
+    (job.render_axes, job.render_grid) = (false, false);
+    job.add_bundle(bundle);
+
+    renderer.render(module, job).unwrap();
+    renderer.take_svg().unwrap()
+}
+
+#[test]
+fn entries_are_drawn_scaled_and_offset() {
+    let mut module = DataModule::new();
+    let run = module.create_trace("run", "f64");
+    module.load_points(run, vec![0.0, 10.0], vec![2.0, 2.0]);
+
+    let mut renderer = SvgRenderer::new(200, 100, 0.0);
+    let entries = BundleEntry::forecast(run, &[], [0xff, 0, 0], 2);
+    let bundle = renderer
+        .create_bundle(&module, 0.0, 10.0, &entries, BundleOptions::default())
+        .unwrap();
+    assert!(draw(&mut renderer, &module, bundle).contains(r#"d="M0.00 80.00L200.00 80.00""#));
+
+    // 2 * 2 + 1 lands halfway up the plot
+    let moved: Vec<_> = entries
+        .into_iter()
+        .map(|e| e.with_y_transform(1.0, 2.0))
+        .collect();
+    assert_eq!(moved[0].y_transform(), (2.0, 1.0));
+    renderer
+        .rebundle(&module, bundle, &[], &[], &moved)
+        .unwrap();
+    assert!(draw(&mut renderer, &module, bundle).contains(r#"d="M0.00 50.00L200.00 50.00""#));
+
+    // restyling by a byte row keeps the transform, which rows don't carry
+    let mut row = (run as u32).to_be_bytes().to_vec();
+    row.extend(3u32.to_be_bytes());
+    row.extend([0, 0, 0xff, 0]);
+    let restyled = BundleEntry::from_rows(&row).unwrap();
+    renderer
+        .rebundle(&module, bundle, &[], &[], &restyled)
+        .unwrap();
+    let svg = draw(&mut renderer, &module, bundle);
+    assert!(svg.contains(r#"d="M0.00 50.00L200.00 50.00""#) && svg.contains("#0000ff"));
+}
+
+#[test]
+fn wire_entries_default_to_no_transform() {
+    let wire = r#"{ "handle": 0, "width": 1, "color": [0, 0, 0] }"#;
+    let entry: BundleEntry = serde_json::from_str(wire).unwrap();
+    assert_eq!(entry.y_transform(), (1.0, 0.0));
+
+    let moved = entry.with_y_transform(-3.0, 0.5);
+    let wire = serde_json::to_string(&moved).unwrap();
+    let entry: BundleEntry = serde_json::from_str(&wire).unwrap();
+    assert_eq!(entry.y_transform(), (0.5, -3.0));
+}
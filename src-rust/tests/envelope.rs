@@ -0,0 +1,36 @@
+use libchartium::data_module::DataModule;
+
+#[test]
+fn envelope_columns_become_traces() {
+    let mut module = DataModule::new();
+    let handle = module.create_envelope_trace("cpu", "f64");
+    module
+        .load_envelope(
+            handle,
+            vec![0.0, 10.0, 20.0],
+            vec![1.0, 2.0, 3.0],
+            vec![5.0, 6.0, 7.0],
+            vec![3.0, 4.0, 5.0],
+        )
+        .unwrap();
+
+    let envelope = module.get_envelope(handle).unwrap();
+    assert_eq!(envelope.mean, handle);
+
+    let values = |handle| {
+        module
+            .get_trace(handle)
+            .unwrap()
+            .get_data_high_prec(0.0, 20.0)
+            .map(|p| p.1)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(values(envelope.min), [1.0, 2.0, 3.0]);
+    assert_eq!(values(envelope.max), [5.0, 6.0, 7.0]);
+    assert_eq!(values(envelope.mean), [3.0, 4.0, 5.0]);
+    assert_eq!(module.get_trace(envelope.max).unwrap().id, "cpu.max");
+
+    module.dispose_trace(handle);
+    assert!(module.get_trace(envelope.min).is_none());
+    assert!(module.get_envelope(handle).is_none());
+}
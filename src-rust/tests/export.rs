@@ -0,0 +1,15 @@
+use libchartium::renderers::export::encode_png;
+
+#[test]
+fn flips_rows_and_unpremultiplies() {
+    // bottom row first: a half transparent red, then an opaque blue
+    let pixels = [128, 0, 0, 128, 0, 0, 255, 255];
+    let png = encode_png(1, 2, &pixels).unwrap();
+
+    let decoder = png::Decoder::new(&png[..]);
+    let mut reader = decoder.read_info().unwrap();
+    let mut image = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut image).unwrap();
+
+    assert_eq!(image, [0, 0, 255, 255, 255, 0, 0, 128]);
+}
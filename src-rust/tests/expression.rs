@@ -0,0 +1,38 @@
+use std::rc::Rc;
+
+use libchartium::trace::{Expression, ExpressionSegment, ImplicitSegment, Segment};
+
+#[test]
+fn follows_precedence_and_binds_variables_in_order() {
+    let expr = Expression::parse("a / b * 100 - -2 ^ 2").unwrap();
+
+    assert_eq!(expr.variables(), ["a", "b"]);
+    assert_eq!(expr.eval(&[1.0, 4.0]), 29.0);
+
+    let expr = Expression::parse("max(x, 2e1) + abs(y - x)").unwrap();
+    assert_eq!(expr.eval(&[30.0, 10.0]), 50.0);
+}
+
+#[test]
+fn rejects_malformed_expressions() {
+    for src in ["a +", "(a", "a b", "foo(a)", "min(a)", "a $ b"] {
+        assert!(Expression::parse(src).is_err(), "{} parsed", src);
+    }
+}
+
+#[test]
+fn evaluates_at_the_first_variable_interpolating_the_others() {
+    let a: Rc<dyn Segment> = Rc::new(ImplicitSegment::new(0.0, 1.0, Rc::new(vec![2.0, 4.0, 6.0])));
+    let b: Rc<dyn Segment> = Rc::new(ImplicitSegment::new(-1.0, 3.0, Rc::new(vec![1.5, 0.0])));
+
+    let expr = Expression::parse("a / b").unwrap();
+    let segment = ExpressionSegment::new(expr, vec![vec![a], vec![b]]);
+
+    // b spans -1..2 and reaches zero at 2, where the division isn't finite
+    assert_eq!((segment.from(), segment.to()), (0.0, 2.0));
+    assert_eq!(
+        segment.iter_high_prec(-5.0, 10.0).collect::<Vec<_>>(),
+        vec![(0.0, 2.0), (1.0, 8.0)]
+    );
+    assert_eq!(segment.value_at(0.5), Some(4.0));
+}
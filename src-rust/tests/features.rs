@@ -0,0 +1,28 @@
+use libchartium::renderers::{FeatureSet, RendererContainer};
+
+#[test]
+fn svg_honors_only_vector_features() {
+    let renderer = RendererContainer::new_svg(200, 100, 0.0);
+
+    let granted = renderer.negotiate(&FeatureSet::all());
+    assert!(granted.dash && granted.text);
+    assert!(!granted.instancing && !granted.msaa && !granted.float_textures);
+
+    let requested = FeatureSet {
+        text: true,
+        msaa: true,
+        ..FeatureSet::new()
+    };
+    assert_eq!(
+        renderer.negotiate(&requested),
+        FeatureSet {
+            text: true,
+            ..FeatureSet::new()
+        }
+    );
+}
+
+#[test]
+fn renderers_name_their_backend() {
+    assert_eq!(RendererContainer::new_svg(200, 100, 0.0).backend(), "svg");
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "ffi")]
+
+use std::ffi::{CStr, CString};
+
+use libchartium::ffi::*;
+
+#[test]
+fn renders_a_loaded_trace_to_svg() {
+    let id = CString::new("temperature").unwrap();
+    let x_type = CString::new("f64").unwrap();
+    let (x, y) = ([0.0, 1.0, 2.0, 3.0], [1.0, 3.0, 2.0, 4.0]);
+
+    unsafe {
+        let module = chartium_module_new();
+        let handle = chartium_create_trace(module, id.as_ptr(), x_type.as_ptr());
+        assert_ne!(handle, INVALID_HANDLE);
+        assert!(chartium_load_trace(
+            module,
+            handle,
+            x.as_ptr(),
+            y.as_ptr(),
+            4
+        ));
+        assert!(!chartium_load_trace(
+            module,
+            handle + 1,
+            x.as_ptr(),
+            y.as_ptr(),
+            4
+        ));
+
+        let job = chartium_job_new(x_type.as_ptr());
+        chartium_job_set_view(job, 0.0, 3.0, 0.0, 5.0);
+        chartium_job_add_trace(job, handle, [255, 0, 0].as_ptr(), 2);
+
+        let renderer = chartium_svg_renderer_new(200, 100);
+        let svg = chartium_render_svg(renderer, module, job);
+        assert!(!svg.is_null());
+        let text = CStr::from_ptr(svg).to_str().unwrap();
+        assert!(text.starts_with("<svg") && text.contains("<path"));
+
+        chartium_string_free(svg);
+        chartium_svg_renderer_free(renderer);
+        chartium_job_free(job);
+        chartium_module_free(module);
+    }
+}
+
+#[test]
+fn decimation_and_ticks_report_their_full_count() {
+    let x: Vec<f32> = (0..1000).map(|i| i as f32).collect();
+    let y: Vec<f32> = x.iter().map(|x| (x * 0.1).sin()).collect();
+    let (mut out_x, mut out_y) = ([0.0; 8], [0.0; 8]);
+
+    let count = unsafe {
+        chartium_downsample(
+            x.as_ptr(),
+            y.as_ptr(),
+            x.len(),
+            1,
+            10,
+            out_x.as_mut_ptr(),
+            out_y.as_mut_ptr(),
+            8,
+        )
+    };
+    assert!(count > 8 && count < x.len());
+    assert_eq!(out_x[0], 0.0);
+
+    let (mut values, mut positions) = ([0.0; 16], [0.0; 16]);
+    let ticks =
+        unsafe { chartium_ticks(0.0, 10.0, values.as_mut_ptr(), positions.as_mut_ptr(), 16) };
+    assert!(ticks > 0 && ticks <= 16);
+    assert_eq!((values[0], positions[0]), (2.0, 0.2));
+    assert_eq!(chartium_abi_version(), ABI_VERSION);
+}
@@ -0,0 +1,110 @@
+use libchartium::{
+    data_module::{fit_view, suggest_range},
+    structs::{AxisScale, Scale, View, ViewLock},
+};
+
+#[test]
+fn view_spans_all_extents() {
+    let view = fit_view(
+        [((0.0, 10.0), (2.0, 3.0)), ((5.0, 20.0), (-1.0, 1.0))],
+        0.0,
+        false,
+        false,
+        &ViewLock::new(),
+    );
+
+    assert_eq!(
+        view,
+        Some(View {
+            x_from: 0.0,
+            x_to: 20.0,
+            y_from: -1.0,
+            y_to: 3.0
+        })
+    );
+    assert_eq!(fit_view([], 0.1, true, true, &ViewLock::new()), None);
+}
+
+#[test]
+fn view_pads_and_rounds_y() {
+    let view = fit_view(
+        [((0.0, 1.0), (12.0, 48.0))],
+        0.0,
+        true,
+        true,
+        &ViewLock::new(),
+    )
+    .unwrap();
+    assert_eq!((view.y_from, view.y_to), (0.0, 50.0));
+
+    let view = fit_view(
+        [((0.0, 1.0), (10.0, 20.0))],
+        0.1,
+        false,
+        false,
+        &ViewLock::new(),
+    )
+    .unwrap();
+    assert!((view.y_from - 9.0).abs() < 1e-9 && (view.y_to - 21.0).abs() < 1e-9);
+
+    // flat traces still get a range to be drawn in
+    let view = fit_view(
+        [((0.0, 1.0), (5.0, 5.0))],
+        0.1,
+        false,
+        false,
+        &ViewLock::new(),
+    )
+    .unwrap();
+    assert_eq!((view.y_from, view.y_to), (4.0, 6.0));
+}
+
+#[test]
+fn locked_axes_keep_their_ranges() {
+    let mut lock = ViewLock::new();
+    lock.lock_y(0.0, 100.0).unwrap();
+    assert!(lock.lock_x(5.0, 5.0).is_err());
+    assert!(lock.lock_x(0.0, f64::NAN).is_err());
+
+    let view = fit_view([((0.0, 10.0), (20.0, 30.0))], 0.1, false, true, &lock).unwrap();
+    assert_eq!(view, View::new((0.0, 10.0), (0.0, 100.0)));
+
+    let linear = (
+        AxisScale::from(Scale::Linear),
+        AxisScale::from(Scale::Linear),
+    );
+    let zoomed = view.zoom(0.5, (4.0, 50.0), linear, &lock);
+    assert_eq!(zoomed, View::new((2.0, 7.0), (0.0, 100.0)));
+
+    let panned = zoomed.pan(1.0, 10.0, &lock);
+    assert_eq!(panned, View::new((3.0, 8.0), (0.0, 100.0)));
+}
+
+#[test]
+fn zooms_ruining_the_view_are_ignored() {
+    let view = View::new((0.0, 10.0), (1.0, 1000.0));
+    let free = ViewLock::new();
+    let scales = (
+        AxisScale::from(Scale::Linear),
+        AxisScale::from(Scale::Log10),
+    );
+
+    assert_eq!(view.zoom(0.0, (5.0, 10.0), scales, &free), view);
+    assert_eq!(view.zoom(f64::NAN, (5.0, 10.0), scales, &free), view);
+    assert_eq!(view.zoom(1e-12, (5.0, 10.0), scales, &free), view);
+
+    // logarithmic axes zoom around the center in decades
+    let zoomed = view.zoom(2.0, (5.0, 10.0), scales, &free);
+    assert!((zoomed.y_from - 0.1).abs() < 1e-9 && (zoomed.y_to - 1e5).abs() < 1e-6);
+}
+
+#[test]
+fn suggested_range_is_padded_and_snapped() {
+    // 10% of the 80 high range is added on both sides before snapping to the ticks
+    assert_eq!(
+        suggest_range([(12.0, 50.0), (-30.0, 20.0)], 10.0),
+        Some((-40.0, 60.0))
+    );
+    assert_eq!(suggest_range([(7.0, 7.0)], 5.0), Some((6.0, 8.0)));
+    assert_eq!(suggest_range([(f64::MAX, f64::MIN)], 5.0), None);
+}
@@ -0,0 +1,47 @@
+use libchartium::{
+    data_module::{fit_view, suggest_range},
+    renderers::ticks::{calc_scaled_ticks, calc_ticks, calc_time_ticks, inflate},
+    structs::{AxisScale, RenderJob, Scale, ViewLock},
+};
+
+#[test]
+fn flat_ranges_inflate_by_a_unit_or_five_percent() {
+    assert_eq!(inflate(0.0, 0.0), (-1.0, 1.0));
+    assert_eq!(inflate(5.0, 5.0), (4.0, 6.0));
+    assert_eq!(inflate(-1000.0, -1000.0), (-1050.0, -950.0));
+
+    // ranges with height and non-finite ones are left as they are
+    assert_eq!(inflate(1.0, 2.0), (1.0, 2.0));
+    assert!(inflate(f64::NAN, f64::NAN).0.is_nan());
+}
+
+#[test]
+fn degenerate_ranges_get_no_ticks() {
+    assert!(calc_ticks(5.0, 0.0).is_empty());
+    assert!(calc_ticks(5.0, f64::NAN).is_empty());
+    assert!(calc_time_ticks(1e12, 1e12).is_empty());
+
+    for scale in [Scale::Linear, Scale::Log10, Scale::SymLog] {
+        assert!(calc_scaled_ticks(3.0, 3.0, AxisScale::from(scale)).is_empty());
+    }
+}
+
+#[test]
+fn flat_traces_are_shown_with_height() {
+    let view = fit_view(
+        [((0.0, 1.0), (1e6, 1e6))],
+        0.0,
+        false,
+        false,
+        &ViewLock::new(),
+    )
+    .unwrap();
+    assert_eq!((view.y_from, view.y_to), (950_000.0, 1_050_000.0));
+    assert_eq!(suggest_range([(0.0, 0.0)], 0.0), Some((-1.0, 1.0)));
+
+    let mut job = RenderJob::new("f64".to_string(), 0, 0);
+    (job.x_from, job.x_to, job.y_from, job.y_to) = (0.0, 10.0, 20.0, 20.0);
+    let padded = job.padded();
+    assert_eq!((padded.y_from, padded.y_to), (19.0, 21.0));
+    assert!(!calc_ticks(padded.y_from, padded.y_to - padded.y_from).is_empty());
+}
@@ -0,0 +1,77 @@
+use libchartium::{
+    data_module::DataModule, renderers::draw_list::JobFingerprint, structs::RenderJob,
+};
+
+#[test]
+fn module_revision_follows_trace_changes() {
+    let mut module = DataModule::new();
+    let trace = module.create_rolling_trace("live", "f64", 16, 0.0);
+    let created = module.revision();
+
+    module.push_rolling(trace, &[1.0, 2.0], &[3.0, 4.0]);
+    let pushed = module.revision();
+    assert!(pushed > created);
+
+    let _ = module.get_trace(trace);
+    assert_eq!(module.revision(), pushed);
+
+    module.dispose_trace(trace);
+    assert!(module.revision() > pushed);
+}
+
+#[test]
+fn frames_differ_by_anything_drawn() {
+    let mut job = RenderJob::new("f64".to_string(), 0, 1);
+    (job.x_from, job.x_to, job.y_from, job.y_to) = (0.0, 10.0, 0.0, 1.0);
+    job.add_bundle(2);
+    let key = JobFingerprint::of(&job, 300, 200, 7);
+
+    assert_eq!(JobFingerprint::of(&job.clone(), 300, 200, 7), key);
+    assert_ne!(JobFingerprint::of(&job, 300, 200, 8), key);
+    assert_ne!(JobFingerprint::of(&job, 301, 200, 7), key);
+
+    let mut legend = job.clone();
+    legend.render_legend = true;
+    assert_ne!(JobFingerprint::of(&legend, 300, 200, 7), key);
+
+    let mut panned = job.clone();
+    panned.x_to = 11.0;
+    assert_ne!(JobFingerprint::of(&panned, 300, 200, 7), key);
+
+    let mut turned = job.clone();
+    turned.marker_rotation = 45.0;
+    assert_ne!(JobFingerprint::of(&turned, 300, 200, 7), key);
+}
+
+#[test]
+fn cached_frames_follow_the_points() {
+    use libchartium::renderers::{BundleEntry, BundleOptions, Renderer, SvgRenderer};
+
+    let mut module = DataModule::new();
+    let trace = module.create_rolling_trace("live", "f64", 16, 0.0);
+    module.push_rolling(trace, &[0.0, 10.0], &[2.0, 2.0]);
+
+    let mut renderer = SvgRenderer::new(200, 100, 0.0);
+    let entries = BundleEntry::forecast(trace, &[], [0, 0, 0xff], 2);
+    let bundle = renderer
+        .create_bundle(&module, 0.0, 20.0, &entries, BundleOptions::default())
+        .unwrap();
+
+    let mut draw = |module: &DataModule, x_to: f64| {
+        let mut job = RenderJob::new("f64".to_string(), 0, 1);
+        (job.x_from, job.x_to, job.y_from, job.y_to) = (0.0, x_to, 0.0, 10.0);
+        (job.render_axes, job.render_grid) = (false, false);
+        job.add_bundle(bundle);
+        renderer.render(module, job).unwrap();
+        renderer.take_svg().unwrap()
+    };
+
+    // switching back to a view draws it as before
+    let zoomed_out = draw(&module, 20.0);
+    let zoomed_in = draw(&module, 10.0);
+    assert_ne!(zoomed_in, zoomed_out);
+    assert_eq!(draw(&module, 20.0), zoomed_out);
+
+    module.push_rolling(trace, &[20.0], &[2.0]);
+    assert_ne!(draw(&module, 20.0), zoomed_out);
+}
@@ -0,0 +1,39 @@
+use libchartium::data_module::GapRules;
+
+#[test]
+fn breaks_at_missing_values_and_jumps() {
+    let mut points = vec![
+        (0.0, 1.0),
+        (1.0, f32::NAN),
+        (2.0, 2.0),
+        (3.0, 3.0),
+        (9.0, 4.0),
+    ];
+    let breaks = GapRules::new(true, 5.0).split(&mut points, None);
+
+    assert_eq!(points, [(0.0, 1.0), (2.0, 2.0), (3.0, 3.0), (9.0, 4.0)]);
+    assert_eq!(breaks, [1, 3]);
+
+    // missing values are kept and bridged unless breaking on them
+    let mut points = vec![(0.0, 1.0), (1.0, f32::NAN), (2.0, 2.0)];
+    assert!(GapRules::default().split(&mut points, None).is_empty());
+    assert_eq!(points.len(), 3);
+}
+
+#[test]
+fn appended_points_continue_the_previous_line() {
+    let rules = GapRules::new(true, 2.0);
+
+    let mut points = vec![(11.0, 1.0), (12.0, 1.0)];
+    assert_eq!(
+        rules.split(&mut points, Some((10.0, 0.0))),
+        Vec::<usize>::new()
+    );
+
+    let mut points = vec![(13.0, 1.0)];
+    assert_eq!(rules.split(&mut points, Some((10.0, 0.0))), [0]);
+
+    // leading missing values don't break a line that hasn't started
+    let mut points = vec![(0.0, f32::NAN), (1.0, 1.0)];
+    assert!(rules.split(&mut points, None).is_empty());
+}
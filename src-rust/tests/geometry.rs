@@ -0,0 +1,225 @@
+use libchartium::renderers::geometry::{
+    band_strip, candle_geometry, dash_segments, loses_precision, simplify, split_f64,
+    stack_area_strip, strips, stroke_strip, visible_span, Candles, LineCap, LineJoin,
+};
+
+#[test]
+fn empty_trace_stacks_to_nothing() {
+    let mut area = vec![];
+    assert!(stack_area_strip(&mut area, &[]).is_empty());
+
+    let mut area = vec![(0.0, 1.0), (1.0, 1.0)];
+    assert!(stack_area_strip(&mut area, &[]).is_empty());
+    assert_eq!(area, vec![(0.0, 1.0), (1.0, 1.0)]);
+}
+
+#[test]
+fn single_point_stacks_without_panic() {
+    let mut area = vec![(0.0, 0.0)];
+    let strip = stack_area_strip(&mut area, &[(0.0, 2.0)]);
+
+    assert_eq!(strip, vec![(0.0, 0.0), (0.0, 2.0)]);
+    assert_eq!(area, vec![(0.0, 2.0)]);
+}
+
+#[test]
+fn mismatched_lengths_use_shorter() {
+    let mut area = vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)];
+    let strip = stack_area_strip(&mut area, &[(0.0, 1.0), (1.0, 1.0)]);
+
+    assert_eq!(strip.len(), 4);
+    assert_eq!(area[2], (2.0, 0.0));
+}
+
+#[test]
+fn stacking_raises_area_to_top() {
+    let mut area = vec![(0.0, 0.0), (1.0, 0.0)];
+
+    stack_area_strip(&mut area, &[(0.0, 1.0), (1.0, 2.0)]);
+    let strip = stack_area_strip(&mut area, &[(0.0, 3.0), (1.0, 5.0)]);
+
+    assert_eq!(strip, vec![(0.0, 1.0), (0.0, 3.0), (1.0, 2.0), (1.0, 5.0)]);
+    assert_eq!(area, vec![(0.0, 3.0), (1.0, 5.0)]);
+}
+
+#[test]
+fn band_pairs_upper_with_lower() {
+    let upper = [(0.0, 2.0), (1.0, 3.0)];
+    let lower = [(0.0, 1.0), (1.0, 1.5)];
+
+    assert_eq!(
+        band_strip(&upper, lower.into_iter()),
+        vec![(0.0, 2.0), (0.0, 1.0), (1.0, 3.0), (1.0, 1.5)]
+    );
+    assert!(band_strip(&[], lower.into_iter()).is_empty());
+}
+
+#[test]
+fn candles_split_by_direction() {
+    let candles = [(0.0, [1.0, 4.0, 0.0, 3.0]), (1.0, [3.0, 3.5, 1.5, 2.0])];
+
+    let Candles { wicks, bodies } = candle_geometry(&candles, 0.25, true);
+    assert_eq!(wicks, vec![(0.0, 0.0), (0.0, 4.0)]);
+    assert_eq!(bodies.len(), 6);
+    assert!(bodies.contains(&(-0.25, 1.0)) && bodies.contains(&(0.25, 3.0)));
+
+    let Candles { wicks, bodies } = candle_geometry(&candles, 0.25, false);
+    assert_eq!(wicks, vec![(1.0, 1.5), (1.0, 3.5)]);
+    assert_eq!(bodies.len(), 6);
+}
+
+#[test]
+fn simplify_keeps_corners_and_ends() {
+    let line = [
+        (0.0, 0.0),
+        (1.0, 0.1),
+        (2.0, 0.0),
+        (3.0, 5.0),
+        (4.0, 10.0),
+        (5.0, 10.0),
+    ];
+
+    assert_eq!(
+        simplify(&line, 0.5),
+        vec![(0.0, 0.0), (2.0, 0.0), (4.0, 10.0), (5.0, 10.0)]
+    );
+    assert_eq!(simplify(&line, 0.0), line.to_vec());
+}
+
+#[test]
+fn stroke_miters_and_bevels_corners() {
+    let corner = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)];
+
+    let mitered = stroke_strip(&corner, 2.0, LineJoin::Miter, LineCap::Butt).strip;
+    let expected = [
+        (0.0, 1.0),
+        (0.0, -1.0),
+        (9.0, 1.0),
+        (11.0, -1.0),
+        (9.0, 10.0),
+        (11.0, 10.0),
+    ];
+    assert_eq!(mitered.len(), expected.len());
+    for (p, e) in mitered.iter().zip(expected) {
+        assert!(
+            (p.0 - e.0).abs() < 1e-5 && (p.1 - e.1).abs() < 1e-5,
+            "{p:?} != {e:?}"
+        );
+    }
+
+    let beveled = stroke_strip(&corner, 2.0, LineJoin::Bevel, LineCap::Butt).strip;
+    assert_eq!(beveled.len(), 8);
+    assert_eq!(
+        beveled[2..6],
+        [(10.0, 1.0), (10.0, -1.0), (9.0, 0.0), (11.0, 0.0)]
+    );
+
+    // a spike back onto itself is too sharp to miter
+    let spike = stroke_strip(
+        &[(0.0, 0.0), (10.0, 0.0), (0.0, 0.1)],
+        2.0,
+        LineJoin::Miter,
+        LineCap::Butt,
+    );
+    assert_eq!(spike.strip.len(), 8);
+    assert_eq!(spike.edges, [1.0, -1.0].repeat(4));
+}
+
+#[test]
+fn stroke_caps_and_degenerate_input() {
+    let line = [(0.0, 0.0), (0.0, 0.0), (10.0, 0.0)];
+
+    assert_eq!(
+        stroke_strip(&line, 2.0, LineJoin::Miter, LineCap::Butt)
+            .strip
+            .len(),
+        4
+    );
+
+    let stroke = stroke_strip(&line, 2.0, LineJoin::Miter, LineCap::Round);
+    assert_eq!(stroke.strip.len(), stroke.edges.len());
+    // the caps fan around the centerline
+    assert!(stroke.edges.contains(&0.0));
+
+    let round = stroke.strip;
+    assert!(round.len() > 4);
+    assert!(round
+        .iter()
+        .all(|p| p.0 >= -1.001 && p.0 <= 11.001 && p.1.abs() <= 1.001));
+    assert!(round.iter().any(|p| p.0 < -0.999) && round.iter().any(|p| p.0 > 10.999));
+
+    assert!(
+        stroke_strip(&[(5.0, 5.0)], 2.0, LineJoin::Miter, LineCap::Butt)
+            .strip
+            .is_empty()
+    );
+    assert!(
+        !stroke_strip(&[(5.0, 5.0)], 2.0, LineJoin::Miter, LineCap::Round)
+            .strip
+            .is_empty()
+    );
+}
+
+#[test]
+fn dashes_repeat_their_pattern() {
+    assert_eq!(
+        dash_segments((0.0, 0.0), (10.0, 0.0), &[3.0, 1.0]),
+        vec![
+            (0.0, 0.0),
+            (3.0, 0.0),
+            (4.0, 0.0),
+            (7.0, 0.0),
+            (8.0, 0.0),
+            (10.0, 0.0)
+        ]
+    );
+
+    // odd patterns swap dashes and gaps when repeated
+    assert_eq!(
+        dash_segments((0.0, 0.0), (0.0, 8.0), &[2.0]),
+        vec![(0.0, 0.0), (0.0, 2.0), (0.0, 4.0), (0.0, 6.0)]
+    );
+
+    let solid = vec![(1.0, 1.0), (5.0, 4.0)];
+    assert_eq!(dash_segments((1.0, 1.0), (5.0, 4.0), &[]), solid);
+    assert_eq!(dash_segments((1.0, 1.0), (5.0, 4.0), &[0.0, -1.0]), solid);
+}
+
+#[test]
+fn strips_split_at_breaks_within_range() {
+    assert_eq!(strips(&[], 2, 6), vec![2..6]);
+    assert_eq!(strips(&[3, 5, 9], 0, 8), vec![0..3, 3..5, 5..8]);
+
+    // breaks at or outside the ends don't make empty strips
+    assert_eq!(strips(&[2, 6], 2, 6), vec![2..6]);
+    assert!(strips(&[1], 4, 4).is_empty());
+}
+
+#[test]
+fn split_origins_keep_their_precision() {
+    let origin = 1_700_000_000_123.456;
+    let (high, low) = split_f64(origin);
+
+    assert_ne!(high as f64, origin);
+    assert!((high as f64 + low as f64 - origin).abs() < 1e-3);
+}
+
+#[test]
+fn offsets_lose_precision_when_zoomed_in() {
+    // a day of milliseconds over 1000 pixels is coarse enough for offsets of a year
+    assert!(!loses_precision(3.2e10, 8.64e7, 1000));
+    // a second over 1000 pixels isn't
+    assert!(loses_precision(3.2e10, 1000.0, 1000));
+    assert!(!loses_precision(500.0, 1000.0, 1000));
+}
+
+#[test]
+fn visible_span_keeps_a_point_past_each_edge() {
+    let xs = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+
+    assert_eq!(visible_span(&xs, |&x| x, 2.5, 3.5), 2..5);
+    assert_eq!(visible_span(&xs, |&x| x, 2.0, 3.0), 1..5);
+    assert_eq!(visible_span(&xs, |&x| x, -2.0, 9.0), 0..6);
+    assert_eq!(visible_span(&xs, |&x| x, 7.0, 9.0), 5..6);
+    assert!(visible_span(&[] as &[f32], |&x| x, 0.0, 1.0).is_empty());
+}
@@ -0,0 +1,61 @@
+use libchartium::{
+    data_module::DataModule,
+    renderers::{raise_order, HitIndex},
+    structs::RenderJob,
+};
+
+#[test]
+fn brushed_points_form_index_runs() {
+    let index = HitIndex::new(
+        100.0,
+        vec![
+            (0.0, 1.0),
+            (1.0, 5.0),
+            (2.0, 6.0),
+            (3.0, 2.0),
+            (4.0, 5.5),
+            (5.0, 9.0),
+        ],
+    );
+
+    assert_eq!(
+        index.runs_within((101.0, 104.0), (4.0, 7.0)),
+        vec![1..3, 4..5]
+    );
+    assert_eq!(index.runs_within((100.0, 105.0), (0.0, 10.0)), vec![0..6]);
+    assert!(index.runs_within((110.0, 120.0), (0.0, 10.0)).is_empty());
+}
+
+#[test]
+fn values_are_interpolated_between_points() {
+    let index = HitIndex::new(100.0, vec![(0.0, 1.0), (10.0, 3.0), (20.0, 3.0)]);
+
+    assert_eq!(index.value_at(105.0), Some(2.0));
+    assert_eq!(index.value_at(110.0), Some(3.0));
+    assert_eq!(index.value_at(99.0), None);
+    assert_eq!(index.value_at(121.0), None);
+}
+
+#[test]
+fn entries_nearest_to_the_cursor_are_drawn_last() {
+    let module = DataModule::new();
+    let mut job = RenderJob::new("f64".to_string(), 0, 0);
+    (job.y_from, job.y_to) = (0.0, 10.0);
+    let plain = (1.0, 0.0);
+    let values = [
+        (0, Some(4.0), plain),
+        (1, None, plain),
+        (2, Some(9.0), plain),
+        (3, Some(0.5), plain),
+    ];
+
+    assert_eq!(raise_order(&job, &module, values), vec![0, 1, 2, 3]);
+
+    (job.raise_at_x, job.raise_at_y) = (Some(0.0), Some(5.0));
+    assert_eq!(raise_order(&job, &module, values), vec![1, 3, 2, 0]);
+
+    // entries are raised where they are drawn, after their own transform
+    let mut values = values;
+    values[3].2 = (10.0, 0.0);
+    assert_eq!(raise_order(&job, &module, values), vec![1, 2, 0, 3]);
+}
@@ -0,0 +1,20 @@
+use std::rc::Rc;
+
+use libchartium::trace::{ImplicitSegment, Segment};
+
+#[test]
+fn generates_x_from_start_and_interval() {
+    let mut segment = ImplicitSegment::new(10.0, 0.5, Rc::new(vec![0.0, 1.0, 2.0, 3.0, 4.0]));
+
+    assert_eq!((segment.from(), segment.to()), (10.0, 12.0));
+    assert_eq!(
+        segment.iter_high_prec(10.2, 11.0).collect::<Vec<_>>(),
+        vec![(10.5, 1.0), (11.0, 2.0)]
+    );
+    assert_eq!(segment.value_at(10.75), Some(1.5));
+    assert_eq!(segment.value_at(12.5), None);
+
+    segment.shrink(10.5, 11.5);
+    assert_eq!((segment.from(), segment.to()), (10.5, 11.5));
+    assert_eq!(segment.iter_high_prec(0.0, 20.0).count(), 3);
+}
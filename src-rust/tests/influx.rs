@@ -0,0 +1,67 @@
+use libchartium::data_module::{parse_annotated_csv, parse_line_protocol};
+
+#[test]
+fn line_protocol_splits_series_by_field_and_tags() {
+    let text = r#"
+# written by the gateway
+weather,location=us\ midwest temperature=82,humidity=71i 1465839830100400200
+weather,location=us\ midwest temperature=80,note="warm, humid" 1465839820100400200
+weather,location=eu temperature=20.5,raining=t 1465839830100400200
+"#;
+
+    let series = parse_line_protocol(text, "ns").unwrap();
+    let names: Vec<_> = series.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            r#"weather.humidity{location="us midwest"}"#,
+            r#"weather.raining{location="eu"}"#,
+            r#"weather.temperature{location="eu"}"#,
+            r#"weather.temperature{location="us midwest"}"#,
+        ]
+    );
+
+    let midwest = &series[3];
+    assert_eq!(midwest.measurement, "weather");
+    assert_eq!(midwest.field, "temperature");
+    assert_eq!(midwest.tags["location"], "us midwest");
+    assert_eq!(midwest.y, [80.0, 82.0]);
+    assert_eq!(midwest.x[1] - midwest.x[0], 10_000.0);
+    assert_eq!(series[1].y, [1.0]);
+
+    assert!(parse_line_protocol("cpu value=1", "ns").is_err());
+    assert!(parse_line_protocol("cpu value=x 1", "s").is_err());
+}
+
+#[test]
+fn annotated_csv_reads_every_table() {
+    let text = "\
+#datatype,string,long,dateTime:RFC3339,double,string,string,string
+#group,false,false,false,false,true,true,true
+#default,_result,,,,,,
+,result,table,_time,_value,_field,_measurement,host
+,,0,2018-05-08T20:50:00Z,10,usage_user,cpu,a
+,,0,2018-05-08T20:50:10Z,12,usage_user,cpu,a
+
+#datatype,string,long,dateTime:RFC3339,boolean,string,string,string
+#group,false,false,false,false,true,true,true
+#default,_result,,,,,,
+,result,table,_time,_value,_field,_measurement,host
+,,1,2018-05-08T20:50:00Z,true,online,status,b
+";
+
+    let series = parse_annotated_csv(text).unwrap();
+    assert_eq!(series.len(), 2);
+
+    assert_eq!(series[0].name, r#"cpu.usage_user{host="a"}"#);
+    assert_eq!(series[0].x, [1525812600000.0, 1525812610000.0]);
+    assert_eq!(series[0].y, [10.0, 12.0]);
+    assert_eq!(series[1].name, r#"status.online{host="b"}"#);
+    assert_eq!(series[1].y, [1.0]);
+
+    let error =
+        "#datatype,string,string\n#group,true,true\n#default,,\n,error,reference\n,bad query,897\n";
+    assert!(parse_annotated_csv(error)
+        .unwrap_err()
+        .contains("bad query"));
+}
@@ -0,0 +1,69 @@
+use libchartium::{
+    renderers::{ticks::for_job, PlotRect, TickFormat},
+    structs::{LabelFormat, RenderJob},
+};
+
+fn label(format: LabelFormat, decimals: Option<u32>, val: f64) -> String {
+    TickFormat::new(format, decimals).label(val)
+}
+
+#[test]
+fn si_and_engineering_labels_use_powers_of_a_thousand() {
+    assert_eq!(label(LabelFormat::Si, None, 1500.0), "1.5k");
+    assert_eq!(label(LabelFormat::Si, None, 2e-5), "20µ");
+    assert_eq!(label(LabelFormat::Si, None, -3e9), "-3G");
+    assert_eq!(label(LabelFormat::Si, None, 0.0), "0");
+    assert_eq!(label(LabelFormat::Si, Some(1), 999.96), "1k");
+
+    assert_eq!(label(LabelFormat::Engineering, None, 15000.0), "15e3");
+    assert_eq!(label(LabelFormat::Engineering, None, 0.00025), "250e-6");
+    assert_eq!(label(LabelFormat::Engineering, None, 42.0), "42");
+}
+
+#[test]
+fn fixed_and_percent_labels() {
+    assert_eq!(label(LabelFormat::Fixed, None, 1.5), "1.50");
+    assert_eq!(label(LabelFormat::Fixed, Some(0), 2.4), "2");
+    assert_eq!(label(LabelFormat::Percent, None, 0.25), "25%");
+    assert_eq!(label(LabelFormat::Percent, Some(1), 0.12345), "12.3%");
+}
+
+#[test]
+fn bytes_and_durations() {
+    assert_eq!(label(LabelFormat::Bytes, None, 512.0), "512 B");
+    assert_eq!(label(LabelFormat::Bytes, None, 1536.0), "1.5 KiB");
+    assert_eq!(
+        label(LabelFormat::Bytes, None, 3.0 * 1024.0 * 1024.0),
+        "3 MiB"
+    );
+
+    assert_eq!(label(LabelFormat::Duration, None, 0.25), "250ms");
+    assert_eq!(label(LabelFormat::Duration, None, 45.0), "45s");
+    assert_eq!(label(LabelFormat::Duration, None, 90.0), "1m 30s");
+    assert_eq!(label(LabelFormat::Duration, None, 5400.0), "1h 30m");
+    assert_eq!(label(LabelFormat::Duration, None, 3599.9), "1h");
+    assert_eq!(label(LabelFormat::Duration, None, -172800.0), "-2d");
+
+    assert_eq!(label(LabelFormat::Duration, None, f64::NAN), "");
+}
+
+#[test]
+fn jobs_label_their_ticks_in_the_axis_format() {
+    let mut job = RenderJob::new("f64".to_string(), 0, 0);
+    (job.x_from, job.x_to, job.y_from, job.y_to) = (0.0, 4096.0, 0.0, 1.0);
+    job.x_label_format = LabelFormat::Bytes;
+    job.x_tick_step = 1024.0;
+    job.y_label_format = LabelFormat::Percent;
+    job.set_y_ticks(vec![0.5], vec![]);
+
+    let result = for_job(&job, &PlotRect::of(&job, 800, 600));
+    let (x_ticks, y_ticks, _) = result.ticks();
+    let x_labels: Vec<_> = x_ticks.iter().map(|t| t.label()).collect();
+    assert_eq!(x_labels, ["1 KiB", "2 KiB", "3 KiB", "4 KiB"]);
+    let y_labels: Vec<_> = y_ticks.iter().map(|t| t.label()).collect();
+    assert_eq!(y_labels, ["50%"]);
+
+    let hash = job.style_hash();
+    job.x_label_decimals = Some(1);
+    assert_ne!(job.style_hash(), hash);
+}
@@ -0,0 +1,63 @@
+use libchartium::{
+    renderers::ticks::{
+        calc_ticks, layout_x_labels, layout_y_labels, with_endpoints, LABEL_ROTATION,
+    },
+    structs::{RenderJob, TickEnds},
+};
+
+#[test]
+fn labels_fitting_side_by_side_stay_as_they_are() {
+    let layout = layout_x_labels(&[0.0, 100.0, 200.0], &[40.0; 3], true);
+
+    assert_eq!((layout.stride, layout.rotation), (1, 0.0));
+}
+
+#[test]
+fn crowded_labels_turn_or_thin_out() {
+    let positions = [0.0, 30.0, 60.0, 90.0, 120.0];
+    let widths = [50.0; 5];
+
+    let turned = layout_x_labels(&positions, &widths, true);
+    assert_eq!((turned.stride, turned.rotation), (1, LABEL_ROTATION));
+
+    let thinned = layout_x_labels(&positions, &widths, false);
+    assert_eq!((thinned.stride, thinned.rotation), (2, 0.0));
+
+    // a single label always fits
+    assert_eq!(layout_x_labels(&[0.0], &[500.0], false).stride, 1);
+    assert_eq!(layout_x_labels(&[0.0, 1.0], &[500.0; 2], false).stride, 2);
+}
+
+#[test]
+fn y_labels_thin_out_by_their_height() {
+    assert_eq!(layout_y_labels(&[0.0, 20.0, 40.0]).stride, 1);
+    assert_eq!(layout_y_labels(&[0.0, 8.0, 16.0, 24.0]).stride, 2);
+}
+
+#[test]
+fn endpoints_replace_the_ticks_crowding_them() {
+    let ticks = calc_ticks(0.5, 9.0);
+    assert_eq!(ticks.first().map(|t| t.val()), Some(1.0));
+
+    // 9 is far enough from the end to stay
+
+    let ticks = with_endpoints(ticks, 0.5, 9.5, |v| format!("{}", v));
+    let values: Vec<_> = ticks.iter().map(|t| t.val()).collect();
+    assert_eq!(
+        values,
+        [0.5, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 9.5]
+    );
+    assert_eq!((ticks[0].pos(), ticks[10].pos()), (0.0, 1.0));
+    assert_eq!(ticks[10].label(), "9.5");
+}
+
+#[test]
+fn nice_bounds_widen_linear_ranges() {
+    let mut job = RenderJob::new("f64".to_string(), 0, 0);
+    (job.x_from, job.x_to, job.y_from, job.y_to) = (0.3, 9.7, -0.13, 0.88);
+    job.tick_ends = TickEnds::NiceBounds;
+
+    let padded = job.padded();
+    assert_eq!((padded.x_from, padded.x_to), (0.0, 10.0));
+    assert_eq!((padded.y_from, padded.y_to), (-0.2, 1.0));
+}
@@ -0,0 +1,43 @@
+use libchartium::renderers::{
+    legend::{LegendItem, LegendLayout},
+    PlotRect,
+};
+
+fn item(handle: usize, label: &str) -> LegendItem {
+    LegendItem {
+        handle,
+        color: [255, 0, 0],
+        width: 2,
+        label: label.to_string(),
+        hidden: false,
+    }
+}
+
+#[test]
+fn legend_fills_columns_of_the_widest_label() {
+    let rect = PlotRect {
+        left: 0,
+        bottom: 0,
+        width: 200,
+        height: 100,
+    };
+    let items: Vec<_> = (0..5).map(|i| item(i, "abcde")).collect();
+    // 10 pixels per character makes every column 70 pixels wide
+    let layout = LegendLayout::of(&items, &rect, |text| text.len() as f32 * 10.0);
+
+    assert_eq!((layout.columns, layout.rows), (2, 3));
+    assert_eq!(layout.cells.len(), 5);
+
+    let [x, y, w, h] = layout.frame;
+    assert!(x >= 0.0 && x + w <= 200.0 && y >= 0.0 && y + h <= 100.0);
+    assert_eq!(layout.cells[0][1], layout.cells[1][1]);
+    assert!(layout.cells[2][1] < layout.cells[0][1]);
+
+    // hits are measured from the top of the canvas
+    let hits = layout.hits(&items, 100);
+    assert_eq!(hits[3].handle, 3);
+    assert_eq!(
+        hits[0].rect[1],
+        100.0 - layout.cells[0][1] - layout.cells[0][3]
+    );
+}
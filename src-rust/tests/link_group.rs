@@ -0,0 +1,44 @@
+use libchartium::structs::{LinkGroup, RenderJob, Scale};
+
+fn job(x: (f64, f64), y: (f64, f64)) -> RenderJob {
+    let mut job = RenderJob::new("f64".to_string(), 0, 0);
+    (job.x_from, job.x_to, job.y_from, job.y_to) = (x.0, x.1, y.0, y.1);
+
+    job
+}
+
+#[test]
+fn members_share_the_x_axis_of_the_first() {
+    let mut group = LinkGroup::new();
+    let mut lead = job((0.0, 10.0), (0.0, 1.0));
+    lead.x_scale = Scale::Log10;
+    group.set_member(3, lead);
+    group.set_member(5, job((50.0, 60.0), (-5.0, 5.0)));
+
+    let member = group.job(5).unwrap();
+    assert_eq!((member.x_from, member.x_to), (0.0, 10.0));
+    assert_eq!(member.x_scale, Scale::Log10);
+    assert_eq!((member.y_from, member.y_to), (-5.0, 5.0));
+    assert_eq!(group.charts(), [3, 5]);
+
+    assert!(group.remove_member(3));
+    assert!(!group.remove_member(3));
+}
+
+#[test]
+fn zooms_and_pans_move_every_member() {
+    let mut group = LinkGroup::new();
+    group.set_member(0, job((0.0, 10.0), (0.0, 1.0)));
+    group.set_member(1, job((0.0, 10.0), (100.0, 200.0)));
+
+    group.zoom_x_at(0.5, 0.0);
+    group.pan_x_by(1.0);
+
+    for (_, job) in group.members() {
+        assert_eq!((job.x_from, job.x_to), (1.0, 6.0));
+    }
+    assert_eq!(
+        group.job(1).map(|j| (j.y_from, j.y_to)),
+        Some((100.0, 200.0))
+    );
+}
@@ -0,0 +1,35 @@
+use libchartium::{
+    data_module::DataModule,
+    renderers::{Renderer, SvgRenderer},
+    structs::RenderJob,
+};
+
+fn draw(job: RenderJob) -> String {
+    let module = DataModule::new();
+    let mut renderer = SvgRenderer::new(200, 100, 0.0);
+    renderer.render(&module, job).unwrap();
+    renderer.take_svg().unwrap()
+}
+
+#[test]
+fn exports_mark_now_and_shade_the_future() {
+    let mut job = RenderJob::new("f64".to_string(), 0, 0);
+    (job.x_from, job.x_to, job.y_from, job.y_to) = (0.0, 10.0, 0.0, 10.0);
+    (job.render_axes, job.render_grid) = (false, false);
+    assert!(!draw(job.clone()).contains("<path"));
+
+    let line = r#"<path d="M99.50 100.00L99.50 0.00""#;
+    job.now = Some(5.0);
+    let svg = draw(job.clone());
+    assert!(svg.contains(line) && !svg.contains("fill-opacity"));
+
+    job.shade_future = true;
+    let svg = draw(job.clone());
+    assert!(svg.contains(line));
+    assert!(svg.contains(r#"<path d="M99.50 100.00L200.00 100.00L200.00 0.00Z"#));
+
+    // before the view only the shading of the whole plot is left
+    job.now = Some(-5.0);
+    let svg = draw(job);
+    assert!(svg.contains(r#"<path d="M-0.50 100.00L200.00 100.00"#) && !svg.contains(line));
+}
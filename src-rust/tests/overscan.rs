@@ -0,0 +1,29 @@
+use libchartium::{data_module::Downsampling, renderers::BundleOptions, structs::RenderJob};
+
+#[test]
+fn bundles_grow_with_their_buckets() {
+    let mut opts = BundleOptions::new(Downsampling::M4, 1000);
+
+    let (from, to, same) = opts.overscanned(100.0, 200.0);
+    assert_eq!((from, to, same.resolution), (100.0, 200.0, 1000));
+
+    opts.overscan = 0.25;
+    let (from, to, grown) = opts.overscanned(100.0, 200.0);
+    assert_eq!((from, to), (75.0, 225.0));
+    assert_eq!(grown.resolution, 1500);
+}
+
+#[test]
+fn direct_traces_are_read_past_the_view() {
+    let mut job = RenderJob::new("f64".to_string(), 0, 0);
+    (job.x_from, job.x_to) = (10.0, 30.0);
+
+    assert_eq!(job.overscan_range(), (10.0, 30.0));
+
+    job.overscan = 0.1;
+    assert_eq!(job.overscan_range(), (8.0, 32.0));
+
+    // a negative overscan doesn't cut into the view
+    job.overscan = -0.5;
+    assert_eq!(job.overscan_range(), (10.0, 30.0));
+}
@@ -0,0 +1,39 @@
+use libchartium::structs::{AxisScale, PaddingUnit, RenderJob, Scale};
+
+#[test]
+fn pads_in_data_units_or_percent() {
+    let linear = AxisScale::from(Scale::Linear);
+    assert_eq!(
+        linear.pad((0.0, 10.0), 2.0, PaddingUnit::Data),
+        (-2.0, 12.0)
+    );
+    assert_eq!(
+        linear.pad((0.0, 10.0), 5.0, PaddingUnit::Percent),
+        (-0.5, 10.5)
+    );
+    assert_eq!(
+        linear.pad((0.0, 10.0), -1.0, PaddingUnit::Data),
+        (0.0, 10.0)
+    );
+
+    // percent of a logarithmic axis is taken of the decades it shows
+    let (from, to) = AxisScale::from(Scale::Log10).pad((1.0, 100.0), 50.0, PaddingUnit::Percent);
+    assert!((from - 0.1).abs() < 1e-12 && (to - 1000.0).abs() < 1e-9);
+}
+
+#[test]
+fn padded_jobs_keep_no_padding() {
+    let mut job = RenderJob::new("f64".to_string(), 0, 0);
+    (job.x_from, job.x_to, job.y_from, job.y_to) = (0.0, 100.0, -1.0, 1.0);
+    (job.y2_from, job.y2_to) = (Some(0.0), Some(10.0));
+    (job.x_padding, job.y_padding) = (10.0, 25.0);
+    job.padding_unit = PaddingUnit::Percent;
+
+    let padded = job.padded();
+    assert_eq!((padded.x_from, padded.x_to), (-10.0, 110.0));
+    assert_eq!((padded.y_from, padded.y_to), (-1.5, 1.5));
+    assert_eq!(padded.y2_range(), Some((-2.5, 12.5)));
+
+    let again = padded.padded();
+    assert_eq!((again.x_from, again.x_to), (padded.x_from, padded.x_to));
+}
@@ -0,0 +1,42 @@
+use libchartium::{
+    data_module::{m4, stats},
+    parallel::{enable_threads, split, threads},
+    renderers::geometry::stack_area_strip,
+};
+
+#[test]
+fn threaded_preparation_matches_single_threaded() {
+    let data: Vec<(f32, f32)> = (0..300_000)
+        .map(|i| (i as f32, (i as f32 * 0.01).sin() * (i % 17) as f32))
+        .collect();
+    let mut values: Vec<f64> = data.iter().map(|p| p.1 as f64).collect();
+    let mut area: Vec<(f32, f32)> = data.iter().map(|p| (p.0, 1.0)).collect();
+
+    enable_threads(false);
+    assert_eq!(threads(), 1);
+    assert_eq!(split(data.len(), 10, |_| true), vec![0..data.len()]);
+    let serial = (
+        m4(&data, 0.0, 300_000.0, 700),
+        stats(&mut values.clone(), &[50.0]).unwrap(),
+        stack_area_strip(&mut area.clone(), &data),
+    );
+
+    enable_threads(true);
+    let ranges = split(data.len(), 1000, |i| i % 1000 == 0);
+    assert_eq!(ranges.first().unwrap().start, 0);
+    assert_eq!(ranges.last().unwrap().end, data.len());
+    assert!(ranges
+        .windows(2)
+        .all(|r| r[0].end == r[1].start && r[1].start % 1000 == 0));
+
+    assert_eq!(m4(&data, 0.0, 300_000.0, 700), serial.0);
+    let parallel = stats(&mut values, &[50.0]).unwrap();
+    assert_eq!(
+        (parallel.count, parallel.min, parallel.max),
+        (serial.1.count, serial.1.min, serial.1.max)
+    );
+    assert!((parallel.mean - serial.1.mean).abs() < 1e-9);
+    assert!((parallel.variance - serial.1.variance).abs() < 1e-9);
+    assert_eq!(stack_area_strip(&mut area, &data), serial.2);
+    assert!(area.iter().zip(&data).all(|(a, d)| a.1 == d.1));
+}
@@ -0,0 +1,54 @@
+use libchartium::data_module::{DataModule, PlaybackClock};
+
+#[test]
+fn clock_wraps_when_looping_and_ends_otherwise() {
+    let mut clock = PlaybackClock::new(10.0, 20.0);
+    clock.speed = 2.0;
+
+    // paused clocks don't move
+    assert_eq!(clock.advance(3.0).after, 10.0);
+    assert_eq!(clock.position, 10.0);
+
+    clock.playing = true;
+    let step = clock.advance(3.0);
+    assert_eq!((step.after, clock.position), (10.0, 16.0));
+
+    clock.looping = true;
+    let step = clock.advance(3.0);
+    assert!(step.restarted && !step.ended);
+    assert_eq!(clock.position, 12.0);
+
+    clock.looping = false;
+    let step = clock.advance(10.0);
+    assert!(step.ended && !clock.playing);
+    assert_eq!(clock.position, 20.0);
+}
+
+#[test]
+fn replays_reveal_the_recording_over_time() {
+    let mut module = DataModule::new();
+    let recorded = module.create_rolling_trace("recorded", "f64", 16, 0.0);
+    module.push_rolling(recorded, &[0.0, 1.0, 2.0, 3.0], &[5.0, 6.0, 7.0, 8.0]);
+
+    let replay = module.create_playback(vec![recorded], 2.0).unwrap()[0];
+    let values = |module: &DataModule| -> Vec<(f64, f64)> {
+        module
+            .get_trace(replay)
+            .unwrap()
+            .get_data_high_prec(f64::MIN, f64::MAX)
+            .collect()
+    };
+    assert_eq!(values(&module), [(0.0, 5.0)]);
+
+    module.play(replay);
+    let frame = module.step_playback(replay, 1.5).unwrap();
+    assert_eq!((frame.x_from, frame.x_to), (-0.5, 1.5));
+    assert_eq!(values(&module), [(0.0, 5.0), (1.0, 6.0)]);
+
+    let frame = module.step_playback(replay, 5.0).unwrap();
+    assert!(frame.ended && !frame.playing);
+    assert_eq!(values(&module).len(), 4);
+
+    module.seek_playback(replay, 1.0);
+    assert_eq!(values(&module), [(0.0, 5.0), (1.0, 6.0)]);
+}
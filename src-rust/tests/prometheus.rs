@@ -0,0 +1,44 @@
+#![cfg(feature = "prometheus")]
+
+use libchartium::data_module::parse_prometheus;
+
+#[test]
+fn matrix_series_become_named_traces() {
+    let json = r#"{
+        "status": "success",
+        "data": {
+            "resultType": "matrix",
+            "result": [
+                {
+                    "metric": { "__name__": "up", "job": "node", "instance": "a:9100" },
+                    "values": [[1435781430.781, "1"], [1435781445.781, "NaN"], [1435781460.781, "+Inf"]]
+                },
+                { "metric": { "job": "idle" }, "values": [] }
+            ]
+        }
+    }"#;
+
+    let series = parse_prometheus(json).unwrap();
+    assert_eq!(series.len(), 1);
+
+    let up = &series[0];
+    assert_eq!(up.name, r#"up{instance="a:9100", job="node"}"#);
+    assert_eq!(up.labels["job"], "node");
+    assert_eq!(
+        up.x,
+        vec![1435781430781.0, 1435781445781.0, 1435781460781.0]
+    );
+    assert_eq!(up.y[0], 1.0);
+    assert!(up.y[1].is_nan());
+    assert_eq!(up.y[2], f64::INFINITY);
+}
+
+#[test]
+fn failed_queries_are_errors() {
+    let json = r#"{"status":"error","errorType":"bad_data","error":"invalid parameter \"query\""}"#;
+    let err = parse_prometheus(json).unwrap_err();
+    assert!(err.contains("bad_data"));
+
+    let vector = r#"{"status":"success","data":{"resultType":"vector","result":[]}}"#;
+    assert!(parse_prometheus(vector).is_err());
+}
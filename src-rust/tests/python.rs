@@ -0,0 +1,61 @@
+#![cfg(feature = "python")]
+
+use libchartium::python::libchartium;
+use pyo3::{prelude::*, types::PyDict, wrap_pymodule};
+
+fn run(script: &str) {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let globals = PyDict::new_bound(py);
+        globals
+            .set_item("chartium", wrap_pymodule!(libchartium)(py))
+            .unwrap();
+        py.run_bound(script, Some(&globals), None)
+            .map_err(|e| e.display(py))
+            .unwrap();
+    });
+}
+
+#[test]
+fn module_round_trip() {
+    run(r#"
+module = chartium.DataModule()
+trace = module.create_trace("load", "f64")
+module.load_trace(trace, [0.0, 1.0, 2.0, 3.0], [4.0, 2.0, 6.0, 8.0])
+
+assert module.points(trace, 1.0, 2.0) == ([1.0, 2.0], [2.0, 6.0])
+assert module.points(trace + 1, 0.0, 3.0) is None
+
+stats = module.trace_stats(trace, 0.0, 3.0, [50.0])
+assert (stats["count"], stats["min"], stats["max"], stats["mean"]) == (4, 2.0, 8.0, 5.0)
+assert stats["percentiles"] == [(50.0, 5.0)]
+
+try:
+    module.load_trace(trace, [0.0], [])
+    raise AssertionError("lengths were not checked")
+except ValueError:
+    pass
+"#);
+}
+
+#[test]
+fn decimation_and_stats() {
+    run(r#"
+x = [float(i) for i in range(1000)]
+y = [float(i % 7) for i in range(1000)]
+
+assert chartium.downsample(x, y, "none", 10) == (x, y)
+reduced_x, reduced_y = chartium.downsample(x, y, "m4", 10)
+assert len(reduced_x) <= 40 and reduced_x[0] == 0.0 and reduced_x[-1] == 999.0
+assert max(reduced_y) == 6.0
+
+assert chartium.stats([float("nan")]) is None
+assert chartium.stats([1.0, 3.0])["mean"] == 2.0
+
+try:
+    chartium.downsample(x, y, "cubic", 10)
+    raise AssertionError("method was not checked")
+except ValueError:
+    pass
+"#);
+}
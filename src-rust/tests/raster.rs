@@ -0,0 +1,56 @@
+#![cfg(feature = "raster")]
+
+use libchartium::{
+    data_module::DataModule,
+    renderers::{RasterRenderer, Renderer, RendererOptions},
+    structs::RenderJob,
+};
+
+fn job(module: &mut DataModule) -> RenderJob {
+    let trace = module.create_trace("flat", "f64");
+    module.load_points(trace, vec![0.0, 5.0, 10.0], vec![2.0, 2.0, 2.0]);
+
+    let mut job = RenderJob::new("f64".to_string(), 1, 0);
+    (job.x_from, job.x_to, job.y_from, job.y_to) = (0.0, 10.0, 0.0, 4.0);
+    (job.render_axes, job.render_grid) = (false, false);
+    job.add_trace(trace, &[255, 0, 0], 3, false);
+    job
+}
+
+/// Premultiplied RGBA of the pixel at `(x, y)`, counted from the bottom left
+fn pixel(pixels: &[u8], width: u32, x: u32, y: u32) -> [u8; 4] {
+    let i = (y * width + x) as usize * 4;
+    pixels[i..i + 4].try_into().unwrap()
+}
+
+#[test]
+fn lines_are_rasterized_where_the_job_places_them() {
+    let mut module = DataModule::new();
+    let job = job(&mut module);
+    let mut renderer = RasterRenderer::new(100, 50, RendererOptions::new(false));
+
+    renderer.render(&module, job).unwrap();
+    let pixels = renderer.pixels();
+
+    assert_eq!(pixel(pixels, 100, 50, 25), [255, 0, 0, 255]);
+    assert_eq!(pixel(pixels, 100, 50, 5), [0, 0, 0, 0]);
+    // the edges of the line are antialiased
+    let edge = pixel(pixels, 100, 50, 23)[3].max(pixel(pixels, 100, 50, 27)[3]);
+    assert!(edge > 0 && edge < 255);
+}
+
+#[test]
+fn offscreen_frames_keep_the_presented_one() {
+    let mut module = DataModule::new();
+    let job = job(&mut module);
+    let mut renderer = RasterRenderer::new(100, 50, RendererOptions::new(false));
+
+    renderer.render(&module, job.clone()).unwrap();
+    let presented = renderer.pixels().to_vec();
+
+    let frame = renderer.render_offscreen(&module, &[job], 40, 20).unwrap();
+    assert_eq!(frame.len(), 40 * 20 * 4);
+    assert_eq!(pixel(&frame, 40, 20, 10), [255, 0, 0, 255]);
+    assert_eq!(renderer.pixels(), &presented[..]);
+    assert_eq!(renderer.backend(), "raster");
+}
@@ -0,0 +1,35 @@
+use libchartium::{
+    data_module::DataModule,
+    renderers::RendererContainer,
+    structs::{LinkGroup, RenderJob},
+};
+
+fn job() -> RenderJob {
+    let mut job = RenderJob::new("f64".to_string(), 0, 0);
+    (job.x_from, job.x_to, job.y_from, job.y_to) = (0.0, 10.0, 0.0, 10.0);
+    job
+}
+
+#[test]
+fn single_chart_renderers_render_batches_of_chart_zero() {
+    let module = DataModule::new();
+    let mut renderer = RendererContainer::new_svg(200, 100, 0.0);
+
+    let results = renderer
+        .render_batch(&module, &[0, 0], vec![job(), job()])
+        .unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(renderer.take_svg().is_some());
+    assert!(renderer.select_chart(0).is_ok());
+}
+
+#[test]
+fn single_chart_renderers_render_link_groups_of_chart_zero() {
+    let module = DataModule::new();
+    let mut renderer = RendererContainer::new_svg(200, 100, 0.0);
+    let mut group = LinkGroup::new();
+    group.set_member(0, job());
+
+    assert_eq!(renderer.render_group(&module, &group).unwrap().len(), 1);
+    assert!(renderer.take_svg().is_some());
+}
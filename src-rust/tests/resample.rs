@@ -0,0 +1,43 @@
+use libchartium::data_module::{resample, DataModule, Interpolation};
+
+#[test]
+fn interpolates_within_the_points_only() {
+    let points = [(0.0, 0.0), (2.0, 4.0), (4.0, 8.0)];
+    let grid = [-1.0, 0.0, 1.0, 1.5, 3.0, 4.0, 5.0];
+
+    let linear = resample(&points, &grid, Interpolation::Linear);
+    assert!(linear[0].is_nan() && linear[6].is_nan());
+    assert_eq!(linear[1..6], [0.0, 2.0, 3.0, 6.0, 8.0]);
+
+    let previous = resample(&points, &grid, Interpolation::Previous);
+    assert_eq!(previous[1..6], [0.0, 0.0, 0.0, 4.0, 8.0]);
+
+    let nearest = resample(&points, &grid, Interpolation::Nearest);
+    assert_eq!(nearest[1..6], [0.0, 0.0, 4.0, 4.0, 8.0]);
+}
+
+#[test]
+fn resampled_traces_follow_their_source() {
+    let mut module = DataModule::new();
+    let source = module.create_rolling_trace("source", "f64", 8, 0.0);
+    module.push_rolling(source, &[0.0, 10.0], &[0.0, 10.0]);
+
+    let aligned = module
+        .resample(source, vec![5.0, 15.0], Interpolation::Linear)
+        .unwrap();
+    let values = |module: &DataModule| -> Vec<f64> {
+        module
+            .get_trace(aligned)
+            .unwrap()
+            .get_data_high_prec(f64::MIN, f64::MAX)
+            .map(|p| p.1)
+            .collect()
+    };
+
+    let before = values(&module);
+    assert_eq!(before[0], 5.0);
+    assert!(before[1].is_nan());
+
+    module.push_rolling(source, &[20.0], &[0.0]);
+    assert_eq!(values(&module), [5.0, 5.0]);
+}
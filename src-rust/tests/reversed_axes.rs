@@ -0,0 +1,66 @@
+use libchartium::{
+    renderers::{ticks::for_job, PlotRect},
+    structs::{AxisScale, RenderJob, Scale},
+};
+
+fn job() -> RenderJob {
+    let mut job = RenderJob::new("f64".to_string(), 0, 0);
+    (job.x_from, job.x_to, job.y_from, job.y_to) = (0.0, 100.0, 0.0, 10.0);
+    job
+}
+
+#[test]
+fn reversed_scales_mirror_positions() {
+    let scale = AxisScale::from(Scale::Log10);
+    assert_eq!(scale.position(10.0, 1.0, 100.0), 0.5);
+    assert_eq!(scale.reversed(true).position(100.0, 1.0, 100.0), 0.0);
+    assert_eq!(scale.reversed(true).position(1.0, 1.0, 100.0), 1.0);
+}
+
+#[test]
+fn ticks_of_reversed_axes_are_mirrored_in_ascending_order() {
+    let mut job = job();
+    let plain = for_job(&job, &PlotRect::of(&job, 800, 600));
+
+    let hash = job.style_hash();
+    job.y_reversed = true;
+    assert_ne!(job.style_hash(), hash);
+
+    let result = for_job(&job, &PlotRect::of(&job, 800, 600));
+    let (x_ticks, y_ticks, _) = result.ticks();
+    let (plain_x, plain_y, _) = plain.ticks();
+
+    assert_eq!(x_ticks.len(), plain_x.len());
+    assert!(y_ticks.windows(2).all(|t| t[0].pos() < t[1].pos()));
+    for (tick, plain) in y_ticks.iter().zip(plain_y.iter().rev()) {
+        assert_eq!((tick.val(), tick.label()), (plain.val(), plain.label()));
+        assert!((tick.pos() - (1.0 - plain.pos())).abs() < 1e-12);
+    }
+}
+
+#[cfg(feature = "raster")]
+#[test]
+fn traces_are_drawn_along_reversed_axes() {
+    use libchartium::{
+        data_module::DataModule,
+        renderers::{RasterRenderer, Renderer, RendererOptions},
+    };
+
+    let mut module = DataModule::new();
+    let trace = module.create_trace("depth", "f64");
+    module.load_points(trace, vec![0.0, 50.0], vec![2.5, 2.5]);
+
+    let mut job = job();
+    (job.render_axes, job.render_grid) = (false, false);
+    (job.x_reversed, job.y_reversed) = (true, true);
+    job.add_trace(trace, &[255, 0, 0], 3, false);
+
+    let mut renderer = RasterRenderer::new(100, 40, RendererOptions::new(false));
+    renderer.render(&module, job).unwrap();
+    let pixel = |x: usize, y: usize| renderer.pixels()[(y * 100 + x) * 4];
+
+    // the line lies on the right half, a quarter down from the top
+    assert_eq!(pixel(75, 30), 255);
+    assert_eq!(pixel(25, 30), 0);
+    assert_eq!(pixel(75, 10), 0);
+}
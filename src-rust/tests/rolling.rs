@@ -0,0 +1,48 @@
+use libchartium::data_module::{DataModule, RollingWindow};
+
+#[test]
+fn window_evicts_points_behind_it() {
+    let mut window = RollingWindow::new(2.0);
+
+    let values: Vec<_> = [(0.0, 4.0), (1.0, 2.0), (2.0, 6.0), (3.0, 1.0)]
+        .into_iter()
+        .map(|(x, y)| window.push(x, y))
+        .collect();
+
+    assert_eq!(values[1].mean, 3.0);
+    assert_eq!(values[2].mean, 4.0);
+    assert_eq!((values[2].min, values[2].max), (2.0, 6.0));
+    assert_eq!((values[3].min, values[3].max), (1.0, 6.0));
+    assert_eq!(values[3].mean, 3.5);
+
+    let alpha = 1.0 - (-0.5f64).exp();
+    assert_eq!(values[1].ema, 4.0 + alpha * (2.0 - 4.0));
+}
+
+#[test]
+fn aggregates_follow_appends_to_live_traces() {
+    let mut module = DataModule::new();
+    let live = module.create_rolling_trace("live", "f64", 4, 0.0);
+    module.push_rolling(live, &[0.0, 1.0], &[2.0, 4.0]);
+
+    let mean = module.rolling(live, 1.5, "mean").unwrap();
+    let envelope = module.rolling(live, 1.5, "envelope").unwrap();
+    let max = module.get_envelope(envelope).unwrap().max;
+
+    let values = |module: &DataModule, handle| -> Vec<(f64, f64)> {
+        module
+            .get_trace(handle)
+            .unwrap()
+            .get_data_high_prec(f64::MIN, f64::MAX)
+            .collect()
+    };
+    assert_eq!(values(&module, mean), [(0.0, 2.0), (1.0, 3.0)]);
+
+    // the oldest points leave the output along with the source
+    module.push_rolling(live, &[2.0, 3.0, 4.0], &[6.0, 8.0, 0.0]);
+    assert_eq!(
+        values(&module, mean),
+        [(1.0, 3.0), (2.0, 5.0), (3.0, 7.0), (4.0, 4.0)]
+    );
+    assert_eq!(values(&module, max)[3], (4.0, 8.0));
+}
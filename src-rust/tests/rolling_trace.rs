@@ -0,0 +1,27 @@
+use libchartium::trace::{RollingTrace, Segment};
+
+#[test]
+fn evicts_past_capacity_across_the_wrap() {
+    let mut trace = RollingTrace::new(3, 0.0);
+    trace.push(&[(0.0, 0.0), (1.0, 1.0), (2.0, 2.0), (3.0, 3.0), (4.0, 4.0)]);
+
+    assert_eq!(trace.len(), 3);
+    assert_eq!(trace.retained_from(), Some(2.0));
+    assert_eq!(
+        trace.iter_high_prec(0.0, 10.0).collect::<Vec<_>>(),
+        vec![(2.0, 2.0), (3.0, 3.0), (4.0, 4.0)]
+    );
+    assert_eq!(trace.value_at(2.5), Some(2.5));
+    assert_eq!(trace.value_at(1.0), None);
+}
+
+#[test]
+fn evicts_outside_the_window_and_drops_out_of_order_points() {
+    let mut trace = RollingTrace::new(100, 5.0);
+    trace.push(&[(0.0, 0.0), (4.0, 1.0), (3.0, 9.0), (8.0, 2.0)]);
+
+    assert_eq!(
+        trace.iter_high_prec(0.0, 10.0).collect::<Vec<_>>(),
+        vec![(4.0, 1.0), (8.0, 2.0)]
+    );
+}
@@ -0,0 +1,53 @@
+use libchartium::data_module::{runs, Run};
+
+fn run(state: f64, start: f64, end: f64) -> Run {
+    Run {
+        state,
+        start,
+        end,
+        duration: end - start,
+    }
+}
+
+#[test]
+fn consecutive_states_merge_into_runs() {
+    let points = [
+        (0.0, 1.0),
+        (1.0, 1.0),
+        (2.0, 2.0),
+        (5.0, 2.0),
+        (6.0, 1.0),
+        (8.0, 1.0),
+    ];
+
+    assert_eq!(
+        runs(None, points.into_iter(), 0.0, 10.0),
+        vec![run(1.0, 0.0, 2.0), run(2.0, 2.0, 6.0), run(1.0, 6.0, 8.0)]
+    );
+    // clipped to the range, the state at its start coming from the preceding point
+    assert_eq!(
+        runs(Some((1.0, 1.0)), points[2..5].iter().copied(), 1.5, 5.5),
+        vec![run(1.0, 1.5, 2.0), run(2.0, 2.0, 5.0)]
+    );
+    assert_eq!(
+        runs(Some((2.0, 2.0)), std::iter::empty(), 3.0, 4.0),
+        vec![run(2.0, 3.0, 4.0)]
+    );
+}
+
+#[test]
+fn gaps_end_runs() {
+    let points = [
+        (0.0, 1.0),
+        (1.0, f64::NAN),
+        (2.0, f64::NAN),
+        (3.0, 1.0),
+        (4.0, 1.0),
+    ];
+
+    assert_eq!(
+        runs(None, points.into_iter(), 0.0, 10.0),
+        vec![run(1.0, 0.0, 1.0), run(1.0, 3.0, 4.0)]
+    );
+    assert!(runs(None, std::iter::empty(), 0.0, 1.0).is_empty());
+}
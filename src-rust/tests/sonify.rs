@@ -0,0 +1,45 @@
+use libchartium::data_module::{sonify, PitchMapping};
+
+#[test]
+fn sonify_maps_values_to_pitch() {
+    let mapping = PitchMapping::new(1000, 100.0, 400.0);
+
+    assert_eq!(mapping.frequency(0.0), 100.0);
+    assert_eq!(mapping.frequency(0.5), 200.0);
+    assert_eq!(mapping.frequency(2.0), 400.0);
+
+    let linear = PitchMapping {
+        logarithmic: false,
+        ..mapping
+    };
+    assert_eq!(linear.frequency(0.5), 250.0);
+
+    // a constant 0 stays at the lowest pitch, 100 Hz at 1 kHz being 10 samples a period
+    let samples = sonify(&[(0.0, 0.0), (10.0, 0.0)], 0.0, 10.0, 0.1, &mapping);
+    assert_eq!(samples.len(), 100);
+
+    let period = std::f64::consts::TAU / 10.0;
+    for (i, s) in samples.iter().enumerate() {
+        let expected = ((i as f64 * period).sin() * 0.5) as f32;
+        assert!((s - expected).abs() < 1e-4, "{i}: {s} != {expected}");
+    }
+}
+
+#[test]
+fn sonify_is_silent_without_data() {
+    let mapping = PitchMapping::default();
+    let samples = sonify(
+        &[(2.0, 1.0), (4.0, f64::NAN), (6.0, 3.0), (8.0, 2.0)],
+        0.0,
+        10.0,
+        1.0,
+        &mapping,
+    );
+
+    assert_eq!(samples.len(), 44100);
+    // before the first point, around the gap and after the last point
+    assert!(samples[..8820].iter().all(|&s| s == 0.0));
+    assert!(samples[17640..26460].iter().all(|&s| s == 0.0));
+    assert!(samples[35300..].iter().all(|&s| s == 0.0));
+    assert!(samples[26500..35200].iter().any(|&s| s != 0.0));
+}
@@ -0,0 +1,31 @@
+use libchartium::data_module::stats;
+
+#[test]
+fn stats_of_values() {
+    let mut values: Vec<f64> = (1..=20).map(|v| v as f64).collect();
+    values.push(f64::NAN);
+
+    let stats = stats(&mut values, &[0.0, 50.0, 90.0, 100.0]).unwrap();
+
+    assert_eq!(stats.count, 20);
+    assert_eq!((stats.min, stats.max), (1.0, 20.0));
+    assert!((stats.mean - 10.5).abs() < 1e-12);
+    // population variance of 1..=n is (n² - 1) / 12
+    assert!((stats.variance - 399.0 / 12.0).abs() < 1e-9);
+
+    let expected = [(0.0, 1.0), (50.0, 10.5), (90.0, 18.1), (100.0, 20.0)];
+    for ((p, value), (ep, evalue)) in stats.percentiles.iter().zip(expected) {
+        assert_eq!(*p, ep);
+        assert!((value - evalue).abs() < 1e-9, "{} != {}", value, evalue);
+    }
+}
+
+#[test]
+fn no_stats_without_finite_values() {
+    assert!(stats(&mut vec![], &[50.0]).is_none());
+    assert!(stats(&mut vec![f64::INFINITY, f64::NAN], &[50.0]).is_none());
+
+    let single = stats(&mut vec![3.0], &[25.0]).unwrap();
+    assert_eq!((single.min, single.max, single.variance), (3.0, 3.0, 0.0));
+    assert_eq!(single.percentiles, [(25.0, 3.0)]);
+}
@@ -0,0 +1,76 @@
+use libchartium::{
+    renderers::{
+        ticks::{calc_ticks, calc_ticks_with, for_job, TickDensity},
+        AxisTick, PlotRect,
+    },
+    structs::RenderJob,
+};
+
+fn values(ticks: &[AxisTick]) -> Vec<f64> {
+    ticks.iter().map(|t| t.val()).collect()
+}
+
+#[test]
+fn default_density_matches_the_plain_ticks() {
+    let plain = calc_ticks(0.0, 100.0);
+    let dense = calc_ticks_with(0.0, 100.0, &TickDensity::default());
+
+    assert_eq!(values(&plain), values(&dense));
+    assert_eq!(TickDensity::new(0, 0.0), TickDensity::default());
+}
+
+#[test]
+fn tick_count_limits_the_ticks() {
+    let ticks = calc_ticks_with(0.0, 100.0, &TickDensity::new(4, 0.0));
+    assert_eq!(values(&ticks), [50.0, 100.0]);
+
+    let ticks = calc_ticks_with(0.0, 100.0, &TickDensity::new(30, 0.0));
+    assert_eq!(ticks.len(), 20);
+}
+
+#[test]
+fn custom_steps_are_kept_unless_too_dense() {
+    let ticks = calc_ticks_with(0.0, 10.0, &TickDensity::new(0, 2.5));
+    assert_eq!(values(&ticks), [2.5, 5.0, 7.5, 10.0]);
+
+    let ticks = calc_ticks_with(0.0, 1e6, &TickDensity::new(0, 1.0));
+    assert!(ticks.len() <= 10);
+}
+
+#[test]
+fn short_axes_get_fewer_ticks() {
+    let density = TickDensity::default().spaced(100, 40);
+    assert_eq!(density.max_ticks, 2.0);
+    assert_eq!(TickDensity::default().spaced(10, 40).max_ticks, 1.0);
+    assert_eq!(TickDensity::default().spaced(10, 0), TickDensity::default());
+
+    let mut job = RenderJob::new("f64".to_string(), 0, 0);
+    (job.x_from, job.x_to, job.y_from, job.y_to) = (0.0, 100.0, 0.0, 100.0);
+    job.min_tick_spacing = 100;
+
+    let result = for_job(&job, &PlotRect::of(&job, 800, 200));
+    let (x_ticks, y_ticks, _) = result.ticks();
+    assert!(x_ticks.len() <= 8 && x_ticks.len() > y_ticks.len());
+    assert!(y_ticks.len() <= 2);
+}
+
+#[test]
+fn listed_ticks_replace_the_generated_ones() {
+    let mut job = RenderJob::new("f64".to_string(), 0, 0);
+    (job.x_from, job.x_to, job.y_from, job.y_to) = (0.0, 100.0, 0.0, 10.0);
+    job.set_x_ticks(
+        vec![75.0, 25.0, 150.0],
+        vec!["low".to_string(), "high".to_string()],
+    );
+
+    let result = for_job(&job, &PlotRect::of(&job, 800, 600));
+    let (x_ticks, y_ticks, _) = result.ticks();
+    let labels: Vec<_> = x_ticks.iter().map(|t| (t.pos(), t.label())).collect();
+    assert_eq!(labels, [(0.25, "high"), (0.75, "low")]);
+    assert!(!y_ticks.is_empty());
+
+    let hash = job.style_hash();
+    job.set_x_ticks(vec![], vec![]);
+    assert_ne!(job.style_hash(), hash);
+    assert!(for_job(&job, &PlotRect::of(&job, 800, 600)).ticks().0.len() > 2);
+}
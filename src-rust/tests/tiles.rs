@@ -0,0 +1,66 @@
+use libchartium::data_module::{Pyramid, PYRAMID_FACTOR};
+
+fn pyramid() -> Pyramid {
+    let points: Vec<_> = (0..10_000)
+        .map(|i| (1000.0 + i as f64, (i as f64 / 50.0).sin()))
+        .collect();
+
+    Pyramid::build(&points)
+}
+
+#[test]
+fn levels_shrink_by_the_factor() {
+    let pyramid = pyramid();
+
+    assert_eq!(pyramid.origin, 1000.0);
+    assert_eq!(pyramid.last_x, 10_999.0);
+    assert!(pyramid.levels.len() >= 2);
+    // every bucket keeps at most its first, last, lowest and highest point
+    assert!(pyramid.levels[0].len() <= 4 * 10_000 / PYRAMID_FACTOR);
+    assert!(pyramid.levels[1].len() < pyramid.levels[0].len());
+
+    let slice = pyramid.slice(0, 6000.0, 7000.0).unwrap();
+    assert!(!slice.is_empty());
+    assert!(slice.iter().all(|p| p.0 >= 0.0 && p.0 <= 1000.0));
+    assert!(pyramid.slice(10, 6000.0, 7000.0).is_none());
+}
+
+#[test]
+fn tiles_round_trip() {
+    let pyramid = pyramid();
+    let bytes = pyramid.encode(100);
+
+    assert_eq!(Pyramid::decode(&bytes), Ok(pyramid));
+}
+
+#[test]
+fn malformed_tiles_are_rejected() {
+    let bytes = pyramid().encode(100);
+
+    assert!(Pyramid::decode(&bytes[..bytes.len() - 4]).is_err());
+    assert!(Pyramid::decode(&[bytes.as_slice(), &[0]].concat()).is_err());
+    assert!(Pyramid::decode(b"nope").is_err());
+
+    // a missing tile leaves a hole in its level
+    let tile = 12 + 100 * 8;
+    let header = 4 + 4 + 8 + 8 + 8 + 12;
+    let mut holed = bytes[..header].to_vec();
+    let count = u32::from_le_bytes(bytes[header - 4..header].try_into().unwrap()) - 1;
+    holed[header - 4..].copy_from_slice(&count.to_le_bytes());
+    holed.extend_from_slice(&bytes[header + tile..]);
+    assert!(Pyramid::decode(&holed).is_err());
+}
+
+#[test]
+fn version_1_tiles_have_no_checksum() {
+    let pyramid = pyramid();
+    let bytes = pyramid.encode(100);
+
+    // version 1 lacks the checksum following `last_x`
+    let mut v1 = [b"CHTP".as_slice(), &1u32.to_le_bytes(), &bytes[8..24]].concat();
+    v1.extend_from_slice(&bytes[32..]);
+
+    let decoded = Pyramid::decode(&v1).unwrap();
+    assert_eq!(decoded.checksum, None);
+    assert_eq!(decoded.levels, pyramid.levels);
+}
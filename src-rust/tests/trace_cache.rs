@@ -0,0 +1,59 @@
+use libchartium::renderers::trace_cache::{TraceCache, TraceKey};
+
+#[test]
+fn nearby_views_share_a_key_covering_them() {
+    let key = TraceKey::of(0, 100.0, 180.0, 1).unwrap();
+    let (from, to) = key.range();
+    assert!(from <= 100.0 && to >= 180.0);
+
+    // panned and zoomed by a little
+    assert_eq!(TraceKey::of(0, 101.0, 181.0, 1), Some(key));
+    assert_eq!(TraceKey::of(0, 100.5, 170.0, 1), Some(key));
+
+    // another trace, new data or a view far away
+    assert_ne!(TraceKey::of(1, 100.0, 180.0, 1), Some(key));
+    assert_ne!(TraceKey::of(0, 100.0, 180.0, 2), Some(key));
+    assert_ne!(TraceKey::of(0, 500.0, 580.0, 1), Some(key));
+    assert_ne!(TraceKey::of(0, 100.0, 1000.0, 1), Some(key));
+
+    assert_eq!(TraceKey::of(0, 5.0, 5.0, 1), None);
+    assert_eq!(TraceKey::of(0, f64::NAN, 5.0, 1), None);
+}
+
+#[test]
+fn keys_cover_their_views() {
+    for (from, to) in [
+        (-3.5, 2.0),
+        (0.0, 1e-6),
+        (1.7e12, 1.7e12 + 3.6e6),
+        (7.0, 1e9),
+    ] {
+        let (cached_from, cached_to) = TraceKey::of(0, from, to, 0).unwrap().range();
+        assert!(cached_from <= from && cached_to >= to, "{from}..{to}");
+        assert!(cached_to - cached_from <= 3.0 * (to - from));
+    }
+}
+
+#[test]
+fn least_recently_used_points_are_evicted() {
+    let keys: Vec<_> = (0..3)
+        .map(|handle| TraceKey::of(handle, 0.0, 10.0, 0).unwrap())
+        .collect();
+    let mut cache = TraceCache::new(2);
+
+    assert!(cache.insert(keys[0], "a").is_empty());
+    assert!(cache.insert(keys[1], "b").is_empty());
+    assert_eq!(cache.get(&keys[0]), Some(&"a"));
+
+    assert_eq!(cache.insert(keys[2], "c"), ["b"]);
+    assert_eq!(cache.get(&keys[1]), None);
+    assert_eq!(cache.len(), 2);
+
+    assert_eq!(cache.insert(keys[2], "d"), ["c"]);
+    assert_eq!(cache.get(&keys[2]), Some(&"d"));
+
+    let mut drained = cache.drain();
+    drained.sort();
+    assert_eq!(drained, ["a", "d"]);
+    assert!(cache.is_empty());
+}
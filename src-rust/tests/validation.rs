@@ -0,0 +1,79 @@
+use libchartium::data_module::{
+    DataModule, ValidationRules, Violation, ViolationAction, ViolationKind,
+};
+
+#[test]
+fn rules_flag_out_of_range_values_and_steps() {
+    let rules = ValidationRules {
+        min: 0.0,
+        max: 10.0,
+        max_step: 3.0,
+        action: ViolationAction::Flag,
+    };
+
+    let mut points = [(0.0, 2.0), (1.0, -1.0), (2.0, 9.0), (3.0, 4.0), (4.0, 12.0)];
+    let (violations, last) = rules.check(&mut points, None);
+
+    let kinds: Vec<_> = violations.iter().map(|v| (v.x, v.kind)).collect();
+    assert_eq!(
+        kinds,
+        [
+            (1.0, ViolationKind::BelowMin),
+            (2.0, ViolationKind::Step),
+            (4.0, ViolationKind::AboveMax),
+        ]
+    );
+    // steps are taken from the last valid value, so 4 follows 2 rather than the spike
+    assert_eq!(last, Some(4.0));
+    assert_eq!(points[1], (1.0, -1.0));
+
+    // the step is checked across appends
+    let (violations, _) = rules.check(&mut [(5.0, 8.0)], last);
+    assert_eq!(
+        violations,
+        [Violation {
+            x: 5.0,
+            y: 8.0,
+            kind: ViolationKind::Step
+        }]
+    );
+}
+
+#[test]
+fn gapped_samples_reach_the_trace_as_missing_values() {
+    let mut module = DataModule::new();
+    let live = module.create_rolling_trace("live", "f64", 8, 0.0);
+
+    let rules = ValidationRules {
+        max: 100.0,
+        action: ViolationAction::Gap,
+        ..ValidationRules::new()
+    };
+    module.set_validation(live, rules, None);
+
+    let mut points = vec![(0.0, 1.0), (1.0, 1e9)];
+    let report = module.validate_append(live, &mut points).unwrap();
+    assert_eq!((report.checked, report.violations.len()), (2, 1));
+
+    module.push_rolling(live, &[2.0, 3.0, 4.0], &[5.0, f64::INFINITY, 6.0]);
+    let values: Vec<_> = module
+        .get_trace(live)
+        .unwrap()
+        .get_data_high_prec(f64::MIN, f64::MAX)
+        .map(|p| p.1)
+        .collect();
+    assert_eq!(values[0], 5.0);
+    assert!(values[1].is_nan());
+    assert_eq!(values[2], 6.0);
+
+    // without rules appends go through untouched
+    module.clear_validation(live);
+    module.push_rolling(live, &[5.0], &[1e9]);
+    let last = module
+        .get_trace(live)
+        .unwrap()
+        .get_data_high_prec(f64::MIN, f64::MAX)
+        .last()
+        .unwrap();
+    assert_eq!(last, (5.0, 1e9));
+}
@@ -0,0 +1,57 @@
+use libchartium::{
+    renderers::BundleEntry,
+    structs::{Marker, RenderJob, Scale, Versioned, WIRE_VERSION},
+};
+
+#[test]
+fn jobs_survive_the_round_trip() {
+    let mut job = RenderJob::new("datetime".to_string(), 1, 1);
+    (job.x_from, job.x_to, job.y_from, job.y_to) = (1.0, 2.0, -3.0, 4.0);
+    job.y_scale = Scale::SymLog;
+    job.y2_from = Some(0.5);
+    job.add_trace(3, &[1, 2, 3], 2, true);
+    job.add_bundle(7);
+    job.blacklist_trace(4);
+
+    let json = serde_json::to_string(&Versioned::new(&job)).unwrap();
+    let wire: Versioned<RenderJob> = serde_json::from_str(&json).unwrap();
+    assert_eq!(wire.version, WIRE_VERSION);
+
+    let restored = wire.into_current().unwrap();
+    assert_eq!(restored.style_hash(), job.style_hash());
+    assert_eq!(restored.view(), job.view());
+    assert_eq!(restored.y2_range(), job.y2_range());
+}
+
+#[test]
+fn missing_fields_take_defaults() {
+    let json = r#"{
+        "version": 1,
+        "data": { "x_type": "f64", "x_to": 10.0, "y_scale": "log10", "traces": [{ "idx": 1, "color": [0, 0, 0], "width": 1 }] }
+    }"#;
+    let job = serde_json::from_str::<Versioned<RenderJob>>(json)
+        .unwrap()
+        .into_current()
+        .unwrap();
+
+    assert_eq!((job.x_from, job.x_to), (0.0, 10.0));
+    assert_eq!(job.y_scale, Scale::Log10);
+    assert!(job.render_axes && job.clear);
+    assert_eq!(job.marker_rotation, 0.0);
+    assert!(!job.get_traces()[0].points_mode);
+
+    let json = r#"{ "version": 1, "data": [{ "handle": 2, "width": 1, "color": [9, 9, 9] }] }"#;
+    let entries: Versioned<Vec<BundleEntry>> = serde_json::from_str(json).unwrap();
+    assert_eq!(entries.into_current().unwrap().len(), 1);
+    assert_eq!(Marker::default(), Marker::None);
+}
+
+#[test]
+fn newer_versions_are_rejected() {
+    let wire = Versioned {
+        version: WIRE_VERSION + 1,
+        data: RenderJob::default(),
+    };
+
+    assert!(wire.into_current().is_err());
+}